@@ -9,6 +9,8 @@ use keys::Address;
 use transaction::{Transaction, TransactionFlags};
 use tree_primitives::accounts_proof::AccountsProof;
 use tree_primitives::accounts_tree_chunk::AccountsTreeChunk;
+use tree_primitives::accounts_tree_node::AccountsTreeNode;
+use tree_primitives::address_nibbles::AddressNibbles;
 
 use crate::tree::AccountsTree;
 
@@ -47,6 +49,15 @@ impl Accounts {
         }
     }
 
+    /// Returns all terminal nodes of the accounts tree, i.e. all accounts ordered by address.
+    /// Returns `None` if a node of the tree is missing from the database.
+    pub fn get_terminal_nodes(&self, txn_option: Option<&db::Transaction>) -> Option<Vec<AccountsTreeNode<Account>>> {
+        match txn_option {
+            Some(txn) => self.tree.get_terminal_nodes(txn, &AddressNibbles::empty(), usize::max_value()),
+            None => self.tree.get_terminal_nodes(&ReadTransaction::new(&self.env), &AddressNibbles::empty(), usize::max_value()),
+        }
+    }
+
     pub fn get_accounts_proof(&self, txn: &db::Transaction, addresses: &[Address]) -> AccountsProof<Account> {
         self.tree.get_accounts_proof(txn, addresses)
     }
@@ -58,6 +69,15 @@ impl Accounts {
         }
     }
 
+    /// Recomputes the root hash of the accounts tree from all of its nodes. Returns `None` if the
+    /// tree is corrupted.
+    pub fn recompute_hash(&self, txn_option: Option<&db::Transaction>) -> Option<Blake2bHash> {
+        match txn_option {
+            Some(txn) => self.tree.recompute_root_hash(txn),
+            None => self.tree.recompute_root_hash(&ReadTransaction::new(&self.env))
+        }
+    }
+
     pub fn hash_with(&self, transactions: &[Transaction], inherents: &[Inherent], block_height: u32) -> Result<Blake2bHash, AccountError> {
         let mut txn = WriteTransaction::new(&self.env);
         self.commit(&mut txn, transactions, inherents, block_height)?;
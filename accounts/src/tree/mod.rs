@@ -273,6 +273,24 @@ impl<A: AccountsTreeLeave> AccountsTree<A> {
         Some(vec)
     }
 
+    /// Recomputes the root hash from the terminal nodes, checking the child hashes stored in the
+    /// branch nodes along the way. Returns `None` if a node is missing or a stored hash is wrong.
+    pub fn recompute_root_hash(&self, txn: &Transaction) -> Option<Blake2bHash> {
+        self.recompute_hash(txn, self.get_root(txn)?)
+    }
+
+    fn recompute_hash(&self, txn: &Transaction, node: AccountsTreeNode<A>) -> Option<Blake2bHash> {
+        if let AccountsTreeNode::BranchNode { ref prefix, ref children } = node {
+            for child in children.iter().flatten() {
+                let child_node = txn.get(&self.db, &(prefix + &child.suffix))?;
+                if self.recompute_hash(txn, child_node)? != child.hash {
+                    return None;
+                }
+            }
+        }
+        Some(node.hash())
+    }
+
     fn get_root(&self, txn: &Transaction) -> Option<AccountsTreeNode<A>> {
         txn.get(&self.db, &AddressNibbles::empty())
     }
@@ -1,12 +1,16 @@
 use std::convert::TryFrom;
 use hex;
 
+use nimiq_accounts::Accounts;
 use nimiq_accounts::tree::AccountsTree;
 use nimiq_database::volatile::VolatileEnvironment;
 use nimiq_database::WriteTransaction;
 use nimiq_keys::Address;
 use nimiq_account::{Account, BasicAccount};
 use nimiq_primitives::coin::Coin;
+use nimiq_tree_primitives::accounts_tree_chunk::subtree_root;
+use nimiq_tree_primitives::accounts_tree_node::AccountsTreeNode;
+use nimiq_tree_primitives::address_nibbles::AddressNibbles;
 
 #[test]
 fn it_can_put_and_get_a_balance() {
@@ -217,3 +221,50 @@ fn it_can_merge_nodes_while_pruning() {
 
     txn.abort();
 }
+
+#[test]
+fn it_rehashes_subtrees_of_chunks() {
+    let env = VolatileEnvironment::new(10).unwrap();
+    let accounts = Accounts::new(env.clone());
+    let genesis_accounts = (1u8..=40).map(|i| {
+        let mut address = [i; Address::SIZE];
+        address[0] = i.wrapping_mul(97);
+        (Address::from(address), Account::Basic(BasicAccount { balance: Coin::try_from(u64::from(i)).unwrap() }))
+    }).collect();
+    let mut txn = WriteTransaction::new(&env);
+    accounts.init(&mut txn, genesis_accounts);
+    txn.commit();
+
+    let mut chunk = accounts.get_chunk("", 100, None).unwrap();
+    assert!(chunk.verify());
+    let root = chunk.proof.nodes().last().unwrap().clone();
+    let nodes: Vec<AccountsTreeNode<Account>> = chunk.terminal_nodes().into_iter().cloned().collect();
+    assert_eq!(nodes.len(), 40);
+
+    let range = |nibble: usize| -> Vec<AccountsTreeNode<Account>> {
+        nodes.iter().filter(|node| node.prefix().get(0) == Some(nibble)).cloned().collect()
+    };
+    let expected = |nibble: usize| {
+        let prefix: AddressNibbles = format!("{:x}", nibble).parse().unwrap();
+        root.get_child_prefix(&prefix)
+            .map(|child_prefix| (child_prefix, root.get_child_hash(&prefix).unwrap().clone()))
+    };
+
+    // The subtree of each first nibble matches the child of the root node.
+    for nibble in 0..16 {
+        assert_eq!(subtree_root(&range(nibble)), expected(nibble));
+    }
+
+    // Missing or forged nodes are detected.
+    let nibble = (0..16).max_by_key(|&nibble| range(nibble).len()).unwrap();
+    let mut missing = range(nibble);
+    assert!(missing.len() > 2);
+    missing.remove(1);
+    assert_ne!(subtree_root(&missing), expected(nibble));
+
+    let mut forged = range(nibble);
+    forged[0] = forged[0].clone().with_account(Account::Basic(BasicAccount { balance: Coin::try_from(1000).unwrap() })).unwrap();
+    assert_ne!(subtree_root(&forged), expected(nibble));
+
+    assert_eq!(subtree_root::<Account>(&[]), None);
+}
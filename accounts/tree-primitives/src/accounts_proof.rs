@@ -60,6 +60,22 @@ impl<A: AccountsTreeLeave> AccountsProof<A> {
         None
     }
 
+    /// Returns whether the proof shows that there is no account at `address`, i.e. it contains
+    /// a branch node on the path to `address` that doesn't have a child leading to it.
+    pub fn proves_absence(&self, address: &Address) -> bool {
+        assert!(self.verified, "AccountsProof must be verified before retrieving accounts. Call verify() first.");
+
+        let prefix = AddressNibbles::from(address);
+        self.nodes.iter().any(|node| {
+            node.is_branch()
+                && node.prefix().is_prefix_of(&prefix)
+                && node.prefix() != &prefix
+                && node.get_child_prefix(&prefix)
+                    .map(|child_prefix| !child_prefix.is_prefix_of(&prefix))
+                    .unwrap_or(true)
+        })
+    }
+
     pub fn root_hash(&self) -> Blake2bHash {
         (&self.nodes[self.nodes.len() - 1]).hash()
     }
@@ -149,6 +165,18 @@ mod tests {
         assert_eq!(None, proof3.get_account(&address2));
         assert_eq!(None, proof3.get_account(&address3));
 
+        // Accounts that aren't part of the tree are only proven to be absent by the branch node
+        // on their path.
+        let address5 = Address::from(hex::decode("0044444444444444444444444444444444444444").unwrap().as_slice());
+        let address6 = Address::from(hex::decode("0021111111111111111111111111111111111111").unwrap().as_slice());
+        assert!(proof2.proves_absence(&address5));
+        assert!(proof2.proves_absence(&address6));
+        assert!(!proof2.proves_absence(&address1));
+        assert!(!proof2.proves_absence(&address2));
+        assert!(!proof2.proves_absence(&address4));
+        assert!(proof3.proves_absence(&address5));
+        assert!(!proof3.proves_absence(&address1));
+
         // must return the correct root hash
         assert!(proof1.root_hash() == r1.hash());
     }
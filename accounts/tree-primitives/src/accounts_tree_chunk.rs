@@ -1,9 +1,10 @@
 use account::AccountsTreeLeave;
 use beserial::{Deserialize, Serialize};
-use hash::Blake2bHash;
+use hash::{Blake2bHash, Hash};
 
 use crate::accounts_proof::AccountsProof;
-use crate::accounts_tree_node::AccountsTreeNode;
+use crate::accounts_tree_node::{AccountsTreeNode, AccountsTreeNodeChild, NO_CHILDREN};
+use crate::address_nibbles::AddressNibbles;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AccountsTreeChunk<A: AccountsTreeLeave> {
@@ -62,3 +63,33 @@ impl<A: AccountsTreeLeave> AccountsTreeChunk<A> {
         Some(self.tail().prefix().to_string())
     }
 }
+
+/// Computes the root of the subtree made up of the given terminal nodes, which must be sorted by
+/// prefix. Returns the prefix and hash of the root, i.e. what its parent branch node stores about
+/// it, or `None` if there are no nodes or two of them have the same prefix.
+///
+/// The proof of a chunk only authenticates its tail. Comparing the result with the child of a
+/// verified branch node authenticates all terminal nodes below that child.
+pub fn subtree_root<A: AccountsTreeLeave>(nodes: &[AccountsTreeNode<A>]) -> Option<(AddressNibbles, Blake2bHash)> {
+    let (first, last) = (nodes.first()?, nodes.last()?);
+    if nodes.len() == 1 {
+        return Some((first.prefix().clone(), first.hash()));
+    }
+
+    // The branch node is at the common prefix of all nodes below it.
+    let prefix = first.prefix().common_prefix(last.prefix());
+    let mut children = NO_CHILDREN;
+    let mut start = 0;
+    while start < nodes.len() {
+        let index = nodes[start].prefix().get(prefix.len())?;
+        let end = start + nodes[start..].iter()
+            .take_while(|node| node.prefix().get(prefix.len()) == Some(index))
+            .count();
+        let (child_prefix, hash) = subtree_root(&nodes[start..end])?;
+        children[index] = Some(AccountsTreeNodeChild { suffix: child_prefix.suffix(prefix.len() as u8), hash });
+        start = end;
+    }
+
+    let hash = AccountsTreeNode::<A>::new_branch(prefix.clone(), children).hash();
+    Some((prefix, hash))
+}
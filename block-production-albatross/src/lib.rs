@@ -31,21 +31,37 @@ use blockchain::reward_registry::SlashedSetSelector;
 pub struct BlockProducer {
     pub blockchain: Arc<Blockchain>,
     pub mempool: Option<Arc<Mempool<Blockchain>>>,
-    pub validator_key: KeyPair,
+    /// The key used to sign the produced blocks. Without it, only unsigned blocks can be
+    /// produced, which are then signed by the caller.
+    pub validator_key: Option<KeyPair>,
 }
 
 impl BlockProducer {
     pub fn new(blockchain: Arc<Blockchain>, mempool: Arc<Mempool<Blockchain>>, validator_key: KeyPair) -> Self {
-        BlockProducer { blockchain, mempool: Some(mempool), validator_key }
+        BlockProducer { blockchain, mempool: Some(mempool), validator_key: Some(validator_key) }
     }
 
     pub fn new_without_mempool(blockchain: Arc<Blockchain>, validator_key: KeyPair) -> Self {
-        BlockProducer { blockchain, mempool: None, validator_key }
+        BlockProducer { blockchain, mempool: None, validator_key: Some(validator_key) }
+    }
+
+    pub fn new_without_key(blockchain: Arc<Blockchain>, mempool: Arc<Mempool<Blockchain>>) -> Self {
+        BlockProducer { blockchain, mempool: Some(mempool), validator_key: None }
+    }
+
+    fn key(&self) -> &KeyPair {
+        self.validator_key.as_ref().expect("Block producer has no validator key")
     }
 
     /// Needs to be called with the Blockchain lock held.
     pub fn next_macro_block_proposal(&self, timestamp: u64, view_number: u32, view_change_proof: Option<ViewChangeProof>) -> (PbftProposal, MacroExtrinsics) {
-        let seed = self.blockchain.head().seed().sign_next(&self.validator_key.secret);
+        let seed = self.blockchain.head().seed().sign_next(&self.key().secret);
+        self.next_macro_block_proposal_with_seed(seed, timestamp, view_number, view_change_proof)
+    }
+
+    /// Like `next_macro_block_proposal`, but with the given seed instead of signing it.
+    /// Needs to be called with the Blockchain lock held.
+    pub fn next_macro_block_proposal_with_seed(&self, seed: VrfSeed, timestamp: u64, view_number: u32, view_change_proof: Option<ViewChangeProof>) -> (PbftProposal, MacroExtrinsics) {
         let mut txn = self.blockchain.write_transaction();
 
         let mut header = self.next_macro_header(&mut txn, timestamp, view_number, &seed);
@@ -62,10 +78,9 @@ impl BlockProducer {
 
     /// Needs to be called with the Blockchain lock held.
     pub fn next_micro_block(&self, fork_proofs: Vec<ForkProof>, timestamp: u64, view_number: u32, extra_data: Vec<u8>, view_change_proof: Option<ViewChangeProof>) -> MicroBlock {
-        let view_changes = ViewChanges::new(self.blockchain.block_number() + 1, self.blockchain.next_view_number(), view_number);
-        let extrinsics = self.next_micro_extrinsics(fork_proofs, extra_data, &view_changes);
-        let header = self.next_micro_header(timestamp, view_number, &extrinsics, &view_changes);
-        let signature = self.validator_key.sign(&header).compress();
+        let seed = self.blockchain.head().seed().sign_next(&self.key().secret);
+        let (header, extrinsics) = self.next_unsigned_micro_block(seed, fork_proofs, timestamp, view_number, extra_data);
+        let signature = self.key().sign(&header).compress();
 
         MicroBlock {
             header,
@@ -77,6 +92,16 @@ impl BlockProducer {
         }
     }
 
+    /// Produces the header and extrinsics of the next micro block with the given seed. The
+    /// header still needs to be signed to build the block.
+    /// Needs to be called with the Blockchain lock held.
+    pub fn next_unsigned_micro_block(&self, seed: VrfSeed, fork_proofs: Vec<ForkProof>, timestamp: u64, view_number: u32, extra_data: Vec<u8>) -> (MicroHeader, MicroExtrinsics) {
+        let view_changes = ViewChanges::new(self.blockchain.block_number() + 1, self.blockchain.next_view_number(), view_number);
+        let extrinsics = self.next_micro_extrinsics(fork_proofs, extra_data, &view_changes);
+        let header = self.next_micro_header(seed, timestamp, view_number, &extrinsics, &view_changes);
+        (header, extrinsics)
+    }
+
     pub fn next_macro_extrinsics(&self, txn: &mut WriteTransaction, seed: &VrfSeed) -> MacroExtrinsics {
         // Determine slashed set without txn, so that it is not garbage collected yet.
         let prev_epoch = policy::epoch_at(self.blockchain.height() + 1) - 1;
@@ -88,9 +113,7 @@ impl BlockProducer {
     }
 
     fn next_micro_extrinsics(&self, fork_proofs: Vec<ForkProof>, extra_data: Vec<u8>, view_changes: &Option<ViewChanges>) -> MicroExtrinsics {
-        let max_size = MicroBlock::MAX_SIZE
-            - MicroHeader::SIZE
-            - MicroExtrinsics::get_metadata_size(fork_proofs.len(), extra_data.len());
+        let max_size = MicroBlock::max_transactions_size(fork_proofs.len(), extra_data.len());
         let mut transactions = self.mempool.as_ref()
             .map(|mempool| mempool.get_transactions_for_block(max_size))
             .unwrap_or_else(Vec::new);
@@ -149,7 +172,7 @@ impl BlockProducer {
         }), self.blockchain.view_number())
             .expect("Failed to commit dummy block to reward registry");
 
-        let mut inherents = self.blockchain.finalize_last_epoch(&self.blockchain.state(), &header);
+        let mut inherents = self.blockchain.finalize_last_epoch(&self.blockchain.state(), &header, None);
 
         // Add slashes for view changes.
         let view_changes = ViewChanges::new(header.block_number, self.blockchain.view_number(), header.view_number);
@@ -173,7 +196,7 @@ impl BlockProducer {
         header
     }
 
-    fn next_micro_header(&self, timestamp: u64, view_number: u32, extrinsics: &MicroExtrinsics, view_changes: &Option<ViewChanges>) -> MicroHeader {
+    fn next_micro_header(&self, seed: VrfSeed, timestamp: u64, view_number: u32, extrinsics: &MicroExtrinsics, view_changes: &Option<ViewChanges>) -> MicroHeader {
         let block_number = self.blockchain.height() + 1;
         let timestamp = u64::max(timestamp, self.blockchain.head().timestamp() + 1);
 
@@ -186,8 +209,6 @@ impl BlockProducer {
             .hash_with(&extrinsics.transactions, &inherents, block_number)
             .expect("Failed to compute accounts hash during block production");
 
-        let seed = self.blockchain.head().seed().sign_next(&self.validator_key.secret);
-
         MicroHeader {
             version: Block::VERSION,
             block_number,
@@ -0,0 +1,210 @@
+use std::io;
+use std::io::{Read, Write};
+
+use failure::Fail;
+
+use beserial::{Deserialize, Serialize, SerializingError};
+use block::{Block, MacroBlock, MicroBlock};
+use database::ReadTransaction;
+use primitives::networks::NetworkId;
+use primitives::policy;
+use transaction::Transaction as BlockchainTransaction;
+
+use crate::blockchain::{Blockchain, PushError, PushResult};
+
+
+#[derive(Debug, Fail)]
+pub enum BlockFileError {
+    #[fail(display = "Block file is for network {}, but blockchain is on {}", _0, _1)]
+    WrongNetwork(NetworkId, NetworkId),
+    #[fail(display = "Block #{} is missing or has been pruned", _0)]
+    MissingBlock(u32),
+    #[fail(display = "Block file contains an invalid entry")]
+    InvalidEntry,
+    #[fail(display = "Block file contains an entry of {} bytes, which exceeds the maximum entry size", _0)]
+    EntryTooLarge(u32),
+    #[fail(display = "Block file is truncated")]
+    Truncated,
+    #[fail(display = "Failed to push block #{}: {}", _0, _1)]
+    Push(u32, #[cause] PushError),
+    #[fail(display = "I/O error: {}", _0)]
+    Io(#[cause] io::Error),
+    #[fail(display = "Serialization error: {}", _0)]
+    Serializing(#[cause] SerializingError),
+}
+
+impl From<io::Error> for BlockFileError {
+    fn from(e: io::Error) -> Self {
+        BlockFileError::Io(e)
+    }
+}
+
+impl From<SerializingError> for BlockFileError {
+    fn from(e: SerializingError) -> Self {
+        BlockFileError::Serializing(e)
+    }
+}
+
+/// Upper bound for the serialized size of a block file entry. An epoch entry contains at most the
+/// transactions of all micro blocks of the epoch.
+const MAX_ENTRY_SIZE: usize = 1 + MacroBlock::MAX_SIZE + 4 + policy::EPOCH_LENGTH as usize * MicroBlock::MAX_SIZE;
+
+/// An entry of a block file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum BlockFileEntry {
+    /// A block that is pushed as is.
+    Block(Block),
+    /// The macro block of a finished epoch and the transactions of that epoch. This is pushed as
+    /// an isolated macro block.
+    Epoch(MacroBlock, #[beserial(len_type(u32))] Vec<BlockchainTransaction>),
+}
+
+impl BlockFileEntry {
+    pub fn block_number(&self) -> u32 {
+        match self {
+            BlockFileEntry::Block(block) => block.block_number(),
+            BlockFileEntry::Epoch(macro_block, _) => macro_block.header.block_number,
+        }
+    }
+}
+
+/// Writes a block file: The network ID, followed by entries that are each prefixed with their
+/// length.
+pub struct BlockFileWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> BlockFileWriter<W> {
+    pub fn new(mut writer: W, network_id: NetworkId) -> Result<Self, BlockFileError> {
+        Serialize::serialize(&network_id, &mut writer)?;
+        Ok(BlockFileWriter { writer })
+    }
+
+    pub fn write(&mut self, entry: &BlockFileEntry) -> Result<(), BlockFileError> {
+        Serialize::serialize(&(entry.serialized_size() as u32), &mut self.writer)?;
+        Serialize::serialize(entry, &mut self.writer)?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W, BlockFileError> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads the entries of a block file.
+pub struct BlockFileReader<R: Read> {
+    reader: R,
+    network_id: NetworkId,
+}
+
+impl<R: Read> BlockFileReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, BlockFileError> {
+        let network_id = Deserialize::deserialize(&mut reader)?;
+        Ok(BlockFileReader { reader, network_id })
+    }
+
+    pub fn network_id(&self) -> NetworkId {
+        self.network_id
+    }
+
+    /// Reads the next entry. Returns `None` at the end of the file.
+    pub fn read(&mut self) -> Result<Option<BlockFileEntry>, BlockFileError> {
+        // The file may only end before the length prefix of an entry.
+        let mut len = [0u8; 4];
+        let mut num_read = 0;
+        while num_read < len.len() {
+            match self.reader.read(&mut len[num_read..]) {
+                Ok(0) if num_read == 0 => return Ok(None),
+                Ok(0) => return Err(BlockFileError::Truncated),
+                Ok(n) => num_read += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {},
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let len = u32::from_be_bytes(len);
+        if len as usize > MAX_ENTRY_SIZE {
+            return Err(BlockFileError::EntryTooLarge(len));
+        }
+        let mut buf = vec![0u8; len as usize];
+        match self.reader.read_exact(&mut buf) {
+            Ok(()) => {},
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Err(BlockFileError::Truncated),
+            Err(e) => return Err(e.into()),
+        }
+        let entry: BlockFileEntry = Deserialize::deserialize_from_vec(&buf)?;
+        if entry.serialized_size() != buf.len() {
+            return Err(BlockFileError::InvalidEntry);
+        }
+        Ok(Some(entry))
+    }
+}
+
+impl Blockchain {
+    /// Writes the main chain to a block file. Finished epochs are written as their macro block and
+    /// epoch transactions, the blocks of the current epoch individually. `progress` is called with
+    /// the block number of every entry written.
+    ///
+    /// Returns the block number of the last block written.
+    pub fn export_blocks<W: Write, F: FnMut(u32)>(&self, writer: &mut BlockFileWriter<W>, mut progress: F) -> Result<u32, BlockFileError> {
+        // Read everything from the same transaction to get a consistent view of the main chain.
+        let txn = ReadTransaction::new(&self.env);
+        let head_number = self.chain_store.get_head(Some(&txn))
+            .and_then(|head_hash| self.chain_store.get_block(&head_hash, false, Some(&txn)))
+            .map(|head| head.block_number())
+            .ok_or(BlockFileError::MissingBlock(0))?;
+
+        let macro_head_number = policy::last_macro_block(head_number);
+        for epoch in 1..=policy::epoch_at(macro_head_number) {
+            let block_number = policy::macro_block_of(epoch);
+            let macro_block = match self.chain_store.get_block_at(block_number, true, Some(&txn)) {
+                Some(Block::Macro(macro_block)) => macro_block,
+                _ => return Err(BlockFileError::MissingBlock(block_number)),
+            };
+            let transactions = self.get_epoch_transactions(epoch, Some(&txn))
+                .ok_or_else(|| BlockFileError::MissingBlock(policy::first_block_of(epoch)))?
+                .collect();
+
+            writer.write(&BlockFileEntry::Epoch(macro_block, transactions))?;
+            progress(block_number);
+        }
+
+        for block_number in macro_head_number + 1..=head_number {
+            let block = self.chain_store.get_block_at(block_number, true, Some(&txn))
+                .ok_or(BlockFileError::MissingBlock(block_number))?;
+
+            writer.write(&BlockFileEntry::Block(block))?;
+            progress(block_number);
+        }
+
+        Ok(head_number)
+    }
+
+    /// Pushes the entries of a block file onto the chain, stopping at the first block that can't
+    /// be pushed. Blocks that are already known are skipped. `progress` is called with the block
+    /// number of every entry read.
+    ///
+    /// Returns the number of entries that weren't known yet.
+    pub fn import_blocks<R: Read, F: FnMut(u32)>(&self, reader: &mut BlockFileReader<R>, mut progress: F) -> Result<u32, BlockFileError> {
+        if reader.network_id() != self.network_id {
+            return Err(BlockFileError::WrongNetwork(reader.network_id(), self.network_id));
+        }
+
+        let mut count = 0;
+        while let Some(entry) = reader.read()? {
+            let block_number = entry.block_number();
+            let result = match entry {
+                BlockFileEntry::Block(block) => self.push(block),
+                BlockFileEntry::Epoch(macro_block, transactions) => self.push_isolated_macro_block(Block::Macro(macro_block), &transactions),
+            };
+            if result.map_err(|e| BlockFileError::Push(block_number, e))? != PushResult::Known {
+                count += 1;
+            }
+            progress(block_number);
+        }
+
+        Ok(count)
+    }
+}
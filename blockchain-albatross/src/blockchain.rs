@@ -31,6 +31,7 @@ use transaction::{Transaction as BlockchainTransaction, TransactionReceipt, Tran
 use tree_primitives::accounts_proof::AccountsProof;
 use tree_primitives::accounts_tree_chunk::AccountsTreeChunk;
 use utils::merkle;
+use utils::merkle::Blake2bMerkleProof;
 use utils::observer::{Listener, ListenerHandle, Notifier};
 use vrf::{VrfSeed, VrfUseCase, AliasMethod};
 
@@ -38,6 +39,8 @@ use crate::chain_info::ChainInfo;
 use crate::chain_store::ChainStore;
 use crate::reward_registry::{EpochStateError, SlashRegistry, SlashedSetSelector};
 use crate::transaction_cache::TransactionCache;
+#[cfg(feature = "transaction-store")]
+use crate::transaction_store::TransactionStore;
 
 
 pub type PushResult = blockchain_base::PushResult;
@@ -101,10 +104,13 @@ pub struct Blockchain {
     pub notifier: RwLock<Notifier<'static, BlockchainEvent>>,
     pub chain_store: Arc<ChainStore>,
     pub(crate) state: RwLock<BlockchainState>,
-    push_lock: Mutex<()>,
+    pub(crate) push_lock: Mutex<()>,
 
     #[cfg(feature = "metrics")]
     metrics: BlockchainMetrics,
+
+    #[cfg(feature = "transaction-store")]
+    pub(crate) transaction_store: TransactionStore,
 }
 
 pub struct BlockchainState {
@@ -115,8 +121,8 @@ pub struct BlockchainState {
     pub(crate) main_chain: ChainInfo,
     head_hash: Blake2bHash,
 
-    macro_head: MacroBlock,
-    macro_head_hash: Blake2bHash,
+    pub(crate) macro_head: MacroBlock,
+    pub(crate) macro_head_hash: Blake2bHash,
 
     // TODO: Instead of Option, we could use a Cell here and use replace on it. That way we know
     // at compile-time that there is always a valid value in there.
@@ -185,6 +191,30 @@ impl Blockchain {
             return Err(BlockchainError::InvalidGenesisBlock)
         }
 
+        let state = Self::load_state(&env, &chain_store, head_hash)?;
+
+        #[cfg(feature = "transaction-store")]
+        let transaction_store = TransactionStore::new(env.clone());
+
+        Ok(Blockchain {
+            env,
+            network_id,
+            //network_time,
+            notifier: RwLock::new(Notifier::new()),
+            chain_store,
+            state: RwLock::new(state),
+            push_lock: Mutex::new(()),
+
+            #[cfg(feature = "metrics")]
+            metrics: BlockchainMetrics::default(),
+
+            #[cfg(feature = "transaction-store")]
+            transaction_store,
+        })
+    }
+
+    /// Loads the state of the main chain ending at `head_hash` from the store.
+    pub(crate) fn load_state(env: &Environment, chain_store: &Arc<ChainStore>, head_hash: Blake2bHash) -> Result<BlockchainState, BlockchainError> {
         // Load main chain from store.
         let main_chain = chain_store
             .get_chain_info(&head_hash, true, None)
@@ -216,7 +246,7 @@ impl Blockchain {
         assert_eq!(transaction_cache.missing_blocks(), policy::TRANSACTION_VALIDITY_WINDOW_ALBATROSS.saturating_sub(main_chain.head.block_number() + 1));
 
         // Initialize SlashRegistry.
-        let slash_registry = SlashRegistry::new(env.clone(), Arc::clone(&chain_store));
+        let slash_registry = SlashRegistry::new(env.clone(), Arc::clone(chain_store));
 
         // Current slots and validators
         let current_slots = Self::slots_from_block(&macro_head);
@@ -229,27 +259,16 @@ impl Blockchain {
             _ => return Err(BlockchainError::InconsistentState),
         };
 
-        Ok(Blockchain {
-            env,
-            network_id,
-            //network_time,
-            notifier: RwLock::new(Notifier::new()),
-            chain_store,
-            state: RwLock::new(BlockchainState {
-                accounts,
-                transaction_cache,
-                reward_registry: slash_registry,
-                main_chain,
-                head_hash,
-                macro_head,
-                macro_head_hash,
-                current_slots: Some(current_slots),
-                previous_slots: Some(last_slots),
-            }),
-            push_lock: Mutex::new(()),
-
-            #[cfg(feature = "metrics")]
-            metrics: BlockchainMetrics::default()
+        Ok(BlockchainState {
+            accounts,
+            transaction_cache,
+            reward_registry: slash_registry,
+            main_chain,
+            head_hash,
+            macro_head,
+            macro_head_hash,
+            current_slots: Some(current_slots),
+            previous_slots: Some(last_slots),
         })
     }
 
@@ -280,6 +299,9 @@ impl Blockchain {
         // Initialize SlashRegistry.
         let slash_registry = SlashRegistry::new(env.clone(), Arc::clone(&chain_store));
 
+        #[cfg(feature = "transaction-store")]
+        let transaction_store = TransactionStore::new(env.clone());
+
         // current slots and validators
         let current_slots = Self::slots_from_block(&genesis_macro_block);
         let last_slots = Slots::default();
@@ -304,7 +326,10 @@ impl Blockchain {
             push_lock: Mutex::new(()),
 
             #[cfg(feature = "metrics")]
-            metrics: BlockchainMetrics::default()
+            metrics: BlockchainMetrics::default(),
+
+            #[cfg(feature = "transaction-store")]
+            transaction_store,
         })
     }
 
@@ -727,6 +752,9 @@ impl Blockchain {
         self.chain_store.put_chain_info(&mut txn, &chain_info.head.parent_hash(), &prev_info, false);
         self.chain_store.set_head(&mut txn, &block_hash);
 
+        #[cfg(feature = "transaction-store")]
+        self.transaction_store.put(&chain_info.head, &mut txn);
+
         // Acquire write lock & commit changes.
         let mut state = self.state.write();
         state.transaction_cache.push_block(&chain_info.head);
@@ -893,6 +921,9 @@ impl Blockchain {
             reverted_block.1.on_main_chain = false;
             reverted_block.1.main_chain_successor = None;
             self.chain_store.put_chain_info(&mut write_txn, &reverted_block.0, &reverted_block.1, false);
+
+            #[cfg(feature = "transaction-store")]
+            self.transaction_store.remove(&reverted_block.1.head, &mut write_txn);
         }
 
         // Update the mainChainSuccessor of the common ancestor block.
@@ -913,6 +944,9 @@ impl Blockchain {
 
             // Include the body of the new block (at position 0).
             self.chain_store.put_chain_info(&mut write_txn, &fork_block.0, &fork_block.1, i == 0);
+
+            #[cfg(feature = "transaction-store")]
+            self.transaction_store.put(&fork_block.1.head, &mut write_txn);
         }
 
         // Commit transaction & update head.
@@ -945,7 +979,7 @@ impl Blockchain {
         match block {
             Block::Macro(ref macro_block) => {
                 // We can rely on `state` here, since we cannot revert macro blocks.
-                let mut inherents = self.finalize_last_epoch(state, &macro_block.header);
+                let mut inherents = self.finalize_last_epoch(state, &macro_block.header, Some(txn));
 
                 // Add slashes for view changes.
                 let view_changes = ViewChanges::new(macro_block.header.block_number, first_view_number, macro_block.header.view_number);
@@ -1114,11 +1148,16 @@ impl Blockchain {
         // We cannot check the accounts hash yet.
         // Apply transactions and inherents to AccountsTree.
         let slots = state.previous_slots.as_ref().expect("Slots for last epoch are missing");
-        let mut inherents = self.inherents_from_slashed_set(&slashed_set, slots);
-        inherents.append(&mut self.finalize_last_epoch(&state, &macro_block.header));
+        let inherents = self.inherents_from_slashed_set(&slashed_set, slots);
 
         // Commit epoch to AccountsTree.
-        let receipts = state.accounts.commit(&mut txn, transactions, &inherents, macro_block.header.block_number);
+        let mut receipts = state.accounts.commit(&mut txn, transactions, &inherents, macro_block.header.block_number);
+
+        // Rewards depend on the stake at the end of the epoch, so they are committed afterwards.
+        if receipts.is_ok() {
+            let inherents = self.finalize_last_epoch(&state, &macro_block.header, Some(&txn));
+            receipts = state.accounts.commit(&mut txn, &[], &inherents, macro_block.header.block_number);
+        }
         if let Err(e) = receipts {
             warn!("Rejecting block - commit failed: {:?}", e);
             txn.abort();
@@ -1154,6 +1193,11 @@ impl Blockchain {
         self.chain_store.put_chain_info(&mut txn, &chain_info.head.parent_hash(), &prev_info, false);
         self.chain_store.set_head(&mut txn, &block_hash);
 
+        // We don't know which micro blocks the epoch's transactions were included in, so they are
+        // stored as part of the macro block that finalized them.
+        #[cfg(feature = "transaction-store")]
+        self.transaction_store.put_finalized(&chain_info.head, transactions, &mut txn);
+
         // Acquire write lock & commit changes.
         let mut state = self.state.write();
         // FIXME: Macro block sync does not preserve transaction replay protection right now.
@@ -1219,9 +1263,10 @@ impl Blockchain {
                 None
             })?;
 
+        // The body of the first block might have been pruned.
         let first_hash = first_block.hash();
         let iter_first = first_block.unwrap_micro()
-            .extrinsics.unwrap()
+            .extrinsics?
             .transactions.into_iter();
 
         // Excludes current block and macro block.
@@ -1429,7 +1474,7 @@ impl Blockchain {
         RwLockReadGuard::map(guard, |s| s.last_validators().unwrap())
     }
 
-    pub fn finalize_last_epoch(&self, state: &BlockchainState, macro_header: &MacroHeader) -> Vec<Inherent> {
+    pub fn finalize_last_epoch(&self, state: &BlockchainState, macro_header: &MacroHeader, txn_option: Option<&Transaction>) -> Vec<Inherent> {
         // It might be that we don't have any micro blocks, thus we need to look at the next macro block.
         let epoch = policy::epoch_at(macro_header.block_number) - 1;
 
@@ -1464,10 +1509,22 @@ impl Blockchain {
         // Peekable iterator to collect slashed slots for stake
         let mut slashed_set_iter = slashed_set.iter().peekable();
 
+        // The stakers' part of the rewards is paid to the staking contract.
+        let validator_registry = NetworkInfo::from_network_id(self.network_id)
+            .validator_registry_address()
+            .expect("No ValidatorRegistry");
+        let staking_account = state.accounts.get(validator_registry, txn_option);
+        let staking_contract = match staking_account {
+            Account::Staking(ref staking_contract) => staking_contract,
+            _ => panic!("Account at validator registry address is not the staking contract!"),
+        };
+
         // All accepted inherents.
         let mut inherents = Vec::new();
         // Remember the number of eligible slots a stake had (that was able to accept the inherent)
         let mut num_eligible_slots_for_accepted_inherent = Vec::new();
+        // All accepted inherents for stakers.
+        let mut staker_inherents = Vec::new();
 
         // Compute inherents
         for validator_slot in validator_slots.iter() {
@@ -1497,22 +1554,46 @@ impl Blockchain {
             let reward = slot_reward.checked_mul(num_eligible_slots as u64)
                 .expect("Overflow in reward");
 
+            // The stakers of the validator get a share of the reward proportional to their stake.
+            let validator_key = validator_slot.public_key().compressed();
+            let stakers_reward = staking_contract.get_validator(validator_key)
+                .map(|validator| validator.stakers_reward(reward))
+                .unwrap_or(Coin::ZERO);
+            let validator_reward = reward - stakers_reward;
+
             let inherent = Inherent {
                 ty: InherentType::Reward,
                 target: validator_slot.reward_address().clone(),
-                value: reward,
+                value: validator_reward,
                 data: vec![],
             };
 
             // Test whether account will accept inherent.
-            let account = state.accounts.get(&inherent.target, None);
+            let account = state.accounts.get(&inherent.target, txn_option);
             if account.check_inherent(&inherent, macro_header.block_number).is_err() {
                 debug!("{} can't accept epoch reward {}", inherent.target, inherent.value);
-                remainder += reward;
+                remainder += validator_reward;
             } else {
                 num_eligible_slots_for_accepted_inherent.push(num_eligible_slots);
                 inherents.push(inherent);
             }
+
+            if !stakers_reward.is_zero() {
+                let inherent = Inherent {
+                    ty: InherentType::Reward,
+                    target: validator_registry.clone(),
+                    value: stakers_reward,
+                    data: validator_key.serialize_to_vec(),
+                };
+
+                // Test whether the staking contract will accept inherent.
+                if staking_account.check_inherent(&inherent, macro_header.block_number).is_err() {
+                    debug!("Stakers of {} can't accept epoch reward {}", validator_slot.reward_address(), inherent.value);
+                    remainder += stakers_reward;
+                } else {
+                    staker_inherents.push(inherent);
+                }
+            }
         }
 
         // Check that number of accepted inherents is equal to length of the map that gives us the
@@ -1531,10 +1612,10 @@ impl Blockchain {
             remainder -= Coin::from_u64_unchecked(1);
         }
 
+        // Stakers are rewarded after the validators.
+        inherents.append(&mut staker_inherents);
+
         // Push finalize epoch inherent for automatically retiring inactive/malicious validators.
-        let validator_registry = NetworkInfo::from_network_id(self.network_id)
-            .validator_registry_address()
-            .expect("No ValidatorRegistry");
         inherents.push(Inherent {
             ty: InherentType::FinalizeEpoch,
             target: validator_registry.clone(),
@@ -1603,9 +1684,126 @@ impl Blockchain {
         self.chain_store.get_macro_blocks(start_block_hash, count, include_body, direction, None)
     }
 
+    /// Removes the bodies of micro blocks that belong to epochs finalized more than `history_epochs`
+    /// epochs ago. Macro blocks and the state are always kept, as are the blocks within the
+    /// transaction validity window, which are needed to rebuild the transaction cache.
+    pub fn prune_history(&self, history_epochs: u32) {
+        let prune_height = Self::history_prune_height(self.block_number(), history_epochs);
+
+        let mut txn = WriteTransaction::new(&self.env);
+        self.chain_store.prune_micro_bodies(&mut txn, prune_height);
+        txn.commit();
+    }
+
+    /// Returns the block height below which micro block bodies can be pruned at the given block
+    /// number, if the bodies of the last `history_epochs` finalized epochs are kept.
+    pub fn history_prune_height(block_number: u32, history_epochs: u32) -> u32 {
+        cmp::min(
+            policy::last_macro_block(block_number)
+                .saturating_sub(history_epochs.saturating_mul(policy::EPOCH_LENGTH)) + 1,
+            block_number.saturating_sub(policy::TRANSACTION_VALIDITY_WINDOW_ALBATROSS),
+        )
+    }
+
     pub fn write_transaction(&self) -> WriteTransaction {
         WriteTransaction::new(&self.env)
     }
+
+    /// Returns a proof for the transactions of the given block that involve any of the given addresses.
+    /// For macro blocks, the proof covers all transactions of the epoch the block finalizes and can be
+    /// verified against the header's `transactions_root`. For micro blocks, it covers the transactions
+    /// of the block itself.
+    pub fn get_transactions_proof(&self, block_hash: &Blake2bHash, addresses: &HashSet<Address>) -> Option<TransactionsProof> {
+        let block = self.get_block(block_hash, /*include_forks*/ false, /*include_body*/ true)?;
+        let transactions: Vec<BlockchainTransaction> = match block {
+            // The genesis block doesn't finalize any transactions.
+            Block::Macro(ref macro_block) if macro_block.header.block_number == 0 => Vec::new(),
+            Block::Macro(ref macro_block) => {
+                self.get_epoch_transactions(policy::epoch_at(macro_block.header.block_number), None)?
+                    .collect()
+            },
+            Block::Micro(micro_block) => micro_block.extrinsics?.transactions,
+        };
+
+        let mut matches = Vec::new();
+        for transaction in transactions.iter() {
+            if addresses.contains(&transaction.sender) || addresses.contains(&transaction.recipient) {
+                matches.push(transaction.clone());
+            }
+        }
+
+        let hashes: Vec<Blake2bHash> = transactions.iter().map(|tx| tx.hash()).collect();
+        let matching_hashes: Vec<Blake2bHash> = matches.iter().map(|tx| tx.hash()).collect();
+        let proof = Blake2bMerkleProof::new(&hashes, &matching_hashes);
+        Some(TransactionsProof {
+            transactions: matches,
+            proof,
+        })
+    }
+
+    /// Returns an accounts proof for the state after the given block.
+    /// The accounts tree only stores the head state, so proofs for older blocks are created by
+    /// reverting the micro blocks on top of it in a write transaction that is aborted afterwards.
+    /// Since receipts are cleared at each macro block, only blocks of the current epoch
+    /// (including the macro head) are supported.
+    pub fn get_accounts_proof(&self, block_hash: &Blake2bHash, addresses: &[Address]) -> Option<AccountsProof<Account>> {
+        self.with_accounts_at(block_hash, |accounts, txn| accounts.get_accounts_proof(txn, addresses))
+    }
+
+    /// Returns the accounts of the given addresses as of the main chain block with the given
+    /// number. Only blocks from the current macro head up to the head are supported, since older
+    /// states can't be reconstructed from the stored receipts.
+    pub fn get_accounts_at(&self, block_number: u32, addresses: &[Address]) -> Option<Vec<Account>> {
+        let block_hash = self.chain_store.get_block_at(block_number, false, None)?.hash();
+        self.with_accounts_at(&block_hash, |accounts, txn| {
+            addresses.iter()
+                .map(|address| accounts.get(address, Some(txn)))
+                .collect()
+        })
+    }
+
+    /// Runs `f` on the accounts tree reverted to the given main chain block of the current epoch.
+    /// The reverted state is never committed.
+    fn with_accounts_at<R, F: FnOnce(&Accounts, &Transaction) -> R>(&self, block_hash: &Blake2bHash, f: F) -> Option<R> {
+        // Acquire locks in the same order as `push_block` does.
+        let _push_lock = self.push_lock.lock();
+        let mut txn = WriteTransaction::new(&self.env);
+        let state = self.state.read();
+
+        let chain_info = self.chain_store.get_chain_info(block_hash, false, Some(&txn))?;
+        if !chain_info.on_main_chain || chain_info.head.block_number() < state.macro_head.header.block_number {
+            return None;
+        }
+
+        self.revert_accounts_to(&state, &mut txn, block_hash)?;
+        assert_eq!(chain_info.head.state_root(), &state.accounts.hash(Some(&txn)),
+                   "Failed to revert to requested block - inconsistent state");
+
+        let result = f(&state.accounts, &txn);
+        txn.abort();
+        Some(result)
+    }
+
+    /// Reverts the accounts tree block by block from the head to the given block of the current
+    /// epoch. The caller must hold the push lock and abort `txn` afterwards.
+    pub(crate) fn revert_accounts_to(&self, state: &BlockchainState, txn: &mut WriteTransaction, block_hash: &Blake2bHash) -> Option<()> {
+        let mut hash = state.head_hash.clone();
+        while &hash != block_hash {
+            let micro_block = match self.chain_store.get_block(&hash, true, Some(&txn)) {
+                Some(Block::Micro(micro_block)) => micro_block,
+                Some(Block::Macro(_)) => unreachable!("Trying to revert macro block"),
+                None => panic!("Corrupted store: Failed to find main chain block while reverting"),
+            };
+            let prev_info = self.chain_store
+                .get_chain_info(&micro_block.header.parent_hash, false, Some(&txn))
+                .expect("Corrupted store: Failed to find main chain predecessor while reverting");
+
+            self.revert_accounts(&state.accounts, txn, &micro_block, prev_info.head.next_view_number()).ok()?;
+
+            hash = micro_block.header.parent_hash;
+        }
+        Some(())
+    }
 }
 
 impl AbstractBlockchain for Blockchain {
@@ -1707,19 +1905,19 @@ impl AbstractBlockchain for Blockchain {
         self.contains(hash, include_forks)
     }
 
-    #[allow(unused_variables)]
     fn get_accounts_proof(&self, block_hash: &Blake2bHash, addresses: &[Address]) -> Option<AccountsProof<Account>> {
-        unimplemented!()
+        self.get_accounts_proof(block_hash, addresses)
     }
 
-    #[allow(unused_variables)]
     fn get_transactions_proof(&self, block_hash: &Blake2bHash, addresses: &HashSet<Address>) -> Option<TransactionsProof> {
-        unimplemented!()
+        self.get_transactions_proof(block_hash, addresses)
     }
 
-    #[allow(unused_variables)]
     fn get_transaction_receipts_by_address(&self, address: &Address, sender_limit: usize, recipient_limit: usize) -> Vec<TransactionReceipt> {
-        unimplemented!()
+        #[cfg(feature = "transaction-store")]
+        return self.get_transaction_receipts_by_address(address, sender_limit, recipient_limit);
+        #[cfg(not(feature = "transaction-store"))]
+        Vec::new()
     }
 
     fn register_listener<T: Listener<BlockchainEvent> + 'static>(&self, listener: T) -> ListenerHandle {
@@ -1730,8 +1928,8 @@ impl AbstractBlockchain for Blockchain {
         self.push_lock.lock()
     }
 
-    fn get_account(&self, address: &Address) -> Account {
-        self.state.read().accounts.get(address, None)
+    fn get_account(&self, address: &Address) -> Option<Account> {
+        Some(self.state.read().accounts.get(address, None))
     }
 
     fn contains_tx_in_validity_window(&self, tx_hash: &Blake2bHash) -> bool {
@@ -25,6 +25,7 @@ impl ChainStore {
     const RECEIPT_DB_NAME: &'static str = "Receipts";
 
     const HEAD_KEY: &'static str = "head";
+    const PRUNED_KEY: &'static str = "pruned";
 
     pub fn new(env: Environment) -> Self {
         let chain_db = env.open_database(Self::CHAIN_DB_NAME.to_string());
@@ -63,16 +64,26 @@ impl ChainStore {
         };
 
         if include_body {
-            if let Some(block) = txn.get(&self.block_db, hash) {
-                chain_info.head = block;
-            } else {
-                warn!("Block body requested but not present");
-            }
+            self.load_body(txn, hash, &mut chain_info);
         }
 
         Some(chain_info)
     }
 
+    /// Replaces the head of the given chain info with the full block. If the body has been pruned,
+    /// the chain info is left with the header only and `false` is returned.
+    fn load_body(&self, txn: &Transaction, hash: &Blake2bHash, chain_info: &mut ChainInfo) -> bool {
+        if let Some(block) = txn.get(&self.block_db, hash) {
+            chain_info.head = block;
+            true
+        } else {
+            if chain_info.head.block_number() >= self.get_pruned_height(Some(txn)) {
+                warn!("Block body requested but not present");
+            }
+            false
+        }
+    }
+
     pub fn put_chain_info(&self, txn: &mut WriteTransaction, hash: &Blake2bHash, chain_info: &ChainInfo, include_body: bool) {
         // Store chain data. Block body will not be persisted.
         txn.put_reserve(&self.chain_db, hash, chain_info);
@@ -128,16 +139,34 @@ impl ChainStore {
         };
 
         if include_body {
-            if let Some(block) = txn.get(&self.block_db, &block_hash) {
-                chain_info.head = block;
-            } else {
-                warn!("Block body requested but not present");
-            }
+            self.load_body(txn, &block_hash, &mut chain_info);
         }
 
         Some(chain_info)
     }
 
+    /// Returns the hashes of all blocks at the given height, including forks, as listed in the
+    /// height index.
+    pub fn get_hashes_at(&self, block_height: u32, txn_option: Option<&Transaction>) -> Vec<Blake2bHash> {
+        let read_txn: ReadTransaction;
+        let txn = match txn_option {
+            Some(txn) => txn,
+            None => {
+                read_txn = ReadTransaction::new(&self.env);
+                &read_txn
+            }
+        };
+
+        let mut hashes = Vec::new();
+        let mut cursor = txn.cursor(&self.height_idx);
+        let mut hash_opt = cursor.seek_key::<u32, Blake2bHash>(&block_height);
+        while let Some(hash) = hash_opt {
+            hashes.push(hash);
+            hash_opt = cursor.next_duplicate::<u32, Blake2bHash>().map(|(_, hash)| hash);
+        }
+        hashes
+    }
+
     pub fn get_block(&self, hash: &Blake2bHash, include_body: bool, txn_option: Option<&Transaction>) -> Option<Block> {
         let read_txn: ReadTransaction;
         let txn = match txn_option {
@@ -207,13 +236,17 @@ impl ChainStore {
         };
 
         while (blocks.len() as u32) < count {
-            if let Some(ref successor) = chain_info.main_chain_successor {
-                let chain_info_opt = self.get_chain_info(successor, include_body, Some(&txn));
+            if let Some(successor) = chain_info.main_chain_successor.clone() {
+                let chain_info_opt = self.get_chain_info(&successor, false, Some(&txn));
                 if chain_info_opt.is_none() {
                     break;
                 }
 
                 chain_info = chain_info_opt.unwrap();
+                // Stop at blocks whose body has been pruned.
+                if include_body && !self.load_body(txn, &successor, &mut chain_info) {
+                    break;
+                }
                 blocks.push(chain_info.head);
             } else {
                 break;
@@ -302,6 +335,41 @@ impl ChainStore {
         }
     }
 
+    /// Returns the block height below which all micro block bodies have been pruned.
+    pub fn get_pruned_height(&self, txn_option: Option<&Transaction>) -> u32 {
+        match txn_option {
+            Some(txn) => txn.get(&self.chain_db, ChainStore::PRUNED_KEY),
+            None => ReadTransaction::new(&self.env).get(&self.chain_db, ChainStore::PRUNED_KEY)
+        }.unwrap_or(0)
+    }
+
+    pub fn set_pruned_height(&self, txn: &mut WriteTransaction, block_height: u32) {
+        txn.put(&self.chain_db, ChainStore::PRUNED_KEY, &block_height);
+    }
+
+    /// Removes the bodies of all micro blocks (including forks) below the given block height.
+    /// Macro blocks are kept as a whole, micro blocks keep their chain info.
+    pub fn prune_micro_bodies(&self, txn: &mut WriteTransaction, block_height: u32) {
+        let pruned_height = self.get_pruned_height(Some(&txn));
+        if block_height <= pruned_height {
+            return;
+        }
+
+        debug!("Pruning micro block bodies #{} to #{}", pruned_height, block_height - 1);
+        for height in pruned_height..block_height {
+            if policy::is_macro_block_at(height) {
+                continue;
+            }
+
+            let hashes = self.get_hashes_at(height, Some(&txn));
+            for hash in hashes.iter() {
+                txn.remove(&self.block_db, hash);
+            }
+        }
+
+        self.set_pruned_height(txn, block_height);
+    }
+
     pub fn put_receipts(&self, txn: &mut WriteTransaction, block_height: u32, receipts: &Receipts) {
         txn.put_reserve(&self.receipt_db, &block_height, receipts);
     }
@@ -0,0 +1,414 @@
+use std::cmp;
+use std::sync::Arc;
+
+use failure::Fail;
+use parking_lot::RwLockUpgradableReadGuard;
+
+use accounts::Accounts;
+use block::{Block, BlockType};
+use blockchain_base::BlockchainError;
+use database::{Environment, ReadTransaction, Transaction, WriteTransaction};
+use hash::Blake2bHash;
+use network_primitives::networks::NetworkInfo;
+use primitives::networks::NetworkId;
+use primitives::policy;
+
+use crate::blockchain::Blockchain;
+use crate::chain_info::ChainInfo;
+use crate::chain_store::ChainStore;
+use crate::reward_registry::SlashRegistry;
+use crate::transaction_cache::TransactionCache;
+#[cfg(feature = "transaction-store")]
+use crate::transaction_store::TransactionStore;
+
+
+/// The first inconsistency found in a database.
+#[derive(Clone, Debug, Fail, PartialEq, Eq)]
+pub enum Inconsistency {
+    #[fail(display = "Head block is missing")]
+    MissingHead,
+    #[fail(display = "Genesis block is missing or belongs to another network")]
+    InvalidGenesisBlock,
+    #[fail(display = "Height index references a missing block at #{}", _0)]
+    DanglingIndexEntry(u32),
+    #[fail(display = "Main chain block #{} is missing", _0)]
+    MissingBlock(u32),
+    #[fail(display = "More than one main chain block at #{}", _0)]
+    DuplicateMainChainBlock(u32),
+    #[fail(display = "Block indexed at #{} has a different block number", _0)]
+    WrongHeight(u32),
+    #[fail(display = "Block #{} doesn't link to its predecessor", _0)]
+    BrokenParentLink(u32),
+    #[fail(display = "Block #{} has the wrong main chain successor", _0)]
+    BrokenSuccessorLink(u32),
+    #[fail(display = "Head block #{} is not at the end of the main chain", _0)]
+    WrongHead(u32),
+    #[fail(display = "Accounts tree is corrupted")]
+    CorruptedAccountsTree,
+    #[fail(display = "Accounts tree doesn't match the state root of head block #{}", _0)]
+    AccountsMismatch(u32),
+    #[fail(display = "Slash registry contains a descriptor for unknown block #{}", _0)]
+    UnexpectedSlashDescriptor(u32),
+    #[fail(display = "Slash registry is missing the descriptor of block #{}", _0)]
+    MissingSlashDescriptor(u32),
+    #[fail(display = "Body of block #{} in the transaction validity window is missing", _0)]
+    MissingBody(u32),
+    #[fail(display = "Block #{} contains a transaction that is already in the validity window", _0)]
+    DuplicateTransaction(u32),
+}
+
+impl Inconsistency {
+    /// Returns the number of the last block before this inconsistency, if the database can be
+    /// rolled back to it.
+    pub fn last_good_block(&self) -> Option<u32> {
+        match self {
+            Inconsistency::MissingHead
+            | Inconsistency::InvalidGenesisBlock
+            | Inconsistency::CorruptedAccountsTree
+            | Inconsistency::UnexpectedSlashDescriptor(_)
+            | Inconsistency::MissingBody(_) => None,
+            Inconsistency::BrokenSuccessorLink(block_number)
+            | Inconsistency::AccountsMismatch(block_number) => Some(*block_number),
+            Inconsistency::DanglingIndexEntry(block_number)
+            | Inconsistency::MissingBlock(block_number)
+            | Inconsistency::DuplicateMainChainBlock(block_number)
+            | Inconsistency::WrongHeight(block_number)
+            | Inconsistency::BrokenParentLink(block_number)
+            | Inconsistency::WrongHead(block_number)
+            | Inconsistency::MissingSlashDescriptor(block_number)
+            | Inconsistency::DuplicateTransaction(block_number) => block_number.checked_sub(1),
+        }
+    }
+
+    /// Whether the main chain itself is broken, rather than the state derived from it.
+    fn is_chain_inconsistency(&self) -> bool {
+        match self {
+            Inconsistency::MissingHead
+            | Inconsistency::InvalidGenesisBlock
+            | Inconsistency::DanglingIndexEntry(_)
+            | Inconsistency::MissingBlock(_)
+            | Inconsistency::DuplicateMainChainBlock(_)
+            | Inconsistency::WrongHeight(_)
+            | Inconsistency::BrokenParentLink(_)
+            | Inconsistency::BrokenSuccessorLink(_)
+            | Inconsistency::WrongHead(_) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum RepairError {
+    #[fail(display = "Can't roll back: {}. Import a snapshot or resync from scratch.", _0)]
+    Unrepairable(#[cause] Inconsistency),
+    #[fail(display = "Database is still inconsistent after rolling back: {}", _0)]
+    StillInconsistent(#[cause] Inconsistency),
+    #[fail(display = "Failed to load the blockchain: {}", _0)]
+    Blockchain(#[cause] BlockchainError),
+}
+
+impl From<BlockchainError> for RepairError {
+    fn from(e: BlockchainError) -> Self {
+        RepairError::Blockchain(e)
+    }
+}
+
+/// Checks the consistency of an Albatross database without loading the blockchain, which would
+/// fail on most inconsistencies.
+pub struct IntegrityChecker {
+    env: Environment,
+    network_id: NetworkId,
+    chain_store: Arc<ChainStore>,
+}
+
+impl IntegrityChecker {
+    pub fn new(env: Environment, network_id: NetworkId) -> Self {
+        let chain_store = Arc::new(ChainStore::new(env.clone()));
+        IntegrityChecker { env, network_id, chain_store }
+    }
+
+    /// Runs all checks and returns the first inconsistency found.
+    pub fn check(&self) -> Result<(), Inconsistency> {
+        let txn = ReadTransaction::new(&self.env);
+
+        info!("Checking main chain");
+        let head = self.check_chain(&txn)?;
+
+        info!("Checking accounts tree");
+        self.check_accounts(&txn, &head)?;
+
+        info!("Checking slash registry");
+        self.check_slash_registry(&txn, &head)?;
+
+        info!("Checking transaction cache");
+        self.check_transaction_cache(&txn, &head)?;
+
+        Ok(())
+    }
+
+    /// Rolls the database back to the last good macro block before the first inconsistency.
+    /// This is only possible if the accounts tree matches a block of that macro block's epoch,
+    /// since the accounts can only be reverted through the receipts of the current epoch.
+    ///
+    /// Returns the number of the macro block that is the new head, or `None` if the database
+    /// was consistent.
+    pub fn repair(&self) -> Result<Option<u32>, RepairError> {
+        let inconsistency = match self.check() {
+            Ok(()) => return Ok(None),
+            Err(inconsistency) => inconsistency,
+        };
+        warn!("Found inconsistency: {}", inconsistency);
+        let last_good = inconsistency.last_good_block()
+            .ok_or_else(|| RepairError::Unrepairable(inconsistency.clone()))?;
+        let macro_block_number = policy::last_macro_block(last_good);
+
+        // Find the last block whose state the accounts tree is in. If only the derived state is
+        // broken, the blocks up to the head are fine to revert. The block must be in the epoch
+        // following the macro block.
+        let top = if inconsistency.is_chain_inconsistency() {
+            last_good
+        } else {
+            let txn = ReadTransaction::new(&self.env);
+            self.chain_store.get_head(Some(&txn))
+                .and_then(|hash| self.chain_store.get_chain_info(&hash, false, Some(&txn)))
+                .map(|info| info.head.block_number())
+                .ok_or(RepairError::Unrepairable(Inconsistency::MissingHead))?
+        };
+        let top = cmp::min(top, policy::macro_block_after(macro_block_number) - 1);
+        let (state_hash, state_number) = self.find_accounts_state(macro_block_number, top)
+            .ok_or_else(|| RepairError::Unrepairable(inconsistency.clone()))?;
+
+        // Truncate the chain to the block the accounts tree is in, so that the blockchain loads.
+        info!("Truncating main chain to #{} - {}", state_number, state_hash);
+        let mut txn = WriteTransaction::new(&self.env);
+        let slash_registry = SlashRegistry::new(self.env.clone(), Arc::clone(&self.chain_store));
+        unlink_main_chain(&self.env, &self.chain_store, &slash_registry, &mut txn, &state_hash, state_number)
+            .map_err(RepairError::Unrepairable)?;
+        txn.commit();
+
+        // Then revert the blocks of the epoch.
+        let blockchain = Blockchain::new(self.env.clone(), self.network_id)?;
+        blockchain.rollback_to_macro_head()?;
+        drop(blockchain);
+
+        self.check().map_err(RepairError::StillInconsistent)?;
+        Ok(Some(macro_block_number))
+    }
+
+    /// Walks the main chain from the genesis block to the head. Finished epochs may be
+    /// incomplete, since they can be stored as isolated macro blocks or as part of a snapshot.
+    fn check_chain(&self, txn: &Transaction) -> Result<ChainInfo, Inconsistency> {
+        let head_hash = self.chain_store.get_head(Some(txn))
+            .ok_or(Inconsistency::MissingHead)?;
+        let head_number = self.chain_store.get_chain_info(&head_hash, false, Some(txn))
+            .ok_or(Inconsistency::MissingHead)?
+            .head.block_number();
+        let macro_head_number = policy::last_macro_block(head_number);
+        let genesis_hash = NetworkInfo::from_network_id(self.network_id).genesis_hash().clone();
+
+        let (mut prev_hash, mut prev_info) = self.main_chain_block_at(0, txn)?
+            .ok_or(Inconsistency::InvalidGenesisBlock)?;
+        if prev_hash != genesis_hash {
+            return Err(Inconsistency::InvalidGenesisBlock);
+        }
+        let mut macro_hash = prev_hash.clone();
+
+        for block_number in 1..=head_number {
+            let (hash, info) = match self.main_chain_block_at(block_number, txn)? {
+                Some(entry) => entry,
+                None if block_number < macro_head_number => continue,
+                None => return Err(Inconsistency::MissingBlock(block_number)),
+            };
+            if info.head.block_number() != block_number {
+                return Err(Inconsistency::WrongHeight(block_number));
+            }
+
+            let prev_number = prev_info.head.block_number();
+            if prev_number + 1 == block_number {
+                if info.head.parent_hash() != &prev_hash {
+                    return Err(Inconsistency::BrokenParentLink(block_number));
+                }
+                if prev_info.main_chain_successor.as_ref() != Some(&hash) {
+                    return Err(Inconsistency::BrokenSuccessorLink(prev_number));
+                }
+            } else if block_number > macro_head_number {
+                return Err(Inconsistency::MissingBlock(prev_number + 1));
+            }
+
+            if let Block::Macro(ref macro_block) = info.head {
+                if macro_block.header.parent_macro_hash != macro_hash {
+                    return Err(Inconsistency::BrokenParentLink(block_number));
+                }
+                macro_hash = hash.clone();
+            }
+
+            prev_hash = hash;
+            prev_info = info;
+        }
+
+        if prev_hash != head_hash {
+            return Err(Inconsistency::WrongHead(head_number));
+        }
+        if prev_info.main_chain_successor.is_some()
+            || self.main_chain_block_at(head_number + 1, txn)?.is_some() {
+            return Err(Inconsistency::BrokenSuccessorLink(head_number));
+        }
+        Ok(prev_info)
+    }
+
+    fn check_accounts(&self, txn: &Transaction, head: &ChainInfo) -> Result<(), Inconsistency> {
+        let accounts = Accounts::new(self.env.clone());
+        let hash = accounts.recompute_hash(Some(txn))
+            .ok_or(Inconsistency::CorruptedAccountsTree)?;
+        if &hash != head.head.state_root() {
+            return Err(Inconsistency::AccountsMismatch(head.head.block_number()));
+        }
+        Ok(())
+    }
+
+    /// Checks that the slash registry has a descriptor for every block of the current epoch and
+    /// none for unknown blocks.
+    fn check_slash_registry(&self, txn: &Transaction, head: &ChainInfo) -> Result<(), Inconsistency> {
+        let head_number = head.head.block_number();
+        let macro_head_number = policy::last_macro_block(head_number);
+
+        let slash_registry = SlashRegistry::new(self.env.clone(), Arc::clone(&self.chain_store));
+        let block_numbers = slash_registry.descriptor_block_numbers(Some(txn));
+        if let Some(&block_number) = block_numbers.last() {
+            if block_number > head_number {
+                return Err(Inconsistency::UnexpectedSlashDescriptor(block_number));
+            }
+        }
+
+        // The genesis block doesn't have a descriptor.
+        let first_block_number = if macro_head_number > 0 { macro_head_number } else { 1 };
+        for block_number in first_block_number..=head_number {
+            if block_numbers.binary_search(&block_number).is_err() {
+                return Err(Inconsistency::MissingSlashDescriptor(block_number));
+            }
+        }
+        Ok(())
+    }
+
+    /// Rebuilds the transaction cache like loading the blockchain does, checking that all blocks
+    /// of the validity window are available and don't repeat transactions.
+    fn check_transaction_cache(&self, txn: &Transaction, head: &ChainInfo) -> Result<(), Inconsistency> {
+        let head_number = head.head.block_number();
+        let first_block_number = (head_number + 1).saturating_sub(policy::TRANSACTION_VALIDITY_WINDOW_ALBATROSS);
+
+        let mut transaction_cache = TransactionCache::new();
+        for block_number in first_block_number..=head_number {
+            // Micro blocks without body are returned with their header only.
+            let block = self.chain_store.get_block_at(block_number, true, Some(txn))
+                .filter(|block| block.ty() == BlockType::Macro || block.transactions().is_some())
+                .ok_or(Inconsistency::MissingBody(block_number))?;
+            if transaction_cache.contains_any(&block) {
+                return Err(Inconsistency::DuplicateTransaction(block_number));
+            }
+            transaction_cache.push_block(&block);
+        }
+        Ok(())
+    }
+
+    /// Returns the main chain block at the given height, if there is one.
+    fn main_chain_block_at(&self, block_number: u32, txn: &Transaction) -> Result<Option<(Blake2bHash, ChainInfo)>, Inconsistency> {
+        let mut main_chain_block = None;
+        for hash in self.chain_store.get_hashes_at(block_number, Some(txn)) {
+            let chain_info = self.chain_store.get_chain_info(&hash, false, Some(txn))
+                .ok_or(Inconsistency::DanglingIndexEntry(block_number))?;
+            if chain_info.on_main_chain {
+                if main_chain_block.is_some() {
+                    return Err(Inconsistency::DuplicateMainChainBlock(block_number));
+                }
+                main_chain_block = Some((hash, chain_info));
+            }
+        }
+        Ok(main_chain_block)
+    }
+
+    /// Searches the main chain from `top` down to `bottom` for the block whose state root matches
+    /// the accounts tree.
+    fn find_accounts_state(&self, bottom: u32, top: u32) -> Option<(Blake2bHash, u32)> {
+        let txn = ReadTransaction::new(&self.env);
+        let hash = Accounts::new(self.env.clone()).recompute_hash(Some(&txn))?;
+        (bottom..=top).rev()
+            .filter_map(|block_number| self.main_chain_block_at(block_number, &txn).ok()?)
+            .find(|(_, info)| info.head.state_root() == &hash)
+            .map(|(block_hash, info)| (block_hash, info.head.block_number()))
+    }
+}
+
+/// Removes all blocks after the given main chain block from the main chain and makes it the head.
+/// Main chain blocks have been applied to the slash registry, so it is reverted for the removed
+/// blocks, which must all be micro blocks. The accounts tree is left untouched.
+fn unlink_main_chain(env: &Environment, chain_store: &ChainStore, slash_registry: &SlashRegistry, txn: &mut WriteTransaction, block_hash: &Blake2bHash, block_number: u32) -> Result<(), Inconsistency> {
+    #[cfg(feature = "transaction-store")]
+    let transaction_store = TransactionStore::new(env.clone());
+    #[cfg(not(feature = "transaction-store"))]
+    let _ = env;
+
+    // Blocks are stored at consecutive heights, so there are none above the first empty height.
+    let mut height = block_number + 1;
+    loop {
+        let hashes = chain_store.get_hashes_at(height, Some(&*txn));
+        if hashes.is_empty() {
+            break;
+        }
+
+        for hash in hashes.iter() {
+            let mut chain_info = match chain_store.get_chain_info(hash, false, Some(&*txn)) {
+                Some(chain_info) if chain_info.on_main_chain => chain_info,
+                _ => continue,
+            };
+
+            let block = chain_store.get_block(hash, true, Some(&*txn))
+                .ok_or(Inconsistency::MissingBody(height))?;
+            if let Block::Macro(_) = block {
+                return Err(Inconsistency::BrokenParentLink(height));
+            }
+            slash_registry.revert_block(txn, &block)
+                .map_err(|_| Inconsistency::MissingSlashDescriptor(height))?;
+
+            #[cfg(feature = "transaction-store")]
+            transaction_store.remove(&block, txn);
+
+            chain_info.on_main_chain = false;
+            chain_info.main_chain_successor = None;
+            chain_store.put_chain_info(txn, hash, &chain_info, false);
+        }
+        height += 1;
+    }
+
+    let mut chain_info = chain_store.get_chain_info(block_hash, false, Some(&*txn))
+        .ok_or(Inconsistency::MissingBlock(block_number))?;
+    chain_info.main_chain_successor = None;
+    chain_store.put_chain_info(txn, block_hash, &chain_info, false);
+    chain_store.set_head(txn, block_hash);
+    Ok(())
+}
+
+impl Blockchain {
+    /// Reverts all blocks of the current epoch, making the macro head the new head.
+    pub(crate) fn rollback_to_macro_head(&self) -> Result<(), RepairError> {
+        // Acquire locks in the same order as `push_block` does.
+        let _push_lock = self.push_lock.lock();
+        let state = self.state.upgradable_read();
+        let macro_head_hash = state.macro_head_hash.clone();
+        let macro_head_number = state.macro_head.header.block_number;
+
+        let mut txn = WriteTransaction::new(&self.env);
+        self.revert_accounts_to(&state, &mut txn, &macro_head_hash)
+            .ok_or(RepairError::Unrepairable(Inconsistency::AccountsMismatch(state.main_chain.head.block_number())))?;
+        unlink_main_chain(&self.env, &self.chain_store, &state.reward_registry, &mut txn, &macro_head_hash, macro_head_number)
+            .map_err(RepairError::Unrepairable)?;
+        // Receipts are only kept for the current epoch.
+        self.chain_store.clear_receipts(&mut txn);
+        txn.commit();
+
+        let mut state = RwLockUpgradableReadGuard::upgrade(state);
+        *state = Blockchain::load_state(&self.env, &self.chain_store, macro_head_hash.clone())?;
+
+        info!("Rolled back to macro block #{} - {}", macro_head_number, macro_head_hash);
+        Ok(())
+    }
+}
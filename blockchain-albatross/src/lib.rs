@@ -19,10 +19,17 @@ extern crate nimiq_tree_primitives as tree_primitives;
 extern crate nimiq_utils as utils;
 extern crate nimiq_vrf as vrf;
 
+pub mod block_file;
 pub mod blockchain;
 pub mod chain_info;
 pub mod chain_store;
+pub mod integrity;
+pub mod light_blockchain;
 pub mod reward_registry;
+pub mod snapshot;
 pub mod transaction_cache;
 
+#[cfg(feature = "transaction-store")]
+pub mod transaction_store;
+
 pub use blockchain::Blockchain;
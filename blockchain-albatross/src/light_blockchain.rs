@@ -0,0 +1,356 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use parking_lot::{MappedRwLockReadGuard, Mutex, MutexGuard, RwLock, RwLockReadGuard};
+
+use account::Account;
+use block::{Block, BlockError, BlockHeader, MacroBlock};
+use blockchain_base::{AbstractBlockchain, BlockchainError, Direction};
+#[cfg(feature = "metrics")]
+use blockchain_base::chain_metrics::BlockchainMetrics;
+use database::{Environment, ReadTransaction, Transaction, WriteTransaction};
+use hash::Blake2bHash;
+use keys::Address;
+use network_primitives::networks::NetworkInfo;
+use network_primitives::time::NetworkTime;
+use primitives::networks::NetworkId;
+use primitives::policy;
+use transaction::{Transaction as BlockchainTransaction, TransactionReceipt, TransactionsProof};
+use tree_primitives::accounts_proof::AccountsProof;
+use tree_primitives::accounts_tree_chunk::AccountsTreeChunk;
+use utils::observer::{Listener, ListenerHandle, Notifier};
+
+use crate::blockchain::{BlockchainEvent, PushError, PushResult};
+use crate::chain_info::ChainInfo;
+use crate::chain_store::ChainStore;
+
+
+/// A blockchain that only follows the macro blocks. Each macro block is verified against the
+/// validators elected by its predecessor, so the chain can be trusted without downloading any
+/// micro blocks or maintaining the accounts tree. Accounts have to be requested from full nodes
+/// with accounts proofs, which can be checked against the `state_root` of the head.
+pub struct LightBlockchain {
+    env: Environment,
+    pub network_id: NetworkId,
+    pub notifier: RwLock<Notifier<'static, BlockchainEvent>>,
+    pub chain_store: Arc<ChainStore>,
+    state: RwLock<LightBlockchainState>,
+    push_lock: Mutex<()>,
+
+    #[cfg(feature = "metrics")]
+    metrics: BlockchainMetrics,
+}
+
+struct LightBlockchainState {
+    head: Block,
+    head_hash: Blake2bHash,
+}
+
+impl LightBlockchain {
+    pub fn new(env: Environment, network_id: NetworkId) -> Result<Self, BlockchainError> {
+        let chain_store = Arc::new(ChainStore::new(env.clone()));
+        let network_info = NetworkInfo::from_network_id(network_id);
+
+        let head_hash = match chain_store.get_head(None) {
+            Some(head_hash) => {
+                // Check that the correct genesis block is stored.
+                let genesis_info = chain_store.get_chain_info(network_info.genesis_hash(), false, None);
+                if !genesis_info.map(|i| i.on_main_chain).unwrap_or(false) {
+                    return Err(BlockchainError::InvalidGenesisBlock)
+                }
+                head_hash
+            },
+            None => {
+                let head_hash = network_info.genesis_hash().clone();
+                let mut txn = WriteTransaction::new(&env);
+                chain_store.put_chain_info(&mut txn, &head_hash, &ChainInfo::initial(network_info.genesis_block::<Block>()), true);
+                chain_store.set_head(&mut txn, &head_hash);
+                txn.commit();
+                head_hash
+            },
+        };
+
+        let head = chain_store.get_block(&head_hash, true, None)
+            .ok_or(BlockchainError::FailedLoadingMainChain)?;
+        if let Block::Micro(_) = head {
+            return Err(BlockchainError::InconsistentState);
+        }
+
+        Ok(LightBlockchain {
+            env,
+            network_id,
+            notifier: RwLock::new(Notifier::new()),
+            chain_store,
+            state: RwLock::new(LightBlockchainState {
+                head,
+                head_hash,
+            }),
+            push_lock: Mutex::new(()),
+
+            #[cfg(feature = "metrics")]
+            metrics: BlockchainMetrics::default(),
+        })
+    }
+
+    /// Pushes a macro block onto the chain. Micro blocks are ignored.
+    pub fn push(&self, block: Block) -> Result<PushResult, PushError> {
+        let result = match block {
+            Block::Macro(macro_block) => self.push_macro_block(macro_block),
+            Block::Micro(_) => Ok(PushResult::Ignored),
+        };
+
+        #[cfg(feature = "metrics")]
+        self.metrics.note(result.clone());
+
+        result
+    }
+
+    fn push_macro_block(&self, macro_block: MacroBlock) -> Result<PushResult, PushError> {
+        // Only one push operation at a time.
+        let _push_lock = self.push_lock.lock();
+
+        // Check if we already know this block.
+        let hash = macro_block.hash();
+        if self.chain_store.get_chain_info(&hash, false, None).is_some() {
+            return Ok(PushResult::Known);
+        }
+
+        // We can only accept macro blocks that follow our head.
+        let head_hash = self.head_hash();
+        if macro_block.header.parent_macro_hash != head_hash {
+            debug!("Rejecting block - does not follow on our head");
+            return Err(PushError::Orphan);
+        }
+
+        let block = Block::Macro(macro_block);
+        if let Err(e) = block.verify(self.network_id) {
+            warn!("Rejecting block - verification failed ({:?})", e);
+            return Err(PushError::InvalidBlock(e));
+        }
+        let macro_block = block.unwrap_macro_ref();
+
+        // The justification must be signed by the validators elected in the previous macro block.
+        {
+            let state = self.state.read();
+            let prev_block = state.head.unwrap_macro_ref();
+            if policy::macro_block_after(prev_block.header.block_number) != macro_block.header.block_number {
+                warn!("Rejecting block - wrong block number ({:?})", macro_block.header.block_number);
+                return Err(PushError::InvalidSuccessor);
+            }
+
+            let justification = macro_block.justification.as_ref()
+                .ok_or(PushError::InvalidBlock(BlockError::NoJustification))?;
+            if let Err(e) = justification.verify(hash.clone(), &prev_block.header.validators, policy::TWO_THIRD_SLOTS) {
+                warn!("Rejecting block - invalid justification ({:?})", e);
+                return Err(PushError::InvalidBlock(BlockError::InvalidJustification));
+            }
+        }
+
+        let mut txn = WriteTransaction::new(&self.env);
+
+        let mut prev_info = self.chain_store.get_chain_info(&head_hash, false, Some(&txn))
+            .expect("Failed to find head chain info");
+        prev_info.main_chain_successor = Some(hash.clone());
+        self.chain_store.put_chain_info(&mut txn, &head_hash, &prev_info, false);
+        self.chain_store.put_chain_info(&mut txn, &hash, &ChainInfo::initial(block.clone()), true);
+        self.chain_store.set_head(&mut txn, &hash);
+        txn.commit();
+
+        {
+            let mut state = self.state.write();
+            state.head = block;
+            state.head_hash = hash.clone();
+        }
+
+        self.notifier.read().notify(BlockchainEvent::Finalized(hash));
+
+        Ok(PushResult::Extended)
+    }
+
+    pub fn head(&self) -> MappedRwLockReadGuard<Block> {
+        let guard = self.state.read();
+        RwLockReadGuard::map(guard, |s| &s.head)
+    }
+
+    pub fn head_hash(&self) -> Blake2bHash {
+        self.state.read().head_hash.clone()
+    }
+
+    pub fn block_number(&self) -> u32 {
+        self.state.read().head.block_number()
+    }
+
+    /// The state root of the head, which accounts proofs are checked against.
+    pub fn state_root(&self) -> Blake2bHash {
+        self.state.read().head.state_root().clone()
+    }
+
+    /// Whether the block with the given header has to be downloaded to follow the chain, i.e.
+    /// it is an unknown macro block after our head. Micro blocks are never needed.
+    pub fn needs_block(&self, header: &BlockHeader) -> bool {
+        match header {
+            BlockHeader::Macro(macro_header) => {
+                macro_header.block_number > self.block_number() && !self.contains(&header.hash())
+            },
+            BlockHeader::Micro(_) => false,
+        }
+    }
+
+    /// Extracts the accounts at `addresses` from an accounts proof against `state_root`.
+    /// Accounts that aren't part of the tree must be proven to be absent.
+    ///
+    /// Returns `None` if the proof is invalid or doesn't cover all addresses.
+    pub fn get_accounts_from_proof(mut proof: AccountsProof<Account>, state_root: &Blake2bHash, addresses: &[Address]) -> Option<Vec<Account>> {
+        if !proof.verify() || &proof.root_hash() != state_root {
+            return None;
+        }
+        addresses.iter()
+            .map(|address| proof.get_account(address)
+                .or_else(|| if proof.proves_absence(address) { Some(Account::INITIAL) } else { None }))
+            .collect()
+    }
+
+    pub fn get_block(&self, hash: &Blake2bHash, include_body: bool) -> Option<Block> {
+        self.chain_store.get_block(hash, include_body, None)
+    }
+
+    pub fn contains(&self, hash: &Blake2bHash) -> bool {
+        self.chain_store.get_chain_info(hash, false, None).is_some()
+    }
+}
+
+impl AbstractBlockchain for LightBlockchain {
+    type Block = Block;
+
+    fn new(env: Environment, network_id: NetworkId, _network_time: Arc<NetworkTime>) -> Result<Self, BlockchainError> {
+        LightBlockchain::new(env, network_id)
+    }
+
+    #[cfg(feature = "metrics")]
+    fn metrics(&self) -> &BlockchainMetrics {
+        &self.metrics
+    }
+
+    fn network_id(&self) -> NetworkId {
+        self.network_id
+    }
+
+    fn head_block(&self) -> MappedRwLockReadGuard<Self::Block> {
+        self.head()
+    }
+
+    fn head_hash(&self) -> Blake2bHash {
+        self.head_hash()
+    }
+
+    fn head_height(&self) -> u32 {
+        self.block_number()
+    }
+
+    fn get_block(&self, hash: &Blake2bHash, include_body: bool) -> Option<Self::Block> {
+        self.get_block(hash, include_body)
+    }
+
+    fn get_block_at(&self, height: u32, include_body: bool) -> Option<Self::Block> {
+        self.chain_store.get_block_at(height, include_body, None)
+    }
+
+    fn get_block_locators(&self, max_count: usize) -> Vec<Blake2bHash> {
+        let mut locators: Vec<Blake2bHash> = Vec::with_capacity(max_count);
+        let mut hash = self.head_hash();
+
+        // Push top ten hashes.
+        locators.push(hash.clone());
+        for _ in 0..10 {
+            match self.chain_store.get_block(&hash, false, None) {
+                Some(Block::Macro(block)) if block.header.block_number > 0 => {
+                    hash = block.header.parent_macro_hash;
+                    locators.push(hash.clone());
+                },
+                _ => break,
+            }
+        }
+
+        let mut step = 2;
+        let mut height = policy::last_macro_block(self.block_number()).saturating_sub((10 + step) * policy::EPOCH_LENGTH);
+        while height > 0 && locators.len() < max_count {
+            match self.chain_store.get_block_at(height, false, None) {
+                Some(block) => locators.push(block.hash()),
+                None => break,
+            }
+
+            step *= 2;
+            height = height.saturating_sub(step * policy::EPOCH_LENGTH);
+        }
+
+        // Push the genesis block hash.
+        let genesis_hash = NetworkInfo::from_network_id(self.network_id).genesis_hash();
+        if locators.last().unwrap() != genesis_hash {
+            // Respect max count, make space for genesis hash if necessary
+            if locators.len() >= max_count {
+                locators.pop();
+            }
+            locators.push(genesis_hash.clone());
+        }
+
+        locators
+    }
+
+    fn get_blocks(&self, start_block_hash: &Blake2bHash, count: u32, include_body: bool, direction: Direction) -> Vec<Self::Block> {
+        self.chain_store.get_blocks(start_block_hash, count, include_body, direction, None)
+    }
+
+    fn push(&self, block: Self::Block) -> Result<PushResult, PushError> {
+        self.push(block)
+    }
+
+    fn contains(&self, hash: &Blake2bHash, _include_forks: bool) -> bool {
+        self.contains(hash)
+    }
+
+    fn get_accounts_proof(&self, _block_hash: &Blake2bHash, _addresses: &[Address]) -> Option<AccountsProof<Account>> {
+        None
+    }
+
+    fn get_transactions_proof(&self, _block_hash: &Blake2bHash, _addresses: &HashSet<Address>) -> Option<TransactionsProof> {
+        None
+    }
+
+    fn get_transaction_receipts_by_address(&self, _address: &Address, _sender_limit: usize, _recipient_limit: usize) -> Vec<TransactionReceipt> {
+        Vec::new()
+    }
+
+    fn register_listener<T: Listener<BlockchainEvent> + 'static>(&self, listener: T) -> ListenerHandle {
+        self.notifier.write().register(listener)
+    }
+
+    fn lock(&self) -> MutexGuard<()> {
+        self.push_lock.lock()
+    }
+
+    fn get_account(&self, _address: &Address) -> Option<Account> {
+        // The light blockchain doesn't know any accounts, they have to be requested with
+        // accounts proofs.
+        None
+    }
+
+    fn contains_tx_in_validity_window(&self, _tx_hash: &Blake2bHash) -> bool {
+        false
+    }
+
+    fn head_hash_from_store(&self, txn: &ReadTransaction) -> Option<Blake2bHash> {
+        self.chain_store.get_head(Some(txn))
+    }
+
+    fn get_accounts_chunk(&self, _prefix: &str, _size: usize, _txn_option: Option<&Transaction>) -> Option<AccountsTreeChunk<Account>> {
+        None
+    }
+
+    fn get_epoch_transactions(&self, _epoch: u32, _txn_option: Option<&Transaction>) -> Option<Vec<BlockchainTransaction>> {
+        None
+    }
+
+    fn validator_registry_address(&self) -> Option<&Address> {
+        NetworkInfo::from_network_id(self.network_id).validator_registry_address()
+    }
+}
@@ -66,6 +66,20 @@ struct BlockDescriptor {
     prev_epoch_state: BitSet,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct BlockDescriptorEntry {
+    block_number: u32,
+    descriptor: BlockDescriptor,
+}
+
+/// The epoch state of the slash registry at a macro block, as included in state snapshots.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SlashRegistryState {
+    #[beserial(len_type(u32))]
+    descriptors: Vec<BlockDescriptorEntry>,
+    previous_reward_pot: Coin,
+}
+
 // TODO Pass in active validator set + seed through parameters
 //      or always load from chain store?
 impl SlashRegistry {
@@ -278,6 +292,73 @@ impl SlashRegistry {
         }
     }
 
+    /// Exports the epoch state at the given macro block. Descriptors of later blocks are ignored,
+    /// so this can be used while the current epoch is still in progress.
+    pub fn export_state(&self, block_number: u32, txn_option: Option<&Transaction>) -> SlashRegistryState {
+        assert!(policy::is_macro_block_at(block_number));
+
+        let read_txn;
+        let txn = if let Some(txn) = txn_option {
+            txn
+        } else {
+            read_txn = ReadTransaction::new(&self.env);
+            &read_txn
+        };
+
+        let mut descriptors = Vec::new();
+        let mut cursor = txn.cursor(&self.slash_registry_db);
+        let mut pos: Option<(u32, BlockDescriptor)> = cursor.first();
+        while let Some((descriptor_block_number, descriptor)) = pos {
+            if descriptor_block_number > block_number {
+                break;
+            }
+            descriptors.push(BlockDescriptorEntry { block_number: descriptor_block_number, descriptor });
+            pos = cursor.next();
+        }
+
+        SlashRegistryState {
+            descriptors,
+            previous_reward_pot: self.reward_pot.previous_reward_pot(),
+        }
+    }
+
+    /// Replaces the epoch state with one that was exported at a macro block.
+    pub fn import_state(&self, txn: &mut WriteTransaction, state: &SlashRegistryState) {
+        {
+            let mut cursor = txn.write_cursor(&self.slash_registry_db);
+            let mut pos: Option<(u32, BlockDescriptor)> = cursor.first();
+            while let Some(_) = pos {
+                cursor.remove();
+                pos = cursor.next();
+            }
+        }
+
+        for entry in state.descriptors.iter() {
+            txn.put(&self.slash_registry_db, &entry.block_number, &entry.descriptor);
+        }
+        self.reward_pot.import(txn, state.previous_reward_pot);
+    }
+
+    /// Returns the block numbers of all stored block descriptors in ascending order.
+    pub fn descriptor_block_numbers(&self, txn_option: Option<&Transaction>) -> Vec<u32> {
+        let read_txn;
+        let txn = if let Some(txn) = txn_option {
+            txn
+        } else {
+            read_txn = ReadTransaction::new(&self.env);
+            &read_txn
+        };
+
+        let mut block_numbers = Vec::new();
+        let mut cursor = txn.cursor(&self.slash_registry_db);
+        let mut pos: Option<(u32, BlockDescriptor)> = cursor.first();
+        while let Some((block_number, _)) = pos {
+            block_numbers.push(block_number);
+            pos = cursor.next();
+        }
+        block_numbers
+    }
+
     #[inline]
     pub fn revert_block(&self, txn: &mut WriteTransaction, block: &Block) -> Result<(), SlashPushError> {
         if let Block::Micro(ref block) = block {
@@ -389,6 +470,50 @@ impl SlashRegistry {
     }
 }
 
+impl SlashRegistryState {
+    /// Checks the state exported at `macro_block` against the blocks of its epoch, which the
+    /// macro block commits to. The block descriptors must lie within the range kept by the
+    /// registry and end at the macro block, and the reward pot must add up to the rewards and
+    /// fees of the epoch.
+    ///
+    /// The slashes themselves can't be checked without the slots of the epoch, so the state
+    /// still has to be obtained from a trusted source or a quorum of peers.
+    pub fn verify(&self, macro_block: &MacroBlock, epoch_blocks: &[Block]) -> bool {
+        let block_number = macro_block.header.block_number;
+        let epoch = policy::epoch_at(block_number);
+        let first_block = policy::first_block_of_registry(epoch);
+
+        let mut prev_block_number = None;
+        for entry in self.descriptors.iter() {
+            if entry.block_number < first_block || entry.block_number > block_number
+                || prev_block_number.map_or(false, |prev| entry.block_number <= prev) {
+                return false;
+            }
+            prev_block_number = Some(entry.block_number);
+        }
+        if prev_block_number != Some(block_number) {
+            return false;
+        }
+
+        // The epoch blocks are the micro blocks preceding the macro block, oldest first.
+        let epoch_start = policy::first_block_of(epoch);
+        if epoch_blocks.len() as u32 != block_number - epoch_start {
+            return false;
+        }
+        let mut reward = RewardPot::reward_for_macro_block(macro_block);
+        for (block, expected_block_number) in epoch_blocks.iter().zip(epoch_start..) {
+            match block {
+                Block::Micro(ref micro_block) if micro_block.header.block_number == expected_block_number
+                    && micro_block.extrinsics.is_some() => {
+                    reward += RewardPot::reward_for_micro_block(micro_block);
+                },
+                _ => return false,
+            }
+        }
+        reward == self.previous_reward_pot
+    }
+}
+
 impl AsDatabaseBytes for BlockDescriptor {
     fn as_database_bytes(&self) -> Cow<[u8]> {
         let v = Serialize::serialize_to_vec(&self);
@@ -55,6 +55,12 @@ impl RewardPot {
         txn.put(&self.reward_pot, Self::PREVIOUS_EPOCH_KEY, &u64::from(reward));
     }
 
+    /// Sets the reward pots to their state at a macro block.
+    pub(super) fn import(&self, txn: &mut WriteTransaction, previous_reward: Coin) {
+        txn.put(&self.reward_pot, Self::CURRENT_EPOCH_KEY, &0u64);
+        txn.put(&self.reward_pot, Self::PREVIOUS_EPOCH_KEY, &u64::from(previous_reward));
+    }
+
     pub(super) fn commit_micro_block(&self, block: &MicroBlock, txn: &mut WriteTransaction) {
         // The total reward of a block is composed of the block reward and transaction fees.
         let mut reward = RewardPot::reward_for_micro_block(block);
@@ -74,7 +80,7 @@ impl RewardPot {
         txn.put(&self.reward_pot, Self::CURRENT_EPOCH_KEY, &u64::from(reward));
     }
 
-    fn reward_for_micro_block(block: &MicroBlock) -> Coin {
+    pub(super) fn reward_for_micro_block(block: &MicroBlock) -> Coin {
         // Block reward
         let mut reward = policy::block_reward_at(block.header.block_number);
 
@@ -87,7 +93,7 @@ impl RewardPot {
         reward
     }
 
-    fn reward_for_macro_block(block: &MacroBlock) -> Coin {
+    pub(super) fn reward_for_macro_block(block: &MacroBlock) -> Coin {
         policy::block_reward_at(block.header.block_number)
     }
 
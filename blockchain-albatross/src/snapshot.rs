@@ -0,0 +1,363 @@
+use std::cmp;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::sync::Arc;
+
+use failure::Fail;
+
+use account::Account;
+use accounts::Accounts;
+use beserial::{Deserialize, Serialize, SerializingError};
+use block::{Block, MacroBlock};
+use blockchain_base::BlockchainError;
+use database::{Environment, WriteTransaction};
+use hash::{Blake2bHash, Hash};
+use network_primitives::networks::NetworkInfo;
+use primitives::networks::NetworkId;
+use primitives::policy;
+use tree_primitives::accounts_tree_node::AccountsTreeNode;
+
+use crate::blockchain::{Blockchain, BlockchainEvent};
+use crate::chain_info::ChainInfo;
+use crate::chain_store::ChainStore;
+use crate::reward_registry::{SlashRegistry, SlashRegistryState};
+#[cfg(feature = "transaction-store")]
+use crate::transaction_store::TransactionStore;
+
+
+#[derive(Debug, Fail)]
+pub enum SnapshotError {
+    #[fail(display = "Block #{} is missing or has been pruned", _0)]
+    MissingBlock(u32),
+    #[fail(display = "Expected {} blocks before the macro block, but found {}", expected, actual)]
+    BlockCount { expected: u32, actual: u32 },
+    #[fail(display = "Accounts tree is missing a node")]
+    MissingAccountsTreeNode,
+    #[fail(display = "Failed to revert the accounts tree to the macro head")]
+    RevertFailed,
+    #[fail(display = "Database already contains a blockchain")]
+    DatabaseNotEmpty,
+    #[fail(display = "No macro block after the genesis block")]
+    MissingMacroBlock,
+    #[fail(display = "Macro block #{} doesn't follow on its predecessor", _0)]
+    InvalidMacroChain(u32),
+    #[fail(display = "Macro block #{} has an invalid justification", _0)]
+    InvalidJustification(u32),
+    #[fail(display = "Invalid block #{}", _0)]
+    InvalidBlock(u32),
+    #[fail(display = "Snapshot contains an invalid accounts tree node")]
+    InvalidAccountsTreeNode,
+    #[fail(display = "Accounts tree doesn't match the state root of the macro block")]
+    InvalidStateRoot,
+    #[fail(display = "Slash registry doesn't match the blocks of the epoch")]
+    InvalidSlashRegistry,
+    #[fail(display = "Blockchain error: {}", _0)]
+    Blockchain(#[cause] BlockchainError),
+    #[fail(display = "I/O error: {}", _0)]
+    Io(#[cause] io::Error),
+    #[fail(display = "Serialization error: {}", _0)]
+    Serializing(#[cause] SerializingError),
+}
+
+impl From<BlockchainError> for SnapshotError {
+    fn from(e: BlockchainError) -> Self {
+        SnapshotError::Blockchain(e)
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+impl From<SerializingError> for SnapshotError {
+    fn from(e: SerializingError) -> Self {
+        SnapshotError::Serializing(e)
+    }
+}
+
+/// The state of the blockchain at a macro block, which a new node can be bootstrapped from
+/// without replaying the chain.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// The macro blocks following the genesis block, up to and including the snapshot block.
+    #[beserial(len_type(u32))]
+    pub macro_blocks: Vec<MacroBlock>,
+    /// The blocks preceding the snapshot block within the transaction validity window, oldest
+    /// first. These are needed to rebuild the transaction cache.
+    #[beserial(len_type(u32))]
+    pub blocks: Vec<Block>,
+    /// The terminal nodes of the accounts tree (including the staking contract) at the snapshot
+    /// block.
+    #[beserial(len_type(u32))]
+    pub accounts: Vec<AccountsTreeNode<Account>>,
+    pub slash_registry: SlashRegistryState,
+}
+
+impl Snapshot {
+    /// The macro block the snapshot was taken at.
+    pub fn macro_block(&self) -> Option<&MacroBlock> {
+        self.macro_blocks.last()
+    }
+
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<Self, SnapshotError> {
+        let mut reader = BufReader::new(File::open(path)?);
+        Ok(Deserialize::deserialize(&mut reader)?)
+    }
+
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), SnapshotError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        Serialize::serialize(self, &mut writer)?;
+        Ok(())
+    }
+
+    /// Verifies that `macro_block` is the successor of `prev_block` in the macro chain and that
+    /// it was signed by the validators elected in `prev_block`.
+    pub fn verify_macro_block(prev_block: &MacroBlock, macro_block: &MacroBlock) -> Result<(), SnapshotError> {
+        let block_number = macro_block.header.block_number;
+        if macro_block.header.parent_macro_hash != prev_block.hash()
+            || block_number != policy::macro_block_after(prev_block.header.block_number) {
+            return Err(SnapshotError::InvalidMacroChain(block_number));
+        }
+
+        // The extrinsics are required to compute the slots.
+        macro_block.verify().map_err(|_| SnapshotError::InvalidBlock(block_number))?;
+        match macro_block.extrinsics {
+            Some(ref extrinsics) if extrinsics.hash::<Blake2bHash>() == macro_block.header.extrinsics_root => {},
+            _ => return Err(SnapshotError::InvalidBlock(block_number)),
+        }
+
+        let justification = macro_block.justification.as_ref()
+            .ok_or(SnapshotError::InvalidJustification(block_number))?;
+        justification.verify(macro_block.hash(), &prev_block.header.validators, policy::TWO_THIRD_SLOTS)
+            .map_err(|_| SnapshotError::InvalidJustification(block_number))?;
+
+        Ok(())
+    }
+
+    /// Verifies the macro chain, the blocks of the transaction validity window and the slash
+    /// registry as far as it is covered by these blocks.
+    fn verify(&self, network_id: NetworkId) -> Result<(), SnapshotError> {
+        let network_info = NetworkInfo::from_network_id(network_id);
+        let genesis_block = network_info.genesis_block::<Block>();
+
+        // Verify the macro chain.
+        let mut prev_block = genesis_block.unwrap_macro_ref();
+        for macro_block in self.macro_blocks.iter() {
+            Snapshot::verify_macro_block(prev_block, macro_block)?;
+            prev_block = macro_block;
+        }
+
+        let macro_block = self.macro_block().ok_or(SnapshotError::MissingMacroBlock)?;
+        let block_number = macro_block.header.block_number;
+
+        // Verify that the blocks of the transaction validity window lead up to the macro block.
+        let count = cmp::min(policy::TRANSACTION_VALIDITY_WINDOW_ALBATROSS - 1, block_number);
+        if self.blocks.len() as u32 != count {
+            return Err(SnapshotError::BlockCount { expected: count, actual: self.blocks.len() as u32 });
+        }
+        let mut expected_hash = macro_block.header.parent_hash.clone();
+        for (i, block) in self.blocks.iter().enumerate().rev() {
+            let expected_block_number = block_number - count + i as u32;
+            if block.block_number() != expected_block_number || block.hash() != expected_hash {
+                return Err(SnapshotError::InvalidBlock(expected_block_number));
+            }
+            block.verify(network_id).map_err(|_| SnapshotError::InvalidBlock(expected_block_number))?;
+            expected_hash = block.parent_hash().clone();
+        }
+
+        // The window always covers the epoch of the macro block.
+        let epoch_start = policy::first_block_of(policy::epoch_at(block_number));
+        let epoch_blocks = &self.blocks[(epoch_start - (block_number - count)) as usize..];
+        if !self.slash_registry.verify(macro_block, epoch_blocks) {
+            return Err(SnapshotError::InvalidSlashRegistry);
+        }
+
+        Ok(())
+    }
+
+    /// Writes the state of a verified snapshot to the database and sets its macro block as the
+    /// head. Accounts that are already in the tree, i.e. the genesis accounts, are replaced.
+    ///
+    /// Returns the hash of the macro block.
+    fn store(&self, env: &Environment, chain_store: &Arc<ChainStore>, network_id: NetworkId, txn: &mut WriteTransaction) -> Result<Blake2bHash, SnapshotError> {
+        let macro_block = self.macro_block().ok_or(SnapshotError::MissingMacroBlock)?;
+        let macro_block_hash = macro_block.hash();
+
+        // Rebuild the accounts tree.
+        let accounts = Accounts::new(env.clone());
+        let mut entries = Vec::with_capacity(self.accounts.len());
+        for node in self.accounts.iter() {
+            match node {
+                AccountsTreeNode::TerminalNode { prefix, account } => {
+                    let address = prefix.to_address().ok_or(SnapshotError::InvalidAccountsTreeNode)?;
+                    entries.push((address, account.clone()));
+                },
+                AccountsTreeNode::BranchNode { .. } => return Err(SnapshotError::InvalidAccountsTreeNode),
+            }
+        }
+        let existing = accounts.get_terminal_nodes(Some(&*txn))
+            .ok_or(SnapshotError::MissingAccountsTreeNode)?
+            .iter()
+            .filter_map(|node| node.prefix().to_address())
+            .map(|address| (address, Account::INITIAL))
+            .collect();
+        accounts.init(txn, existing);
+        accounts.init(txn, entries);
+        if accounts.hash(Some(&*txn)) != macro_block.header.state_root {
+            return Err(SnapshotError::InvalidStateRoot);
+        }
+
+        let slash_registry = SlashRegistry::new(env.clone(), Arc::clone(chain_store));
+        slash_registry.import_state(txn, &self.slash_registry);
+
+        // Store the genesis block and the macro chain.
+        let network_info = NetworkInfo::from_network_id(network_id);
+        let genesis_block = network_info.genesis_block::<Block>();
+        chain_store.put_chain_info(txn, network_info.genesis_hash(), &ChainInfo::initial(genesis_block), true);
+        for macro_block in self.macro_blocks.iter() {
+            let chain_info = ChainInfo::initial(Block::Macro(macro_block.clone()));
+            chain_store.put_chain_info(txn, &macro_block.hash(), &chain_info, true);
+        }
+
+        // Store the blocks of the transaction validity window. The snapshot doesn't contain any
+        // older transactions, so the transaction store only covers these blocks.
+        #[cfg(feature = "transaction-store")]
+        let transaction_store = TransactionStore::new(env.clone());
+        for (i, block) in self.blocks.iter().enumerate() {
+            let mut chain_info = ChainInfo::initial(block.clone());
+            chain_info.main_chain_successor = Some(self.blocks.get(i + 1)
+                .map(Block::hash)
+                .unwrap_or_else(|| macro_block_hash.clone()));
+            chain_store.put_chain_info(txn, &block.hash(), &chain_info, true);
+
+            #[cfg(feature = "transaction-store")]
+            transaction_store.put(block, txn);
+        }
+
+        // There is no history before the transaction validity window.
+        if let Some(block) = self.blocks.first() {
+            chain_store.set_pruned_height(txn, block.block_number());
+        }
+
+        chain_store.set_head(txn, &macro_block_hash);
+        Ok(macro_block_hash)
+    }
+}
+
+impl Blockchain {
+    /// Creates a snapshot of the state at the current macro head.
+    pub fn export_snapshot(&self) -> Result<Snapshot, SnapshotError> {
+        // Acquire locks in the same order as `push_block` does.
+        let _push_lock = self.push_lock.lock();
+        let mut txn = WriteTransaction::new(&self.env);
+        let state = self.state.read();
+
+        let macro_head = state.macro_head.clone();
+        let macro_head_hash = state.macro_head_hash.clone();
+        if macro_head.header.block_number == 0 {
+            return Err(SnapshotError::MissingMacroBlock);
+        }
+
+        // Collect the macro chain down to the genesis block.
+        let mut macro_blocks = vec![macro_head.clone()];
+        let mut parent_macro_hash = macro_head.header.parent_macro_hash.clone();
+        while macro_blocks.last().unwrap().header.block_number > policy::EPOCH_LENGTH {
+            let block_number = macro_blocks.last().unwrap().header.block_number - policy::EPOCH_LENGTH;
+            let macro_block = match self.chain_store.get_block(&parent_macro_hash, true, Some(&txn)) {
+                Some(Block::Macro(macro_block)) => macro_block,
+                _ => return Err(SnapshotError::MissingBlock(block_number)),
+            };
+            parent_macro_hash = macro_block.header.parent_macro_hash.clone();
+            macro_blocks.push(macro_block);
+        }
+        macro_blocks.reverse();
+
+        // Collect the blocks of the transaction validity window.
+        let block_number = macro_head.header.block_number;
+        let count = cmp::min(policy::TRANSACTION_VALIDITY_WINDOW_ALBATROSS - 1, block_number);
+        let mut blocks = self.chain_store.get_blocks_backward(&macro_head_hash, count, true, Some(&txn));
+        if blocks.len() as u32 != count {
+            return Err(SnapshotError::BlockCount { expected: count, actual: blocks.len() as u32 });
+        }
+        blocks.reverse();
+
+        // The accounts tree and the slash registry are at the state of the head block.
+        self.revert_accounts_to(&state, &mut txn, &macro_head_hash)
+            .ok_or(SnapshotError::RevertFailed)?;
+        assert_eq!(macro_head.header.state_root, state.accounts.hash(Some(&txn)),
+                   "Failed to revert to macro head - inconsistent state");
+        let accounts = state.accounts.get_terminal_nodes(Some(&txn))
+            .ok_or(SnapshotError::MissingAccountsTreeNode)?;
+        let slash_registry = state.reward_registry.export_state(block_number, Some(&txn));
+        txn.abort();
+
+        Ok(Snapshot {
+            macro_blocks,
+            blocks,
+            accounts,
+            slash_registry,
+        })
+    }
+
+    /// Exports the slash registry at the given macro block. Since the registry can't be reverted,
+    /// this is only possible for the current macro head.
+    pub fn export_slash_registry(&self, block_hash: &Blake2bHash) -> Option<SlashRegistryState> {
+        let state = self.state.read();
+        if &state.macro_head_hash != block_hash || state.macro_head.header.block_number == 0 {
+            return None;
+        }
+        Some(state.reward_registry.export_state(state.macro_head.header.block_number, None))
+    }
+
+    /// Bootstraps a blockchain in an empty database from a snapshot.
+    ///
+    /// The macro chain is verified starting at the genesis block, so that the `PbftProof` of each
+    /// macro block can be checked against the validators elected by its predecessor. The snapshot
+    /// is only accepted if the rebuilt accounts tree matches the state root of its macro block.
+    pub fn import_snapshot(env: Environment, network_id: NetworkId, snapshot: Snapshot) -> Result<Self, SnapshotError> {
+        let chain_store = Arc::new(ChainStore::new(env.clone()));
+        if chain_store.get_head(None).is_some() {
+            return Err(SnapshotError::DatabaseNotEmpty);
+        }
+
+        snapshot.verify(network_id)?;
+
+        let mut txn = WriteTransaction::new(&env);
+        let macro_block_hash = snapshot.store(&env, &chain_store, network_id, &mut txn)?;
+        txn.commit();
+
+        info!("Imported snapshot at macro block #{} - {}", snapshot.macro_block().unwrap().header.block_number, macro_block_hash);
+
+        Ok(Blockchain::new(env, network_id)?)
+    }
+
+    /// Replaces the state of a blockchain that is still at its genesis block with a snapshot. In
+    /// contrast to `import_snapshot`, this can be used while the blockchain is already in use,
+    /// e.g. when the snapshot was assembled from the network.
+    pub fn apply_snapshot(&self, snapshot: Snapshot) -> Result<(), SnapshotError> {
+        snapshot.verify(self.network_id)?;
+
+        // Acquire locks in the same order as `push_block` does.
+        let _push_lock = self.push_lock.lock();
+        let mut state = self.state.write();
+        if state.main_chain.head.block_number() > 0 {
+            return Err(SnapshotError::DatabaseNotEmpty);
+        }
+
+        let mut txn = WriteTransaction::new(&self.env);
+        let macro_block_hash = snapshot.store(&self.env, &self.chain_store, self.network_id, &mut txn)?;
+        txn.commit();
+
+        *state = Blockchain::load_state(&self.env, &self.chain_store, macro_block_hash.clone())?;
+        drop(state);
+
+        info!("Applied snapshot at macro block #{} - {}", snapshot.macro_block().unwrap().header.block_number, macro_block_hash);
+
+        self.notifier.read().notify(BlockchainEvent::Finalized(macro_block_hash));
+        Ok(())
+    }
+}
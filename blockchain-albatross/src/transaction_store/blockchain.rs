@@ -0,0 +1,26 @@
+use hash::Blake2bHash;
+use keys::Address;
+use transaction::TransactionReceipt;
+use database::ReadTransaction;
+
+use crate::blockchain::Blockchain;
+use crate::transaction_store::TransactionInfo;
+
+impl Blockchain {
+    /// Transactions of epochs that were pushed as isolated macro blocks are reported as part of
+    /// the macro block. A node bootstrapped from a snapshot only knows the transactions from the
+    /// snapshot's transaction validity window on.
+    pub fn get_transaction_receipts_by_address(&self, address: &Address, sender_limit: usize, recipient_limit: usize) -> Vec<TransactionReceipt> {
+        let mut receipts;
+
+        let txn = ReadTransaction::new(&self.env);
+        receipts = self.transaction_store.get_by_sender(address, sender_limit, Some(&txn));
+        receipts.extend(self.transaction_store.get_by_recipient(address, recipient_limit, Some(&txn)));
+
+        receipts.drain(..).map(TransactionReceipt::from).collect()
+    }
+
+    pub fn get_transaction_info_by_hash(&self, transaction_hash: &Blake2bHash) -> Option<TransactionInfo> {
+        self.transaction_store.get_by_hash(transaction_hash, None)
+    }
+}
\ No newline at end of file
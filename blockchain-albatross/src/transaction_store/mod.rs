@@ -0,0 +1,3 @@
+pub use blockchain_base::transaction_store::{TransactionInfo, TransactionStore};
+
+pub mod blockchain;
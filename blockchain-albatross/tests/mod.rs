@@ -1,17 +1,27 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 
-use beserial::Deserialize;
+use beserial::{Deserialize, Serialize};
+use nimiq_account::{Account, Inherent, InherentType};
 use nimiq_block_albatross::{Block, MacroBlock, PbftCommitMessage, PbftPrepareMessage, PbftProofBuilder, PbftProposal, SignedPbftCommitMessage, SignedPbftPrepareMessage, ViewChangeProof, SignedViewChange, ViewChange, ViewChangeProofBuilder, MacroExtrinsics};
 use nimiq_block_production_albatross::BlockProducer;
+use nimiq_blockchain_albatross::block_file::{BlockFileEntry, BlockFileError, BlockFileReader, BlockFileWriter};
 use nimiq_blockchain_albatross::blockchain::{Blockchain, PushResult, PushError};
+use nimiq_blockchain_albatross::integrity::{Inconsistency, IntegrityChecker};
+use nimiq_blockchain_albatross::light_blockchain::LightBlockchain;
+use nimiq_blockchain_albatross::snapshot::{Snapshot, SnapshotError};
 use nimiq_blockchain_base::AbstractBlockchain;
 use nimiq_blockchain_base::Direction;
 use nimiq_bls::{KeyPair, SecretKey};
 use nimiq_database::volatile::VolatileEnvironment;
 use nimiq_hash::{Blake2bHash, Hash};
+use nimiq_keys::{Address, KeyPair as SchnorrKeyPair, SecureGenerate};
+use nimiq_mempool::{Mempool, MempoolConfig, ReturnCode};
 use nimiq_network_primitives::{networks::NetworkId};
+use nimiq_primitives::coin::Coin;
 use nimiq_primitives::policy;
-use nimiq_database::Environment;
+use nimiq_transaction::{SignatureProof, Transaction};
+use nimiq_database::{Environment, WriteTransaction};
 
 mod signed;
 mod macro_block_sync;
@@ -22,6 +32,7 @@ const SECRET_KEY: &'static str = "49ea68eb6b8afdf4ca4d4c0a0b295c76ca85225293693b
 struct TemporaryBlockProducer {
     env: Environment,
     blockchain: Arc<Blockchain>,
+    mempool: Arc<Mempool<Blockchain>>,
     producer: BlockProducer,
 }
 
@@ -31,10 +42,12 @@ impl TemporaryBlockProducer {
         let blockchain = Arc::new(Blockchain::new(env.clone(), NetworkId::UnitAlbatross).unwrap());
 
         let keypair = KeyPair::from(SecretKey::deserialize_from_vec(&hex::decode(SECRET_KEY).unwrap()).unwrap());
-        let producer = BlockProducer::new_without_mempool(Arc::clone(&blockchain), keypair);
+        let mempool = Mempool::new(Arc::clone(&blockchain), MempoolConfig::default());
+        let producer = BlockProducer::new(Arc::clone(&blockchain), Arc::clone(&mempool), keypair);
         TemporaryBlockProducer {
             env,
             blockchain,
+            mempool,
             producer
         }
     }
@@ -264,3 +277,502 @@ fn it_can_rebranch_at_macro_block() {
     assert_eq!(temp_producer1.push(fork2), Ok(PushResult::Rebranched));
     assert_eq!(temp_producer2.push(fork1), Ok(PushResult::Ignored));
 }
+
+#[test]
+fn it_can_create_accounts_proofs_for_past_blocks() {
+    let temp_producer = TemporaryBlockProducer::new();
+    let validator_registry = temp_producer.blockchain.validator_registry_address().unwrap().clone();
+
+    let block1 = temp_producer.next_block(0, vec![]);
+    let block2 = temp_producer.next_block(1, vec![]);
+    let block3 = temp_producer.next_block(1, vec![]);
+
+    // Proofs for the head and for previous blocks of the current epoch.
+    for block in &[&block1, &block2, &block3] {
+        let mut proof = temp_producer.blockchain.get_accounts_proof(&block.hash(), &[validator_registry.clone()]).unwrap();
+        assert!(proof.verify());
+        assert_eq!(&proof.root_hash(), block.state_root());
+        assert!(proof.get_account(&validator_registry).is_some());
+    }
+
+    // Creating a proof must not change the head state.
+    assert_eq!(temp_producer.blockchain.state().accounts().hash(None), *block3.state_root());
+
+    // Fill up the epoch.
+    let macro_block = loop {
+        let block = temp_producer.next_block(0, vec![]);
+        if let Block::Macro(_) = block {
+            break block;
+        }
+    };
+
+    // Blocks of finalized epochs are not supported.
+    assert!(temp_producer.blockchain.get_accounts_proof(&block1.hash(), &[validator_registry.clone()]).is_none());
+
+    let mut proof = temp_producer.blockchain.get_accounts_proof(&macro_block.hash(), &[validator_registry.clone()]).unwrap();
+    assert!(proof.verify());
+    assert_eq!(&proof.root_hash(), macro_block.state_root());
+}
+
+#[test]
+fn it_can_look_up_accounts_at_past_blocks() {
+    let temp_producer = TemporaryBlockProducer::new();
+    let validator_registry = temp_producer.blockchain.validator_registry_address().unwrap().clone();
+    let staking_account = temp_producer.blockchain.get_account(&validator_registry).unwrap();
+
+    // Fund an account outside of the chain, so that it can send a transaction.
+    let keypair = SchnorrKeyPair::generate_default_csprng();
+    let sender = Address::from(&keypair.public);
+    let recipient = Address::from([2u8; Address::SIZE]);
+    let funding = Inherent { ty: InherentType::Reward, target: sender.clone(), value: Coin::from_u64_unchecked(1000), data: vec![] };
+    let mut txn = WriteTransaction::new(&temp_producer.env);
+    temp_producer.blockchain.state().accounts().commit(&mut txn, &[], &[funding], 1).unwrap();
+    txn.commit();
+
+    let block1 = temp_producer.next_block(0, vec![]);
+
+    // The next block moves some of the balance.
+    let mut transaction = Transaction::new_basic(sender.clone(), recipient.clone(), Coin::from_u64_unchecked(100), Coin::ZERO, block1.block_number(), NetworkId::UnitAlbatross);
+    transaction.proof = SignatureProof::from(keypair.public.clone(), keypair.sign(&transaction.serialize_content())).serialize_to_vec();
+    assert_eq!(temp_producer.mempool.push_transaction(transaction), ReturnCode::Accepted);
+    let block2 = temp_producer.next_block(1, vec![]);
+    assert_eq!(block2.transactions().map(Vec::len), Some(1));
+
+    let addresses = [sender.clone(), recipient.clone(), validator_registry.clone()];
+    let balances = |accounts: Vec<Account>| accounts.iter().map(Account::balance).collect::<Vec<Coin>>();
+    let accounts = temp_producer.blockchain.get_accounts_at(block1.block_number(), &addresses).unwrap();
+    assert_eq!(balances(accounts.clone()), vec![Coin::from_u64_unchecked(1000), Coin::ZERO, staking_account.balance()]);
+    assert_eq!(accounts[2], staking_account);
+    let accounts = temp_producer.blockchain.get_accounts_at(block2.block_number(), &addresses).unwrap();
+    assert_eq!(balances(accounts), vec![Coin::from_u64_unchecked(900), Coin::from_u64_unchecked(100), staking_account.balance()]);
+
+    // Looking up accounts must not change the head state.
+    assert_eq!(temp_producer.blockchain.state().accounts().hash(None), *block2.state_root());
+
+    // Unknown blocks are not supported.
+    assert!(temp_producer.blockchain.get_accounts_at(block2.block_number() + 1, &[validator_registry.clone()]).is_none());
+
+    // Fill up the epoch.
+    loop {
+        if let Block::Macro(_) = temp_producer.next_block(0, vec![]) {
+            break;
+        }
+    }
+
+    // Blocks of finalized epochs are not supported.
+    assert!(temp_producer.blockchain.get_accounts_at(block1.block_number(), &[validator_registry]).is_none());
+}
+
+#[test]
+fn it_can_create_transactions_proofs() {
+    let temp_producer = TemporaryBlockProducer::new();
+    let mut addresses = HashSet::new();
+    addresses.insert(Address::default());
+
+    let micro_block = temp_producer.next_block(0, vec![]);
+    let proof = temp_producer.blockchain.get_transactions_proof(&micro_block.hash(), &addresses).unwrap();
+    assert!(proof.transactions.is_empty());
+
+    let macro_block = loop {
+        if let Block::Macro(macro_block) = temp_producer.next_block(0, vec![]) {
+            break macro_block;
+        }
+    };
+
+    // Proofs for macro blocks can be verified against the transactions root.
+    let proof = temp_producer.blockchain.get_transactions_proof(&macro_block.hash(), &addresses).unwrap();
+    assert!(proof.transactions.is_empty());
+    assert_eq!(proof.proof.compute_root(vec![]).unwrap(), macro_block.header.transactions_root);
+
+    // No transaction receipts without transactions.
+    assert!(temp_producer.blockchain.get_transaction_receipts_by_address(&Address::default(), 10, 10).is_empty());
+}
+
+#[test]
+fn it_indexes_the_transactions_of_isolated_macro_blocks() {
+    let temp_producer = TemporaryBlockProducer::new();
+
+    // Fund an account outside of the chain, so that it can send a transaction.
+    let keypair = SchnorrKeyPair::generate_default_csprng();
+    let sender = Address::from(&keypair.public);
+    let recipient = Address::from([2u8; Address::SIZE]);
+    let funding = Inherent { ty: InherentType::Reward, target: sender.clone(), value: Coin::from_u64_unchecked(1000), data: vec![] };
+    let mut txn = WriteTransaction::new(&temp_producer.env);
+    temp_producer.blockchain.state().accounts().commit(&mut txn, &[], &[funding.clone()], 1).unwrap();
+    txn.commit();
+
+    let block1 = temp_producer.next_block(0, vec![]);
+    let mut transaction = Transaction::new_basic(sender.clone(), recipient.clone(), Coin::from_u64_unchecked(100), Coin::ZERO, block1.block_number(), NetworkId::UnitAlbatross);
+    transaction.proof = SignatureProof::from(keypair.public.clone(), keypair.sign(&transaction.serialize_content())).serialize_to_vec();
+    assert_eq!(temp_producer.mempool.push_transaction(transaction.clone()), ReturnCode::Accepted);
+    let block2 = temp_producer.next_block(0, vec![]);
+    assert_eq!(block2.transactions().map(Vec::len), Some(1));
+
+    let macro_block = loop {
+        if let Block::Macro(macro_block) = temp_producer.next_block(0, vec![]) {
+            break macro_block;
+        }
+    };
+
+    // The transaction is indexed as part of the micro block that included it.
+    let receipts = temp_producer.blockchain.get_transaction_receipts_by_address(&recipient, 10, 10);
+    assert_eq!(receipts.len(), 1);
+    assert_eq!(receipts[0].block_hash, block2.hash());
+
+    // A chain that only pushes the macro block indexes the transaction as part of it.
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Blockchain::new(env.clone(), NetworkId::UnitAlbatross).unwrap();
+    let mut txn = WriteTransaction::new(&env);
+    blockchain.state().accounts().commit(&mut txn, &[], &[funding], 1).unwrap();
+    txn.commit();
+
+    let transactions: Vec<Transaction> = temp_producer.blockchain.get_epoch_transactions(1, None).unwrap().collect();
+    assert_eq!(blockchain.push_isolated_macro_block(Block::Macro(macro_block.clone()), &transactions), Ok(PushResult::Extended));
+
+    let info = blockchain.get_transaction_info_by_hash(&transaction.hash()).unwrap();
+    assert_eq!(info.block_hash, macro_block.hash());
+    assert_eq!(info.block_height, macro_block.header.block_number);
+    assert_eq!(info.index, 0);
+    let receipts = blockchain.get_transaction_receipts_by_address(&sender, 10, 10);
+    assert_eq!(receipts.len(), 1);
+    assert_eq!(receipts[0].transaction_hash, transaction.hash::<Blake2bHash>());
+}
+
+#[test]
+fn it_can_prune_micro_block_bodies() {
+    let temp_producer = TemporaryBlockProducer::new();
+
+    let micro_block = temp_producer.next_block(0, vec![]);
+    let macro_block = loop {
+        let block = temp_producer.next_block(0, vec![]);
+        if let Block::Macro(_) = block {
+            break block;
+        }
+    };
+    let next_micro_block = temp_producer.next_block(0, vec![]);
+
+    let chain_store = &temp_producer.blockchain.chain_store;
+    let mut txn = temp_producer.blockchain.write_transaction();
+    chain_store.prune_micro_bodies(&mut txn, macro_block.block_number() + 1);
+    txn.commit();
+    assert_eq!(chain_store.get_pruned_height(None), macro_block.block_number() + 1);
+
+    // Pruned micro blocks are returned with their header only.
+    let pruned_block = temp_producer.blockchain.get_block(&micro_block.hash(), false, true).unwrap();
+    assert_eq!(pruned_block.hash(), micro_block.hash());
+    assert!(pruned_block.transactions().is_none());
+    let pruned_block = temp_producer.blockchain.get_block_at(micro_block.block_number(), true).unwrap();
+    assert_eq!(pruned_block.hash(), micro_block.hash());
+    assert!(pruned_block.transactions().is_none());
+    assert!(temp_producer.blockchain.get_block(&micro_block.hash(), false, false).is_some());
+
+    // Callers that need the body don't get it.
+    assert!(temp_producer.blockchain.get_epoch_transactions(1, None).is_none());
+    let genesis_hash = temp_producer.blockchain.get_block_at(0, false).unwrap().hash();
+    assert!(temp_producer.blockchain.get_blocks(&genesis_hash, 10, true, Direction::Forward).is_empty());
+    assert_eq!(temp_producer.blockchain.get_blocks(&genesis_hash, 10, false, Direction::Forward).len(), 10);
+
+    // Macro blocks and micro blocks of the current epoch are kept.
+    assert_eq!(temp_producer.blockchain.get_block(&macro_block.hash(), false, true), Some(macro_block));
+    assert_eq!(temp_producer.blockchain.get_block(&next_micro_block.hash(), false, true), Some(next_micro_block));
+}
+
+#[test]
+fn it_computes_the_history_prune_height() {
+    let window = policy::TRANSACTION_VALIDITY_WINDOW_ALBATROSS;
+    let macro_block_number = policy::macro_block_of(100);
+
+    // Nothing is pruned before the transaction validity window has passed.
+    assert_eq!(Blockchain::history_prune_height(window - 1, 0), 0);
+
+    // Few history epochs are bounded by the transaction validity window.
+    assert_eq!(Blockchain::history_prune_height(macro_block_number + 5, 1), macro_block_number + 5 - window);
+
+    // Otherwise the history is counted from the last macro block, so it moves at the epoch boundary.
+    let history_epochs = window / policy::EPOCH_LENGTH + 2;
+    let history_length = history_epochs * policy::EPOCH_LENGTH;
+    assert_eq!(Blockchain::history_prune_height(macro_block_number - 1, history_epochs),
+               macro_block_number - policy::EPOCH_LENGTH - history_length + 1);
+    assert_eq!(Blockchain::history_prune_height(macro_block_number, history_epochs),
+               macro_block_number - history_length + 1);
+    assert_eq!(Blockchain::history_prune_height(macro_block_number + 1, history_epochs),
+               macro_block_number - history_length + 1);
+
+    // Huge values don't overflow, they keep the whole history.
+    assert_eq!(Blockchain::history_prune_height(macro_block_number, u32::max_value()), 1);
+}
+
+#[test]
+fn it_can_export_and_import_snapshots() {
+    let temp_producer = TemporaryBlockProducer::new();
+    assert!(temp_producer.blockchain.export_snapshot().is_err());
+
+    let macro_block = loop {
+        if let Block::Macro(macro_block) = temp_producer.next_block(0, vec![]) {
+            break macro_block;
+        }
+    };
+    temp_producer.next_block(0, vec![]);
+
+    // Snapshots are taken at the macro head.
+    let snapshot = temp_producer.blockchain.export_snapshot().unwrap();
+    assert_eq!(snapshot.macro_block(), Some(&macro_block));
+    assert_eq!(snapshot.blocks.len() as u32, macro_block.header.block_number);
+
+    let snapshot = Snapshot::deserialize_from_vec(&snapshot.serialize_to_vec()).unwrap();
+
+    // The rebuilt accounts tree must match the state root.
+    let mut invalid_snapshot = snapshot.clone();
+    invalid_snapshot.accounts.pop();
+    let env = VolatileEnvironment::new(10).unwrap();
+    match Blockchain::import_snapshot(env, NetworkId::UnitAlbatross, invalid_snapshot) {
+        Err(SnapshotError::InvalidStateRoot) => {},
+        _ => panic!("Snapshot with invalid accounts tree was accepted"),
+    }
+
+    // The snapshot must contain exactly the blocks of the transaction validity window.
+    let mut invalid_snapshot = snapshot.clone();
+    invalid_snapshot.blocks.push(invalid_snapshot.blocks[0].clone());
+    let env = VolatileEnvironment::new(10).unwrap();
+    match Blockchain::import_snapshot(env, NetworkId::UnitAlbatross, invalid_snapshot) {
+        Err(SnapshotError::BlockCount { expected, actual }) => {
+            assert_eq!(expected, macro_block.header.block_number);
+            assert_eq!(actual, expected + 1);
+        },
+        _ => panic!("Snapshot with too many blocks was accepted"),
+    }
+    let mut invalid_snapshot = snapshot.clone();
+    invalid_snapshot.blocks.clear();
+    let env = VolatileEnvironment::new(10).unwrap();
+    match Blockchain::import_snapshot(env, NetworkId::UnitAlbatross, invalid_snapshot) {
+        Err(SnapshotError::BlockCount { actual: 0, .. }) => {},
+        _ => panic!("Snapshot without blocks was accepted"),
+    }
+
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Blockchain::import_snapshot(env.clone(), NetworkId::UnitAlbatross, snapshot.clone()).unwrap();
+    assert_eq!(blockchain.head_hash(), macro_block.hash());
+    assert_eq!(blockchain.state().accounts().hash(None), macro_block.header.state_root);
+    assert_eq!(blockchain.get_block_at(1, true), temp_producer.blockchain.get_block_at(1, true));
+
+    // Snapshots can only be imported into an empty database.
+    match Blockchain::import_snapshot(env, NetworkId::UnitAlbatross, snapshot) {
+        Err(SnapshotError::DatabaseNotEmpty) => {},
+        _ => panic!("Snapshot was imported twice"),
+    }
+}
+
+#[test]
+fn it_can_apply_snapshots() {
+    let temp_producer = TemporaryBlockProducer::new();
+    while let Block::Micro(_) = temp_producer.next_block(0, vec![]) {}
+    let macro_block = temp_producer.blockchain.macro_head().clone();
+    let snapshot = temp_producer.blockchain.export_snapshot().unwrap();
+    let slash_registry = temp_producer.blockchain.export_slash_registry(&macro_block.hash()).unwrap();
+    assert_eq!(slash_registry.serialize_to_vec(), snapshot.slash_registry.serialize_to_vec());
+
+    // The genesis accounts are replaced by the accounts of the snapshot.
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Blockchain::new(env, NetworkId::UnitAlbatross).unwrap();
+    blockchain.apply_snapshot(snapshot.clone()).unwrap();
+    assert_eq!(blockchain.head_hash(), macro_block.hash());
+    assert_eq!(blockchain.state().accounts().hash(None), macro_block.header.state_root);
+
+    // The blockchain continues from the snapshot.
+    let micro_block = temp_producer.next_block(0, vec![]);
+    assert_eq!(blockchain.push(micro_block), Ok(PushResult::Extended));
+    assert_eq!(blockchain.head_hash(), temp_producer.blockchain.head_hash());
+
+    match blockchain.apply_snapshot(snapshot) {
+        Err(SnapshotError::DatabaseNotEmpty) => {},
+        _ => panic!("Snapshot was applied to a blockchain that isn't at genesis"),
+    }
+}
+
+#[test]
+fn it_rejects_snapshots_with_invalid_slash_registries() {
+    let temp_producer = TemporaryBlockProducer::new();
+    while let Block::Micro(_) = temp_producer.next_block(0, vec![]) {}
+    let snapshot = temp_producer.blockchain.export_snapshot().unwrap();
+
+    // The reward pot is serialized last and has to add up to the rewards of the epoch.
+    let mut serialized = snapshot.serialize_to_vec();
+    *serialized.last_mut().unwrap() ^= 1;
+    let invalid_snapshot = Snapshot::deserialize_from_vec(&serialized).unwrap();
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Blockchain::new(env, NetworkId::UnitAlbatross).unwrap();
+    match blockchain.apply_snapshot(invalid_snapshot) {
+        Err(SnapshotError::InvalidSlashRegistry) => {},
+        _ => panic!("Snapshot with invalid reward pot was applied"),
+    }
+
+    // The registry must belong to the macro block of the snapshot.
+    temp_producer.next_block(0, vec![]);
+    while let Block::Micro(_) = temp_producer.next_block(0, vec![]) {}
+    let mut invalid_snapshot = temp_producer.blockchain.export_snapshot().unwrap();
+    invalid_snapshot.slash_registry = snapshot.slash_registry.clone();
+    match blockchain.apply_snapshot(invalid_snapshot) {
+        Err(SnapshotError::InvalidSlashRegistry) => {},
+        _ => panic!("Snapshot with slash registry of another epoch was applied"),
+    }
+
+    blockchain.apply_snapshot(snapshot).unwrap();
+}
+
+#[test]
+fn it_can_verify_and_repair_the_database() {
+    let temp_producer = TemporaryBlockProducer::new();
+    while let Block::Micro(_) = temp_producer.next_block(0, vec![]) {}
+    let macro_block = temp_producer.blockchain.macro_head().clone();
+    let block1 = temp_producer.next_block(0, vec![]);
+    temp_producer.next_block(0, vec![]);
+
+    let checker = IntegrityChecker::new(temp_producer.env.clone(), NetworkId::UnitAlbatross);
+    assert_eq!(checker.check(), Ok(()));
+    assert_eq!(checker.repair().unwrap(), None);
+
+    // Move the head back without unlinking the blocks after it.
+    let mut txn = WriteTransaction::new(&temp_producer.env);
+    temp_producer.blockchain.chain_store.set_head(&mut txn, &block1.hash());
+    txn.commit();
+    assert_eq!(checker.check(), Err(Inconsistency::BrokenSuccessorLink(block1.block_number())));
+
+    assert_eq!(checker.repair().unwrap(), Some(macro_block.header.block_number));
+    assert_eq!(checker.check(), Ok(()));
+
+    let blockchain = Blockchain::new(temp_producer.env.clone(), NetworkId::UnitAlbatross).unwrap();
+    assert_eq!(blockchain.head_hash(), macro_block.hash());
+    assert_eq!(blockchain.state().accounts().hash(None), macro_block.header.state_root);
+}
+
+#[test]
+fn it_can_export_and_import_blocks() {
+    let temp_producer = TemporaryBlockProducer::new();
+    while let Block::Micro(_) = temp_producer.next_block(0, vec![]) {}
+    let micro_block = temp_producer.next_block(0, vec![]);
+
+    // Finished epochs are exported as one entry, the current epoch block by block.
+    let mut writer = BlockFileWriter::new(Vec::new(), NetworkId::UnitAlbatross).unwrap();
+    let mut exported = vec![];
+    let head_number = temp_producer.blockchain.export_blocks(&mut writer, |block_number| exported.push(block_number)).unwrap();
+    assert_eq!(head_number, micro_block.block_number());
+    assert_eq!(exported, vec![policy::EPOCH_LENGTH, policy::EPOCH_LENGTH + 1]);
+    let file = writer.finish().unwrap();
+
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Blockchain::new(env, NetworkId::UnitAlbatross).unwrap();
+    let mut reader = BlockFileReader::new(&file[..]).unwrap();
+    assert_eq!(blockchain.import_blocks(&mut reader, |_| {}).unwrap(), 2);
+    assert_eq!(blockchain.head_hash(), temp_producer.blockchain.head_hash());
+    assert_eq!(blockchain.state().accounts().hash(None), temp_producer.blockchain.state().accounts().hash(None));
+
+    // Importing the same file again doesn't change anything.
+    let mut reader = BlockFileReader::new(&file[..]).unwrap();
+    assert_eq!(blockchain.import_blocks(&mut reader, |_| {}).unwrap(), 0);
+
+    // A file that ends within an entry or its length prefix is truncated.
+    for len in &[file.len() - 1, NetworkId::UnitAlbatross.serialized_size() + 2] {
+        let mut reader = BlockFileReader::new(&file[..*len]).unwrap();
+        let result = loop {
+            match reader.read() {
+                Ok(Some(_)) => {},
+                result => break result,
+            }
+        };
+        match result {
+            Err(BlockFileError::Truncated) => {},
+            _ => panic!("Truncated block file was read"),
+        }
+    }
+
+    // Entries can't exceed the maximum entry size.
+    let mut file = NetworkId::UnitAlbatross.serialize_to_vec();
+    file.extend_from_slice(&u32::max_value().to_be_bytes());
+    let mut reader = BlockFileReader::new(&file[..]).unwrap();
+    match reader.read() {
+        Err(BlockFileError::EntryTooLarge(len)) => assert_eq!(len, u32::max_value()),
+        _ => panic!("Oversized entry was read"),
+    }
+
+    // Import stops at the first block that can't be pushed.
+    let mut writer = BlockFileWriter::new(Vec::new(), NetworkId::UnitAlbatross).unwrap();
+    writer.write(&BlockFileEntry::Block(micro_block)).unwrap();
+    let file = writer.finish().unwrap();
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Blockchain::new(env, NetworkId::UnitAlbatross).unwrap();
+    let mut reader = BlockFileReader::new(&file[..]).unwrap();
+    match blockchain.import_blocks(&mut reader, |_| {}) {
+        Err(BlockFileError::Push(block_number, PushError::Orphan)) => assert_eq!(block_number, policy::EPOCH_LENGTH + 1),
+        _ => panic!("Orphan block was imported"),
+    }
+}
+
+#[test]
+fn it_can_follow_macro_blocks_with_light_blockchain() {
+    let temp_producer = TemporaryBlockProducer::new();
+    let env = VolatileEnvironment::new(10).unwrap();
+    let light_blockchain = LightBlockchain::new(env, NetworkId::UnitAlbatross).unwrap();
+
+    let mut macro_blocks = vec![];
+    for _ in 0..2 {
+        loop {
+            match temp_producer.next_block(0, vec![]) {
+                Block::Macro(macro_block) => {
+                    macro_blocks.push(macro_block);
+                    break;
+                },
+                micro_block => {
+                    assert!(!light_blockchain.needs_block(&micro_block.header()));
+                    assert_eq!(light_blockchain.push(micro_block), Ok(PushResult::Ignored));
+                },
+            }
+        }
+    }
+
+    // Only unknown macro blocks after the head need to be downloaded.
+    let header = Block::Macro(macro_blocks[0].clone()).header();
+    assert!(light_blockchain.needs_block(&header));
+
+    // Macro blocks must follow on the macro head.
+    assert_eq!(light_blockchain.push(Block::Macro(macro_blocks[1].clone())), Err(PushError::Orphan));
+
+    assert_eq!(light_blockchain.push(Block::Macro(macro_blocks[0].clone())), Ok(PushResult::Extended));
+    assert_eq!(light_blockchain.push(Block::Macro(macro_blocks[0].clone())), Ok(PushResult::Known));
+    assert_eq!(light_blockchain.push(Block::Macro(macro_blocks[1].clone())), Ok(PushResult::Extended));
+    assert_eq!(light_blockchain.head_hash(), temp_producer.blockchain.head_hash());
+    assert_eq!(light_blockchain.state_root(), temp_producer.blockchain.head().state_root().clone());
+    assert!(!light_blockchain.needs_block(&header));
+
+    // The light blockchain doesn't know any accounts.
+    let validator_registry = temp_producer.blockchain.validator_registry_address().unwrap().clone();
+    assert_eq!(light_blockchain.get_account(&validator_registry), None);
+}
+
+#[test]
+fn it_can_get_accounts_from_proofs_with_light_blockchain() {
+    let temp_producer = TemporaryBlockProducer::new();
+    let macro_block = loop {
+        if let Block::Macro(macro_block) = temp_producer.next_block(0, vec![]) {
+            break macro_block;
+        }
+    };
+    let state_root = macro_block.header.state_root.clone();
+
+    let validator_registry = temp_producer.blockchain.validator_registry_address().unwrap().clone();
+    let unknown_address = Address::from([0x42u8; Address::SIZE]);
+    let addresses = vec![validator_registry.clone(), unknown_address];
+    let proof = temp_producer.blockchain.get_accounts_proof(&macro_block.hash(), &addresses).unwrap();
+
+    // Accounts that are part of the tree are taken from the proof, absent accounts are empty.
+    let accounts = LightBlockchain::get_accounts_from_proof(proof.clone(), &state_root, &addresses).unwrap();
+    assert_eq!(accounts[0], temp_producer.blockchain.get_account(&validator_registry).unwrap());
+    assert_eq!(accounts[1], Account::INITIAL);
+
+    // The proof must match the state root.
+    assert_eq!(LightBlockchain::get_accounts_from_proof(proof.clone(), &Blake2bHash::default(), &addresses), None);
+
+    // Accounts that the proof doesn't cover are rejected.
+    let other_address = Address::from([0x23u8; Address::SIZE]);
+    let proof = temp_producer.blockchain.get_accounts_proof(&macro_block.hash(), &[validator_registry.clone()]).unwrap();
+    assert_eq!(LightBlockchain::get_accounts_from_proof(proof, &state_root, &[validator_registry, other_address]), None);
+}
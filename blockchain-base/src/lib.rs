@@ -1,3 +1,7 @@
+#[cfg(feature = "transaction-store")]
+#[macro_use]
+extern crate beserial_derive;
+
 extern crate nimiq_account as account;
 extern crate nimiq_block_base as block_base;
 extern crate nimiq_database as database;
@@ -32,6 +36,8 @@ use utils::observer::{Listener, ListenerHandle};
 
 #[cfg(feature = "metrics")]
 pub mod chain_metrics;
+#[cfg(feature = "transaction-store")]
+pub mod transaction_store;
 
 pub trait AbstractBlockchain: Sized + Send + Sync {
     // TODO: Should this be `Block + 'static`? Our implementations would satisfy this anyway. And
@@ -100,7 +106,9 @@ pub trait AbstractBlockchain: Sized + Send + Sync {
 
     fn lock(&self) -> MutexGuard<()>;
 
-    fn get_account(&self, address: &Address) -> Account;
+    /// Returns the account at `address` in the state of the head. Returns `None` if the state of
+    /// the accounts isn't known, e.g. to a blockchain that only follows the macro blocks.
+    fn get_account(&self, address: &Address) -> Option<Account>;
 
     fn contains_tx_in_validity_window(&self, tx_hash: &Blake2bHash) -> bool;
 
@@ -802,8 +802,8 @@ impl AbstractBlockchain for Blockchain {
         self.push_lock.lock()
     }
 
-    fn get_account(&self, address: &Address) -> Account {
-        self.state.read().accounts.get(address, None)
+    fn get_account(&self, address: &Address) -> Option<Account> {
+        Some(self.state.read().accounts.get(address, None))
     }
 
     fn contains_tx_in_validity_window(&self, tx_hash: &Blake2bHash) -> bool {
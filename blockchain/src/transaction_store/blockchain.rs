@@ -6,16 +6,6 @@ use database::ReadTransaction;
 use crate::blockchain::Blockchain;
 use crate::transaction_store::TransactionInfo;
 
-impl From<TransactionInfo> for TransactionReceipt {
-    fn from(info: TransactionInfo) -> Self {
-        TransactionReceipt {
-            transaction_hash: info.transaction_hash,
-            block_hash: info.block_hash,
-            block_height: info.block_height,
-        }
-    }
-}
-
 impl Blockchain {
     pub fn get_transaction_receipts_by_address(&self, address: &Address, sender_limit: usize, recipient_limit: usize) -> Vec<TransactionReceipt> {
         let mut receipts;
@@ -16,6 +16,7 @@ use hash::Hash;
 use super::{
     AggregatePublicKey as GenericAggregatePublicKey,
     AggregateSignature as GenericAggregateSignature,
+    BatchVerification as GenericBatchVerification,
     hash_to_g1,
     KeyPair as GenericKeyPair,
     PublicKey as GenericPublicKey,
@@ -64,6 +65,7 @@ impl Ord for PublicKey {
 
 pub type AggregatePublicKey = GenericAggregatePublicKey<Bls12>;
 pub type AggregateSignature = GenericAggregateSignature<Bls12>;
+pub type BatchVerification = GenericBatchVerification<Bls12>;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct PublicKeyAffine {
@@ -288,6 +288,65 @@ impl<E: Engine> Default for AggregateSignature<E> {
     }
 }
 
+/// Verifies many signatures over the same message with a single pairing check.
+///
+/// Every (public key, signature) pair is weighted with a random non-zero 64-bit scalar before
+/// they are summed up. Without the weights, an invalid signature could be compensated by another
+/// invalid signature in the same batch.
+///
+/// If the batch fails, at least one of the signatures is invalid. It doesn't tell which one.
+#[derive(Clone)]
+pub struct BatchVerification<E: Engine> {
+    hash: SigHash,
+    entries: Vec<(E::G2, E::G1)>,
+}
+
+impl<E: Engine> BatchVerification<E> {
+    pub fn new(hash: SigHash) -> Self {
+        BatchVerification {
+            hash,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, public_key: &PublicKey<E>, signature: &Signature<E>) {
+        self.entries.push((public_key.p_pub, signature.s));
+    }
+
+    pub fn push_aggregate(&mut self, public_key: &AggregatePublicKey<E>, signature: &AggregateSignature<E>) {
+        self.push(&public_key.0, &signature.0);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Checks `e(sum(r_i * s_i), g2) == e(H(m), sum(r_i * p_i))` for random scalars `r_i`.
+    pub fn verify<R: Rng + CryptoRng>(&self, rng: &mut R) -> bool {
+        let mut signature = E::G1::zero();
+        let mut public_key = E::G2::zero();
+        for (p_pub, s) in &self.entries {
+            let r = rng.gen_range(1, u64::max_value());
+
+            let mut s = *s;
+            s.mul_assign(r);
+            signature.add_assign(&s);
+
+            let mut p_pub = *p_pub;
+            p_pub.mul_assign(r);
+            public_key.add_assign(&p_pub);
+        }
+
+        let lhs = E::pairing(signature, E::G2Affine::one());
+        let rhs = E::pairing(hash_to_g1::<E>(self.hash.clone()), public_key);
+        lhs == rhs
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::vec::Vec;
@@ -358,4 +417,40 @@ mod tests {
             true
         );
     }
+
+    #[test]
+    fn batch_verify_same_messages() {
+        let mut rng = XorShiftRng::from_seed([0x44, 0x6d, 0x4f, 0xbc, 0x6c, 0x27, 0x2f, 0xd6, 0xd0, 0xaf, 0x63, 0xb9, 0x3d, 0x86, 0x55, 0x54]);
+        let mut batch_rng = ChaChaRng::from_seed([0; 32]);
+
+        let message = "Same message";
+        let hash = Hash::hash::<SigHash>(&message);
+        let keypairs: Vec<KeyPair<Bls12>> = (0..20).map(|_| KeyPair::generate_predictable(&mut rng)).collect();
+
+        let mut batch = BatchVerification::new(hash.clone());
+        for keypair in &keypairs[..10] {
+            batch.push(&keypair.public, &keypair.sign(&message));
+        }
+        let akey = AggregatePublicKey::from_public_keys(&keypairs[10..].iter().map(|keypair| keypair.public).collect::<Vec<_>>());
+        let asig = AggregateSignature::from_signatures(&keypairs[10..].iter().map(|keypair| keypair.sign(&message)).collect::<Vec<_>>());
+        batch.push_aggregate(&akey, &asig);
+        assert_eq!(batch.len(), 11);
+        assert!(batch.verify(&mut batch_rng));
+
+        // A signature over another message fails the whole batch
+        let mut forged = batch.clone();
+        forged.push(&keypairs[0].public, &keypairs[0].sign(&"Other message"));
+        assert!(!forged.verify(&mut batch_rng));
+
+        // Two invalid signatures that sum up to valid ones must not pass either
+        let mut swapped = BatchVerification::new(hash);
+        let mut s0 = keypairs[0].sign(&message);
+        let mut s1 = keypairs[1].sign(&message);
+        let delta = keypairs[2].sign(&message);
+        s0.s.add_assign(&delta.s);
+        s1.s.sub_assign(&delta.s);
+        swapped.push(&keypairs[0].public, &s0);
+        swapped.push(&keypairs[1].public, &s1);
+        assert!(!swapped.verify(&mut batch_rng));
+    }
 }
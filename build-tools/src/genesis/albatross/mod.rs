@@ -115,11 +115,12 @@ impl GenesisBuilder {
         self
     }
 
-    pub fn with_genesis_validator(&mut self, validator_key: BlsPublicKey, reward_address: Address, balance: Coin) -> &mut Self {
+    pub fn with_genesis_validator(&mut self, validator_key: BlsPublicKey, reward_address: Address, balance: Coin, commission: u16) -> &mut Self {
         self.validators.push(config::GenesisValidator {
             validator_key,
             reward_address,
-            balance
+            balance,
+            commission,
         });
         self
     }
@@ -249,7 +250,7 @@ impl GenesisBuilder {
         let mut contract = StakingContract::default();
 
         for validator in self.validators.iter() {
-            contract.create_validator(validator.validator_key.compress(), validator.reward_address.clone(), validator.balance)?;
+            contract.create_validator(validator.validator_key.compress(), validator.reward_address.clone(), validator.balance, validator.commission)?;
         }
 
         for stake in self.stakes.iter() {
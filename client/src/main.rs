@@ -12,6 +12,8 @@ use tokio;
 use tokio::timer::Interval;
 
 use nimiq::prelude::*;
+use nimiq::config::command_line::Command;
+use nimiq::config::config::ConsensusConfig;
 use nimiq::extras::logging::{initialize_logging, log_error_cause_chain};
 use nimiq::extras::deadlock::initialize_deadlock_detection;
 use nimiq::extras::panic::initialize_panic_reporting;
@@ -45,6 +47,17 @@ fn main_inner() -> Result<(), Error> {
     let config = builder.build()?;
     debug!("Final configuration: {:#?}", config);
 
+    // Run command instead of the client, if one was given
+    if let Some(command) = command_line.command {
+        return run_command(command, config);
+    }
+
+    // Light clients only follow the macro blocks and don't run any servers
+    if config.consensus == ConsensusConfig::Light {
+        run_light_client(config, config_file.log.statistics);
+        return Ok(());
+    }
+
     // We need to instantiate the client when the tokio runtime is already alive, so we use
     // a lazy future for it.
     tokio::run(
@@ -63,10 +76,17 @@ fn main_inner() -> Result<(), Error> {
             let client: Client = Client::try_from(config)?;
             client.initialize()?;
 
+            // The RPC methods are shared by the RPC server and the websocket RPC server
+            let rpc_modules = if rpc_config.is_some() || ws_rpc_config.is_some() {
+                Some(nimiq::extras::rpc_server::RpcModules::new(&client))
+            } else {
+                None
+            };
+
             // Initialize RPC server
-            if let Some(rpc_config) = rpc_config {
+            if let (Some(rpc_config), Some(rpc_modules)) = (rpc_config, &rpc_modules) {
                 use nimiq::extras::rpc_server::initialize_rpc_server;
-                let rpc_server = initialize_rpc_server(&client, rpc_config)
+                let rpc_server = initialize_rpc_server(&client, rpc_config, rpc_modules)
                     .expect("Failed to initialize RPC server");
                 tokio::spawn(rpc_server.into_future());
             }
@@ -88,9 +108,9 @@ fn main_inner() -> Result<(), Error> {
 
             // Initialize Websocket RPC server
             // TODO: Configuration
-            if let Some(ws_rpc_config) = ws_rpc_config {
+            if let (Some(ws_rpc_config), Some(rpc_modules)) = (ws_rpc_config, &rpc_modules) {
                 use nimiq::extras::ws_rpc_server::initialize_ws_rcp_server;
-                let ws_rpc_server = initialize_ws_rcp_server(&client, ws_rpc_config)
+                let ws_rpc_server = initialize_ws_rcp_server(&client, ws_rpc_config, rpc_modules)
                     .expect("Failed to initialize websocket RPC server");
                 tokio::spawn(ws_rpc_server.into_future());
             }
@@ -134,6 +154,56 @@ fn main_inner() -> Result<(), Error> {
     Ok(())
 }
 
+fn run_light_client(config: ClientConfig, statistics_interval: u64) {
+    tokio::run(
+        future::lazy(move || {
+            info!("Initializing light client");
+            let client = LightClient::try_from(config)?;
+            client.initialize()?;
+
+            info!("Connecting to network");
+            client.connect()?;
+
+            Ok(client)
+        })
+            .and_then(move |client| {
+                let (statistics_interval, show_statistics) = match statistics_interval {
+                    0 => (10, false),
+                    interval => (interval, true),
+                };
+
+                Interval::new_interval(Duration::from_secs(statistics_interval))
+                    .map_err(|e| panic!("Timer failed: {}", e))
+                    .for_each(move |_| {
+                        if show_statistics {
+                            let peer_count = client.network().connections.peer_count();
+                            let blockchain = client.blockchain();
+                            info!("Macro head: #{} - {}, Peers: {}", blockchain.block_number(), blockchain.head_hash(), peer_count);
+                        }
+
+                        future::ok::<(), Error>(())
+                    })
+            })
+            .map_err(|e: Error| warn!("{}", e)));
+}
+
+fn run_command(command: Command, config: ClientConfig) -> Result<(), Error> {
+    use nimiq::extras::block_file::{export_blocks, import_blocks};
+    use nimiq::extras::snapshot::{export_snapshot, import_snapshot};
+    use nimiq::extras::verify_db::verify_db;
+    use nimiq::extras::slashing_protection::{export_slashing_protection, import_slashing_protection};
+
+    match command {
+        Command::ExportSnapshot { file } => export_snapshot(config, file),
+        Command::ImportSnapshot { file } => import_snapshot(config, file),
+        Command::ExportBlocks { file } => export_blocks(config, file),
+        Command::ImportBlocks { file } => import_blocks(config, file),
+        Command::VerifyDb { repair } => verify_db(config, repair),
+        Command::ExportSlashingProtection { file } => export_slashing_protection(config, file),
+        Command::ImportSlashingProtection { file } => import_slashing_protection(config, file),
+    }
+}
+
 fn main() {
     if let Err(e) = main_inner() {
         log_error_cause_chain(&e);
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::collections::VecDeque;
 use std::sync::{Arc, Weak};
 use std::sync::atomic::AtomicBool;
@@ -27,25 +27,33 @@ pub struct AccountsChunkCache<B: AbstractBlockchain + 'static> {
     blockchain: Arc<B>,
     env: Environment,
     computing_enabled: AtomicBool,
-    chunks_by_prefix_by_block: RwLock<HashMap<Blake2bHash, HashMap<String, SerializedChunk>>>,
+    /// Whether the chunks of finalized blocks are computed in advance for the state sync of
+    /// other nodes.
+    serves_state: bool,
+    chunks_by_prefix_by_block: RwLock<HashMap<Blake2bHash, BTreeMap<String, SerializedChunk>>>,
     tasks_by_block: RwLock<HashMap<Blake2bHash, Vec<Task>>>,
     block_history_order: RwLock<VecDeque<Blake2bHash>>,
+    /// The chunks of the latest finalized block are kept outside of the backlog, so that they
+    /// stay available for state sync during the whole epoch.
+    finalized_block: RwLock<Option<Blake2bHash>>,
     weak_self: MutableOnce<Weak<Self>>
 }
 
 impl<B: AbstractBlockchain + 'static> AccountsChunkCache<B> {
 
     const MAX_BLOCKS_BACKLOG: usize = 10;
-    const CHUNK_SIZE_MAX: usize = 5000;
+    pub(crate) const CHUNK_SIZE_MAX: usize = 5000;
 
-    pub fn new(env: Environment, blockchain: Arc<B>) -> Arc<Self> {
+    pub fn new(env: Environment, blockchain: Arc<B>, serves_state: bool) -> Arc<Self> {
         let cache = AccountsChunkCache {
             blockchain,
             env,
             computing_enabled: AtomicBool::new(false),
+            serves_state,
             chunks_by_prefix_by_block: RwLock::new(HashMap::with_capacity(Self::MAX_BLOCKS_BACKLOG + 1)),
             tasks_by_block: RwLock::new(HashMap::with_capacity(Self::MAX_BLOCKS_BACKLOG + 1)),
             block_history_order: RwLock::new(VecDeque::with_capacity(Self::MAX_BLOCKS_BACKLOG + 1)),
+            finalized_block: RwLock::new(None),
             weak_self: MutableOnce::new(Weak::new()),
         };
         let cache_arc = Arc::new(cache);
@@ -58,32 +66,39 @@ impl<B: AbstractBlockchain + 'static> AccountsChunkCache<B> {
     }
 
     /// Request a chunk from the cache.
+    ///
+    /// Once all chunks of a block have been computed, a prefix that isn't the start of a chunk is
+    /// answered with the chunk covering it, i.e. the one with the greatest start before the prefix.
     pub fn get_chunk(&self, hash: &Blake2bHash, prefix: &str) -> GetChunkFuture<B> {
         // Start computing of chunks on the first get_chunk request.
         // Swap should ensure that this is only triggered *once* and that no race condition can occur.
         if !self.computing_enabled.swap(true, Ordering::AcqRel) {
-            self.compute_chunks_for_block();
+            self.compute_chunks_for_block(None);
         }
         GetChunkFuture::new(hash.clone(), prefix.to_string(), self.weak_self.upgrade().unwrap())
     }
 
     /// Trigger computation of chunks asynchronously after blockchain events.
     fn on_blockchain_event(&self, event: &BlockchainEvent<B::Block>) {
-        if !self.computing_enabled.load(Ordering::Acquire) {
-            // Only pre-compute chunks after a chunk was requested for the first time
-            return;
-        }
         match event {
-            BlockchainEvent::Extended(_) | BlockchainEvent::Rebranched(_, _) => {
-                self.compute_chunks_for_block();
+            // Chunks of finalized blocks are always computed if we serve the state sync, since
+            // nodes doing a state sync request them.
+            BlockchainEvent::Finalized(hash) if self.serves_state => {
+                self.compute_chunks_for_block(Some(hash.clone()));
+            },
+            // Only pre-compute chunks after a chunk was requested for the first time
+            _ if !self.computing_enabled.load(Ordering::Acquire) => (),
+            BlockchainEvent::Extended(_) | BlockchainEvent::Rebranched(_, _) | BlockchainEvent::Finalized(_) => {
+                self.compute_chunks_for_block(None);
             },
-            BlockchainEvent::Finalized(_) => (),
         }
     }
 
     /// Internal function to asynchronously triggering the computation and caching of chunks.
     /// This function assumes to be called at most *once* per block hash.
-    fn compute_chunks_for_block(&self) {
+    ///
+    /// If `finalized_hash` is given, the chunks are only computed if that block is still the head.
+    fn compute_chunks_for_block(&self, finalized_hash: Option<Blake2bHash>) {
         let weak = self.weak_self.clone();
         thread::spawn(move || {
             let this: Arc<Self> = upgrade_weak!(weak);
@@ -92,11 +107,24 @@ impl<B: AbstractBlockchain + 'static> AccountsChunkCache<B> {
                 Some(hash) => hash,
                 None => return,
             };
+            if let Some(finalized_hash) = finalized_hash {
+                if finalized_hash != hash {
+                    return;
+                }
+
+                // Replace the chunks of the previous finalized block.
+                if let Some(block_hash) = this.finalized_block.write().replace(hash.clone()) {
+                    if block_hash != hash {
+                        this.chunks_by_prefix_by_block.write().remove(&block_hash);
+                    }
+                }
+                this.block_history_order.write().retain(|block_hash| block_hash != &hash);
+            }
 
             // Check that this hash is not yet worked on.
             {
                 let mut guard = this.tasks_by_block.write();
-                if guard.contains_key(&hash) {
+                if guard.contains_key(&hash) || this.chunks_by_prefix_by_block.read().contains_key(&hash) {
                     return;
                 }
                 // Requests for accounts tree chunks of `block` are accepted now.
@@ -107,7 +135,7 @@ impl<B: AbstractBlockchain + 'static> AccountsChunkCache<B> {
 
             // Compute and store chunks.
             let chunk_start = Instant::now();
-            this.chunks_by_prefix_by_block.write().insert(hash.clone(), HashMap::new());
+            this.chunks_by_prefix_by_block.write().insert(hash.clone(), BTreeMap::new());
             let mut prefix = "".to_string();
             while let Some(chunk) = this.blockchain.get_accounts_chunk(&prefix[..], Self::CHUNK_SIZE_MAX, Some(&txn)) {
                 if let Some(chunks_by_prefix) = this.chunks_by_prefix_by_block.write().get_mut(&hash) {
@@ -129,9 +157,14 @@ impl<B: AbstractBlockchain + 'static> AccountsChunkCache<B> {
             // The chunks are cached, so newly created requests will not need to enter them into the list of tasks.
             this.tasks_by_block.write().remove(&hash.clone());
 
-            let num_chunks = this.chunks_by_prefix_by_block.read().get(&hash).map_or(0, HashMap::len);
+            let num_chunks = this.chunks_by_prefix_by_block.read().get(&hash).map_or(0, BTreeMap::len);
             trace!("Computing {} chunks for block {} tree took {:?}", num_chunks, hash, chunk_start.elapsed());
 
+            // The chunks of the finalized block are kept until the next block is finalized.
+            if this.finalized_block.read().as_ref() == Some(&hash) {
+                return;
+            }
+
             // Put those blocks that are cached into a history, so that we can remove them later on.
             this.block_history_order.write().push_back(hash.clone());
 
@@ -195,11 +228,16 @@ impl<B: AbstractBlockchain> Future for GetChunkFuture<B> {
                 tasks.push(task::current());
                 self.running = true;
             }
-            Ok(Async::NotReady)
-        } else {
-            // Else, the block is unknown, too far in the past or something else.
-            // Return None in these cases.
-            Ok(Async::Ready(None))
+            return Ok(Async::NotReady);
+        }
+        // All chunks of the block are computed, so deliver the chunk covering the prefix.
+        if let Some(chunks_by_prefix) = self.chunk_cache.chunks_by_prefix_by_block.read().get(&self.hash) {
+            if let Some((_, chunk)) = chunks_by_prefix.range(..self.prefix.clone()).next_back() {
+                return Ok(Async::Ready(Some(chunk.clone())));
+            }
         }
+        // Else, the block is unknown, too far in the past or something else.
+        // Return None in these cases.
+        Ok(Async::Ready(None))
     }
 }
@@ -5,15 +5,20 @@ use std::collections::HashMap;
 use std::sync::{Arc, Weak};
 use std::time::Duration;
 
+use futures::{future, Future};
 use parking_lot::RwLock;
 use rand::seq::SliceRandom;
 use rand::thread_rng;
 
+use account::Account;
+use blockchain_albatross::light_blockchain::LightBlockchain;
 use blockchain_base::{AbstractBlockchain, BlockchainEvent};
 use database::Environment;
+use keys::Address;
 use macros::upgrade_weak;
 use mempool::{Mempool, MempoolEvent, MempoolConfig};
 use network::{Network, NetworkConfig, NetworkEvent, Peer};
+use network::connection::close_type::CloseType;
 use network_primitives::networks::NetworkId;
 use network_primitives::time::NetworkTime;
 use transaction::Transaction;
@@ -26,6 +31,7 @@ use crate::consensus_agent::{ConsensusAgent, ConsensusAgentEvent, sync::BlockQue
 use crate::error::Error;
 use crate::inventory::InventoryManager;
 use crate::protocol::ConsensusProtocol;
+use crate::protocol::albatross::AlbatrossLightConsensusProtocol;
 
 pub struct Consensus<P: ConsensusProtocol + 'static> {
     pub blockchain: Arc<P::Blockchain>,
@@ -65,6 +71,9 @@ struct ConsensusState<P: ConsensusProtocol + 'static> {
     agents: ConsensusAgentMap<P>,
 
     sync_peer: Option<Arc<Peer>>,
+
+    /// Flag indicating that the blockchain sync is suspended, e.g. during a state sync.
+    sync_suspended: bool,
 }
 
 impl<P: ConsensusProtocol> Consensus<P> {
@@ -75,8 +84,10 @@ impl<P: ConsensusProtocol> Consensus<P> {
         let network_time = Arc::new(NetworkTime::new());
         let blockchain = Arc::new(<P::Blockchain as AbstractBlockchain>::new(env.clone(), network_id, Arc::clone(&network_time))?);
         let mempool = Mempool::new(Arc::clone(&blockchain), mempool_config);
+        // Only full nodes serve the state sync of other nodes.
+        let serves_state = network_config.services().provided.is_full_node();
         let network = Network::new(Arc::clone(&blockchain), network_config, network_time, network_id)?;
-        let accounts_chunk_cache = AccountsChunkCache::new(env.clone(), Arc::clone(&blockchain));
+        let accounts_chunk_cache = AccountsChunkCache::new(env.clone(), Arc::clone(&blockchain), serves_state);
         let block_queue = BlockQueue::new(Arc::clone(&blockchain));
 
         let this = Arc::new(Consensus {
@@ -95,6 +106,7 @@ impl<P: ConsensusProtocol> Consensus<P> {
                 agents: HashMap::new(),
 
                 sync_peer: None,
+                sync_suspended: false,
             }),
 
             self_weak: MutableOnce::new(Weak::new()),
@@ -265,7 +277,7 @@ impl<P: ConsensusProtocol> Consensus<P> {
         let mut state = self.state.write();
 
         // Wait for ongoing sync to finish.
-        if state.sync_peer.is_some() {
+        if state.sync_peer.is_some() || state.sync_suspended {
             return;
         }
 
@@ -333,4 +345,49 @@ impl<P: ConsensusProtocol> Consensus<P> {
     pub fn established(&self) -> bool {
         self.state.read().established
     }
+
+    /// Stops syncing the blockchain with new peers. A sync that is already running is finished.
+    pub(crate) fn suspend_sync(&self) {
+        self.state.write().sync_suspended = true;
+    }
+
+    /// Resumes syncing the blockchain after `suspend_sync`.
+    pub(crate) fn resume_sync(&self) {
+        self.state.write().sync_suspended = false;
+        self.sync_blockchain();
+    }
+}
+
+impl Consensus<AlbatrossLightConsensusProtocol> {
+    /// Requests the given accounts at our head from a synced full node. The accounts proof is
+    /// verified against the state root of the head.
+    pub fn get_accounts(&self, addresses: Vec<Address>) -> impl Future<Item=Vec<Account>, Error=Error> {
+        let agent = {
+            let state = self.state.read();
+            let candidates: Vec<&Arc<ConsensusAgent<AlbatrossLightConsensusProtocol>>> = state.agents.values()
+                .filter(|&agent| agent.synced() && agent.peer.peer_address().services.is_full_node())
+                .collect();
+            candidates.choose(&mut thread_rng()).map(|&agent| Arc::clone(agent))
+        };
+        let agent = match agent {
+            Some(agent) => agent,
+            None => return future::Either::A(future::err(Error::NoSyncedPeer)),
+        };
+
+        let (block_hash, state_root) = {
+            let head = self.blockchain.head();
+            (head.hash(), head.state_root().clone())
+        };
+
+        future::Either::B(agent.get_accounts_proof(block_hash, addresses.clone())
+            .map_err(|_| Error::RequestCancelled)
+            .and_then(move |proof| {
+                let proof = proof.ok_or(Error::MissingAccountsProof)?;
+                LightBlockchain::get_accounts_from_proof(proof, &state_root, &addresses).ok_or_else(|| {
+                    warn!("Invalid accounts proof from {} - closing the channel", agent.peer.peer_address());
+                    agent.peer.channel.close(CloseType::InvalidAccountsProof);
+                    Error::InvalidAccountsProof
+                })
+            }))
+    }
 }
@@ -1,20 +1,26 @@
+use std::collections::VecDeque;
 use std::sync::{Arc, Weak};
 use std::time::Duration;
 
+use futures::sync::oneshot;
 use parking_lot::Mutex;
 use parking_lot::MutexGuard;
 use parking_lot::RwLock;
 use rand::Rng;
 
+use account::Account;
 use beserial::Serialize;
 use block_base::Block;
 use blockchain_base::{AbstractBlockchain, PushError, PushResult};
 use hash::Blake2bHash;
+use keys::Address;
 use macros::upgrade_weak;
 use mempool::{Mempool, ReturnCode};
 use network::connection::close_type::CloseType;
 use network::Peer;
 use network_messages::{
+    AccountsProofMessage,
+    GetAccountsProofMessage,
     GetBlockProofMessage,
     GetBlocksMessage,
     GetEpochTransactionsMessage,
@@ -25,6 +31,7 @@ use network_messages::{
 use network_primitives::subscription::Subscription;
 use primitives::coin::Coin;
 use transaction::Transaction;
+use tree_primitives::accounts_proof::AccountsProof;
 use utils::mutable_once::MutableOnce;
 use utils::observer::{Notifier, weak_listener, weak_passthru_listener};
 use utils::rate_limit::RateLimit;
@@ -83,12 +90,16 @@ pub struct ConsensusAgentState {
 
     /// Rate limit for GetEpochTransactions messages.
     epoch_transactions_limit: RateLimit,
+
+    /// Our pending GetAccountsProof requests, in the order they were sent.
+    accounts_proof_requests: VecDeque<(Blake2bHash, oneshot::Sender<Option<AccountsProof<Account>>>)>,
 }
 
 #[derive(Ord, PartialOrd, PartialEq, Eq, Hash, Clone, Copy, Debug)]
 enum ConsensusAgentTimer {
     Mempool,
     ResyncThrottle,
+    AccountsProof,
 }
 
 
@@ -115,6 +126,7 @@ impl<P: ConsensusProtocol + 'static> ConsensusAgent<P> {
     const GET_BLOCKS_TIMEOUT: Duration = Duration::from_secs(10);
     const GET_BLOCKS_MAX_RESULTS: u16 = 500;
     const RESYNC_THROTTLE: Duration = Duration::from_millis(10);
+    const ACCOUNTS_PROOF_TIMEOUT: Duration = Duration::from_secs(5);
 
     const CHAIN_PROOF_RATE_LIMIT: usize = 3; // per minute
     const BLOCK_PROOF_RATE_LIMIT: usize = 60; // per minute
@@ -156,6 +168,8 @@ impl<P: ConsensusProtocol + 'static> ConsensusAgent<P> {
                 transactions_proof_limit: RateLimit::new_per_minute(Self::TRANSACTIONS_PROOF_RATE_LIMIT),
                 accounts_proof_limit: RateLimit::new_per_minute(Self::ACCOUNTS_PROOF_RATE_LIMIT),
                 epoch_transactions_limit: RateLimit::new_per_minute(Self::EPOCH_TRANSACTIONS_RATE_LIMIT),
+
+                accounts_proof_requests: VecDeque::new(),
             }),
 
             notifier: RwLock::new(Notifier::new()),
@@ -197,6 +211,9 @@ impl<P: ConsensusProtocol + 'static> ConsensusAgent<P> {
         msg_notifier.get_epoch_transactions.write().register(weak_passthru_listener(
             Arc::downgrade(this),
             |this, msg: GetEpochTransactionsMessage| this.on_get_epoch_transactions(msg)));
+        msg_notifier.accounts_proof.write().register(weak_passthru_listener(
+            Arc::downgrade(this),
+            |this, msg| this.on_accounts_proof(msg)));
     }
 
     pub fn relay_block(&self, block: &<P::Blockchain as AbstractBlockchain>::Block) -> bool {
@@ -220,6 +237,47 @@ impl<P: ConsensusProtocol + 'static> ConsensusAgent<P> {
         self.state.read().synced
     }
 
+    /// Requests a proof for the given accounts at the given block from the peer. The proof is
+    /// `None` if the peer can't provide it. If the peer doesn't answer in time, the connection is
+    /// closed and the request is cancelled.
+    pub fn get_accounts_proof(&self, block_hash: Blake2bHash, addresses: Vec<Address>) -> oneshot::Receiver<Option<AccountsProof<Account>>> {
+        let (sender, receiver) = oneshot::channel();
+        self.state.write().accounts_proof_requests.push_back((block_hash.clone(), sender));
+
+        let weak = self.self_weak.clone();
+        self.timers.reset_delay(ConsensusAgentTimer::AccountsProof, move || {
+            let this = upgrade_weak!(weak);
+            this.peer.channel.close(CloseType::GetAccountsProofTimeout);
+        }, Self::ACCOUNTS_PROOF_TIMEOUT);
+
+        self.peer.channel.send_or_close(GetAccountsProofMessage::new(block_hash, addresses));
+        receiver
+    }
+
+    fn on_accounts_proof(&self, msg: AccountsProofMessage) {
+        trace!("[ACCOUNTS-PROOF] from {}", self.peer.peer_address());
+        let mut state = self.state.write();
+
+        // Peers answer requests in order.
+        let sender = match state.accounts_proof_requests.pop_front() {
+            Some((block_hash, sender)) if block_hash == msg.block_hash => sender,
+            _ => {
+                drop(state);
+                warn!("Unsolicited accounts proof from {} - closing the channel", self.peer.peer_address());
+                self.peer.channel.close(CloseType::InvalidAccountsProof);
+                return;
+            },
+        };
+
+        if state.accounts_proof_requests.is_empty() {
+            self.timers.clear_delay(&ConsensusAgentTimer::AccountsProof);
+        }
+        drop(state);
+
+        // The requester might not be interested anymore.
+        let _ = sender.send(msg.proof);
+    }
+
     pub fn sync(&self) {
         self.state.write().syncing = true;
         self.sync_protocol.initiate_sync();
@@ -239,7 +297,7 @@ impl<P: ConsensusProtocol + 'static> ConsensusAgent<P> {
         }
 
         // If we know our sync target block, the sync is finished.
-        if self.blockchain.contains(&self.state.read().sync_target, true) {
+        if self.sync_protocol.is_finished() || self.blockchain.contains(&self.state.read().sync_target, true) {
             self.sync_finished(sync_guard);
             return;
         }
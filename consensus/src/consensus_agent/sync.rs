@@ -7,9 +7,11 @@ use std::time::Duration;
 use parking_lot::{RwLock, RwLockUpgradableReadGuard};
 
 use block_albatross::Block as AlbatrossBlock;
+use block_albatross::BlockHeader as AlbatrossBlockHeader;
 use block_albatross::BlockError as AlbatrossBlockError;
 use block_base::{Block, BlockError};
 use blockchain_albatross::Blockchain as AlbatrossBlockchain;
+use blockchain_albatross::light_blockchain::LightBlockchain;
 use blockchain_base::{AbstractBlockchain, PushError, PushResult};
 use collections::LimitHashSet;
 use hash::Blake2bHash;
@@ -27,9 +29,17 @@ use utils::timers::Timers;
 pub trait SyncProtocol<B: AbstractBlockchain>: Send + Sync {
     fn new(blockchain: Arc<B>, block_queue: Arc<RwLock<BlockQueue<B>>>, peer: Arc<Peer>) -> Arc<Self>;
     fn initiate_sync(&self) {}
+    /// Whether the sync is complete although we don't know the peer's head block, e.g. because
+    /// only macro blocks are synced.
+    fn is_finished(&self) -> bool { false }
     fn get_block_locators(&self, max_count: usize) -> Vec<Blake2bHash>;
     fn request_blocks(&self, locators: Vec<Blake2bHash>, max_results: u16);
     fn on_block(&self, block: B::Block);
+    /// Whether blocks that are announced after the sync are requested as headers only.
+    fn requests_headers(&self) -> bool { false }
+    /// Handles a header that was requested instead of the full block. Returns whether the full
+    /// block is needed as well.
+    fn on_header(&self, _header: <B::Block as Block>::Header) -> bool { false }
     fn on_epoch_transactions(&self, epoch_transactions: EpochTransactionsMessage);
     fn on_no_new_objects_announced(&self) {}
     fn on_all_objects_received(&self) {}
@@ -86,6 +96,15 @@ impl<B: AbstractBlockchain> SyncProtocol<B> for FullSync<B> {
         self.block_queue.write().push(block, Weak::clone(&self.self_weak));
     }
 
+    fn requests_headers(&self) -> bool {
+        true
+    }
+
+    fn on_header(&self, header: AlbatrossBlockHeader) -> bool {
+        // Only the macro blocks are pushed, micro blocks don't need to be downloaded at all.
+        self.blockchain.needs_block(&header)
+    }
+
     fn on_epoch_transactions(&self, _epoch_transactions: EpochTransactionsMessage) {
         warn!("We didn't expect any epoch transactions from {} - discarding and closing the channel", self.peer.peer_address());
         self.peer.channel.close(CloseType::UnexpectedEpochTransactions);
@@ -362,6 +381,74 @@ impl SyncProtocol<AlbatrossBlockchain> for MacroBlockSync {
     }
 }
 
+/// Syncs the macro blocks of the peer's chain into a `LightBlockchain`.
+pub struct LightSync {
+    blockchain: Arc<LightBlockchain>,
+    peer: Arc<Peer>,
+    /// Whether we are still expecting new macro blocks from the peer.
+    syncing: RwLock<bool>,
+    notifier: RwLock<PassThroughNotifier<'static, SyncEvent<AlbatrossBlockError>>>,
+}
+
+impl SyncProtocol<LightBlockchain> for LightSync {
+    fn new(blockchain: Arc<LightBlockchain>, _: Arc<RwLock<BlockQueue<LightBlockchain>>>, peer: Arc<Peer>) -> Arc<Self> {
+        Arc::new(Self {
+            blockchain,
+            peer,
+            syncing: RwLock::new(false),
+            notifier: RwLock::new(PassThroughNotifier::new()),
+        })
+    }
+
+    fn initiate_sync(&self) {
+        *self.syncing.write() = true;
+    }
+
+    fn is_finished(&self) -> bool {
+        !*self.syncing.read()
+    }
+
+    fn get_block_locators(&self, max_count: usize) -> Vec<Blake2bHash> {
+        self.blockchain.get_block_locators(max_count)
+    }
+
+    fn request_blocks(&self, locators: Vec<Blake2bHash>, max_results: u16) {
+        self.peer.channel.send_or_close(GetBlocksMessage::new_with_macro(
+            locators,
+            max_results,
+            GetBlocksDirection::Forward,
+        ));
+    }
+
+    fn on_block(&self, block: AlbatrossBlock) {
+        let hash = block.hash();
+        let result = self.blockchain.push(block);
+        self.notifier.read().notify(SyncEvent::BlockProcessed(hash, result));
+    }
+
+    fn on_epoch_transactions(&self, _epoch_transactions: EpochTransactionsMessage) {
+        warn!("We didn't expect any epoch transactions from {} - discarding and closing the channel", self.peer.peer_address());
+        self.peer.channel.close(CloseType::UnexpectedEpochTransactions);
+    }
+
+    fn on_no_new_objects_announced(&self) {
+        // The peer doesn't have any macro blocks we don't know yet.
+        *self.syncing.write() = false;
+    }
+
+    fn register_listener<L: PassThroughListener<SyncEvent<<AlbatrossBlock as Block>::Error>> + 'static>(&self, listener: L) {
+        self.notifier.write().register(listener)
+    }
+
+    fn deregister_listener(&self) {
+        self.notifier.write().deregister()
+    }
+
+    fn notify(&self, event: SyncEvent<<AlbatrossBlock as Block>::Error>) {
+        self.notifier.read().notify(event)
+    }
+}
+
 
 pub struct BlockQueue<B: AbstractBlockchain> {
     blockchain: Arc<B>,
@@ -1,6 +1,7 @@
 use failure::Fail;
 
 use network::error::Error as NetworkError;
+use blockchain_albatross::snapshot::SnapshotError;
 use blockchain_base::BlockchainError;
 
 #[derive(Fail, Debug)]
@@ -9,6 +10,24 @@ pub enum Error {
     NetworkError(#[cause] NetworkError),
     #[fail(display = "{}", _0)]
     BlockchainError(#[cause] BlockchainError),
+    #[fail(display = "No synced full node to send the request to")]
+    NoSyncedPeer,
+    #[fail(display = "Request was cancelled")]
+    RequestCancelled,
+    #[fail(display = "Peer couldn't provide an accounts proof")]
+    MissingAccountsProof,
+    #[fail(display = "Peer sent an invalid accounts proof")]
+    InvalidAccountsProof,
+    #[fail(display = "{}", _0)]
+    SnapshotError(#[cause] SnapshotError),
+    #[fail(display = "No full node to sync the state from")]
+    NoFullNode,
+    #[fail(display = "Peers sent different slash registries")]
+    InconsistentSlashRegistry,
+    #[fail(display = "Too few peers could provide the slash registry")]
+    SlashRegistryQuorumNotReached,
+    #[fail(display = "Peers couldn't provide the complete state")]
+    MissingState,
 }
 
 impl From<NetworkError> for Error {
@@ -22,3 +41,9 @@ impl From<BlockchainError> for Error {
         Error::BlockchainError(e)
     }
 }
+
+impl From<SnapshotError> for Error {
+    fn from(e: SnapshotError) -> Self {
+        Error::SnapshotError(e)
+    }
+}
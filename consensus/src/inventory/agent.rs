@@ -464,8 +464,28 @@ impl<P: ConsensusProtocol + 'static> InventoryAgent<P> {
     }
 
     fn on_header(&self, header: <<P::Blockchain as AbstractBlockchain>::Block as Block>::Header) {
-        trace!("[HEADER] #{} {}", header.height(), header.hash());
-        warn!("Unsolicited header message received from {}, discarding", self.peer.peer_address());
+        let hash = header.hash();
+        trace!("[HEADER] #{} {}", header.height(), hash);
+
+        // Check if we have requested this header.
+        let vector = InvVector::from_block_hash(hash);
+        let state = self.state.read();
+        if !state.objects_in_flight.contains(&vector) && !state.objects_that_flew.contains(&vector) {
+            warn!("Unsolicited header message received from {}, discarding", self.peer.peer_address());
+            return;
+        }
+
+        // Give up read lock before processing the header.
+        drop(state);
+
+        // If the full block is needed, it stays in flight until it is received.
+        if self.sync_protocol.on_header(header) {
+            self.peer.channel.send_or_close(Message::GetData(vec![vector]));
+            return;
+        }
+
+        // Mark object as received.
+        self.on_object_received(&vector);
     }
 
     fn on_tx(&self, msg: TxMessage) {
@@ -638,8 +658,20 @@ impl<P: ConsensusProtocol + 'static> InventoryAgent<P> {
             this.no_more_data();
         }, Self::REQUEST_TIMEOUT);
 
-        // Request data from peer.
-        self.peer.channel.send_or_close(Message::GetData(vectors));
+        // Request data from peer. Once synced, some sync protocols only need the headers of
+        // announced blocks.
+        if !state.bypass_mgr && self.sync_protocol.requests_headers() {
+            let (block_vectors, tx_vectors): (Vec<InvVector>, Vec<InvVector>) = vectors.into_iter()
+                .partition(|vector| vector.ty == InvVectorType::Block);
+            if !block_vectors.is_empty() {
+                self.peer.channel.send_or_close(Message::GetHeader(block_vectors));
+            }
+            if !tx_vectors.is_empty() {
+                self.peer.channel.send_or_close(Message::GetData(tx_vectors));
+            }
+        } else {
+            self.peer.channel.send_or_close(Message::GetData(vectors));
+        }
     }
 
     fn on_object_received(&self, vector: &InvVector) {
@@ -813,7 +845,9 @@ impl<P: ConsensusProtocol + 'static> InventoryAgent<P> {
             match vector.ty {
                 InvVectorType::Block => {
                     // TODO raw blocks. Needed?
-                    let block_opt = self.blockchain.get_block(&vector.hash, true);
+                    // Blocks whose body has been pruned are reported as not found.
+                    let block_opt = self.blockchain.get_block(&vector.hash, true)
+                        .filter(|block| !block.is_light());
                     match block_opt {
                         Some(block) => {
                             if self.peer.channel.send(P::MessageAdapter::new_block_message(block)).is_err() {
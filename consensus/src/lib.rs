@@ -1,6 +1,7 @@
 #[macro_use]
 extern crate log;
 
+extern crate nimiq_account as account;
 extern crate nimiq_block_albatross as block_albatross;
 extern crate nimiq_block_base as block_base;
 extern crate nimiq_blockchain as blockchain;
@@ -9,6 +10,7 @@ extern crate nimiq_blockchain_base as blockchain_base;
 extern crate nimiq_collections as collections;
 extern crate nimiq_database as database;
 extern crate nimiq_hash as hash;
+extern crate nimiq_keys as keys;
 extern crate nimiq_macros as macros;
 extern crate nimiq_mempool as mempool;
 extern crate nimiq_messages as network_messages;
@@ -16,17 +18,20 @@ extern crate nimiq_network as network;
 extern crate nimiq_network_primitives as network_primitives;
 extern crate nimiq_primitives as primitives;
 extern crate nimiq_transaction as transaction;
+extern crate nimiq_tree_primitives as tree_primitives;
 extern crate nimiq_utils as utils;
 
 pub mod consensus;
 pub mod consensus_agent;
 pub mod inventory;
 pub mod error;
+pub mod state_sync;
 mod accounts_chunk_cache;
 mod protocol;
 
 pub use self::consensus::{Consensus, ConsensusEvent};
 pub use self::error::Error;
+pub use self::state_sync::StateSync;
 pub use self::protocol::nimiq::NimiqConsensusProtocol;
-pub use self::protocol::albatross::AlbatrossConsensusProtocol;
+pub use self::protocol::albatross::{AlbatrossConsensusProtocol, AlbatrossLightConsensusProtocol};
 pub use self::protocol::ConsensusProtocol;
@@ -1,8 +1,9 @@
 use blockchain_albatross::Blockchain;
+use blockchain_albatross::light_blockchain::LightBlockchain;
 use network_messages::AlbatrossMessageAdapter;
 
 use crate::protocol::ConsensusProtocol;
-use crate::consensus_agent::sync::FullSync;
+use crate::consensus_agent::sync::{FullSync, LightSync};
 
 pub struct AlbatrossConsensusProtocol {}
 impl ConsensusProtocol for AlbatrossConsensusProtocol {
@@ -10,3 +11,11 @@ impl ConsensusProtocol for AlbatrossConsensusProtocol {
     type MessageAdapter = AlbatrossMessageAdapter;
     type SyncProtocol = FullSync<Self::Blockchain>;
 }
+
+/// Only follows the macro blocks and requests accounts from peers when needed.
+pub struct AlbatrossLightConsensusProtocol {}
+impl ConsensusProtocol for AlbatrossLightConsensusProtocol {
+    type Blockchain = LightBlockchain;
+    type MessageAdapter = AlbatrossMessageAdapter;
+    type SyncProtocol = LightSync;
+}
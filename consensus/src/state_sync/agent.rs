@@ -0,0 +1,296 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use futures::sync::oneshot;
+use parking_lot::RwLock;
+
+use account::Account;
+use beserial::{Deserialize, Serialize};
+use block_albatross::Block;
+use blockchain_albatross::Blockchain;
+use blockchain_albatross::reward_registry::SlashRegistryState;
+use hash::Blake2bHash;
+use macros::upgrade_weak;
+use network::connection::close_type::CloseType;
+use network::Peer;
+use network_messages::{
+    AccountsTreeChunkData,
+    AccountsTreeChunkMessage,
+    BlocksMessage,
+    GetAccountsTreeChunkMessage,
+    GetBlocksByNumberMessage,
+    GetSlashRegistryMessage,
+    Message,
+    SlashRegistryMessage,
+};
+use tree_primitives::accounts_tree_chunk::AccountsTreeChunk;
+use utils::mutable_once::MutableOnce;
+use utils::observer::weak_passthru_listener;
+use utils::rate_limit::RateLimit;
+use utils::timers::Timers;
+
+struct StateSyncAgentState {
+    /// Rate limit for GetBlocksByNumber messages.
+    blocks_limit: RateLimit,
+
+    /// Rate limit for GetSlashRegistry messages.
+    slash_registry_limit: RateLimit,
+
+    /// Our pending GetBlocksByNumber requests, in the order they were sent.
+    blocks_requests: VecDeque<oneshot::Sender<Vec<Block>>>,
+
+    /// Our pending GetAccountsTreeChunk requests, in the order they were sent.
+    accounts_tree_chunk_requests: VecDeque<(Blake2bHash, oneshot::Sender<Option<AccountsTreeChunk<Account>>>)>,
+
+    /// Our pending GetSlashRegistry requests, in the order they were sent.
+    slash_registry_requests: VecDeque<(Blake2bHash, oneshot::Sender<Option<SlashRegistryState>>)>,
+}
+
+#[derive(Ord, PartialOrd, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+enum StateSyncAgentTimer {
+    Blocks,
+    AccountsTreeChunk,
+    SlashRegistry,
+}
+
+/// Serves and sends the requests of the state sync for a single full node peer.
+pub struct StateSyncAgent {
+    pub peer: Arc<Peer>,
+    blockchain: Arc<Blockchain>,
+    state: RwLock<StateSyncAgentState>,
+    self_weak: MutableOnce<Weak<StateSyncAgent>>,
+    timers: Timers<StateSyncAgentTimer>,
+}
+
+impl StateSyncAgent {
+    const BLOCKS_TIMEOUT: Duration = Duration::from_secs(10);
+    const ACCOUNTS_TREE_CHUNK_TIMEOUT: Duration = Duration::from_secs(30);
+    const SLASH_REGISTRY_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// The serialized size of the blocks sent in a single message, which must stay below the
+    /// maximum message size. At least one block is sent in any case.
+    const BLOCKS_MAX_SIZE: usize = 5 * 1024 * 1024;
+
+    const BLOCKS_RATE_LIMIT: usize = 120; // per minute
+    const SLASH_REGISTRY_RATE_LIMIT: usize = 10; // per minute
+
+    pub fn new(blockchain: Arc<Blockchain>, peer: Arc<Peer>) -> Arc<Self> {
+        let this = Arc::new(StateSyncAgent {
+            peer,
+            blockchain,
+            state: RwLock::new(StateSyncAgentState {
+                blocks_limit: RateLimit::new_per_minute(Self::BLOCKS_RATE_LIMIT),
+                slash_registry_limit: RateLimit::new_per_minute(Self::SLASH_REGISTRY_RATE_LIMIT),
+
+                blocks_requests: VecDeque::new(),
+                accounts_tree_chunk_requests: VecDeque::new(),
+                slash_registry_requests: VecDeque::new(),
+            }),
+            self_weak: MutableOnce::new(Weak::new()),
+            timers: Timers::new(),
+        });
+        StateSyncAgent::init_listeners(&this);
+        this
+    }
+
+    fn init_listeners(this: &Arc<Self>) {
+        unsafe { this.self_weak.replace(Arc::downgrade(this)) };
+
+        let msg_notifier = &this.peer.channel.msg_notifier;
+        msg_notifier.get_blocks_by_number.write().register(weak_passthru_listener(
+            Arc::downgrade(this),
+            |this, msg| this.on_get_blocks_by_number(msg)));
+        msg_notifier.get_slash_registry.write().register(weak_passthru_listener(
+            Arc::downgrade(this),
+            |this, msg| this.on_get_slash_registry(msg)));
+        msg_notifier.blocks.write().register(weak_passthru_listener(
+            Arc::downgrade(this),
+            |this, msg| this.on_blocks(msg)));
+        msg_notifier.accounts_tree_chunk.write().register(weak_passthru_listener(
+            Arc::downgrade(this),
+            |this, msg| this.on_accounts_tree_chunk(msg)));
+        msg_notifier.slash_registry.write().register(weak_passthru_listener(
+            Arc::downgrade(this),
+            |this, msg| this.on_slash_registry(msg)));
+    }
+
+    /// Requests the main chain blocks with the given numbers from the peer. The peer answers with
+    /// a prefix of the requested blocks, which is empty if it doesn't know the first one.
+    pub fn get_blocks(&self, block_numbers: Vec<u32>) -> oneshot::Receiver<Vec<Block>> {
+        let (sender, receiver) = oneshot::channel();
+        self.state.write().blocks_requests.push_back(sender);
+
+        let weak = self.self_weak.clone();
+        self.timers.reset_delay(StateSyncAgentTimer::Blocks, move || {
+            let this = upgrade_weak!(weak);
+            this.peer.channel.close(CloseType::GetBlocksByNumberTimeout);
+        }, Self::BLOCKS_TIMEOUT);
+
+        self.peer.channel.send_or_close(GetBlocksByNumberMessage::new(block_numbers));
+        receiver
+    }
+
+    /// Requests the accounts tree chunk covering `start_prefix` at the given block from the peer.
+    /// The chunk isn't verified yet.
+    pub fn get_accounts_tree_chunk(&self, block_hash: Blake2bHash, start_prefix: String) -> oneshot::Receiver<Option<AccountsTreeChunk<Account>>> {
+        let (sender, receiver) = oneshot::channel();
+        self.state.write().accounts_tree_chunk_requests.push_back((block_hash.clone(), sender));
+
+        let weak = self.self_weak.clone();
+        self.timers.reset_delay(StateSyncAgentTimer::AccountsTreeChunk, move || {
+            let this = upgrade_weak!(weak);
+            this.peer.channel.close(CloseType::GetAccountsTreeChunkTimeout);
+        }, Self::ACCOUNTS_TREE_CHUNK_TIMEOUT);
+
+        self.peer.channel.send_or_close(Message::GetAccountsTreeChunk(Box::new(GetAccountsTreeChunkMessage {
+            block_hash,
+            start_prefix,
+        })));
+        receiver
+    }
+
+    /// Requests the slash registry at the given macro block from the peer.
+    pub fn get_slash_registry(&self, block_hash: Blake2bHash) -> oneshot::Receiver<Option<SlashRegistryState>> {
+        let (sender, receiver) = oneshot::channel();
+        self.state.write().slash_registry_requests.push_back((block_hash.clone(), sender));
+
+        let weak = self.self_weak.clone();
+        self.timers.reset_delay(StateSyncAgentTimer::SlashRegistry, move || {
+            let this = upgrade_weak!(weak);
+            this.peer.channel.close(CloseType::GetSlashRegistryTimeout);
+        }, Self::SLASH_REGISTRY_TIMEOUT);
+
+        self.peer.channel.send_or_close(GetSlashRegistryMessage::new(block_hash));
+        receiver
+    }
+
+    /// Cancels all pending requests, e.g. after the peer disconnected.
+    pub fn cancel_requests(&self) {
+        self.timers.clear_all();
+
+        let mut state = self.state.write();
+        state.blocks_requests.clear();
+        state.accounts_tree_chunk_requests.clear();
+        state.slash_registry_requests.clear();
+    }
+
+    fn on_get_blocks_by_number(&self, msg: GetBlocksByNumberMessage) {
+        trace!("[GET-BLOCKS-BY-NUMBER] from {}", self.peer.peer_address());
+        if !self.state.write().blocks_limit.note_single() {
+            warn!("Rejecting GetBlocksByNumber message - rate-limit exceeded");
+            self.peer.channel.send_or_close(BlocksMessage::new(Vec::new()));
+            return;
+        }
+
+        let mut blocks = Vec::new();
+        let mut size = 0;
+        for &block_number in msg.block_numbers.iter().take(GetBlocksByNumberMessage::BLOCKS_MAX_COUNT) {
+            let block = match self.blockchain.get_block_at(block_number, true) {
+                Some(block) => block,
+                None => break,
+            };
+            size += block.serialized_size();
+            if size > Self::BLOCKS_MAX_SIZE && !blocks.is_empty() {
+                break;
+            }
+            blocks.push(block);
+        }
+        self.peer.channel.send_or_close(BlocksMessage::new(blocks));
+    }
+
+    fn on_get_slash_registry(&self, msg: GetSlashRegistryMessage) {
+        trace!("[GET-SLASH-REGISTRY] from {}", self.peer.peer_address());
+        if !self.state.write().slash_registry_limit.note_single() {
+            warn!("Rejecting GetSlashRegistry message - rate-limit exceeded");
+            self.peer.channel.send_or_close(SlashRegistryMessage::new(msg.block_hash, Vec::new()));
+            return;
+        }
+
+        let state = self.blockchain.export_slash_registry(&msg.block_hash)
+            .map(|state| state.serialize_to_vec())
+            .unwrap_or_default();
+        self.peer.channel.send_or_close(SlashRegistryMessage::new(msg.block_hash, state));
+    }
+
+    fn on_blocks(&self, msg: BlocksMessage) {
+        trace!("[BLOCKS] from {}", self.peer.peer_address());
+        let mut state = self.state.write();
+
+        // Peers answer requests in order.
+        let sender = match state.blocks_requests.pop_front() {
+            Some(sender) => sender,
+            None => {
+                drop(state);
+                warn!("Unsolicited blocks from {} - closing the channel", self.peer.peer_address());
+                self.peer.channel.close(CloseType::InvalidBlock);
+                return;
+            },
+        };
+
+        if state.blocks_requests.is_empty() {
+            self.timers.clear_delay(&StateSyncAgentTimer::Blocks);
+        }
+        drop(state);
+
+        // The requester might not be interested anymore.
+        let _ = sender.send(msg.blocks);
+    }
+
+    fn on_accounts_tree_chunk(&self, msg: AccountsTreeChunkMessage) {
+        trace!("[ACCOUNTS-TREE-CHUNK] from {}", self.peer.peer_address());
+        let mut state = self.state.write();
+
+        // Peers answer requests in order.
+        let sender = match state.accounts_tree_chunk_requests.pop_front() {
+            Some((block_hash, sender)) if block_hash == msg.block_hash => sender,
+            _ => {
+                drop(state);
+                warn!("Unsolicited accounts tree chunk from {} - closing the channel", self.peer.peer_address());
+                self.peer.channel.close(CloseType::InvalidAccountsTreeChunk);
+                return;
+            },
+        };
+
+        if state.accounts_tree_chunk_requests.is_empty() {
+            self.timers.clear_delay(&StateSyncAgentTimer::AccountsTreeChunk);
+        }
+        drop(state);
+
+        // Received chunks are always deserialized into the structured variant.
+        let chunk = match msg.chunk {
+            Some(AccountsTreeChunkData::Structured(chunk)) => Some(chunk),
+            _ => None,
+        };
+        let _ = sender.send(chunk);
+    }
+
+    fn on_slash_registry(&self, msg: SlashRegistryMessage) {
+        trace!("[SLASH-REGISTRY] from {}", self.peer.peer_address());
+        let mut state = self.state.write();
+
+        // Peers answer requests in order.
+        let sender = match state.slash_registry_requests.pop_front() {
+            Some((block_hash, sender)) if block_hash == msg.block_hash => sender,
+            _ => {
+                drop(state);
+                warn!("Unsolicited slash registry from {} - closing the channel", self.peer.peer_address());
+                self.peer.channel.close(CloseType::InvalidBlock);
+                return;
+            },
+        };
+
+        if state.slash_registry_requests.is_empty() {
+            self.timers.clear_delay(&StateSyncAgentTimer::SlashRegistry);
+        }
+        drop(state);
+
+        // An empty state means that the peer couldn't export the registry.
+        let slash_registry = if msg.state.is_empty() {
+            None
+        } else {
+            SlashRegistryState::deserialize_from_vec(&msg.state).ok()
+        };
+        let _ = sender.send(slash_registry);
+    }
+}
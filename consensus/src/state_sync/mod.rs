@@ -0,0 +1,529 @@
+use std::cmp;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::mem;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use futures::{future, Future};
+use futures::future::{Either, Loop};
+use parking_lot::{Mutex, RwLock};
+use rand::seq::IteratorRandom;
+use rand::thread_rng;
+
+use account::Account;
+use beserial::Serialize;
+use block_albatross::{Block, MacroBlock};
+use blockchain_albatross::Blockchain;
+use blockchain_albatross::reward_registry::SlashRegistryState;
+use blockchain_albatross::snapshot::{Snapshot, SnapshotError};
+use hash::Blake2bHash;
+use macros::upgrade_weak;
+use network::{NetworkEvent, Peer};
+use network::connection::close_type::CloseType;
+use network_messages::GetBlocksByNumberMessage;
+use primitives::policy;
+use tree_primitives::accounts_tree_chunk::subtree_root;
+use tree_primitives::accounts_tree_node::AccountsTreeNode;
+use tree_primitives::address_nibbles::AddressNibbles;
+use utils::mutable_once::MutableOnce;
+use utils::timers::Timers;
+
+use crate::accounts_chunk_cache::AccountsChunkCache;
+use crate::consensus::Consensus;
+use crate::error::Error;
+use crate::protocol::albatross::AlbatrossConsensusProtocol;
+
+pub use self::agent::StateSyncAgent;
+
+mod agent;
+
+type AlbatrossConsensus = Consensus<AlbatrossConsensusProtocol>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StateSyncStatus {
+    /// The state sync waits for peers to connect.
+    Pending,
+    Running,
+    /// The state sync is done or wasn't needed, and the blockchain is synced by the consensus.
+    Finished,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum StateSyncTimer {
+    Start,
+}
+
+struct StateSyncState {
+    agents: HashMap<Arc<Peer>, Arc<StateSyncAgent>>,
+    status: StateSyncStatus,
+}
+
+/// Bootstraps an empty Albatross full node from the state at the latest macro block instead of
+/// replaying the whole chain:
+///
+/// 1. The macro chain is requested from a few peers with distinct addresses and verified from the
+///    genesis block on. It ends at the latest macro block any of them knows.
+/// 2. The slash registry at the latest macro block is requested from a few peers with distinct
+///    addresses. A quorum of them has to provide it and all of them have to agree on it. Its
+///    reward pot is later checked against the blocks of the epoch.
+/// 3. The accounts tree chunks and the blocks of the transaction validity window are requested in
+///    parallel from all full nodes. Each range of the accounts tree is requested from a single
+///    peer. Its chunks' proofs are checked against the state root as they arrive, and the whole
+///    range is re-hashed against the root node once it is complete. Peers sending invalid or
+///    incomplete ranges are banned and the range is requested from another peer.
+/// 4. The state is applied to the blockchain as a `Snapshot`, after which the consensus syncs the
+///    remaining blocks as usual.
+///
+/// Every full node serves the requests of the state sync, regardless of whether it syncs its own
+/// state. Light nodes don't compute the accounts tree chunks of finalized blocks in advance.
+pub struct StateSync {
+    consensus: Arc<AlbatrossConsensus>,
+    state: RwLock<StateSyncState>,
+    timers: Timers<StateSyncTimer>,
+    self_weak: MutableOnce<Weak<StateSync>>,
+}
+
+impl StateSync {
+    /// Time to wait for more peers to connect before starting the state sync.
+    const START_THROTTLE: Duration = Duration::from_secs(5);
+    /// Time to wait before retrying a failed state sync.
+    const RETRY_DELAY: Duration = Duration::from_secs(30);
+    /// Number of peers to request the macro chain from.
+    const MACRO_CHAIN_PEERS: usize = 3;
+    /// Number of peers to request the slash registry from.
+    const SLASH_REGISTRY_PEERS: usize = 3;
+    /// Number of peers that have to provide the same slash registry.
+    const SLASH_REGISTRY_QUORUM: usize = 2;
+
+    /// Creates the state sync for the given consensus. If `sync_state` is set and the blockchain
+    /// is still at its genesis block, the blockchain sync of the consensus is suspended until the
+    /// state has been synced.
+    pub fn new(consensus: Arc<AlbatrossConsensus>, sync_state: bool) -> Arc<Self> {
+        let status = if sync_state && consensus.blockchain.head_height() == 0 {
+            consensus.suspend_sync();
+            StateSyncStatus::Pending
+        } else {
+            StateSyncStatus::Finished
+        };
+
+        let this = Arc::new(StateSync {
+            consensus,
+            state: RwLock::new(StateSyncState {
+                agents: HashMap::new(),
+                status,
+            }),
+            timers: Timers::new(),
+            self_weak: MutableOnce::new(Weak::new()),
+        });
+        StateSync::init_listeners(&this);
+        this
+    }
+
+    fn init_listeners(this: &Arc<Self>) {
+        unsafe { this.self_weak.replace(Arc::downgrade(this)) };
+
+        let weak = Arc::downgrade(this);
+        this.consensus.network.notifier.write().register(move |e: &NetworkEvent| {
+            let this = upgrade_weak!(weak);
+            match e {
+                NetworkEvent::PeerJoined(peer) => this.on_peer_joined(peer),
+                NetworkEvent::PeerLeft(peer) => this.on_peer_left(peer),
+                _ => {}
+            }
+        });
+    }
+
+    /// Returns whether the state sync is finished or wasn't needed.
+    pub fn finished(&self) -> bool {
+        self.state.read().status == StateSyncStatus::Finished
+    }
+
+    fn on_peer_joined(&self, peer: &Arc<Peer>) {
+        if !peer.peer_address().services.is_full_node() {
+            return;
+        }
+
+        let agent = StateSyncAgent::new(Arc::clone(&self.consensus.blockchain), Arc::clone(peer));
+        let mut state = self.state.write();
+        state.agents.insert(Arc::clone(peer), agent);
+
+        // If no more peers connect within the specified timeout, start the state sync.
+        if state.status == StateSyncStatus::Pending {
+            let weak = self.self_weak.clone();
+            self.timers.reset_delay(StateSyncTimer::Start, move || {
+                let this = upgrade_weak!(weak);
+                this.start();
+            }, Self::START_THROTTLE);
+        }
+    }
+
+    fn on_peer_left(&self, peer: &Arc<Peer>) {
+        if let Some(agent) = self.state.write().agents.remove(peer) {
+            agent.cancel_requests();
+        }
+    }
+
+    fn start(&self) {
+        {
+            let mut state = self.state.write();
+            if state.status != StateSyncStatus::Pending {
+                return;
+            }
+            state.status = StateSyncStatus::Running;
+        }
+
+        // Blocks might have been pushed in the meantime.
+        if self.consensus.blockchain.head_height() > 0 {
+            self.on_sync_finished(Ok(()));
+            return;
+        }
+
+        let this = upgrade_weak!(self.self_weak);
+        info!("Syncing state with {} peers", self.state.read().agents.len());
+        let weak = Arc::downgrade(&this);
+        tokio::spawn(StateSync::sync(this).then(move |result| {
+            if let Some(this) = weak.upgrade() {
+                this.on_sync_finished(result);
+            }
+            Ok::<(), ()>(())
+        }));
+    }
+
+    fn on_sync_finished(&self, result: Result<(), Error>) {
+        match result {
+            Ok(()) => {
+                self.state.write().status = StateSyncStatus::Finished;
+                info!("State sync finished at block #{} [{}]", self.consensus.blockchain.head_height(), self.consensus.blockchain.head_hash());
+                self.consensus.resume_sync();
+            },
+            Err(e) => {
+                warn!("State sync failed: {}", e);
+                self.state.write().status = StateSyncStatus::Pending;
+
+                let weak = self.self_weak.clone();
+                self.timers.reset_delay(StateSyncTimer::Start, move || {
+                    let this = upgrade_weak!(weak);
+                    this.start();
+                }, Self::RETRY_DELAY);
+            },
+        }
+    }
+
+    fn sync(this: Arc<Self>) -> impl Future<Item=(), Error=Error> {
+        let agents = this.independent_agents(Self::MACRO_CHAIN_PEERS);
+        if agents.is_empty() {
+            return Either::A(future::err(Error::NoFullNode));
+        }
+        let genesis_block = this.consensus.blockchain.macro_head().clone();
+
+        Either::B(StateSync::sync_macro_chain(agents, genesis_block).and_then(move |macro_blocks| {
+            let macro_block = match macro_blocks.last() {
+                Some(macro_block) => macro_block.clone(),
+                // There is no state to sync yet.
+                None => return Either::A(future::ok(())),
+            };
+            let block_hash = macro_block.hash();
+            info!("Syncing state at macro block #{} [{}]", macro_block.header.block_number, block_hash);
+
+            let blockchain = Arc::clone(&this.consensus.blockchain);
+            Either::B(this.sync_slash_registry(block_hash.clone())
+                .join3(
+                    this.sync_accounts(block_hash, macro_block.header.state_root.clone()),
+                    this.sync_blocks(macro_block.header.block_number))
+                .and_then(move |(slash_registry, accounts, blocks)| {
+                    blockchain.apply_snapshot(Snapshot {
+                        macro_blocks,
+                        blocks,
+                        accounts,
+                        slash_registry,
+                    }).map_err(Error::from)
+                }))
+        }))
+    }
+
+    /// Requests the macro blocks following the genesis block from a few peers with distinct
+    /// addresses and verifies each of them against the validators of its predecessor. Each peer
+    /// continues the chain where the previous one stopped, so a single peer can't hide the latest
+    /// macro blocks.
+    fn sync_macro_chain(agents: Vec<Arc<StateSyncAgent>>, genesis_block: MacroBlock) -> impl Future<Item=Vec<MacroBlock>, Error=Error> {
+        future::loop_fn((agents.into_iter(), Vec::new(), false), move |(mut agents, macro_blocks, answered)| {
+            let agent = match agents.next() {
+                Some(agent) => agent,
+                None if answered => return Either::A(future::ok(Loop::Break(macro_blocks))),
+                None => return Either::A(future::err(Error::RequestCancelled)),
+            };
+            Either::B(StateSync::extend_macro_chain(agent, genesis_block.clone(), macro_blocks)
+                .map(move |(macro_blocks, agent_answered)| Loop::Continue((agents, macro_blocks, answered || agent_answered))))
+        })
+    }
+
+    /// Requests the macro blocks following `macro_blocks` from a single peer until it doesn't know
+    /// any further ones. Returns the extended chain and whether the peer answered at all. A peer
+    /// sending an invalid macro block is banned.
+    fn extend_macro_chain(agent: Arc<StateSyncAgent>, genesis_block: MacroBlock, macro_blocks: Vec<MacroBlock>) -> impl Future<Item=(Vec<MacroBlock>, bool), Error=Error> {
+        future::loop_fn((agent, genesis_block, macro_blocks, false), |(agent, genesis_block, mut macro_blocks, answered): (_, _, Vec<MacroBlock>, _)| {
+            let block_number = macro_blocks.last().unwrap_or(&genesis_block).header.block_number;
+            let block_numbers = (1..=GetBlocksByNumberMessage::BLOCKS_MAX_COUNT as u32)
+                .map(|i| block_number + i * policy::EPOCH_LENGTH)
+                .collect();
+
+            agent.get_blocks(block_numbers).then(move |result| {
+                let blocks = match result {
+                    Ok(blocks) => blocks,
+                    Err(_) => return Ok(Loop::Break((macro_blocks, answered))),
+                };
+
+                // The peer doesn't know any further macro blocks.
+                if blocks.is_empty() {
+                    return Ok(Loop::Break((macro_blocks, true)));
+                }
+
+                for block in blocks {
+                    let result = match block {
+                        Block::Macro(macro_block) => {
+                            Snapshot::verify_macro_block(macro_blocks.last().unwrap_or(&genesis_block), &macro_block)
+                                .map(|_| macro_block)
+                        },
+                        Block::Micro(micro_block) => Err(SnapshotError::InvalidMacroChain(micro_block.header.block_number)),
+                    };
+                    match result {
+                        Ok(macro_block) => macro_blocks.push(macro_block),
+                        Err(e) => {
+                            warn!("Invalid macro block from {}: {} - closing the channel", agent.peer.peer_address(), e);
+                            agent.peer.channel.close(CloseType::ReceivedInvalidBlock);
+                            return Ok(Loop::Break((macro_blocks, answered)));
+                        },
+                    }
+                }
+                Ok(Loop::Continue((agent, genesis_block, macro_blocks, true)))
+            })
+        })
+    }
+
+    /// Requests the slash registry from a few peers with distinct addresses. Since the slashes
+    /// aren't covered by the state root, a quorum of these peers must provide the registry and
+    /// all of them must agree on it.
+    fn sync_slash_registry(&self, block_hash: Blake2bHash) -> impl Future<Item=SlashRegistryState, Error=Error> {
+        let requests: Vec<_> = self.independent_agents(Self::SLASH_REGISTRY_PEERS).iter()
+            .map(|agent| agent.get_slash_registry(block_hash.clone())
+                .then(|result| Ok::<_, Error>(result.unwrap_or(None))))
+            .collect();
+
+        future::join_all(requests).and_then(|slash_registries| {
+            StateSync::agreed_response(slash_registries.into_iter().flatten().collect(), Self::SLASH_REGISTRY_QUORUM)
+        })
+    }
+
+    /// Returns the response if at least `quorum` peers sent it and no peer sent a different one.
+    fn agreed_response<T: Serialize>(mut responses: Vec<T>, quorum: usize) -> Result<T, Error> {
+        if responses.len() < quorum {
+            return Err(Error::SlashRegistryQuorumNotReached);
+        }
+        let response = responses.pop().ok_or(Error::SlashRegistryQuorumNotReached)?;
+        let serialized = response.serialize_to_vec();
+        if responses.iter().any(|other| other.serialize_to_vec() != serialized) {
+            return Err(Error::InconsistentSlashRegistry);
+        }
+        Ok(response)
+    }
+
+    /// Requests the accounts tree chunks. The tree is split by the first nibble of the addresses,
+    /// so that the ranges can be synced from different peers in parallel.
+    fn sync_accounts(&self, block_hash: Blake2bHash, state_root: Blake2bHash) -> impl Future<Item=Vec<AccountsTreeNode<Account>>, Error=Error> {
+        let nodes = Arc::new(Mutex::new(Vec::new()));
+        let nodes_moved = Arc::clone(&nodes);
+        StateSync::distribute(self.all_agents(), (0..16).collect(), move |agent, nibble| {
+            let nodes = Arc::clone(&nodes_moved);
+            StateSync::sync_accounts_range(Arc::clone(agent), block_hash.clone(), state_root.clone(), nibble)
+                .map(move |range_nodes| {
+                    nodes.lock().extend(range_nodes);
+                    vec![]
+                })
+        }).map(move |_| {
+            let mut nodes = mem::replace(&mut *nodes.lock(), Vec::new());
+            nodes.sort_by(|a, b| a.prefix().cmp(b.prefix()));
+            nodes
+        })
+    }
+
+    /// Requests all chunks of the accounts whose address starts with `nibble` from a single peer.
+    ///
+    /// Each chunk's proof is checked against the state root, but it only authenticates the tail of
+    /// the chunk. So once the range is complete, its nodes are re-hashed and compared with the
+    /// root node, which catches forged, missing and left out nodes. Either way, the peer is banned.
+    fn sync_accounts_range(agent: Arc<StateSyncAgent>, block_hash: Blake2bHash, state_root: Blake2bHash, nibble: usize) -> impl Future<Item=Vec<AccountsTreeNode<Account>>, Error=()> {
+        let start = if nibble == 0 { String::new() } else { format!("{:x}", nibble) };
+        future::loop_fn((start, BTreeMap::new()), move |(start, mut nodes): (String, BTreeMap<String, AccountsTreeNode<Account>>)| {
+            let agent = Arc::clone(&agent);
+            let state_root = state_root.clone();
+            agent.get_accounts_tree_chunk(block_hash.clone(), start).then(move |result| {
+                let mut chunk = match result {
+                    Ok(Some(chunk)) => chunk,
+                    _ => return Err(()),
+                };
+                if !chunk.verify() || chunk.root() != state_root {
+                    warn!("Invalid accounts tree chunk from {} - banning the peer", agent.peer.peer_address());
+                    agent.peer.channel.close(CloseType::ReceivedInvalidAccountsTreeChunk);
+                    return Err(());
+                }
+
+                for node in chunk.terminal_nodes() {
+                    if node.is_terminal() && node.prefix().get(0) == Some(nibble) {
+                        nodes.insert(node.prefix().to_string(), node.clone());
+                    }
+                }
+
+                // Chunks that aren't full reach the end of the tree.
+                let tail = chunk.tail().prefix();
+                let complete = chunk.nodes.is_empty()
+                    || chunk.len() < AccountsChunkCache::<Blockchain>::CHUNK_SIZE_MAX
+                    || tail.get(0).map_or(true, |tail_nibble| tail_nibble > nibble);
+                if !complete {
+                    return Ok(Loop::Continue((tail.to_string(), nodes)));
+                }
+
+                let nodes: Vec<_> = nodes.into_iter().map(|(_, node)| node).collect();
+                let root = chunk.proof.nodes().last().expect("Verified proofs contain the root node");
+                let range_prefix: AddressNibbles = format!("{:x}", nibble).parse().expect("Valid nibble");
+                let expected = root.get_child_prefix(&range_prefix)
+                    .and_then(|prefix| root.get_child_hash(&range_prefix).map(|hash| (prefix, hash.clone())));
+                if subtree_root(&nodes) != expected {
+                    warn!("Accounts tree chunks from {} don't match the state root - banning the peer", agent.peer.peer_address());
+                    agent.peer.channel.close(CloseType::ReceivedInvalidAccountsTreeChunk);
+                    return Err(());
+                }
+                Ok(Loop::Break(nodes))
+            })
+        })
+    }
+
+    /// Requests the blocks of the transaction validity window preceding the macro block.
+    fn sync_blocks(&self, macro_block_number: u32) -> impl Future<Item=Vec<Block>, Error=Error> {
+        let count = cmp::min(policy::TRANSACTION_VALIDITY_WINDOW_ALBATROSS - 1, macro_block_number);
+        let block_numbers: Vec<u32> = (macro_block_number - count..macro_block_number).collect();
+        let batches: VecDeque<Vec<u32>> = block_numbers.chunks(GetBlocksByNumberMessage::BLOCKS_MAX_COUNT)
+            .map(<[u32]>::to_vec)
+            .collect();
+
+        let blocks = Arc::new(Mutex::new(BTreeMap::new()));
+        let blocks_moved = Arc::clone(&blocks);
+        StateSync::distribute(self.all_agents(), batches, move |agent, block_numbers: Vec<u32>| {
+            let agent = Arc::clone(agent);
+            let blocks = Arc::clone(&blocks_moved);
+            agent.get_blocks(block_numbers.clone()).then(move |result| {
+                let received = match result {
+                    Ok(ref received) if received.is_empty() => return Err(()),
+                    Ok(received) => received,
+                    Err(_) => return Err(()),
+                };
+                if received.len() > block_numbers.len()
+                    || received.iter().zip(block_numbers.iter()).any(|(block, &block_number)| block.block_number() != block_number) {
+                    warn!("Unexpected blocks from {} - closing the channel", agent.peer.peer_address());
+                    agent.peer.channel.close(CloseType::InvalidBlock);
+                    return Err(());
+                }
+
+                // The peer might not have sent all blocks at once.
+                let remaining = block_numbers[received.len()..].to_vec();
+                let mut blocks = blocks.lock();
+                for block in received {
+                    blocks.insert(block.block_number(), block);
+                }
+                Ok(if remaining.is_empty() { vec![] } else { vec![remaining] })
+            })
+        }).map(move |_| {
+            mem::replace(&mut *blocks.lock(), BTreeMap::new()).into_iter()
+                .map(|(_, block)| block)
+                .collect()
+        })
+    }
+
+    /// Processes the tasks in parallel, with one request per agent at a time. A successfully
+    /// processed task can yield follow-up tasks, which are processed next. If a task fails, it is
+    /// put back and the agent isn't used anymore.
+    ///
+    /// Fails with `Error::MissingState` if not all tasks could be processed.
+    fn distribute<T, F, R>(agents: Vec<Arc<StateSyncAgent>>, tasks: VecDeque<T>, process: F) -> impl Future<Item=(), Error=Error>
+        where T: Clone + Send + 'static,
+              F: Fn(&Arc<StateSyncAgent>, T) -> R + Send + Sync + 'static,
+              R: Future<Item=Vec<T>, Error=()> + Send + 'static {
+        let queue = Arc::new(Mutex::new(tasks));
+        let process = Arc::new(process);
+
+        let workers: Vec<_> = agents.into_iter().map(|agent| {
+            let queue = Arc::clone(&queue);
+            let process = Arc::clone(&process);
+            future::loop_fn((), move |_| {
+                let task = match queue.lock().pop_front() {
+                    Some(task) => task,
+                    None => return Either::A(future::ok(Loop::Break(()))),
+                };
+                let queue = Arc::clone(&queue);
+                Either::B((*process)(&agent, task.clone()).then(move |result| {
+                    let mut queue = queue.lock();
+                    match result {
+                        Ok(follow_ups) => {
+                            for follow_up in follow_ups.into_iter().rev() {
+                                queue.push_front(follow_up);
+                            }
+                            Ok(Loop::Continue(()))
+                        },
+                        Err(()) => {
+                            queue.push_back(task);
+                            Ok(Loop::Break(()))
+                        },
+                    }
+                }))
+            })
+        }).collect();
+
+        future::join_all(workers).then(move |_: Result<Vec<()>, ()>| {
+            if queue.lock().is_empty() {
+                Ok(())
+            } else {
+                Err(Error::MissingState)
+            }
+        })
+    }
+
+    /// Picks up to `count` random agents, at most one per address, so that a single host can't
+    /// answer in the name of several peers.
+    fn independent_agents(&self, count: usize) -> Vec<Arc<StateSyncAgent>> {
+        let mut net_addresses = HashSet::new();
+        self.state.read().agents.values()
+            .filter(|agent| match agent.peer.net_address() {
+                Some(net_address) => net_addresses.insert(net_address),
+                None => false,
+            })
+            .cloned()
+            .choose_multiple(&mut thread_rng(), count)
+    }
+
+    fn all_agents(&self) -> Vec<Arc<StateSyncAgent>> {
+        self.state.read().agents.values().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_requires_a_quorum_of_agreeing_responses() {
+        assert_eq!(StateSync::agreed_response(vec![1u32, 1, 1], 2).unwrap(), 1);
+        assert_eq!(StateSync::agreed_response(vec![1u32, 1], 2).unwrap(), 1);
+
+        match StateSync::agreed_response(vec![1u32], 2) {
+            Err(Error::SlashRegistryQuorumNotReached) => {},
+            _ => panic!("Response of a single peer was accepted"),
+        }
+        match StateSync::agreed_response(Vec::<u32>::new(), 2) {
+            Err(Error::SlashRegistryQuorumNotReached) => {},
+            _ => panic!("Missing response was accepted"),
+        }
+        match StateSync::agreed_response(vec![1u32, 1, 2], 2) {
+            Err(Error::InconsistentSlashRegistry) => {},
+            _ => panic!("Conflicting responses were accepted"),
+        }
+    }
+}
@@ -0,0 +1,72 @@
+#!/bin/bash
+#
+# Stalls the pBFT round of the first macro block and checks that it is finished after a view change.
+#
+# Two of the four equally staked validators are disconnected right before the macro block, so that
+# the pBFT round can't complete. After the pBFT timeout, they are reconnected. The remaining
+# validators time out the round and, together with the reconnected ones, change the view of the
+# macro block. The proposer of the new view then proposes the macro block again with the view change
+# proof.
+#
+# The disconnect races with the first pBFT round. If the round completes before the validators are
+# disconnected, the result is inconclusive and the scenario should be run again.
+
+set -e
+
+MACRO_BLOCK=128
+PBFT_TIMEOUT=60
+RPC_URL=http://localhost:8648
+NETWORK=$(basename "$(pwd)" | tr -d '.-')_default
+STALLED="validator-02 validator-03"
+
+function block_number () {
+    curl -s -X POST -H 'Content-Type: application/json' \
+        -d '{"jsonrpc":"2.0","method":"blockNumber","params":[],"id":1}' \
+        $RPC_URL | sed -n 's/.*"result":\([0-9]*\).*/\1/p'
+}
+
+function set_connected () {
+    for service in $STALLED; do
+        container=$(docker-compose ps -q $service)
+        if [[ "$1" == "true" ]]; then
+            docker network connect --alias "$service.devnet" $NETWORK $container
+        else
+            docker network disconnect $NETWORK $container
+        fi
+    done
+}
+
+function finish () {
+    docker-compose logs validator-00 | grep -i "pbft\|view change" | tail -n 50
+    docker-compose down
+    exit $1
+}
+
+docker-compose up -d --build
+
+echo "Waiting for block #$((MACRO_BLOCK - 1))"
+until [[ "$(block_number)" -ge $((MACRO_BLOCK - 1)) ]]; do
+    sleep 0.1
+done
+
+echo "Stalling pBFT round by disconnecting $STALLED"
+set_connected false
+sleep $((PBFT_TIMEOUT + 5))
+set_connected true
+
+echo "Waiting for the chain to grow past the macro block"
+for i in $(seq 1 180); do
+    if [[ "$(block_number)" -gt $MACRO_BLOCK ]]; then
+        if docker-compose logs validator-00 | grep -q "pBFT round for #$MACRO_BLOCK.* timed out"; then
+            echo "Macro block #$MACRO_BLOCK was finished after a view change"
+            finish 0
+        else
+            echo "pBFT round completed before it was stalled, run the scenario again"
+            finish 2
+        fi
+    fi
+    sleep 1
+done
+
+echo "Chain is stuck at #$(block_number)" >&2
+finish 1
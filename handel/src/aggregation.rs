@@ -1,13 +1,14 @@
 use std::sync::{Arc, Weak};
 use std::fmt;
+use std::time::{Duration, Instant};
 
 use macros::upgrade_weak;
 use parking_lot::{RwLock, RwLockUpgradableReadGuard};
 use futures::{future, Future};
+use rand::{Rng, thread_rng};
 
 use utils::observer::PassThroughNotifier;
 use utils::mutable_once::MutableOnce;
-use utils::timers::Timers;
 
 use crate::store::SignatureStore;
 use crate::level::Level;
@@ -18,6 +19,7 @@ use crate::todo::TodoList;
 use crate::protocol::Protocol;
 use crate::update::LevelUpdate;
 use crate::sender::Sender;
+use crate::executor::{Executor, Timers, TokioExecutor};
 
 
 
@@ -41,8 +43,6 @@ struct AggregationState {
 
     /// Our contribution
     contribution: Option<IndividualSignature>,
-
-    next_level_timeout: usize,
 }
 
 
@@ -59,9 +59,15 @@ pub struct Aggregation<P: Protocol> {
     /// The underlying protocol
     pub protocol: P,
 
+    /// Runs the background work and provides the time
+    executor: Arc<dyn Executor>,
+
     /// Timers for updates and level timeouts
     timers: Timers<AggregationTimer>,
 
+    /// When the aggregation was started. Level timeouts are relative to this.
+    started: Instant,
+
     /// Internal state
     state: RwLock<AggregationState>,
 
@@ -75,7 +81,13 @@ pub struct Aggregation<P: Protocol> {
 
 impl<P: Protocol + fmt::Debug> Aggregation<P> {
     pub fn new(protocol: P, config: Config) -> Arc<Self> {
-        let levels = Level::create_levels(protocol.partitioner());
+        Self::with_executor(protocol, config, Arc::new(TokioExecutor), &mut thread_rng())
+    }
+
+    /// Creates an aggregation that runs on `executor` and uses `rng` to pick the order in which
+    /// peers are contacted.
+    pub fn with_executor<R: Rng>(protocol: P, config: Config, executor: Arc<dyn Executor>, rng: &mut R) -> Arc<Self> {
+        let levels = Level::create_levels(protocol.partitioner(), rng);
         let todos = Arc::new(TodoList::new(protocol.evaluator()));
 
         // create aggregation
@@ -84,10 +96,11 @@ impl<P: Protocol + fmt::Debug> Aggregation<P> {
             levels,
             todos,
             protocol,
-            timers: Timers::new(),
+            timers: Timers::new(Arc::clone(&executor)),
+            started: executor.now(),
+            executor,
             state: RwLock::new(AggregationState {
                 result: None,
-                next_level_timeout: 0,
                 contribution: None,
             }),
             self_weak: MutableOnce::new(Weak::new()),
@@ -149,22 +162,7 @@ impl<P: Protocol + fmt::Debug> Aggregation<P> {
         }, this.config.update_interval);
 
         // register timer for level timeouts
-        // TODO: This ignores the timeout strategy
-        let weak = Arc::downgrade(this);
-        this.timers.set_interval(AggregationTimer::Timeout, move || {
-            let this = upgrade_weak!(weak);
-            let mut state = this.state.write();
-            let level = state.next_level_timeout;
-            if level < this.num_levels() {
-                trace!("Timeout for {:?} at level {}", this.protocol, level);
-                state.next_level_timeout += 1;
-                drop(state);
-                this.start_level(level);
-            }
-            else {
-                this.timers.clear_interval(&AggregationTimer::Timeout);
-            }
-        }, this.config.timeout);
+        this.schedule_level_timeout(0);
 
         // spawn thread handling TODOs
         //tokio::spawn(Arc::clone(&this.todos).into_future());
@@ -174,6 +172,29 @@ impl<P: Protocol + fmt::Debug> Aggregation<P> {
         self.levels.len()
     }
 
+    /// Time since the aggregation was started
+    fn elapsed(&self) -> Duration {
+        self.executor.now().duration_since(self.started)
+    }
+
+    /// Starts `level` when it times out, and then schedules the timeout of the next level
+    fn schedule_level_timeout(&self, level: usize) {
+        if level >= self.num_levels() {
+            return;
+        }
+
+        let timeout = self.config.timeouts.level_timeout(level);
+        let delay = timeout.checked_sub(self.elapsed()).unwrap_or_default();
+
+        let weak = Weak::clone(&self.self_weak);
+        self.timers.reset_delay(AggregationTimer::Timeout, move || {
+            let this = upgrade_weak!(weak);
+            trace!("Timeout for {:?} at level {}", this.protocol, level);
+            this.start_level(level);
+            this.schedule_level_timeout(level + 1);
+        }, delay);
+    }
+
     /// Starts level `level`
     fn start_level(&self, level: usize) {
         let level = self.levels.get(level)
@@ -231,6 +252,7 @@ impl<P: Protocol + fmt::Debug> Aggregation<P> {
             if best.len() == level.num_peers() {
                 trace!("Level {} complete", level.id);
                 level_state.receive_completed = true;
+                self.config.timeouts.level_completed(level.id, self.elapsed());
 
                 if level.id + 1 < self.levels.len() {
                     // activate next level
@@ -380,6 +402,6 @@ impl<P: Protocol + fmt::Debug> Aggregation<P> {
                 })
         };
 
-        tokio::spawn(process_fut);
+        self.executor.spawn(Box::new(process_fut));
     }
 }
@@ -1,5 +1,22 @@
+use std::sync::Arc;
 use std::time::Duration;
 
+use crate::timeout::{LinearTimeout, TimeoutStrategy};
+
+
+/// Errors of invalid Handel configurations
+#[derive(Clone, Debug, Fail, PartialEq)]
+pub enum ConfigError {
+    #[fail(display = "{} must be greater than 0", _0)]
+    Zero(&'static str),
+    #[fail(display = "{} must be a duration greater than 0", _0)]
+    ZeroDuration(&'static str),
+    #[fail(display = "{} must be at least 1, but is {}", _0, _1)]
+    InvalidFactor(&'static str, f64),
+    #[fail(display = "{} must not be greater than {}", _0, _1)]
+    InvalidRange(&'static str, &'static str),
+}
+
 
 #[derive(Clone, Debug)]
 pub struct Config {
@@ -9,21 +26,36 @@ pub struct Config {
     /// Frequency at which updates are sent to peers
     pub update_interval: Duration,
 
-    /// Timeout for levels
-    pub timeout: Duration,
+    /// Strategy deciding when levels time out
+    pub timeouts: Arc<dyn TimeoutStrategy>,
 
     /// How many peers are contacted at each level
     pub peer_count: usize,
 
 }
 
+impl Config {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.update_count == 0 {
+            return Err(ConfigError::Zero("update_count"));
+        }
+        if self.update_interval == Duration::default() {
+            return Err(ConfigError::ZeroDuration("update_interval"));
+        }
+        if self.peer_count == 0 {
+            return Err(ConfigError::Zero("peer_count"));
+        }
+        Ok(())
+    }
+}
+
 
 impl Default for Config {
     fn default() -> Self {
         Config {
             update_count: 1,
             update_interval: Duration::from_millis(100),
-            timeout: Duration::from_millis(500),
+            timeouts: Arc::new(LinearTimeout::default()),
             peer_count: 10,
         }
     }
@@ -0,0 +1,111 @@
+/// Where an aggregation runs its background work and gets its time from
+///
+/// Aggregations normally run on the tokio runtime they were created in. The simulation runs them
+/// on a single-threaded event loop with a virtual clock instead.
+
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::{future, Future};
+use futures::sync::oneshot;
+use parking_lot::Mutex;
+use tokio::timer::Delay;
+
+
+pub type BoxFuture = Box<dyn Future<Item=(), Error=()> + Send>;
+
+
+pub trait Executor: Send + Sync {
+    /// The current time
+    fn now(&self) -> Instant;
+
+    /// Runs `future` in the background
+    fn spawn(&self, future: BoxFuture);
+
+    /// Future that resolves once `deadline` passed
+    fn delay(&self, deadline: Instant) -> BoxFuture;
+}
+
+
+/// Runs futures on the current tokio runtime and uses real time
+#[derive(Clone, Debug, Default)]
+pub struct TokioExecutor;
+
+impl Executor for TokioExecutor {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn spawn(&self, future: BoxFuture) {
+        tokio::spawn(future);
+    }
+
+    fn delay(&self, deadline: Instant) -> BoxFuture {
+        Box::new(Delay::new(deadline)
+            .map_err(|e| warn!("Timer failed: {}", e)))
+    }
+}
+
+
+/// Delays and intervals on an `Executor`. Like `utils::timers::Timers`, but they don't need a
+/// tokio runtime. All of them are canceled when the `Timers` are dropped.
+pub struct Timers<K: Eq + Hash + Debug> {
+    executor: Arc<dyn Executor>,
+    cancel: Mutex<HashMap<K, oneshot::Sender<()>>>,
+}
+
+impl<K: Eq + Hash + Debug> Timers<K> {
+    pub fn new(executor: Arc<dyn Executor>) -> Self {
+        Self {
+            executor,
+            cancel: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Calls `func` every `period`, replacing the timer that was set for `key` before
+    pub fn set_interval<F>(&self, key: K, func: F, period: Duration)
+        where F: Fn() + Send + Sync + 'static {
+        let executor = Arc::clone(&self.executor);
+        let func = Arc::new(func);
+        let task = future::loop_fn(self.executor.now() + period, move |deadline| {
+            let func = Arc::clone(&func);
+            executor.delay(deadline)
+                .map(move |_| {
+                    func();
+                    future::Loop::<(), Instant>::Continue(deadline + period)
+                })
+        });
+        self.start(key, task);
+    }
+
+    /// Calls `func` after `delay`, replacing the timer that was set for `key` before
+    pub fn reset_delay<F>(&self, key: K, func: F, delay: Duration)
+        where F: FnOnce() + Send + 'static {
+        let task = self.executor.delay(self.executor.now() + delay)
+            .map(move |_| func());
+        self.start(key, task);
+    }
+
+    /// Cancels the timer that was set for `key`
+    pub fn clear_interval(&self, key: &K) {
+        if let Some(cancel) = self.cancel.lock().remove(key) {
+            cancel.send(()).unwrap_or(());
+        }
+    }
+
+    fn start<F: Future<Item=(), Error=()> + Send + 'static>(&self, key: K, task: F) {
+        // The task ends when it's canceled or the sender is dropped with the timers.
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        let task = task.select(cancel_rx.map_err(|_| ()))
+            .map(|_| ()).map_err(|_| ());
+
+        if let Some(cancel) = self.cancel.lock().insert(key, cancel_tx) {
+            cancel.send(()).unwrap_or(());
+        }
+        self.executor.spawn(Box::new(task));
+    }
+}
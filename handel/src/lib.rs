@@ -39,4 +39,6 @@ pub mod protocol;
 pub mod update;
 pub mod aggregation;
 pub mod sender;
+pub mod executor;
+pub mod simulation;
 mod todo;
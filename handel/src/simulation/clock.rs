@@ -0,0 +1,254 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, VecDeque};
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::{Async, Future, Poll};
+use futures::executor::{self, Notify, Spawn};
+use futures::task::{self, Task};
+use parking_lot::Mutex;
+
+use crate::executor::{BoxFuture, Executor};
+
+
+
+/// IDs of the tasks that were notified and need to be polled again
+#[derive(Default)]
+struct ReadyQueue(Mutex<VecDeque<usize>>);
+
+impl Notify for ReadyQueue {
+    fn notify(&self, id: usize) {
+        self.0.lock().push_back(id);
+    }
+}
+
+
+/// A task waiting for the clock to reach `deadline`
+struct Timer {
+    deadline: Instant,
+
+    /// Timers with the same deadline fire in the order they were set
+    seq: u64,
+
+    task: Task,
+}
+
+impl PartialEq for Timer {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Timer {}
+
+impl PartialOrd for Timer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Timer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.deadline, self.seq).cmp(&(other.deadline, other.seq))
+    }
+}
+
+
+struct ClockState {
+    now: Instant,
+    timers: BinaryHeap<Reverse<Timer>>,
+    next_seq: u64,
+
+    /// Tasks by ID. A task is `None` once it finished, or while it's being polled.
+    tasks: Vec<Option<Spawn<BoxFuture>>>,
+}
+
+
+/// Single-threaded event loop with a virtual clock
+///
+/// The clock only advances when no task can make progress, and then jumps to the next timer. Runs
+/// are thus deterministic and don't take longer than the computations they involve, no matter how
+/// much time is simulated.
+#[derive(Clone)]
+pub struct VirtualClock {
+    state: Arc<Mutex<ClockState>>,
+    ready: Arc<ReadyQueue>,
+
+    /// Origin of the simulated time
+    start: Instant,
+}
+
+impl VirtualClock {
+    pub fn new() -> Self {
+        let start = Instant::now();
+        Self {
+            state: Arc::new(Mutex::new(ClockState {
+                now: start,
+                timers: BinaryHeap::new(),
+                next_seq: 0,
+                tasks: Vec::new(),
+            })),
+            ready: Arc::default(),
+            start,
+        }
+    }
+
+    /// Simulated time since the clock was created
+    pub fn elapsed(&self) -> Duration {
+        self.now().duration_since(self.start)
+    }
+
+    /// Runs the tasks until `done` returns `true`, or no more timers fire before `deadline`. In
+    /// the latter case, the clock is advanced to `deadline`.
+    pub fn run_until<F: FnMut() -> bool>(&self, deadline: Duration, mut done: F) {
+        let deadline = self.start + deadline;
+        loop {
+            self.poll_ready();
+            if done() {
+                return;
+            }
+
+            let task = {
+                let mut state = self.state.lock();
+                let next = state.timers.peek()
+                    .map(|Reverse(timer)| timer.deadline)
+                    .filter(|&next| next <= deadline);
+                if next.is_none() {
+                    state.now = state.now.max(deadline);
+                    return;
+                }
+                let Reverse(timer) = state.timers.pop().unwrap();
+                state.now = state.now.max(timer.deadline);
+                timer.task
+            };
+            task.notify();
+        }
+    }
+
+    /// Drops all tasks and timers. Tasks might hold the clock themselves, so this needs to be
+    /// called once the clock isn't used anymore.
+    pub fn shutdown(&self) {
+        let mut state = self.state.lock();
+        let tasks = mem::replace(&mut state.tasks, Vec::new());
+        let timers = mem::replace(&mut state.timers, BinaryHeap::new());
+        drop(state);
+
+        // Dropping the tasks might notify other tasks, so don't hold the lock.
+        drop(tasks);
+        drop(timers);
+        self.ready.0.lock().clear();
+    }
+
+    /// Polls the notified tasks until none is left
+    fn poll_ready(&self) {
+        loop {
+            let id = match self.ready.0.lock().pop_front() {
+                Some(id) => id,
+                None => return,
+            };
+
+            // Don't hold the lock while polling, since the task might spawn other tasks or set
+            // timers.
+            let task = self.state.lock().tasks.get_mut(id).and_then(Option::take);
+            if let Some(mut task) = task {
+                if let Ok(Async::NotReady) = task.poll_future_notify(&self.ready, id) {
+                    self.state.lock().tasks[id] = Some(task);
+                }
+            }
+        }
+    }
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Executor for VirtualClock {
+    fn now(&self) -> Instant {
+        self.state.lock().now
+    }
+
+    fn spawn(&self, future: BoxFuture) {
+        let id = {
+            let mut state = self.state.lock();
+            state.tasks.push(Some(executor::spawn(future)));
+            state.tasks.len() - 1
+        };
+        self.ready.notify(id);
+    }
+
+    fn delay(&self, deadline: Instant) -> BoxFuture {
+        Box::new(VirtualDelay {
+            clock: self.clone(),
+            deadline,
+            registered: false,
+        })
+    }
+}
+
+
+/// Resolves once the virtual clock reached `deadline`
+struct VirtualDelay {
+    clock: VirtualClock,
+    deadline: Instant,
+    registered: bool,
+}
+
+impl Future for VirtualDelay {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        let mut state = self.clock.state.lock();
+        if state.now >= self.deadline {
+            return Ok(Async::Ready(()));
+        }
+
+        if !self.registered {
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state.timers.push(Reverse(Timer {
+                deadline: self.deadline,
+                seq,
+                task: task::current(),
+            }));
+            self.registered = true;
+        }
+        Ok(Async::NotReady)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_fires_timers_in_simulated_time() {
+        let clock = VirtualClock::new();
+        let fired = Arc::new(Mutex::new(Vec::new()));
+
+        for &millis in &[300u64, 100, 200, 100] {
+            let fired = Arc::clone(&fired);
+            let elapsed = clock.clone();
+            clock.spawn(Box::new(clock.delay(clock.now() + Duration::from_millis(millis))
+                .map(move |_| fired.lock().push((millis, elapsed.elapsed())))));
+        }
+
+        clock.run_until(Duration::from_millis(250), || false);
+        assert_eq!(*fired.lock(), vec![
+            (100, Duration::from_millis(100)),
+            (100, Duration::from_millis(100)),
+            (200, Duration::from_millis(200)),
+        ]);
+        assert_eq!(clock.elapsed(), Duration::from_millis(250));
+
+        clock.run_until(Duration::from_secs(1), || fired.lock().len() == 4);
+        assert_eq!(fired.lock().last(), Some(&(300, Duration::from_millis(300))));
+        assert_eq!(clock.elapsed(), Duration::from_millis(300));
+        clock.shutdown();
+    }
+}
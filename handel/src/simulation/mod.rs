@@ -0,0 +1,385 @@
+/// In-process simulation of Handel aggregations
+///
+/// Runs one `Aggregation` per identity in a single process. The aggregations talk to each other
+/// over an in-memory network with configurable latency and message loss. A fraction of the
+/// identities can be offline or byzantine. The simulation reports how long each honest node took
+/// to reach the threshold, how many messages it sent and how much verification work it did.
+///
+/// All randomness (keys, faulty identities, order of peers, latency and loss) is derived from a
+/// seed. The aggregations run on a single-threaded event loop with a virtual clock, so runs with
+/// the same seed are identical. All durations are simulated time.
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use bls::SecureGenerate;
+use bls::bls12_381::KeyPair;
+use hash::{Blake2bHasher, Hasher};
+
+use crate::aggregation::{Aggregation, AggregationEvent};
+use crate::config::Config;
+use crate::multisig::IndividualSignature;
+
+use self::network::SimulatedNetwork;
+use self::protocol::{SimulatedProtocol, SimulatedRegistry, VerificationStats};
+
+pub use self::clock::VirtualClock;
+pub use self::network::NetworkConfig;
+
+pub mod clock;
+pub mod network;
+pub mod protocol;
+
+
+
+/// How a simulated identity behaves
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeBehaviour {
+    /// Follows the protocol
+    Honest,
+
+    /// Never contributes and doesn't receive any messages
+    Offline,
+
+    /// Follows the protocol, but contributes a signature over a different message
+    Byzantine,
+}
+
+
+#[derive(Clone, Debug)]
+pub struct SimulationConfig {
+    /// Number of identities
+    pub num_nodes: usize,
+
+    /// Number of votes needed for a final signature
+    pub threshold: usize,
+
+    /// Fraction of identities that are offline
+    pub offline: f64,
+
+    /// Fraction of identities that are byzantine
+    pub byzantine: f64,
+
+    /// Handel configuration used by all aggregations
+    pub handel: Config,
+
+    pub network: NetworkConfig,
+
+    /// The simulation is aborted after this (simulated) time
+    pub deadline: Duration,
+
+    /// Seed for all randomness in the simulation
+    pub seed: u64,
+}
+
+impl SimulationConfig {
+    /// Configuration for `num_nodes` identities that need a two-third majority
+    pub fn new(num_nodes: usize) -> Self {
+        SimulationConfig {
+            num_nodes,
+            threshold: num_nodes * 2 / 3 + 1,
+            offline: 0.0,
+            byzantine: 0.0,
+            handel: Config::default(),
+            network: NetworkConfig::default(),
+            deadline: Duration::from_secs(30),
+            seed: 0,
+        }
+    }
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self::new(16)
+    }
+}
+
+
+/// Results of a single node
+#[derive(Clone, Debug)]
+pub struct NodeReport {
+    pub node_id: usize,
+    pub behaviour: NodeBehaviour,
+
+    /// Time from the start of the simulation until the node had a final signature
+    pub time_to_threshold: Option<Duration>,
+
+    pub messages_sent: usize,
+    pub messages_received: usize,
+
+    pub individual_verifications: usize,
+    pub multisig_verifications: usize,
+
+    /// Number of public keys aggregated to verify multi-signatures
+    pub aggregated_keys: usize,
+}
+
+
+/// Results of a simulation run
+#[derive(Clone, Debug)]
+pub struct SimulationReport {
+    pub nodes: Vec<NodeReport>,
+
+    /// Simulated time until all honest nodes reached the threshold, or the deadline if they
+    /// didn't
+    pub elapsed: Duration,
+}
+
+impl SimulationReport {
+    pub fn honest(&self) -> impl Iterator<Item=&NodeReport> {
+        self.nodes.iter().filter(|node| node.behaviour == NodeBehaviour::Honest)
+    }
+
+    /// Whether all honest nodes reached the threshold
+    pub fn is_complete(&self) -> bool {
+        self.honest().all(|node| node.time_to_threshold.is_some())
+    }
+
+    /// Time until the last honest node reached the threshold
+    pub fn max_time_to_threshold(&self) -> Option<Duration> {
+        self.honest()
+            .map(|node| node.time_to_threshold)
+            .fold(Some(Duration::default()), |max, time| Some(max?.max(time?)))
+    }
+
+    pub fn messages_sent(&self) -> usize {
+        self.nodes.iter().map(|node| node.messages_sent).sum()
+    }
+
+    pub fn verifications(&self) -> usize {
+        self.nodes.iter().map(|node| node.individual_verifications + node.multisig_verifications).sum()
+    }
+}
+
+impl fmt::Display for SimulationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        writeln!(f, "{:>5} {:>10} {:>12} {:>8} {:>8} {:>10} {:>10} {:>8}",
+                 "node", "behaviour", "threshold", "sent", "recv", "individual", "multisig", "keys")?;
+        for node in &self.nodes {
+            let time = node.time_to_threshold
+                .map(|time| format!("{}ms", time.as_millis()))
+                .unwrap_or_else(|| "-".to_string());
+            writeln!(f, "{:>5} {:>10} {:>12} {:>8} {:>8} {:>10} {:>10} {:>8}",
+                     node.node_id, format!("{:?}", node.behaviour), time, node.messages_sent,
+                     node.messages_received, node.individual_verifications, node.multisig_verifications,
+                     node.aggregated_keys)?;
+        }
+        write!(f, "elapsed: {}ms, messages: {}, verifications: {}",
+               self.elapsed.as_millis(), self.messages_sent(), self.verifications())
+    }
+}
+
+
+pub struct Simulation {
+    config: SimulationConfig,
+    key_pairs: Vec<KeyPair>,
+    behaviours: Vec<NodeBehaviour>,
+    rng: StdRng,
+}
+
+impl Simulation {
+    pub fn new(config: SimulationConfig) -> Self {
+        assert!(config.num_nodes > 0, "Simulation needs at least one node");
+        assert!(config.offline + config.byzantine <= 1.0, "More faulty than total identities");
+
+        let mut rng = StdRng::seed_from_u64(config.seed);
+
+        let key_pairs = (0..config.num_nodes)
+            .map(|_| KeyPair::generate(&mut rng))
+            .collect();
+
+        // Pick the faulty identities at random
+        let num_offline = (config.num_nodes as f64 * config.offline).round() as usize;
+        let num_byzantine = (config.num_nodes as f64 * config.byzantine).round() as usize;
+        let mut node_ids: Vec<usize> = (0..config.num_nodes).collect();
+        node_ids.shuffle(&mut rng);
+        let mut behaviours = vec![NodeBehaviour::Honest; config.num_nodes];
+        for &node_id in &node_ids[..num_offline] {
+            behaviours[node_id] = NodeBehaviour::Offline;
+        }
+        for &node_id in &node_ids[num_offline..num_offline + num_byzantine] {
+            behaviours[node_id] = NodeBehaviour::Byzantine;
+        }
+
+        Self {
+            config,
+            key_pairs,
+            behaviours,
+            rng,
+        }
+    }
+
+    pub fn behaviours(&self) -> &[NodeBehaviour] {
+        &self.behaviours
+    }
+
+    /// Runs the aggregations until all honest nodes have a final signature or the deadline passed
+    pub fn run(self) -> SimulationReport {
+        let Simulation { config, key_pairs, behaviours, mut rng } = self;
+        let num_nodes = config.num_nodes;
+
+        let message_hash = Blake2bHasher::default().digest(b"handel simulation");
+        let forged_hash = Blake2bHasher::default().digest(b"byzantine");
+
+        let clock = VirtualClock::new();
+        let registry = Arc::new(SimulatedRegistry::new(key_pairs.iter().map(|key_pair| key_pair.public.clone()).collect()));
+        let network = Arc::new(SimulatedNetwork::new(config.network.clone(), num_nodes, StdRng::seed_from_u64(rng.gen()), clock.clone()));
+        let verification_stats: Vec<Arc<VerificationStats>> = (0..num_nodes).map(|_| Arc::default()).collect();
+        let times_to_threshold = Arc::new(Mutex::new(vec![None; num_nodes]));
+        let num_honest = behaviours.iter().filter(|&&behaviour| behaviour == NodeBehaviour::Honest).count();
+
+        let mut aggregations = Vec::new();
+        for (node_id, &behaviour) in behaviours.iter().enumerate() {
+            if behaviour == NodeBehaviour::Offline {
+                continue;
+            }
+            let protocol = SimulatedProtocol::new(node_id, message_hash.clone(), config.threshold,
+                Arc::clone(&registry), Arc::clone(&network), Arc::clone(&verification_stats[node_id]));
+            let aggregation = Aggregation::with_executor(protocol, config.handel.clone(), Arc::new(clock.clone()), &mut rng);
+            network.connect(node_id, &aggregation);
+
+            if behaviour == NodeBehaviour::Honest {
+                let times_to_threshold = Arc::clone(&times_to_threshold);
+                let clock = clock.clone();
+                aggregation.notifier.write().register(move |event: AggregationEvent| {
+                    match event {
+                        AggregationEvent::Complete { .. } => {
+                            times_to_threshold.lock()[node_id] = Some(clock.elapsed());
+                        },
+                    }
+                });
+            }
+            aggregations.push((node_id, aggregation));
+        }
+
+        for (node_id, aggregation) in &aggregations {
+            let hash = match behaviours[*node_id] {
+                NodeBehaviour::Byzantine => forged_hash.clone(),
+                _ => message_hash.clone(),
+            };
+            let signature = key_pairs[*node_id].sign_hash(hash);
+            aggregation.push_contribution(IndividualSignature::new(signature, *node_id));
+        }
+
+        let is_complete = || times_to_threshold.lock().iter().filter(|time| time.is_some()).count() == num_honest;
+        clock.run_until(config.deadline, &is_complete);
+        if !is_complete() {
+            debug!("Simulation deadline passed");
+        }
+        let elapsed = clock.elapsed();
+
+        // The tasks of the clock hold the network and aggregations
+        drop(aggregations);
+        clock.shutdown();
+
+        let times_to_threshold = times_to_threshold.lock();
+        let nodes = behaviours.iter().enumerate()
+            .map(|(node_id, &behaviour)| {
+                let messages = network.stats(node_id);
+                let verifications = &verification_stats[node_id];
+                NodeReport {
+                    node_id,
+                    behaviour,
+                    time_to_threshold: times_to_threshold[node_id],
+                    messages_sent: messages.sent.load(Ordering::Relaxed),
+                    messages_received: messages.received.load(Ordering::Relaxed),
+                    individual_verifications: verifications.individual.load(Ordering::Relaxed),
+                    multisig_verifications: verifications.multisig.load(Ordering::Relaxed),
+                    aggregated_keys: verifications.aggregated_keys.load(Ordering::Relaxed),
+                }
+            })
+            .collect();
+
+        SimulationReport {
+            nodes,
+            elapsed,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simulation_config(num_nodes: usize) -> SimulationConfig {
+        let mut config = SimulationConfig::new(num_nodes);
+        config.network.latency = Duration::from_millis(5);
+        config.network.jitter = Duration::from_millis(5);
+        config.deadline = Duration::from_secs(20);
+        config
+    }
+
+    #[test]
+    fn it_picks_faulty_nodes_from_the_seed() {
+        let mut config = simulation_config(16);
+        config.offline = 0.25;
+        config.byzantine = 0.125;
+
+        let behaviours = Simulation::new(config.clone()).behaviours().to_vec();
+        assert_eq!(behaviours.iter().filter(|&&b| b == NodeBehaviour::Offline).count(), 4);
+        assert_eq!(behaviours.iter().filter(|&&b| b == NodeBehaviour::Byzantine).count(), 2);
+        assert_eq!(Simulation::new(config).behaviours(), &behaviours[..]);
+    }
+
+    #[test]
+    fn it_reaches_the_threshold() {
+        let config = simulation_config(8);
+        let report = Simulation::new(config.clone()).run();
+
+        assert!(report.is_complete(), "Simulation didn't complete:\n{}", report);
+        assert!(report.messages_sent() > 0);
+        assert!(report.verifications() > 0);
+
+        // The simulation stops as soon as the last honest node is done.
+        let max_time_to_threshold = report.max_time_to_threshold().unwrap();
+        assert_eq!(report.elapsed, max_time_to_threshold);
+        assert!(max_time_to_threshold < config.deadline);
+    }
+
+    #[test]
+    fn it_reaches_the_threshold_with_faulty_nodes() {
+        let mut config = simulation_config(16);
+        config.offline = 0.125;
+        config.byzantine = 0.125;
+        config.network.loss = 0.05;
+        let report = Simulation::new(config.clone()).run();
+
+        assert!(report.is_complete(), "Simulation didn't complete:\n{}", report);
+        assert!(report.elapsed < config.deadline);
+        for node in report.nodes.iter().filter(|node| node.behaviour == NodeBehaviour::Offline) {
+            assert_eq!(node.messages_sent, 0);
+            assert_eq!(node.messages_received, 0);
+        }
+    }
+
+    #[test]
+    fn it_fails_without_enough_honest_nodes() {
+        let mut config = simulation_config(8);
+        config.offline = 0.5;
+        config.deadline = Duration::from_secs(2);
+        let report = Simulation::new(config.clone()).run();
+
+        assert!(!report.is_complete());
+        assert_eq!(report.max_time_to_threshold(), None);
+        assert_eq!(report.elapsed, config.deadline);
+    }
+
+    #[test]
+    fn it_repeats_runs_with_the_same_seed() {
+        let mut config = simulation_config(16);
+        config.byzantine = 0.125;
+        config.network.loss = 0.05;
+        config.seed = 42;
+
+        let report = Simulation::new(config.clone()).run();
+        assert_eq!(Simulation::new(config).run().to_string(), report.to_string());
+    }
+}
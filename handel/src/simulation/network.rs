@@ -0,0 +1,140 @@
+use std::io::Error as IoError;
+use std::sync::{Arc, Weak};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use futures::Future;
+use parking_lot::{Mutex, RwLock};
+use rand::Rng;
+use rand::rngs::StdRng;
+
+use crate::aggregation::Aggregation;
+use crate::executor::Executor;
+use crate::sender::Sender;
+use crate::update::LevelUpdate;
+
+use super::clock::VirtualClock;
+use super::protocol::SimulatedProtocol;
+
+
+
+/// Properties of the simulated network links
+#[derive(Clone, Debug)]
+pub struct NetworkConfig {
+    /// Minimum time it takes to deliver a message
+    pub latency: Duration,
+
+    /// Maximum random delay that is added to `latency`
+    pub jitter: Duration,
+
+    /// Probability that a message is lost
+    pub loss: f64,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        NetworkConfig {
+            latency: Duration::from_millis(20),
+            jitter: Duration::from_millis(10),
+            loss: 0.0,
+        }
+    }
+}
+
+
+/// Message counters of a single node
+#[derive(Debug, Default)]
+pub struct MessageStats {
+    pub sent: AtomicUsize,
+    pub received: AtomicUsize,
+}
+
+
+/// In-memory network connecting the aggregations of a simulation
+pub struct SimulatedNetwork {
+    config: NetworkConfig,
+
+    /// The aggregations by node ID. Offline nodes don't have one.
+    nodes: RwLock<Vec<Option<Weak<Aggregation<SimulatedProtocol>>>>>,
+
+    /// Message counters by node ID
+    stats: Vec<MessageStats>,
+
+    /// Source of randomness for latency and message loss
+    rng: Mutex<StdRng>,
+
+    /// Delivers the messages after their latency
+    clock: VirtualClock,
+}
+
+impl SimulatedNetwork {
+    pub fn new(config: NetworkConfig, num_nodes: usize, rng: StdRng, clock: VirtualClock) -> Self {
+        Self {
+            config,
+            nodes: RwLock::new(vec![None; num_nodes]),
+            stats: (0..num_nodes).map(|_| MessageStats::default()).collect(),
+            rng: Mutex::new(rng),
+            clock,
+        }
+    }
+
+    /// Connects the aggregation of node `node_id` to the network
+    pub fn connect(&self, node_id: usize, aggregation: &Arc<Aggregation<SimulatedProtocol>>) {
+        self.nodes.write()[node_id] = Some(Arc::downgrade(aggregation));
+    }
+
+    pub fn stats(&self, node_id: usize) -> &MessageStats {
+        &self.stats[node_id]
+    }
+
+    /// Sends `update` from `from` to `to`
+    pub fn send(self: &Arc<Self>, from: usize, to: usize, update: LevelUpdate) {
+        self.stats[from].sent.fetch_add(1, Ordering::Relaxed);
+
+        let latency = {
+            let mut rng = self.rng.lock();
+            if self.config.loss > 0.0 && rng.gen_bool(self.config.loss) {
+                trace!("Dropping update from {} to {}", from, to);
+                return;
+            }
+            let jitter = self.config.jitter.as_micros() as u64;
+            let jitter = if jitter > 0 { rng.gen_range(0, jitter) } else { 0 };
+            self.config.latency + Duration::from_micros(jitter)
+        };
+
+        let network = Arc::clone(self);
+        self.clock.spawn(Box::new(self.clock.delay(self.clock.now() + latency)
+            .map(move |_| {
+                let aggregation = network.nodes.read()[to].as_ref()
+                    .and_then(|weak| weak.upgrade());
+                if let Some(aggregation) = aggregation {
+                    network.stats[to].received.fetch_add(1, Ordering::Relaxed);
+                    aggregation.push_update(update);
+                }
+            })));
+    }
+}
+
+
+/// Sender of a single node in the simulated network
+pub struct SimulatedSender {
+    node_id: usize,
+    network: Arc<SimulatedNetwork>,
+}
+
+impl SimulatedSender {
+    pub fn new(node_id: usize, network: Arc<SimulatedNetwork>) -> Self {
+        Self {
+            node_id,
+            network,
+        }
+    }
+}
+
+impl Sender for SimulatedSender {
+    type Error = IoError;
+
+    fn send_to(&self, peer_id: usize, update: LevelUpdate) {
+        self.network.send(self.node_id, peer_id, update);
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use futures::future;
+use futures::future::FutureResult;
+use parking_lot::RwLock;
+
+use bls::bls12_381::PublicKey;
+use hash::Blake2bHash;
+
+use crate::evaluator::WeightedVote;
+use crate::identity::{IdentityRegistry, WeightRegistry};
+use crate::multisig::Signature;
+use crate::partitioner::BinomialPartitioner;
+use crate::protocol::Protocol;
+use crate::store::ReplaceStore;
+use crate::verifier::{MultithreadedVerifier, VerificationResult, Verifier};
+
+use super::network::{SimulatedNetwork, SimulatedSender};
+
+
+
+/// Every identity of the simulation has a weight of 1
+pub struct SimulatedRegistry {
+    public_keys: Vec<PublicKey>,
+}
+
+impl SimulatedRegistry {
+    pub fn new(public_keys: Vec<PublicKey>) -> Self {
+        Self { public_keys }
+    }
+}
+
+impl IdentityRegistry for SimulatedRegistry {
+    fn public_key(&self, id: usize) -> Option<PublicKey> {
+        self.public_keys.get(id).cloned()
+    }
+}
+
+impl WeightRegistry for SimulatedRegistry {
+    fn weight(&self, id: usize) -> Option<usize> {
+        if id < self.public_keys.len() { Some(1) } else { None }
+    }
+}
+
+
+/// Verification counters of a single node
+#[derive(Debug, Default)]
+pub struct VerificationStats {
+    /// Number of individual signatures verified
+    pub individual: AtomicUsize,
+
+    /// Number of multi-signatures verified
+    pub multisig: AtomicUsize,
+
+    /// Number of public keys aggregated to verify multi-signatures
+    pub aggregated_keys: AtomicUsize,
+}
+
+
+/// Verifies signatures right away, on the thread of the simulation. Verification doesn't take
+/// any simulated time.
+pub struct SimulatedVerifier {
+    message_hash: Blake2bHash,
+    registry: Arc<SimulatedRegistry>,
+}
+
+impl SimulatedVerifier {
+    pub fn new(message_hash: Blake2bHash, registry: Arc<SimulatedRegistry>) -> Self {
+        Self {
+            message_hash,
+            registry,
+        }
+    }
+}
+
+impl Verifier for SimulatedVerifier {
+    type Output = FutureResult<VerificationResult, ()>;
+
+    fn verify(&self, signature: &Signature) -> Self::Output {
+        let registry = Arc::clone(&self.registry);
+        let message_hash = self.message_hash.clone();
+        future::ok(match signature {
+            Signature::Individual(individual) => {
+                MultithreadedVerifier::verify_individual(registry, message_hash, individual)
+            },
+            Signature::Multi(multisig) => {
+                MultithreadedVerifier::verify_multisig(registry, message_hash, multisig)
+            },
+        })
+    }
+}
+
+
+/// Verifier that counts the work it passes on to `inner`
+pub struct CountingVerifier<V: Verifier> {
+    inner: V,
+    stats: Arc<VerificationStats>,
+}
+
+impl<V: Verifier> CountingVerifier<V> {
+    pub fn new(inner: V, stats: Arc<VerificationStats>) -> Self {
+        Self {
+            inner,
+            stats,
+        }
+    }
+}
+
+impl<V: Verifier> Verifier for CountingVerifier<V> {
+    type Output = V::Output;
+
+    fn verify(&self, signature: &Signature) -> Self::Output {
+        match signature {
+            Signature::Individual(_) => {
+                self.stats.individual.fetch_add(1, Ordering::Relaxed);
+            },
+            Signature::Multi(multisig) => {
+                self.stats.multisig.fetch_add(1, Ordering::Relaxed);
+                self.stats.aggregated_keys.fetch_add(multisig.len(), Ordering::Relaxed);
+            },
+        }
+        self.inner.verify(signature)
+    }
+}
+
+
+pub type SimulatedStore = ReplaceStore<BinomialPartitioner>;
+pub type SimulatedEvaluator = WeightedVote<SimulatedStore, SimulatedRegistry, BinomialPartitioner>;
+
+
+/// Protocol of a single node in the simulation
+pub struct SimulatedProtocol {
+    node_id: usize,
+    registry: Arc<SimulatedRegistry>,
+    verifier: Arc<CountingVerifier<SimulatedVerifier>>,
+    partitioner: Arc<BinomialPartitioner>,
+    store: Arc<RwLock<SimulatedStore>>,
+    evaluator: Arc<SimulatedEvaluator>,
+    sender: Arc<SimulatedSender>,
+}
+
+impl SimulatedProtocol {
+    pub fn new(node_id: usize, message_hash: Blake2bHash, threshold: usize, registry: Arc<SimulatedRegistry>,
+               network: Arc<SimulatedNetwork>, verification_stats: Arc<VerificationStats>) -> Self {
+        let num_nodes = registry.public_keys.len();
+        let verifier = Arc::new(CountingVerifier::new(
+            SimulatedVerifier::new(message_hash, Arc::clone(&registry)),
+            verification_stats,
+        ));
+        let partitioner = Arc::new(BinomialPartitioner::new(node_id, num_nodes));
+        let store = Arc::new(RwLock::new(ReplaceStore::new(Arc::clone(&partitioner))));
+        let evaluator = Arc::new(WeightedVote::new(
+            Arc::clone(&store),
+            Arc::clone(&registry),
+            Arc::clone(&partitioner),
+            threshold,
+        ));
+        let sender = Arc::new(SimulatedSender::new(node_id, network));
+
+        Self {
+            node_id,
+            registry,
+            verifier,
+            partitioner,
+            store,
+            evaluator,
+            sender,
+        }
+    }
+}
+
+impl fmt::Debug for SimulatedProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "SimulatedProtocol {{ node_id: {} }}", self.node_id)
+    }
+}
+
+impl Protocol for SimulatedProtocol {
+    type Registry = SimulatedRegistry;
+    type Verifier = CountingVerifier<SimulatedVerifier>;
+    type Store = SimulatedStore;
+    type Evaluator = SimulatedEvaluator;
+    type Partitioner = BinomialPartitioner;
+    type Sender = SimulatedSender;
+
+    fn registry(&self) -> Arc<Self::Registry> {
+        Arc::clone(&self.registry)
+    }
+
+    fn verifier(&self) -> Arc<Self::Verifier> {
+        Arc::clone(&self.verifier)
+    }
+
+    fn store(&self) -> Arc<RwLock<Self::Store>> {
+        Arc::clone(&self.store)
+    }
+
+    fn evaluator(&self) -> Arc<Self::Evaluator> {
+        Arc::clone(&self.evaluator)
+    }
+
+    fn partitioner(&self) -> Arc<Self::Partitioner> {
+        Arc::clone(&self.partitioner)
+    }
+
+    fn sender(&self) -> Arc<Self::Sender> {
+        Arc::clone(&self.sender)
+    }
+
+    fn node_id(&self) -> usize {
+        self.node_id
+    }
+}
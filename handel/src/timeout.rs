@@ -1,22 +1,34 @@
-/// A timeout strategy defines after which period a level timeouts
+/// A timeout strategy defines after which period a level times out
 ///
-/// TODO: This is not used currently
+/// When a level times out, the aggregation starts it even though the previous levels didn't
+/// complete yet.
 
 
+use std::fmt;
+use std::sync::Arc;
 use std::time::Duration;
-use std::ops::Range;
 
-use futures::{Stream, stream};
-use tokio::util::StreamExt;
+use parking_lot::RwLock;
 
+use crate::config::ConfigError;
 
-pub trait TimeoutStrategy {
-    type Timeouts: Stream<Item=usize, Error=()>;
 
-    fn timeouts(&self, num_levels: usize) -> Self::Timeouts;
+pub trait TimeoutStrategy: fmt::Debug + Send + Sync {
+    /// Time since the start of the aggregation after which `level` is started at the latest
+    fn level_timeout(&self, level: usize) -> Duration;
+
+    /// Called when `level` completed `elapsed` after the start of the aggregation
+    fn level_completed(&self, _level: usize, _elapsed: Duration) {}
 }
 
 
+/// Scales `duration` by `factor` with millisecond precision
+fn scale(duration: Duration, factor: f64) -> Duration {
+    Duration::from_millis((duration.as_millis() as f64 * factor).round() as u64)
+}
+
+
+/// Levels time out one after another, each `period` after the previous one
 #[derive(Clone, Debug)]
 pub struct LinearTimeout {
     period: Duration,
@@ -37,18 +49,236 @@ impl Default for LinearTimeout {
 }
 
 impl TimeoutStrategy for LinearTimeout {
-    type Timeouts = Box<dyn Stream<Item=usize, Error=()> + Send>;
-
-    fn timeouts(&self, num_levels: usize) -> Self::Timeouts {
-        debug!("Creating timeout stream: period={:?}, levels={}", self.period, num_levels);
-        Box::new(stream::iter_ok::<Range<usize>, ()>(0..num_levels)
-            .throttle(self.period.clone())
-            .map(|level| {
-                debug!("Timeout for level {}", level);
-                level
-            })
-            .map_err(|e| {
-                warn!("Throttle error: {:?}", e);
-            }))
+    fn level_timeout(&self, level: usize) -> Duration {
+        self.period * (level as u32 + 1)
+    }
+}
+
+
+/// The time between two level timeouts grows by `factor` with every level, starting with `base`
+/// and never exceeding `max`.
+///
+/// Higher levels contain more peers and thus take longer to complete.
+#[derive(Clone, Debug)]
+pub struct ExponentialTimeout {
+    base: Duration,
+    factor: f64,
+    max: Duration,
+}
+
+impl ExponentialTimeout {
+    pub fn new(base: Duration, factor: f64, max: Duration) -> Self {
+        ExponentialTimeout {
+            base,
+            factor,
+            max,
+        }
+    }
+}
+
+impl TimeoutStrategy for ExponentialTimeout {
+    fn level_timeout(&self, level: usize) -> Duration {
+        let mut timeout = Duration::default();
+        let mut period = self.base;
+        for _ in 0..=level {
+            timeout += period.min(self.max);
+            period = scale(period, self.factor);
+        }
+        timeout
+    }
+}
+
+
+/// Learns when levels complete and times them out `multiplier` times later than they usually
+/// complete.
+///
+/// Completion times are tracked as a moving average per level. Until the previous level completed
+/// once, a level times out `period` after the previous one. The time between two level timeouts
+/// is kept between `min` and `max`.
+///
+/// The strategy should be shared between the aggregations of the same kind, so that later
+/// aggregations benefit from the earlier ones.
+#[derive(Debug)]
+pub struct AdaptiveTimeout {
+    period: Duration,
+    multiplier: f64,
+    min: Duration,
+    max: Duration,
+
+    /// Moving average of the completion time of each level
+    completion_times: RwLock<Vec<Option<Duration>>>,
+}
+
+impl AdaptiveTimeout {
+    /// Weight of a new observation in the moving average
+    const SMOOTHING: f64 = 0.2;
+
+    pub fn new(period: Duration, multiplier: f64, min: Duration, max: Duration) -> Self {
+        AdaptiveTimeout {
+            period,
+            multiplier,
+            min,
+            max,
+            completion_times: RwLock::new(Vec::new()),
+        }
+    }
+}
+
+impl TimeoutStrategy for AdaptiveTimeout {
+    fn level_timeout(&self, level: usize) -> Duration {
+        let completion_times = self.completion_times.read();
+        let mut timeout = Duration::default();
+        for current in 0..=level {
+            // A level can start when the previous level completed. Level 0 is only our own
+            // contribution and can start right away.
+            let expected = match current.checked_sub(1) {
+                Some(previous) => completion_times.get(previous).and_then(|completion_time| *completion_time)
+                    .map(|completion_time| scale(completion_time, self.multiplier))
+                    .unwrap_or(timeout + self.period),
+                None => Duration::default(),
+            };
+            let min = timeout + self.min;
+            let max = timeout + self.max;
+            timeout = expected.max(min).min(max);
+        }
+        timeout
+    }
+
+    fn level_completed(&self, level: usize, elapsed: Duration) {
+        let mut completion_times = self.completion_times.write();
+        if completion_times.len() <= level {
+            completion_times.resize(level + 1, None);
+        }
+        let average = &mut completion_times[level];
+        *average = Some(match *average {
+            Some(average) => scale(average, 1.0 - Self::SMOOTHING) + scale(elapsed, Self::SMOOTHING),
+            None => elapsed,
+        });
+    }
+}
+
+
+/// Selects a timeout strategy and its parameters
+#[derive(Clone, Debug, PartialEq)]
+pub enum TimeoutConfig {
+    Linear {
+        period: Duration,
+    },
+    Exponential {
+        base: Duration,
+        factor: f64,
+        max: Duration,
+    },
+    Adaptive {
+        period: Duration,
+        multiplier: f64,
+        min: Duration,
+        max: Duration,
+    },
+}
+
+impl TimeoutConfig {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        match self {
+            TimeoutConfig::Linear { period } => {
+                if *period == Duration::default() {
+                    return Err(ConfigError::ZeroDuration("timeout"));
+                }
+            },
+            TimeoutConfig::Exponential { base, factor, max } => {
+                if *base == Duration::default() {
+                    return Err(ConfigError::ZeroDuration("timeout"));
+                }
+                if factor.is_nan() || *factor < 1.0 {
+                    return Err(ConfigError::InvalidFactor("timeout_factor", *factor));
+                }
+                if max < base {
+                    return Err(ConfigError::InvalidRange("timeout", "max_timeout"));
+                }
+            },
+            TimeoutConfig::Adaptive { period, multiplier, min, max } => {
+                if *period == Duration::default() || *min == Duration::default() {
+                    return Err(ConfigError::ZeroDuration("timeout"));
+                }
+                if multiplier.is_nan() || *multiplier < 1.0 {
+                    return Err(ConfigError::InvalidFactor("timeout_factor", *multiplier));
+                }
+                if max < min {
+                    return Err(ConfigError::InvalidRange("min_timeout", "max_timeout"));
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Creates a new instance of the strategy
+    pub fn build(&self) -> Arc<dyn TimeoutStrategy> {
+        match self {
+            TimeoutConfig::Linear { period } => Arc::new(LinearTimeout::new(*period)),
+            TimeoutConfig::Exponential { base, factor, max } => Arc::new(ExponentialTimeout::new(*base, *factor, *max)),
+            TimeoutConfig::Adaptive { period, multiplier, min, max } => Arc::new(AdaptiveTimeout::new(*period, *multiplier, *min, *max)),
+        }
+    }
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        TimeoutConfig::Linear {
+            period: Duration::from_millis(500),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn it_times_out_linearly() {
+        let timeouts = LinearTimeout::new(ms(500));
+        assert_eq!(timeouts.level_timeout(0), ms(500));
+        assert_eq!(timeouts.level_timeout(3), ms(2000));
+    }
+
+    #[test]
+    fn it_times_out_exponentially() {
+        let timeouts = ExponentialTimeout::new(ms(100), 2.0, ms(500));
+        assert_eq!(timeouts.level_timeout(0), ms(100));
+        assert_eq!(timeouts.level_timeout(1), ms(300));
+        assert_eq!(timeouts.level_timeout(2), ms(700));
+        // 100 + 200 + 400 + 500 + 500
+        assert_eq!(timeouts.level_timeout(4), ms(1700));
+    }
+
+    #[test]
+    fn it_learns_completion_times() {
+        let timeouts = AdaptiveTimeout::new(ms(500), 2.0, ms(50), ms(1000));
+        assert_eq!(timeouts.level_timeout(0), ms(50));
+        assert_eq!(timeouts.level_timeout(1), ms(550));
+
+        timeouts.level_completed(0, ms(10));
+        timeouts.level_completed(1, ms(200));
+        // Level 1 is expected after 20ms, but must time out at least `min` after level 0
+        assert_eq!(timeouts.level_timeout(1), ms(100));
+        assert_eq!(timeouts.level_timeout(2), ms(400));
+
+        // The moving average only moves a bit towards new observations
+        timeouts.level_completed(1, ms(400));
+        assert_eq!(timeouts.level_timeout(2), ms(480));
+    }
+
+    #[test]
+    fn it_validates_timeout_configs() {
+        assert!(TimeoutConfig::default().validate().is_ok());
+        assert!(TimeoutConfig::Linear { period: ms(0) }.validate().is_err());
+        assert!(TimeoutConfig::Exponential { base: ms(100), factor: 0.5, max: ms(1000) }.validate().is_err());
+        assert!(TimeoutConfig::Exponential { base: ms(100), factor: 2.0, max: ms(50) }.validate().is_err());
+        assert!(TimeoutConfig::Adaptive { period: ms(500), multiplier: 2.0, min: ms(100), max: ms(50) }.validate().is_err());
+        assert!(TimeoutConfig::Adaptive { period: ms(500), multiplier: 2.0, min: ms(50), max: ms(1000) }.validate().is_ok());
     }
 }
@@ -1,12 +1,18 @@
+use std::slice;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
 
 use futures::{future, Future};
-use futures::future::FutureResult;
+use futures::future::{FutureResult, MapErr};
+use futures::sync::oneshot;
 use futures_cpupool::{CpuPool, CpuFuture};
 use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use rand::thread_rng;
 
 use hash::Blake2bHash;
-use bls::bls12_381::AggregatePublicKey;
+use bls::bls12_381::{AggregatePublicKey, AggregateSignature, BatchVerification};
 
 use crate::multisig::{Signature, IndividualSignature, MultiSignature};
 use crate::identity::IdentityRegistry;
@@ -73,7 +79,7 @@ impl<I: IdentityRegistry> MultithreadedVerifier<I> {
         Self::new(message_hash, identity_registry, Arc::clone(&SHARED_CPU_POOL))
     }
 
-    fn verify_individual(identity_registry: Arc<I>, message_hash: Blake2bHash, individual: &IndividualSignature) -> VerificationResult {
+    pub(crate) fn verify_individual(identity_registry: Arc<I>, message_hash: Blake2bHash, individual: &IndividualSignature) -> VerificationResult {
         if let Some(public_key) = identity_registry.public_key(individual.signer) {
             if public_key.verify_hash(message_hash, &individual.signature) {
                 VerificationResult::Ok
@@ -87,7 +93,7 @@ impl<I: IdentityRegistry> MultithreadedVerifier<I> {
         }
     }
 
-    fn verify_multisig(identity_registry: Arc<I>, message_hash: Blake2bHash, multisig: &MultiSignature) -> VerificationResult {
+    pub(crate) fn verify_multisig(identity_registry: Arc<I>, message_hash: Blake2bHash, multisig: &MultiSignature) -> VerificationResult {
         let mut aggregated_public_key = AggregatePublicKey::new();
         for signer in multisig.signers.iter() {
             if let Some(public_key) = identity_registry.public_key(signer) {
@@ -129,3 +135,314 @@ impl<I: IdentityRegistry + Sync + Send + 'static> Verifier for MultithreadedVeri
         })
     }
 }
+
+
+
+/// Signatures waiting for a batch verification
+type PendingSignatures = Vec<(Signature, oneshot::Sender<VerificationResult>)>;
+
+/// A signature of a batch with the (aggregated) public key it's checked against
+struct BatchEntry {
+    public_key: AggregatePublicKey,
+    signature: AggregateSignature,
+    result_tx: oneshot::Sender<VerificationResult>,
+}
+
+struct BatchState<I: IdentityRegistry> {
+    message_hash: Blake2bHash,
+    identity_registry: Arc<I>,
+
+    /// Signatures that were submitted, but aren't part of a running batch yet
+    pending: Mutex<PendingSignatures>,
+
+    /// Whether a batch job is scheduled on the CPU pool
+    scheduled: AtomicBool,
+
+    /// Number of batches checked so far, including the parts of failed batches
+    num_batches: AtomicUsize,
+}
+
+/// Clears the `scheduled` flag if the batch job panics. Otherwise no batch job would be scheduled
+/// anymore and all further verifications would stall. The pending signatures are canceled, since
+/// they were submitted without scheduling a job.
+struct ScheduledGuard<'a, I: IdentityRegistry>(&'a BatchState<I>);
+
+impl<'a, I: IdentityRegistry> Drop for ScheduledGuard<'a, I> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            let mut pending = self.0.pending.lock();
+            pending.clear();
+            self.0.scheduled.store(false, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Verifier that checks signatures in batches on a CPU pool.
+///
+/// Signatures that arrive while a batch is being verified are collected and verified together in
+/// the next batch. If a batch fails, it is split in halves that are checked on their own, until
+/// the forged signatures are found.
+pub struct BatchVerifier<I: IdentityRegistry> {
+    state: Arc<BatchState<I>>,
+    cpu_pool: Arc<CpuPool>,
+}
+
+impl<I: IdentityRegistry> BatchVerifier<I> {
+    /// Maximum number of signatures verified in one batch. This bounds the work that is needed to
+    /// find the forged signatures in a failed batch.
+    pub const MAX_BATCH_SIZE: usize = 64;
+
+    pub fn new(message_hash: Blake2bHash, identity_registry: Arc<I>, cpu_pool: Arc<CpuPool>) -> Self {
+        Self {
+            state: Arc::new(BatchState {
+                message_hash,
+                identity_registry,
+                pending: Mutex::new(Vec::new()),
+                scheduled: AtomicBool::new(false),
+                num_batches: AtomicUsize::new(0),
+            }),
+            cpu_pool,
+        }
+    }
+
+    pub fn shared(message_hash: Blake2bHash, identity_registry: Arc<I>) -> Self {
+        Self::new(message_hash, identity_registry, Arc::clone(&SHARED_CPU_POOL))
+    }
+
+    /// Number of batches checked so far, including the parts of failed batches
+    pub fn num_batches(&self) -> usize {
+        self.state.num_batches.load(Ordering::Relaxed)
+    }
+
+    /// Verifies batches until there are no more pending signatures
+    fn process_pending(state: &BatchState<I>) {
+        let _guard = ScheduledGuard(state);
+        loop {
+            let batch: PendingSignatures = {
+                let mut pending = state.pending.lock();
+                if pending.is_empty() {
+                    // Cleared while holding the lock, so that signatures pushed from now on
+                    // schedule a new job.
+                    state.scheduled.store(false, Ordering::SeqCst);
+                    return;
+                }
+                let n = pending.len().min(Self::MAX_BATCH_SIZE);
+                pending.drain(..n).collect()
+            };
+            Self::verify_batch(state, batch);
+        }
+    }
+
+    fn verify_batch(state: &BatchState<I>, batch: PendingSignatures) {
+        let mut entries = Vec::with_capacity(batch.len());
+
+        for (signature, result_tx) in batch {
+            let entry = match &signature {
+                Signature::Individual(individual) => {
+                    state.identity_registry.public_key(individual.signer)
+                        .map(|public_key| (
+                            AggregatePublicKey::from_public_keys(slice::from_ref(&public_key)),
+                            AggregateSignature::from_signatures(slice::from_ref(&individual.signature)),
+                        ))
+                        .ok_or(VerificationResult::UnknownSigner { signer: individual.signer })
+                },
+                Signature::Multi(multisig) => {
+                    Self::aggregate_public_key(&state.identity_registry, multisig)
+                        .map(|public_key| (public_key, multisig.signature))
+                },
+            };
+            match entry {
+                Ok((public_key, signature)) => entries.push(BatchEntry { public_key, signature, result_tx }),
+                Err(result) => result_tx.send(result).unwrap_or(()),
+            }
+        }
+
+        if !entries.is_empty() {
+            Self::verify_entries(state, entries);
+        }
+    }
+
+    /// Checks `entries` in one batch. If the batch fails, both halves are checked on their own.
+    fn verify_entries(state: &BatchState<I>, mut entries: Vec<BatchEntry>) {
+        let mut verification = BatchVerification::new(state.message_hash.clone());
+        for entry in &entries {
+            verification.push_aggregate(&entry.public_key, &entry.signature);
+        }
+        state.num_batches.fetch_add(1, Ordering::Relaxed);
+
+        if verification.verify(&mut thread_rng()) {
+            for entry in entries {
+                entry.result_tx.send(VerificationResult::Ok).unwrap_or(());
+            }
+        }
+        else if entries.len() == 1 {
+            // A batch of one signature is an ordinary verification.
+            for entry in entries {
+                entry.result_tx.send(VerificationResult::Forged).unwrap_or(());
+            }
+        }
+        else {
+            debug!("Batch of {} signatures failed, bisecting it", entries.len());
+            let second_half = entries.split_off(entries.len() / 2);
+            Self::verify_entries(state, entries);
+            Self::verify_entries(state, second_half);
+        }
+    }
+
+    fn aggregate_public_key(identity_registry: &I, multisig: &MultiSignature) -> Result<AggregatePublicKey, VerificationResult> {
+        let mut aggregated_public_key = AggregatePublicKey::new();
+        for signer in multisig.signers.iter() {
+            let public_key = identity_registry.public_key(signer)
+                .ok_or(VerificationResult::UnknownSigner { signer })?;
+            aggregated_public_key.aggregate(&public_key);
+        }
+        Ok(aggregated_public_key)
+    }
+}
+
+fn verification_canceled(_: oneshot::Canceled) {
+    warn!("Signature verification was canceled");
+}
+
+impl<I: IdentityRegistry + Sync + Send + 'static> Verifier for BatchVerifier<I> {
+    type Output = MapErr<oneshot::Receiver<VerificationResult>, fn(oneshot::Canceled)>;
+
+    fn verify(&self, signature: &Signature) -> Self::Output {
+        let (result_tx, result_rx) = oneshot::channel();
+        self.state.pending.lock().push((signature.clone(), result_tx));
+
+        if !self.state.scheduled.swap(true, Ordering::SeqCst) {
+            let state = Arc::clone(&self.state);
+            self.cpu_pool.spawn_fn(move || {
+                Self::process_pending(&state);
+                Ok::<(), ()>(())
+            }).forget();
+        }
+
+        result_rx.map_err(verification_canceled as fn(oneshot::Canceled))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use bls::bls12_381::{KeyPair, PublicKey};
+    use bls::SecureGenerate;
+    use collections::bitset::BitSet;
+    use hash::{Blake2bHasher, Hasher};
+
+    use super::*;
+
+    struct TestRegistry(Vec<KeyPair>);
+
+    impl IdentityRegistry for TestRegistry {
+        fn public_key(&self, id: usize) -> Option<PublicKey> {
+            if id == TestRegistry::PANICKING_ID {
+                panic!("Test registry panics for identity {}", id);
+            }
+            self.0.get(id).map(|key_pair| key_pair.public.clone())
+        }
+    }
+
+    impl TestRegistry {
+        const PANICKING_ID: usize = 1000;
+
+        fn generate(num_keys: usize) -> Self {
+            TestRegistry((0..num_keys).map(|_| KeyPair::generate_default_csprng()).collect())
+        }
+
+        fn individual(&self, id: usize, hash: Blake2bHash) -> Signature {
+            Signature::Individual(IndividualSignature::new(self.0[id].sign_hash(hash), id))
+        }
+    }
+
+    /// A CPU pool with one thread that is blocked until the sender is used or dropped. Signatures
+    /// submitted in the meantime end up in the same batch.
+    fn blocked_cpu_pool() -> (Arc<CpuPool>, mpsc::Sender<()>) {
+        let cpu_pool = Arc::new(CpuPool::new(1));
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        cpu_pool.spawn_fn(move || {
+            release_rx.recv().unwrap_or(());
+            Ok::<(), ()>(())
+        }).forget();
+        (cpu_pool, release_tx)
+    }
+
+    #[test]
+    fn it_batch_verifies_signatures() {
+        let message_hash = Blake2bHasher::default().digest(b"batch");
+        let registry = TestRegistry::generate(4);
+
+        let mut multisig = AggregateSignature::new();
+        let mut signers = BitSet::new();
+        for (id, key_pair) in registry.0.iter().enumerate().skip(1).take(2) {
+            multisig.aggregate(&key_pair.sign_hash(message_hash.clone()));
+            signers.insert(id);
+        }
+        let multisig = Signature::Multi(MultiSignature::new(multisig, signers));
+        let unknown = Signature::Individual(IndividualSignature::new(registry.0[0].sign_hash(message_hash.clone()), 4));
+        let signatures = vec![
+            registry.individual(0, message_hash.clone()),
+            multisig,
+            registry.individual(3, message_hash.clone()),
+            unknown,
+        ];
+
+        let (cpu_pool, release_tx) = blocked_cpu_pool();
+        let verifier = BatchVerifier::new(message_hash, Arc::new(registry), cpu_pool);
+        let results: Vec<_> = signatures.iter().map(|signature| verifier.verify(signature)).collect();
+        release_tx.send(()).unwrap();
+        let results: Vec<VerificationResult> = results.into_iter().map(|result| result.wait().unwrap()).collect();
+
+        assert_eq!(results, vec![
+            VerificationResult::Ok,
+            VerificationResult::Ok,
+            VerificationResult::Ok,
+            VerificationResult::UnknownSigner { signer: 4 },
+        ]);
+        // The known signers were checked in a single batch.
+        assert_eq!(verifier.num_batches(), 1);
+    }
+
+    #[test]
+    fn it_bisects_failed_batches() {
+        let message_hash = Blake2bHasher::default().digest(b"batch");
+        let registry = TestRegistry::generate(8);
+        let forged_hash = Blake2bHasher::default().digest(b"forged");
+        let signatures: Vec<Signature> = (0..8)
+            .map(|id| registry.individual(id, if id == 5 { forged_hash.clone() } else { message_hash.clone() }))
+            .collect();
+
+        let (cpu_pool, release_tx) = blocked_cpu_pool();
+        let verifier = BatchVerifier::new(message_hash, Arc::new(registry), cpu_pool);
+        let results: Vec<_> = signatures.iter().map(|signature| verifier.verify(signature)).collect();
+        release_tx.send(()).unwrap();
+        let results: Vec<VerificationResult> = results.into_iter().map(|result| result.wait().unwrap()).collect();
+
+        for (id, result) in results.iter().enumerate() {
+            let expected = if id == 5 { VerificationResult::Forged } else { VerificationResult::Ok };
+            assert_eq!(result, &expected);
+        }
+        // The whole batch, and both halves at each of the 3 bisection steps.
+        assert_eq!(verifier.num_batches(), 7);
+    }
+
+    #[test]
+    fn it_schedules_batches_after_a_panic() {
+        let message_hash = Blake2bHasher::default().digest(b"batch");
+        let registry = TestRegistry::generate(1);
+        let valid = registry.individual(0, message_hash.clone());
+        let panicking = Signature::Individual(IndividualSignature::new(registry.0[0].sign_hash(message_hash.clone()), TestRegistry::PANICKING_ID));
+
+        let verifier = BatchVerifier::new(message_hash, Arc::new(registry), Arc::new(CpuPool::new(1)));
+        assert!(verifier.verify(&panicking).wait().is_err());
+
+        // Signatures submitted while the job unwinds are canceled, later ones are verified again.
+        let result = (0..100)
+            .map(|_| verifier.verify(&valid).wait())
+            .find(Result::is_ok);
+        assert_eq!(result, Some(Ok(VerificationResult::Ok)));
+    }
+}
@@ -3,18 +3,26 @@ use std::sync::{Arc, Weak};
 
 #[cfg(feature="validator")]
 use validator::validator::Validator;
+#[cfg(feature="validator")]
+use validator::signer::{AuthKey, LocalSigner, RemoteSigner, Signer};
+#[cfg(feature="validator")]
+use validator::slashing_protection::SlashingProtection;
 use consensus::{
     Consensus as AbstractConsensus,
     AlbatrossConsensusProtocol,
+    StateSync,
 };
 use database::Environment;
 use network::{NetworkConfig, Network as GenericNetwork};
 use mempool::Mempool as GenericMempool;
 use network_primitives::services::ServiceFlags;
 use blockchain::Blockchain;
+use blockchain::blockchain::BlockchainEvent;
+use blockchain::chain_store::ChainStore;
+use utils::observer::weak_listener;
 
 use crate::error::Error;
-use crate::config::config::{ClientConfig, ProtocolConfig};
+use crate::config::config::{ClientConfig, ConsensusConfig, ProtocolConfig};
 
 
 /// Alias for the Consensus specialized over Albatross
@@ -41,46 +49,61 @@ pub(crate) struct ClientInner {
     /// reach consensus.
     consensus: Arc<Consensus>,
 
+    /// The state sync, which serves the state to other full nodes and bootstraps the blockchain
+    /// from the network if the state sync consensus is configured.
+    state_sync: Arc<StateSync>,
+
     /// The block production logic. This is optional and can also be fully disabled at compile-time
     #[cfg(feature="validator")]
     validator: Option<Arc<Validator>>
 }
 
 
-impl TryFrom<ClientConfig> for ClientInner {
-    type Error = Error;
+/// Creates the network config from the client config and initializes the peer key.
+///
+/// TODO: `NetworkConfig` could use some refactoring. So we might as well adapt it to the
+/// client API.
+pub(crate) fn network_config(config: &ClientConfig) -> Result<NetworkConfig, Error> {
+    let mut network_config = match config.protocol.clone() {
+        ProtocolConfig::Dumb => {
+            NetworkConfig::new_dumb_network_config()
+        },
+        ProtocolConfig::Rtc => {
+            panic!("WebRTC is not yet implemented")
+        },
+        ProtocolConfig::Ws { host, port } => {
+            NetworkConfig::new_ws_network_config(host, port, false, config.reverse_proxy.clone())
+        },
+        ProtocolConfig::Wss { host, port, pkcs12_key_file, pkcs12_passphrase } => {
+            let pkcs12_key_file = pkcs12_key_file.to_str()
+                .unwrap_or_else(|| panic!("Failed to convert path to PKCS#12 key file to string: {}", pkcs12_key_file.display()))
+                .to_string();
+            NetworkConfig::new_wss_network_config(host, port, false, pkcs12_key_file, pkcs12_passphrase, config.reverse_proxy.clone())
+        }
+    };
 
-    fn try_from(config: ClientConfig) -> Result<Self, Self::Error> {
-        // Create network config
-        // TODO: `NetworkConfig` could use some refactoring. So we might as well adapt it to the
-        // client API.
+    // Set user agent
+    network_config.set_user_agent(config.user_agent.clone().into());
 
-        let mut network_config = match config.protocol {
-            ProtocolConfig::Dumb => {
-                NetworkConfig::new_dumb_network_config()
-            },
-            ProtocolConfig::Rtc => {
-                panic!("WebRTC is not yet implemented")
-            },
-            ProtocolConfig::Ws { host, port } => {
-                NetworkConfig::new_ws_network_config(host, port, false, config.reverse_proxy)
-            },
-            ProtocolConfig::Wss { host, port, pkcs12_key_file, pkcs12_passphrase } => {
-                let pkcs12_key_file = pkcs12_key_file.to_str()
-                    .unwrap_or_else(|| panic!("Failed to convert path to PKCS#12 key file to string: {}", pkcs12_key_file.display()))
-                    .to_string();
-                NetworkConfig::new_wss_network_config(host, port, false, pkcs12_key_file, pkcs12_passphrase, config.reverse_proxy)
-            }
-        };
+    // Set custom seeds
+    network_config.set_additional_seeds(config.seeds.clone());
 
-        // Set user agent
-        network_config.set_user_agent(config.user_agent.into());
+    // Initialize peer key
+    config.storage.init_key_store(&mut network_config)?;
 
-        // Set custom seeds
-        network_config.set_additional_seeds(config.seeds);
+    Ok(network_config)
+}
 
-        // Initialize peer key
-        config.storage.init_key_store(&mut network_config)?;
+
+impl TryFrom<ClientConfig> for ClientInner {
+    type Error = Error;
+
+    fn try_from(config: ClientConfig) -> Result<Self, Self::Error> {
+        if config.consensus == ConsensusConfig::Light {
+            return Err(Error::config_error("Light consensus is only supported by the light client"));
+        }
+
+        let mut network_config = network_config(&config)?;
 
         // Load validator wallet key
         #[cfg(feature="validator")]
@@ -89,10 +112,29 @@ impl TryFrom<ClientConfig> for ClientInner {
             .expect("Failed to load validator configuration")
             .expect("Failed to load validator wallet key");
 
-        // Load validator key (before we give away ownership of the storage config)
+        // Handel configurations of the validator's signature aggregations
         #[cfg(feature="validator")]
-        let validator_key = config.storage.validator_key()
-            .expect("Failed to load validator key");
+        let aggregation_config = config.validator.as_ref()
+            .map(|config| config.aggregation().clone())
+            .unwrap_or_default();
+
+        // Set up the signer holding the validator key. It's either a remote signer, or the key is
+        // loaded from the key file and protected by a local slashing protection database.
+        // Note: The remote signer enforces slashing protection on its side.
+        #[cfg(feature="validator")]
+        let signer: Option<Arc<dyn Signer>> = match config.validator.as_ref().map(|config| config.remote_signer()) {
+            Some(Some(remote_signer)) => {
+                let auth_key = AuthKey::load(&remote_signer.auth_key_file)?;
+                Some(Arc::new(RemoteSigner::connect(remote_signer.address.clone(), auth_key)?))
+            },
+            Some(None) => {
+                let validator_key = config.storage.validator_key()
+                    .expect("Failed to load validator key");
+                let slashing_protection = SlashingProtection::new(config.storage.slashing_protection_database(config.network)?);
+                Some(Arc::new(LocalSigner::new(validator_key, slashing_protection)))
+            },
+            None => None,
+        };
 
         // Add validator service flag, if necessary
         #[cfg(feature="validator")]
@@ -106,8 +148,18 @@ impl TryFrom<ClientConfig> for ClientInner {
         }
 
         // Open database
+        let history_epochs = config.database.history_epochs();
         let environment = config.storage.database(config.network, config.consensus, config.database)?;
 
+        // Don't advertise as archive node, if we prune the block history or were bootstrapped from
+        // a snapshot or the network
+        let state_sync = config.consensus == ConsensusConfig::StateSync;
+        if history_epochs.is_some() || state_sync || ChainStore::new(environment.clone()).get_pruned_height(None) > 0 {
+            let mut services = network_config.services().clone();
+            services.provided.remove(ServiceFlags::ARCHIVE);
+            network_config.set_services(services);
+        }
+
         // Create Nimiq consensus
         if !config.network.is_albatross() {
             return Err(Error::config_error(&format!("{} is not compatible with Albatross", config.network)));
@@ -118,15 +170,28 @@ impl TryFrom<ClientConfig> for ClientInner {
             network_config,
             config.mempool,
         )?;
+        let state_sync = StateSync::new(Arc::clone(&consensus), state_sync);
+
+        // Prune the block history whenever an epoch is finalized
+        if let Some(history_epochs) = history_epochs {
+            consensus.blockchain.notifier.write().register(weak_listener(
+                Arc::downgrade(&consensus.blockchain),
+                move |blockchain, event| {
+                    if let BlockchainEvent::Finalized(_) = event {
+                        blockchain.prune_history(history_epochs);
+                    }
+                }));
+        }
 
         #[cfg(feature="validator")]
-        let validator = config.validator.map(|_config| {
-            Validator::new(Arc::clone(&consensus), validator_key, validator_wallet_key)
+        let validator = signer.map(|signer| {
+            Validator::new(Arc::clone(&consensus), signer, validator_wallet_key, aggregation_config)
         }).transpose()?;
 
         Ok(ClientInner {
             environment,
             consensus,
+            state_sync,
             #[cfg(feature="validator")]
             validator,
         })
@@ -191,6 +256,11 @@ impl Client {
         Arc::clone(&self.inner.consensus.mempool)
     }
 
+    /// Returns a reference to the *StateSync*
+    pub fn state_sync(&self) -> Arc<StateSync> {
+        Arc::clone(&self.inner.state_sync)
+    }
+
     /// Returns a reference to the *Validator* or `None`.
     #[cfg(feature="validator")]
     pub fn validator(&self) -> Option<Arc<Validator>> {
@@ -10,7 +10,7 @@ use crate::config::config_file::ConsensusType;
 
 /*lazy_static! {
     static ref VALID_LOG_LEVELS: [&'static str; 6] = ["off", "error", "warn", "info", "debug", "trace"];
-    static ref VALID_CONSENSUS_TYPES: [&'static str; 2] = ["full", "macro-sync"];
+    static ref VALID_CONSENSUS_TYPES: [&'static str; 4] = ["full", "macro-sync", "light", "state-sync"];
 }*/
 
 
@@ -72,7 +72,7 @@ pub struct CommandLine {
     #[structopt(long)]
     pub passive: bool,
 
-    /// Configure consensus type, one of full (default), or macro-sync
+    /// Configure consensus type, one of full (default), macro-sync, light, or state-sync
     ///
     /// # Examples
     ///
@@ -89,6 +89,95 @@ pub struct CommandLine {
     ///
     #[structopt(long)]
     pub network: Option<NetworkId>,
+
+    /// Run a command instead of the client.
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(rename_all="kebab")]
+pub enum Command {
+    /// Export a snapshot of the state at the current macro head to a file
+    ///
+    /// # Examples
+    ///
+    /// * `nimiq-client export-snapshot snapshot.bin`
+    ///
+    ExportSnapshot {
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+    },
+
+    /// Bootstrap an empty database from a snapshot file
+    ///
+    /// # Examples
+    ///
+    /// * `nimiq-client import-snapshot snapshot.bin`
+    ///
+    ImportSnapshot {
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+    },
+
+    /// Export the main chain to a block file
+    ///
+    /// # Examples
+    ///
+    /// * `nimiq-client export-blocks blocks.bin`
+    ///
+    ExportBlocks {
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+    },
+
+    /// Import the blocks of a block file, stopping at the first invalid block
+    ///
+    /// # Examples
+    ///
+    /// * `nimiq-client import-blocks blocks.bin`
+    ///
+    ImportBlocks {
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+    },
+
+    /// Check the consistency of the database, optionally rolling back to the last good macro block
+    ///
+    /// # Examples
+    ///
+    /// * `nimiq-client verify-db`
+    /// * `nimiq-client verify-db --repair`
+    ///
+    VerifyDb {
+        /// Roll back to the last good macro block if an inconsistency is found
+        #[structopt(long)]
+        repair: bool,
+    },
+
+    /// Export the validator's slashing protection database to an interchange file
+    ///
+    /// # Examples
+    ///
+    /// * `nimiq-client export-slashing-protection slashing-protection.bin`
+    ///
+    #[cfg(feature="validator")]
+    ExportSlashingProtection {
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+    },
+
+    /// Merge an interchange file into the validator's slashing protection database
+    ///
+    /// # Examples
+    ///
+    /// * `nimiq-client import-slashing-protection slashing-protection.bin`
+    ///
+    #[cfg(feature="validator")]
+    ImportSlashingProtection {
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+    },
 }
 
 impl CommandLine {
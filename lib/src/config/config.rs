@@ -3,6 +3,8 @@ use std::fmt::Display;
 use std::path::{Path, PathBuf};
 use std::net::IpAddr;
 use std::str::FromStr;
+#[cfg(feature="validator")]
+use std::time::Duration;
 
 use derive_builder::Builder;
 use enum_display_derive::Display;
@@ -12,6 +14,14 @@ use url::Url;
 use bls::SecureGenerate;
 #[cfg(feature="validator")]
 use bls::bls12_381::KeyPair as BlsKeyPair;
+#[cfg(feature="validator")]
+use handel::config::Config as HandelConfig;
+#[cfg(feature="validator")]
+use handel::timeout::TimeoutConfig;
+#[cfg(feature="validator")]
+use validator::signature_aggregation::AggregationConfig;
+#[cfg(feature="validator")]
+use validator::signer::SignerAddress;
 use database::Environment;
 use database::lmdb::{LmdbEnvironment, open as LmdbFlags};
 use database::volatile::VolatileEnvironment;
@@ -20,11 +30,14 @@ use mempool::MempoolConfig;
 use network::network_config::{NetworkConfig, ReverseProxyConfig, Seed};
 use network_primitives::address::{NetAddress, SeedList, PeerUri};
 use primitives::networks::NetworkId;
+use primitives::policy;
+use utils::ip_allowlist::IpAllowlist;
 use utils::key_store::Error as KeyStoreError;
 use utils::key_store::KeyStore;
 use keys::PublicKey;
 
 use crate::client::Client;
+use crate::light_client::LightClient;
 use crate::config::command_line::CommandLine;
 use crate::config::config_file;
 use crate::config::config_file::ConfigFile;
@@ -48,6 +61,8 @@ use crate::error::Error;
 pub enum ConsensusConfig {
     Full,
     MacroSync,
+    Light,
+    StateSync,
 }
 
 impl Default for ConsensusConfig {
@@ -123,6 +138,13 @@ pub struct ValidatorConfig {
     // TODO
     /// Validator wallet key
     validator_wallet_key: Option<keys::KeyPair>,
+
+    /// Remote signer holding the validator key. If not set, the validator key is loaded from
+    /// the key file.
+    remote_signer: Option<RemoteSignerConfig>,
+
+    /// Handel configurations for view change and pBFT aggregations
+    aggregation: AggregationConfig,
 }
 
 impl ValidatorConfig {
@@ -130,6 +152,66 @@ impl ValidatorConfig {
     pub fn validator_wallet_key(self) -> Result<Option<keys::KeyPair>, Error> {
         Ok(self.validator_wallet_key)
     }
+
+    #[cfg(feature="validator")]
+    pub fn remote_signer(&self) -> Option<&RemoteSignerConfig> {
+        self.remote_signer.as_ref()
+    }
+
+    #[cfg(feature="validator")]
+    pub fn aggregation(&self) -> &AggregationConfig {
+        &self.aggregation
+    }
+
+    /// Builds the Handel configuration of an aggregation from the `[validator.<section>]` settings
+    #[cfg(feature="validator")]
+    fn aggregation_config(settings: Option<&config_file::AggregationSettings>, section: &str) -> Result<HandelConfig, Error> {
+        let settings = settings.cloned().unwrap_or_default();
+        let invalid = |reason: String| Error::config_error(format!("Invalid [validator.{}] settings: {}", section, reason));
+
+        let strategy = settings.timeout_strategy.unwrap_or_default();
+        let unused = match strategy {
+            config_file::TimeoutStrategy::Linear => settings.timeout_factor.map(|_| "timeout_factor")
+                .or_else(|| settings.min_timeout.map(|_| "min_timeout"))
+                .or_else(|| settings.max_timeout.map(|_| "max_timeout")),
+            config_file::TimeoutStrategy::Exponential => settings.min_timeout.map(|_| "min_timeout"),
+            config_file::TimeoutStrategy::Adaptive => None,
+        };
+        if let Some(setting) = unused {
+            return Err(invalid(format!("{} is not used by {:?} timeouts", setting, strategy)));
+        }
+
+        let timeout = Duration::from_millis(settings.timeout.unwrap_or(consts::HANDEL_DEFAULT_TIMEOUT));
+        let factor = settings.timeout_factor.unwrap_or(consts::HANDEL_DEFAULT_TIMEOUT_FACTOR);
+        let min = Duration::from_millis(settings.min_timeout.unwrap_or(consts::HANDEL_DEFAULT_MIN_TIMEOUT));
+        let max = Duration::from_millis(settings.max_timeout.unwrap_or(consts::HANDEL_DEFAULT_MAX_TIMEOUT));
+        let timeouts = match strategy {
+            config_file::TimeoutStrategy::Linear => TimeoutConfig::Linear { period: timeout },
+            config_file::TimeoutStrategy::Exponential => TimeoutConfig::Exponential { base: timeout, factor, max },
+            config_file::TimeoutStrategy::Adaptive => TimeoutConfig::Adaptive { period: timeout, multiplier: factor, min, max },
+        };
+        timeouts.validate().map_err(|e| invalid(e.to_string()))?;
+
+        let defaults = HandelConfig::default();
+        let config = HandelConfig {
+            update_count: settings.update_count.unwrap_or(defaults.update_count),
+            update_interval: settings.update_interval.map(Duration::from_millis).unwrap_or(defaults.update_interval),
+            timeouts: timeouts.build(),
+            peer_count: settings.peer_count.unwrap_or(defaults.peer_count),
+        };
+        config.validate().map_err(|e| invalid(e.to_string()))?;
+        Ok(config)
+    }
+}
+
+#[cfg(feature="validator")]
+#[derive(Debug, Clone)]
+pub struct RemoteSignerConfig {
+    /// Address of the signer
+    pub address: SignerAddress,
+
+    /// Path to the file with the key shared with the signer
+    pub auth_key_file: PathBuf,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
@@ -168,6 +250,16 @@ impl FileStorageConfig {
     pub fn system() -> Self {
         Self::from_directory(paths::system())
     }
+
+    #[cfg(feature="validator")]
+    fn validator_key_store(&self) -> Result<KeyStore, Error> {
+        let key_path = self.validator_key.as_ref()
+            .ok_or_else(|| Error::config_error("No path for validator key specified"))?;
+        let key_path = key_path.to_str()
+            .ok_or_else(|| Error::config_error(format!("Failed to convert path of validator key to string: {}", key_path.display())))?
+            .to_string();
+        Ok(KeyStore::new(key_path))
+    }
 }
 
 impl Default for FileStorageConfig {
@@ -190,7 +282,18 @@ pub struct DatabaseConfig {
 
     /// Additional LMDB flags
     #[builder(default="LmdbFlags::NOMETASYNC")]
-    flags: LmdbFlags::Flags
+    flags: LmdbFlags::Flags,
+
+    /// Number of finalized epochs for which micro block bodies are kept. Older micro block
+    /// bodies are pruned. Default: `None`, i.e. keep the full history (archive node)
+    #[builder(default)]
+    history_epochs: Option<u32>,
+}
+
+impl DatabaseConfig {
+    pub fn history_epochs(&self) -> Option<u32> {
+        self.history_epochs
+    }
 }
 
 impl Default for DatabaseConfig {
@@ -199,6 +302,7 @@ impl Default for DatabaseConfig {
             size: 50 * 1024 * 1024,
             max_dbs: 10,
             flags: LmdbFlags::NOMETASYNC,
+            history_epochs: None,
         }
     }
 }
@@ -216,6 +320,7 @@ impl From<config_file::DatabaseSettings> for DatabaseConfig {
             size: db_settings.size.unwrap_or(default.size),
             max_dbs: db_settings.max_dbs.unwrap_or(default.max_dbs),
             flags,
+            history_epochs: db_settings.history_epochs,
         }
     }
 }
@@ -262,6 +367,11 @@ impl StorageConfig {
     /// Returns a `Result` which is either a `Environment` or a `Error`.
     ///
     pub fn database(&self, network_id: NetworkId, consensus: ConsensusConfig, db_config: DatabaseConfig) -> Result<Environment, Error> {
+        // After the state sync, the database is the same as the one of a full node.
+        let consensus = match consensus {
+            ConsensusConfig::StateSync => ConsensusConfig::Full,
+            consensus => consensus,
+        };
         let db_name = format!("{}-{}-consensus", network_id, consensus).to_lowercase();
         info!("Opening database: {}", db_name);
 
@@ -280,6 +390,30 @@ impl StorageConfig {
         })
     }
 
+    /// Returns the slashing protection database of the validator for the given network ID.
+    /// This is a separate environment, so that it survives deleting the chain database.
+    ///
+    #[cfg(feature="validator")]
+    pub fn slashing_protection_database(&self, network_id: NetworkId) -> Result<Environment, Error> {
+        let db_name = format!("{}-slashing-protection", network_id).to_lowercase();
+        info!("Opening slashing protection database: {}", db_name);
+
+        Ok(match self {
+            StorageConfig::Volatile => {
+                VolatileEnvironment::new(1)?
+            },
+            StorageConfig::Filesystem(file_storage) => {
+                let db_path = file_storage.database_parent.join(db_name);
+                let db_path = db_path.to_str()
+                    .ok_or_else(|| Error::config_error(format!("Failed to convert database path to string: {}", db_path.display())))?
+                    .to_string();
+                // Don't relax syncing here: A record must be on disk before the message is signed.
+                LmdbEnvironment::new(&db_path, 1024 * 1024, 1, LmdbFlags::Flags::empty())?
+            },
+            _ => return Err(self.not_available()),
+        })
+    }
+
     pub(crate) fn init_key_store(&self, network_config: &mut NetworkConfig) -> Result<(), Error> {
         // TODO: Move this out of here and load keys from database
         match self {
@@ -310,12 +444,7 @@ impl StorageConfig {
                 BlsKeyPair::generate_default_csprng()
             },
             StorageConfig::Filesystem(file_storage) => {
-                let key_path = file_storage.validator_key.as_ref()
-                    .ok_or_else(|| Error::config_error("No path for validator key specified"))?;
-                let key_path = key_path.to_str()
-                    .ok_or_else(|| Error::config_error(format!("Failed to convert path of validator key to string: {}", key_path.display())))?
-                    .to_string();
-                let key_store = KeyStore::new(key_path);
+                let key_store = file_storage.validator_key_store()?;
                 match key_store.load_key() {
                     Err(KeyStoreError::IoError(_)) => {
                         let validator_key = BlsKeyPair::generate_default_csprng();
@@ -329,6 +458,20 @@ impl StorageConfig {
         })
     }
 
+    /// Loads the existing validator key. Unlike `validator_key`, this never generates a key, so
+    /// it's used by commands that only concern the validator's existing key.
+    #[cfg(feature="validator")]
+    pub(crate) fn existing_validator_key(&self) -> Result<BlsKeyPair, Error> {
+        match self {
+            StorageConfig::Volatile => Err(Error::config_error("Volatile storage has no validator key")),
+            StorageConfig::Filesystem(file_storage) => {
+                file_storage.validator_key_store()?.load_key()
+                    .map_err(|e| Error::config_error(format!("Failed to load validator key: {}", e)))
+            },
+            _ => Err(self.not_available()),
+        }
+    }
+
     fn not_available(&self) -> Error {
         Error::Config(format!("Storage backend not implemented: {:?}", self))
     }
@@ -390,16 +533,23 @@ pub struct RpcServerConfig {
     #[builder(setter(strip_option))]
     pub corsdomain: Option<Vec<String>>,
 
-    /// If specified, only allow connections from these IP addresses
+    /// If specified, only allow connections from these IP addresses and ranges
     ///
     #[builder(setter(strip_option))]
-    pub allow_ips: Option<Vec<IpAddr>>,
+    pub allow_ips: Option<IpAllowlist>,
 
     /// If specified, only allow these RPC methods
     ///
     #[builder(setter(strip_option))]
     pub allowed_methods: Option<Vec<String>>,
 
+    /// Maximum number of requests in a JSON-RPC batch, 0 for no limit
+    ///
+    /// Default: `100`
+    ///
+    #[builder(default="consts::RPC_DEFAULT_MAX_BATCH_SIZE")]
+    pub max_batch_size: usize,
+
     /// If specified, require HTTP basic auth with these credentials
     #[builder(setter(strip_option))]
     pub credentials: Option<Credentials>,
@@ -423,6 +573,23 @@ pub struct WsRpcServerConfig {
     #[builder(default="consts::WS_RPC_DEFAULT_PORT")]
     pub port: u16,
 
+    /// If specified, only allow connections from these IP addresses and ranges
+    ///
+    #[builder(setter(strip_option))]
+    pub allow_ips: Option<IpAllowlist>,
+
+    /// If specified, only allow these RPC methods
+    ///
+    #[builder(setter(strip_option))]
+    pub allowed_methods: Option<Vec<String>>,
+
+    /// Maximum number of requests in a JSON-RPC batch, 0 for no limit
+    ///
+    /// Default: `100`
+    ///
+    #[builder(default="consts::RPC_DEFAULT_MAX_BATCH_SIZE")]
+    pub max_batch_size: usize,
+
     /// If specified, require HTTP basic auth with these credentials
     #[builder(setter(strip_option))]
     pub credentials: Option<Credentials>,
@@ -446,6 +613,11 @@ pub struct MetricsServerConfig {
     #[builder(default="consts::METRICS_DEFAULT_PORT")]
     pub port: u16,
 
+    /// If specified, only allow connections from these IP addresses and ranges
+    ///
+    #[builder(setter(strip_option))]
+    pub allow_ips: Option<IpAllowlist>,
+
     /// If specified, require HTTP basic auth with these credentials
     #[builder(setter(strip_option))]
     pub credentials: Option<Credentials>,
@@ -554,6 +726,12 @@ impl ClientConfig {
     pub fn instantiate_client(self) -> Result<Client, Error> {
         Client::try_from(self)
     }
+
+    /// Instantiates the Nimiq light client from this configuration
+    ///
+    pub fn instantiate_light_client(self) -> Result<LightClient, Error> {
+        LightClient::try_from(self)
+    }
 }
 
 impl ClientConfigBuilder {
@@ -600,6 +778,20 @@ impl ClientConfigBuilder {
         self.consensus(ConsensusConfig::MacroSync)
     }
 
+    /// Sets the client to only follow the macro blocks. Accounts are requested from full nodes
+    /// on demand.
+    ///
+    pub fn light(&mut self) -> &mut Self {
+        self.consensus(ConsensusConfig::Light)
+    }
+
+    /// Sets the client to sync the state at the latest macro block from full nodes, instead of
+    /// the full block chain. Afterwards it behaves like a full node.
+    ///
+    pub fn state_sync(&mut self) -> &mut Self {
+        self.consensus(ConsensusConfig::StateSync)
+    }
+
     /// Sets the *Dumb* protocol - i.e. no incoming connections will be accepted.
     ///
     /// # Notes
@@ -704,7 +896,9 @@ impl ClientConfigBuilder {
     #[cfg(feature="validator")]
     pub fn validator(&mut self) -> &mut Self {
         self.validator = Some(Some(ValidatorConfig {
-            validator_wallet_key: None
+            validator_wallet_key: None,
+            remote_signer: None,
+            aggregation: AggregationConfig::default(),
         }));
         self
     }
@@ -765,6 +959,12 @@ impl ClientConfigBuilder {
         self.storage = Some(file_storage.into());
 
         // Configure database
+        if let Some(history_epochs) = config_file.database.history_epochs {
+            if history_epochs.checked_mul(policy::EPOCH_LENGTH).is_none() {
+                return Err(Error::config_error(format!("Invalid [database] settings: history_epochs must be at most {}",
+                                                       u32::max_value() / policy::EPOCH_LENGTH)));
+            }
+        }
         self.database(config_file.database.clone());
 
         // Configure reverse proxy config
@@ -779,17 +979,7 @@ impl ClientConfigBuilder {
                 let bind_to = rpc_config.bind.as_ref()
                     .and_then(|addr| addr.into_ip_address());
 
-                let allow_ips = if rpc_config.allowip.is_empty() {
-                    None
-                }
-                else {
-                    let result = rpc_config.allowip.iter().map(|s| {
-                        s.parse::<IpAddr>().map_err({
-                            |e| Error::config_error(format!("Invalid IP: {}", e))
-                        })
-                    }).collect::<Result<Vec<IpAddr>, Error>>();
-                    Some(result?)
-                };
+                let allow_ips = parse_allowip(&rpc_config.allowip)?;
 
                 let credentials = match (&rpc_config.username, &rpc_config.password) {
                     (Some(u), Some(p)) => {
@@ -805,6 +995,7 @@ impl ClientConfigBuilder {
                     corsdomain: Some(rpc_config.corsdomain.clone()),
                     allow_ips,
                     allowed_methods: Some(rpc_config.methods.clone()),
+                    max_batch_size: rpc_config.max_batch_size.unwrap_or(consts::RPC_DEFAULT_MAX_BATCH_SIZE),
                     credentials,
                 }));
             }
@@ -824,9 +1015,14 @@ impl ClientConfigBuilder {
                     _ => return Err(Error::config_error("Either both username and password have to be set or none."))
                 };
 
+                let allow_ips = parse_allowip(&ws_rpc_config.allowip)?;
+
                 self.ws_rpc_server = Some(Some(WsRpcServerConfig {
                     bind_to,
                     port: ws_rpc_config.port.unwrap_or(consts::WS_RPC_DEFAULT_PORT),
+                    allow_ips,
+                    allowed_methods: Some(ws_rpc_config.methods.clone()),
+                    max_batch_size: ws_rpc_config.max_batch_size.unwrap_or(consts::RPC_DEFAULT_MAX_BATCH_SIZE),
                     credentials,
                 }));
             }
@@ -842,9 +1038,12 @@ impl ClientConfigBuilder {
                     Credentials::new("metrics", password)
                 });
 
+                let allow_ips = parse_allowip(&metrics_config.allowip)?;
+
                 self.metrics_server = Some(Some(MetricsServerConfig {
                     bind_to,
                     port: metrics_config.port.unwrap_or(consts::METRICS_DEFAULT_PORT),
+                    allow_ips,
                     credentials,
                 }));
             }
@@ -870,8 +1069,29 @@ impl ClientConfigBuilder {
                     ))
                 }).unwrap_or_else(|| None);
 
+                let settings = config_file.validator.as_ref().unwrap();
+                let remote_signer = match settings.signer {
+                    Some(ref address) => {
+                        let address = address.parse()
+                            .map_err(|e| Error::config_error(format!("Invalid signer address: {}: {}", address, e)))?;
+                        let auth_key_file = settings.signer_auth_key_file.as_ref()
+                            .ok_or_else(|| Error::config_error("A remote signer requires signer_auth_key_file"))?;
+                        Some(RemoteSignerConfig { address, auth_key_file: PathBuf::from(auth_key_file) })
+                    },
+                    None => None,
+                };
+
+                let aggregation = AggregationConfig {
+                    view_change: ValidatorConfig::aggregation_config(settings.view_change_aggregation.as_ref(), "view_change_aggregation")?,
+                    // Built twice, so that prepare and commit don't share a timeout strategy
+                    pbft_prepare: ValidatorConfig::aggregation_config(settings.pbft_aggregation.as_ref(), "pbft_aggregation")?,
+                    pbft_commit: ValidatorConfig::aggregation_config(settings.pbft_aggregation.as_ref(), "pbft_aggregation")?,
+                };
+
                 self.validator = Some(Some(ValidatorConfig {
-                    validator_wallet_key: wallet_key
+                    validator_wallet_key: wallet_key,
+                    remote_signer,
+                    aggregation,
                 }));
             }
         }
@@ -909,3 +1129,79 @@ impl ClientConfigBuilder {
         Ok(self)
     }
 }
+
+/// Parses the `allowip` setting of a server. An empty list allows all IPs.
+fn parse_allowip(allowip: &[String]) -> Result<Option<IpAllowlist>, Error> {
+    if allowip.is_empty() {
+        return Ok(None);
+    }
+    IpAllowlist::parse(allowip)
+        .map(Some)
+        .map_err(|e| Error::config_error(format!("Invalid IP: {}", e)))
+}
+
+#[cfg(all(test, feature="validator"))]
+mod tests {
+    use super::*;
+
+    fn aggregation_config(settings: &str) -> Result<HandelConfig, Error> {
+        let settings: config_file::AggregationSettings = toml::from_str(settings).unwrap();
+        ValidatorConfig::aggregation_config(Some(&settings), "test_aggregation")
+    }
+
+    fn level_timeouts(config: &HandelConfig) -> Vec<Duration> {
+        (0..5).map(|level| config.timeouts.level_timeout(level)).collect()
+    }
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn it_uses_default_aggregation_settings() {
+        let config = ValidatorConfig::aggregation_config(None, "test_aggregation").unwrap();
+        let defaults = HandelConfig::default();
+        assert_eq!(config.update_count, defaults.update_count);
+        assert_eq!(config.update_interval, defaults.update_interval);
+        assert_eq!(config.peer_count, defaults.peer_count);
+        assert_eq!(level_timeouts(&config), vec![ms(500), ms(1000), ms(1500), ms(2000), ms(2500)]);
+    }
+
+    #[test]
+    fn it_builds_configured_timeouts() {
+        let config = aggregation_config(r#"
+            update_count = 2
+            timeout_strategy = "exponential"
+            timeout = 100
+            timeout_factor = 3.0
+            max_timeout = 1000
+        "#).unwrap();
+        assert_eq!(config.update_count, 2);
+        let expected = TimeoutConfig::Exponential { base: ms(100), factor: 3.0, max: ms(1000) }.build();
+        assert_eq!(level_timeouts(&config), (0..5).map(|level| expected.level_timeout(level)).collect::<Vec<_>>());
+
+        assert!(aggregation_config(r#"
+            timeout_strategy = "adaptive"
+            timeout = 300
+            timeout_factor = 1.5
+            min_timeout = 50
+            max_timeout = 2000
+        "#).is_ok());
+    }
+
+    #[test]
+    fn it_rejects_unused_aggregation_settings() {
+        assert!(aggregation_config("timeout_factor = 2.0").is_err());
+        assert!(aggregation_config("max_timeout = 1000").is_err());
+        assert!(aggregation_config("timeout_strategy = \"linear\"\nmin_timeout = 100").is_err());
+        assert!(aggregation_config("timeout_strategy = \"exponential\"\nmin_timeout = 100").is_err());
+    }
+
+    #[test]
+    fn it_rejects_invalid_aggregation_settings() {
+        assert!(aggregation_config("timeout = 0").is_err());
+        assert!(aggregation_config("update_count = 0").is_err());
+        assert!(aggregation_config("timeout_strategy = \"exponential\"\ntimeout_factor = 0.5").is_err());
+        assert!(aggregation_config("timeout_strategy = \"adaptive\"\nmin_timeout = 1000\nmax_timeout = 500").is_err());
+    }
+}
@@ -257,6 +257,8 @@ pub struct ConsensusSettings {
 pub enum ConsensusType {
     Full,
     MacroSync,
+    Light,
+    StateSync,
 }
 
 impl Default for ConsensusType {
@@ -276,6 +278,8 @@ impl FromStr for ConsensusType {
         Ok(match s.to_lowercase().as_str() {
             "full" => Self::Full,
             "macro-sync" => Self::MacroSync,
+            "light" => Self::Light,
+            "state-sync" => Self::StateSync,
             _ => return Err(ConsensusTypeParseError(s.to_string()))
         })
     }
@@ -286,6 +290,8 @@ impl From<ConsensusType> for config::ConsensusConfig {
         match consensus_type {
             ConsensusType::Full => Self::Full,
             ConsensusType::MacroSync => Self::MacroSync,
+            ConsensusType::Light => Self::Light,
+            ConsensusType::StateSync => Self::StateSync,
         }
     }
 }
@@ -348,6 +354,7 @@ pub struct RpcServerSettings {
     pub allowip: Vec<String>,
     #[serde(default)]
     pub methods: Vec<String>,
+    pub max_batch_size: Option<usize>,
     pub username: Option<String>,
     pub password: Option<String>,
 }
@@ -360,6 +367,11 @@ pub struct WsRpcServerSettings {
     #[serde(default)]
     pub bind: Option<address::NetAddress>,
     pub port: Option<u16>,
+    #[serde(default)]
+    pub allowip: Vec<String>,
+    #[serde(default)]
+    pub methods: Vec<String>,
+    pub max_batch_size: Option<usize>,
     pub username: Option<String>,
     pub password: Option<String>,
 }
@@ -372,6 +384,8 @@ pub struct MetricsServerSettings {
     #[serde(default)]
     pub bind: Option<address::NetAddress>,
     pub port: Option<u16>,
+    #[serde(default)]
+    pub allowip: Vec<String>,
     pub password: Option<String>,
 }
 
@@ -440,6 +454,7 @@ pub struct DatabaseSettings {
     pub size: Option<usize>,
     pub max_dbs: Option<u32>,
     pub no_lmdb_sync: Option<bool>,
+    pub history_epochs: Option<u32>,
 }
 
 impl Default for DatabaseSettings {
@@ -449,6 +464,7 @@ impl Default for DatabaseSettings {
             size: Some(1024 * 1024 * 50),
             max_dbs: Some(10),
             no_lmdb_sync: None,
+            history_epochs: None,
         }
     }
 }
@@ -535,4 +551,36 @@ impl From<MempoolFilterSettings> for MempoolRules {
 pub struct ValidatorSettings {
     pub key_file: Option<String>,
     pub wallet_private_key: Option<String>,
+    pub signer: Option<String>,
+    pub signer_auth_key_file: Option<String>,
+    pub view_change_aggregation: Option<AggregationSettings>,
+    pub pbft_aggregation: Option<AggregationSettings>,
+}
+
+/// Handel settings of a signature aggregation. Durations are in milliseconds.
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct AggregationSettings {
+    pub update_count: Option<usize>,
+    pub update_interval: Option<u64>,
+    pub peer_count: Option<usize>,
+    pub timeout_strategy: Option<TimeoutStrategy>,
+    pub timeout: Option<u64>,
+    pub timeout_factor: Option<f64>,
+    pub min_timeout: Option<u64>,
+    pub max_timeout: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeoutStrategy {
+    Linear,
+    Exponential,
+    Adaptive,
+}
+
+impl Default for TimeoutStrategy {
+    fn default() -> Self {
+        TimeoutStrategy::Linear
+    }
 }
@@ -10,12 +10,29 @@ pub const REVERSE_PROXY_DEFAULT_PORT: u16 = 8444;
 /// The default port for the RPC server
 pub const RPC_DEFAULT_PORT: u16 = 8648;
 
+/// The default maximum number of requests in a JSON-RPC batch
+pub const RPC_DEFAULT_MAX_BATCH_SIZE: usize = 100;
+
 /// The default port for the WebSocket server
 pub const WS_RPC_DEFAULT_PORT: u16 = 8650;
 
 /// The default port for the metrics server
 pub const METRICS_DEFAULT_PORT: u16 = 8649;
 
+/// The default Handel level timeout in milliseconds. This is the period of linear and adaptive
+/// timeouts and the first period of exponential timeouts.
+pub const HANDEL_DEFAULT_TIMEOUT: u64 = 500;
+
+/// The default growth of exponential timeouts and the default multiplier of adaptive timeouts
+pub const HANDEL_DEFAULT_TIMEOUT_FACTOR: f64 = 2.0;
+
+/// The default minimum time between two level timeouts of adaptive timeouts in milliseconds
+pub const HANDEL_DEFAULT_MIN_TIMEOUT: u64 = 100;
+
+/// The default maximum time between two level timeouts of exponential and adaptive timeouts in
+/// milliseconds
+pub const HANDEL_DEFAULT_MAX_TIMEOUT: u64 = 4000;
+
 /// Returns the default bind, i.e. localhost
 pub fn default_bind() -> IpAddr {
     IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))
@@ -9,8 +9,16 @@ use database::volatile::VolatileDatabaseError;
 use network::error::Error as NetworkError;
 use utils::key_store::Error as KeyStoreError;
 use consensus::Error as ConsensusError;
+use blockchain::block_file::BlockFileError;
+use blockchain::integrity::{Inconsistency, RepairError};
+use blockchain_base::BlockchainError;
+use blockchain::snapshot::SnapshotError;
 #[cfg(feature="validator")]
 use validator::error::Error as ValidatorError;
+#[cfg(feature="validator")]
+use validator::slashing_protection::SlashingProtectionError;
+#[cfg(feature="validator")]
+use validator::signer::SignerError;
 #[cfg(feature="rpc-server")]
 use rpc_server::error::Error as RpcServerError;
 
@@ -38,10 +46,33 @@ pub enum Error {
     #[fail(display = "Config file parsing error: {}", _0)]
     Toml(#[cause] TomlError),
 
+    #[fail(display = "Blockchain error: {}", _0)]
+    Blockchain(#[cause] BlockchainError),
+
+    #[fail(display = "Snapshot error: {}", _0)]
+    Snapshot(#[cause] SnapshotError),
+
+    #[fail(display = "Block file error: {}", _0)]
+    BlockFile(#[cause] BlockFileError),
+
+    #[fail(display = "Database is inconsistent: {}", _0)]
+    Inconsistency(#[cause] Inconsistency),
+
+    #[fail(display = "Failed to repair database: {}", _0)]
+    Repair(#[cause] RepairError),
+
     #[cfg(feature="validator")]
     #[fail(display = "Validator error: {}", _0)]
     Validator(#[cause] ValidatorError),
 
+    #[cfg(feature="validator")]
+    #[fail(display = "Slashing protection error: {}", _0)]
+    SlashingProtection(#[cause] SlashingProtectionError),
+
+    #[cfg(feature="validator")]
+    #[fail(display = "Signer error: {}", _0)]
+    Signer(#[cause] SignerError),
+
     #[cfg(feature="rpc-server")]
     #[fail(display = "RPC server error: {}", _0)]
     RpcServer(#[cause] RpcServerError),
@@ -107,6 +138,36 @@ impl From<TomlError> for Error {
     }
 }
 
+impl From<BlockchainError> for Error {
+    fn from(e: BlockchainError) -> Self {
+        Self::Blockchain(e)
+    }
+}
+
+impl From<SnapshotError> for Error {
+    fn from(e: SnapshotError) -> Self {
+        Self::Snapshot(e)
+    }
+}
+
+impl From<BlockFileError> for Error {
+    fn from(e: BlockFileError) -> Self {
+        Self::BlockFile(e)
+    }
+}
+
+impl From<Inconsistency> for Error {
+    fn from(e: Inconsistency) -> Self {
+        Self::Inconsistency(e)
+    }
+}
+
+impl From<RepairError> for Error {
+    fn from(e: RepairError) -> Self {
+        Self::Repair(e)
+    }
+}
+
 #[cfg(feature="validator")]
 impl From<ValidatorError> for Error {
     fn from(e: ValidatorError) -> Self {
@@ -114,6 +175,20 @@ impl From<ValidatorError> for Error {
     }
 }
 
+#[cfg(feature="validator")]
+impl From<SlashingProtectionError> for Error {
+    fn from(e: SlashingProtectionError) -> Self {
+        Self::SlashingProtection(e)
+    }
+}
+
+#[cfg(feature="validator")]
+impl From<SignerError> for Error {
+    fn from(e: SignerError) -> Self {
+        Self::Signer(e)
+    }
+}
+
 #[cfg(feature="rpc-server")]
 impl From<RpcServerError> for Error {
     fn from(e: RpcServerError) -> Self {
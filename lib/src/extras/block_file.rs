@@ -0,0 +1,52 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use blockchain::Blockchain;
+use blockchain::block_file::{BlockFileReader, BlockFileWriter};
+use primitives::policy;
+
+use crate::config::config::ClientConfig;
+use crate::error::Error;
+
+
+/// Exports the main chain of the client's database to a block file.
+pub fn export_blocks<P: AsRef<Path>>(config: ClientConfig, path: P) -> Result<(), Error> {
+    if !config.network.is_albatross() {
+        return Err(Error::config_error(&format!("{} is not compatible with Albatross", config.network)));
+    }
+
+    let environment = config.storage.database(config.network, config.consensus, config.database)?;
+    let blockchain = Blockchain::new(environment, config.network)?;
+
+    let mut writer = BlockFileWriter::new(BufWriter::new(File::create(path)?), config.network)?;
+    let head_number = blockchain.export_blocks(&mut writer, |block_number| {
+        if policy::is_macro_block_at(block_number) {
+            info!("Exported epoch {}", policy::epoch_at(block_number));
+        }
+    })?;
+    writer.finish()?;
+
+    info!("Exported blocks up to #{}", head_number);
+    Ok(())
+}
+
+/// Imports a block file into the client's database, stopping at the first invalid block.
+pub fn import_blocks<P: AsRef<Path>>(config: ClientConfig, path: P) -> Result<(), Error> {
+    if !config.network.is_albatross() {
+        return Err(Error::config_error(&format!("{} is not compatible with Albatross", config.network)));
+    }
+
+    let mut reader = BlockFileReader::new(BufReader::new(File::open(path)?))?;
+    let environment = config.storage.database(config.network, config.consensus, config.database)?;
+    let blockchain = Blockchain::new(environment, config.network)?;
+
+    let count = blockchain.import_blocks(&mut reader, |block_number| {
+        if policy::is_macro_block_at(block_number) {
+            info!("Imported epoch {}", policy::epoch_at(block_number));
+        }
+    })?;
+
+    info!("Imported {} new blocks and epochs, head is now #{} - {}", count, blockchain.block_number(), blockchain.head_hash());
+    Ok(())
+}
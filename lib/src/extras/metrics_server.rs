@@ -1,6 +1,10 @@
+use std::sync::Arc;
+
 use metrics_server::MetricsServer;
 use metrics_server::error::Error;
 use metrics_server::AlbatrossChainMetrics;
+#[cfg(feature="validator")]
+use metrics_server::ValidatorMetrics;
 
 use crate::config::config::MetricsServerConfig;
 use crate::client::Client;
@@ -18,13 +22,23 @@ pub fn initialize_metrics_server(client: &Client, config: MetricsServerConfig, p
         (None, None)
     };
 
-     Ok(MetricsServer::new::<_, AlbatrossChainMetrics>(
+    #[allow(unused_mut)]
+    let mut extra_metrics: Vec<Arc<dyn metrics_server::server::Metrics>> = Vec::new();
+    #[cfg(feature="validator")] {
+        if let Some(validator) = client.validator() {
+            extra_metrics.push(Arc::new(ValidatorMetrics::new(validator, client.blockchain())));
+        }
+    }
+
+    Ok(MetricsServer::new::<_, AlbatrossChainMetrics>(
         ip,
         config.port,
+        config.allow_ips.unwrap_or_default(),
         username,
         password,
         pkcs12_key_file,
         pkcs12_passphrase,
-        client.consensus()
+        client.consensus(),
+        extra_metrics,
     )?)
 }
@@ -12,5 +12,11 @@ pub mod metrics_server;
 #[cfg(feature = "ws-rpc-server")]
 pub mod ws_rpc_server;
 
+pub mod block_file;
+pub mod snapshot;
+#[cfg(feature = "validator")]
+pub mod slashing_protection;
+pub mod verify_db;
+
 #[cfg(feature = "launcher")]
 pub mod launcher;
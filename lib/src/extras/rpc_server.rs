@@ -2,7 +2,8 @@ use std::collections::HashSet;
 use std::sync::Arc;
 use std::iter::FromIterator;
 
-use rpc_server::{RpcServer, JsonRpcConfig};
+use rpc_server::{Handler, RpcServer, JsonRpcConfig};
+use rpc_server::handler::Method;
 use rpc_server::handlers::*;
 
 use crate::client::Client;
@@ -11,7 +12,7 @@ use crate::error::Error;
 use crate::config::consts::default_bind;
 
 
-pub fn initialize_rpc_server(client: &Client, config: RpcServerConfig) -> Result<RpcServer, Error> {
+pub fn initialize_rpc_server(client: &Client, config: RpcServerConfig, modules: &RpcModules) -> Result<RpcServer, Error> {
     let ip = config.bind_to.unwrap_or_else(default_bind);
     info!("Initializing RPC server: {}:{}", ip, config.port);
 
@@ -34,37 +35,114 @@ pub fn initialize_rpc_server(client: &Client, config: RpcServerConfig) -> Result
         username,
         password,
         methods,
-        allowip: (), // TODO: config.allow_ips,
+        allowip: config.allow_ips.unwrap_or_default(),
         corsdomain,
+        max_batch_size: Some(config.max_batch_size),
     };
 
     // Initialize RPC server
     let rpc_server = RpcServer::new(ip, config.port, json_rpc_config)?;
-    let handler = Arc::clone(&rpc_server.handler);
+    modules.install(&rpc_server.handler);
 
-    // Install RPC modules
-    #[cfg(feature="validator")] {
-        if let Some(validator) = client.validator() {
-            let block_production_handler = BlockProductionAlbatrossHandler::new(validator);
-            handler.add_module(block_production_handler);
+    Ok(rpc_server)
+}
+
+/// The RPC methods of a client
+///
+/// The methods are created once and installed into both the RPC server and the websocket RPC
+/// server, so that both servers share the state of the modules. E.g. a wallet unlocked over one
+/// server is unlocked over the other one as well.
+#[derive(Clone, Default)]
+pub struct RpcModules {
+    methods: Vec<(&'static str, Method)>,
+}
+
+impl RpcModules {
+    pub fn new(client: &Client) -> Self {
+        let mut modules = Self::default();
+
+        #[cfg(feature="validator")] {
+            if let Some(validator) = client.validator() {
+                modules.add_module(BlockProductionAlbatrossHandler::new(validator));
+            }
+        }
+
+        modules.add_module(BlockchainAlbatrossHandler::new(client.blockchain()));
+        modules.add_module(ConsensusHandler::new(client.consensus()));
+        modules.add_module(NetworkHandler::new(&client.consensus()));
+
+        let wallet_handler = WalletHandler::new(client.environment());
+        let wallet_manager = Arc::clone(&wallet_handler.unlocked_wallets);
+        modules.add_module(wallet_handler);
+
+        modules.add_module(MempoolAlbatrossHandler::new(client.blockchain(), client.mempool(), Some(wallet_manager)));
+
+        modules
+    }
+
+    pub fn add_module<M: Module>(&mut self, module: M) {
+        self.methods.extend(module.methods());
+    }
+
+    /// Registers the methods with `handler`
+    pub fn install(&self, handler: &Handler) {
+        for (name, method) in &self.methods {
+            handler.register_method(*name, method.clone());
         }
     }
+}
 
-    let blockchain_handler = BlockchainAlbatrossHandler::new(client.blockchain());
-    handler.add_module(blockchain_handler);
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use json::{JsonValue, Null};
+
+    use database::volatile::VolatileEnvironment;
+    use keys::{KeyPair, PrivateKey};
+    use rpc_server::jsonrpc::Handler as _;
+
+    use super::*;
+
+    fn handler(modules: &RpcModules) -> Handler {
+        let handler = Handler::new(JsonRpcConfig {
+            username: None,
+            password: None,
+            methods: HashSet::new(),
+            allowip: Default::default(),
+            corsdomain: Vec::new(),
+            max_batch_size: None,
+        });
+        modules.install(&handler);
+        handler
+    }
 
-    let consensus_handler = ConsensusHandler::new(client.consensus());
-    handler.add_module(consensus_handler);
+    fn call(handler: &Handler, method: &str, params: Vec<JsonValue>) -> Result<JsonValue, JsonValue> {
+        handler.call_method(method, params).expect("Method not installed")
+    }
 
-    let network_handler = NetworkHandler::new(&client.consensus());
-    handler.add_module(network_handler);
+    #[test]
+    fn it_shares_modules_between_servers() {
+        let env = VolatileEnvironment::new(10).unwrap();
+        let mut modules = RpcModules::default();
+        modules.add_module(WalletHandler::new(env));
 
-    let wallet_handler = WalletHandler::new(client.environment());
-    let wallet_manager = Arc::clone(&wallet_handler.unlocked_wallets);
-    handler.add_module(wallet_handler);
+        let http = handler(&modules);
+        let ws = handler(&modules);
 
-    let mempool_handler = MempoolAlbatrossHandler::new(client.mempool(), Some(wallet_manager));
-    handler.add_module(mempool_handler);
+        let private_key = "b410a7a583cbc13ef4f1cbddace30928bcb4f9c13722414bc4a2faaba3f4e187";
+        let address = call(&http, "importRawKey", vec![private_key.into(), "secret".into()]).unwrap();
+        let sign_params = vec!["message".into(), address.clone()];
 
-    Ok(rpc_server)
+        // The account is locked on both servers
+        assert!(call(&ws, "sign", sign_params.clone()).is_err());
+
+        // Unlocking it over one server unlocks it on the other one as well
+        assert_eq!(call(&http, "unlockAccount", vec![address.clone(), "secret".into()]), Ok(true.into()));
+        let signature = call(&ws, "sign", sign_params.clone()).unwrap();
+        assert_eq!(signature["publicKey"], KeyPair::from(PrivateKey::from_str(private_key).unwrap()).public.to_hex());
+
+        assert_eq!(call(&ws, "lockAccount", vec![address, Null]), Ok(true.into()));
+        assert!(call(&http, "sign", sign_params).is_err());
+    }
 }
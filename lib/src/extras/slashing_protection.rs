@@ -0,0 +1,43 @@
+use std::path::Path;
+
+use validator::slashing_protection::SlashingProtection;
+
+use crate::config::config::ClientConfig;
+use crate::error::Error;
+
+
+/// Exports the slashing protection database of the client's validator to an interchange file.
+/// The validator key must already exist.
+pub fn export_slashing_protection<P: AsRef<Path>>(config: ClientConfig, path: P) -> Result<(), Error> {
+    check_local_signer(&config)?;
+    let validator_key = config.storage.existing_validator_key()?;
+    let environment = config.storage.slashing_protection_database(config.network)?;
+    let exported = SlashingProtection::new(environment)
+        .export_to_file(path, config.network, validator_key.public.compress())?;
+
+    info!("Exported {} slashing protection records", exported);
+    Ok(())
+}
+
+/// Merges an interchange file into the slashing protection database of the client's validator.
+/// This must be done before the validator is started on this machine, but after its key was put
+/// in place.
+pub fn import_slashing_protection<P: AsRef<Path>>(config: ClientConfig, path: P) -> Result<(), Error> {
+    check_local_signer(&config)?;
+    let validator_key = config.storage.existing_validator_key()?;
+    let environment = config.storage.slashing_protection_database(config.network)?;
+    let (updated, total) = SlashingProtection::new(environment)
+        .import_from_file(path, config.network, &validator_key.public.compress())?;
+
+    info!("Imported {} of {} slashing protection records", updated, total);
+    Ok(())
+}
+
+/// With a remote signer, the slashing protection database is kept by the signer and is exported
+/// and imported with its `export` and `import` commands.
+fn check_local_signer(config: &ClientConfig) -> Result<(), Error> {
+    if config.validator.as_ref().and_then(|validator| validator.remote_signer()).is_some() {
+        return Err(Error::config_error("The validator uses a remote signer, use the export and import commands of nimiq-signer instead"));
+    }
+    Ok(())
+}
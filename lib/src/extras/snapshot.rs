@@ -0,0 +1,38 @@
+use std::path::Path;
+
+use blockchain::Blockchain;
+use blockchain::snapshot::Snapshot;
+
+use crate::config::config::ClientConfig;
+use crate::error::Error;
+
+
+/// Exports a snapshot of the state at the current macro head of the client's database to a
+/// file.
+pub fn export_snapshot<P: AsRef<Path>>(config: ClientConfig, path: P) -> Result<(), Error> {
+    if !config.network.is_albatross() {
+        return Err(Error::config_error(&format!("{} is not compatible with Albatross", config.network)));
+    }
+
+    let environment = config.storage.database(config.network, config.consensus, config.database)?;
+    let blockchain = Blockchain::new(environment, config.network)?;
+
+    let snapshot = blockchain.export_snapshot()?;
+    snapshot.write_to_file(path)?;
+
+    let macro_block = snapshot.macro_block().expect("Snapshot without macro block");
+    info!("Exported snapshot at macro block #{} - {}", macro_block.header.block_number, macro_block.hash());
+    Ok(())
+}
+
+/// Bootstraps the client's database from a snapshot file. The database must be empty.
+pub fn import_snapshot<P: AsRef<Path>>(config: ClientConfig, path: P) -> Result<(), Error> {
+    if !config.network.is_albatross() {
+        return Err(Error::config_error(&format!("{} is not compatible with Albatross", config.network)));
+    }
+
+    let snapshot = Snapshot::read_from_file(path)?;
+    let environment = config.storage.database(config.network, config.consensus, config.database)?;
+    Blockchain::import_snapshot(environment, config.network, snapshot)?;
+    Ok(())
+}
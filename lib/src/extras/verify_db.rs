@@ -0,0 +1,28 @@
+use blockchain::integrity::IntegrityChecker;
+
+use crate::config::config::ClientConfig;
+use crate::error::Error;
+
+
+/// Checks the consistency of the client's database. If `repair` is set, the database is rolled
+/// back to the last good macro block before the first inconsistency.
+pub fn verify_db(config: ClientConfig, repair: bool) -> Result<(), Error> {
+    if !config.network.is_albatross() {
+        return Err(Error::config_error(&format!("{} is not compatible with Albatross", config.network)));
+    }
+
+    let environment = config.storage.database(config.network, config.consensus, config.database)?;
+    let checker = IntegrityChecker::new(environment, config.network);
+
+    if !repair {
+        checker.check()?;
+        info!("Database is consistent");
+        return Ok(());
+    }
+
+    match checker.repair()? {
+        Some(block_number) => info!("Rolled back database to macro block #{}", block_number),
+        None => info!("Database is consistent"),
+    }
+    Ok(())
+}
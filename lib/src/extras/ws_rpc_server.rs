@@ -1,17 +1,49 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::iter::FromIterator;
+
+use rpc_server::{Handler, JsonRpcConfig};
 use ws_rpc_server::WsRpcServer;
 
 use crate::error::Error;
 use crate::client::Client;
 use crate::config::config::WsRpcServerConfig;
 use crate::config::consts::default_bind;
+use crate::extras::rpc_server::RpcModules;
 
-pub fn initialize_ws_rcp_server(client: &Client, config: WsRpcServerConfig) -> Result<WsRpcServer, Error> {
+pub fn initialize_ws_rcp_server(client: &Client, config: WsRpcServerConfig, modules: &RpcModules) -> Result<WsRpcServer, Error> {
     let ip = config.bind_to.unwrap_or_else(default_bind);
 
     info!("Initializing websocket RPC server: {}:{}", ip, config.port);
 
-    let server = WsRpcServer::new(ip, config.port)?;
+    let (username, password) = if let Some(credentials) = config.credentials {
+        (Some(credentials.username), Some(credentials.password))
+    }
+    else {
+        warn!("No password set for websocket RPC server!");
+        (None, None)
+    };
+
+    let methods = config.allowed_methods
+        .map(|methods| HashSet::from_iter(methods))
+        .unwrap_or_default();
+
+    // IPs are filtered by the websocket server itself.
+    let json_rpc_config = JsonRpcConfig {
+        username,
+        password,
+        methods,
+        allowip: Default::default(),
+        corsdomain: Vec::new(),
+        max_batch_size: Some(config.max_batch_size),
+    };
+
+    let handler = Arc::new(Handler::new(json_rpc_config));
+    modules.install(&handler);
+
+    let server = WsRpcServer::new(ip, config.port, config.allow_ips.unwrap_or_default(), handler)?;
     server.register_blockchain(client.consensus());
+    server.register_mempool(client.mempool());
     #[cfg(feature="validator")] {
         if let Some(validator) = client.validator() {
             server.register_validator(validator)
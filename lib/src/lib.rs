@@ -12,11 +12,14 @@ extern crate nimiq_mempool as mempool;
 extern crate nimiq_utils as utils;
 extern crate nimiq_keys as keys;
 extern crate nimiq_blockchain_albatross as blockchain;
+extern crate nimiq_blockchain_base as blockchain_base;
 
 #[cfg(feature="validator")]
 extern crate nimiq_validator as validator;
 #[cfg(feature="validator")]
 extern crate nimiq_bls as bls;
+#[cfg(feature="validator")]
+extern crate nimiq_handel as handel;
 
 #[cfg(feature="rpc-server")]
 extern crate nimiq_rpc_server as rpc_server;
@@ -31,5 +34,6 @@ extern crate nimiq_ws_rpc_server as ws_rpc_server;
 pub mod config;
 pub mod error;
 pub mod client;
+pub mod light_client;
 pub mod prelude;
 pub mod extras;
@@ -0,0 +1,102 @@
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use consensus::{
+    Consensus as AbstractConsensus,
+    AlbatrossLightConsensusProtocol,
+};
+use database::Environment;
+use network::Network as GenericNetwork;
+use network_primitives::services::Services;
+use blockchain::light_blockchain::LightBlockchain;
+
+use crate::client::network_config;
+use crate::error::Error;
+use crate::config::config::{ClientConfig, ConsensusConfig};
+
+
+/// Alias for the Consensus specialized over the Albatross light protocol
+pub type LightConsensus = AbstractConsensus<AlbatrossLightConsensusProtocol>;
+pub type LightNetwork = GenericNetwork<LightBlockchain>;
+
+
+/// A client that only follows the macro blocks of the chain. Accounts can be requested from full
+/// nodes through the consensus, which verifies them against the state root of the head.
+///
+/// The light client doesn't run a validator nor any of the servers.
+///
+pub struct LightClient {
+    /// The database environment, which only holds the macro blocks.
+    environment: Environment,
+
+    /// The consensus object, which maintains the light blockchain and the network.
+    consensus: Arc<LightConsensus>,
+}
+
+
+impl LightClient {
+    /// Initializes the Nimiq network stack.
+    pub fn initialize(&self) -> Result<(), Error> {
+        self.consensus.network.initialize()?;
+        Ok(())
+    }
+
+    /// After calling this the network stack will start connecting to other peers.
+    pub fn connect(&self) -> Result<(), Error> {
+        self.consensus.network.connect()?;
+        Ok(())
+    }
+
+    /// Returns a reference to the *Consensus*.
+    pub fn consensus(&self) -> Arc<LightConsensus> {
+        Arc::clone(&self.consensus)
+    }
+
+    /// Returns a reference to the *Network* stack
+    pub fn network(&self) -> Arc<LightNetwork> {
+        Arc::clone(&self.consensus.network)
+    }
+
+    /// Returns a reference to the light blockchain
+    pub fn blockchain(&self) -> Arc<LightBlockchain> {
+        Arc::clone(&self.consensus.blockchain)
+    }
+
+    /// Returns the database environment.
+    pub fn environment(&self) -> Environment {
+        self.environment.clone()
+    }
+}
+
+impl TryFrom<ClientConfig> for LightClient {
+    type Error = Error;
+
+    fn try_from(config: ClientConfig) -> Result<Self, Self::Error> {
+        if config.consensus != ConsensusConfig::Light {
+            return Err(Error::config_error(&format!("{} consensus is not supported by the light client", config.consensus)));
+        }
+
+        // Light clients only provide the light service and connect to full nodes
+        let mut network_config = network_config(&config)?;
+        network_config.set_services(Services::light());
+
+        // Open database
+        let environment = config.storage.database(config.network, config.consensus, config.database)?;
+
+        // Create Nimiq consensus
+        if !config.network.is_albatross() {
+            return Err(Error::config_error(&format!("{} is not compatible with Albatross", config.network)));
+        }
+        let consensus = LightConsensus::new(
+            environment.clone(),
+            config.network,
+            network_config,
+            config.mempool,
+        )?;
+
+        Ok(LightClient {
+            environment,
+            consensus,
+        })
+    }
+}
@@ -6,3 +6,4 @@ pub use crate::config::config::ClientConfig;
 pub use crate::config::config_file::ConfigFile;
 pub use crate::config::command_line::CommandLine;
 pub use crate::client::{Client, Consensus};
+pub use crate::light_client::{LightClient, LightConsensus};
@@ -0,0 +1,175 @@
+use std::collections::VecDeque;
+
+use beserial::Serialize;
+use hash::Blake2bHash;
+use transaction::Transaction;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeeHistogramBucket {
+    /// Lower bound of this bucket in Luna per byte.
+    pub min_fee_per_byte: f64,
+    /// Number of transactions in this bucket.
+    pub count: usize,
+    /// Accumulated serialized size of the transactions in this bucket.
+    pub size: usize,
+}
+
+/// Histogram of transactions by fee per byte.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeeHistogram {
+    buckets: Vec<FeeHistogramBucket>,
+}
+
+impl FeeHistogram {
+    /// Lower bounds of the buckets in Luna per byte.
+    pub const BUCKETS: [f64; 14] = [0.0, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0, 10000.0];
+
+    pub fn new() -> Self {
+        FeeHistogram {
+            buckets: Self::BUCKETS.iter()
+                .map(|&min_fee_per_byte| FeeHistogramBucket {
+                    min_fee_per_byte,
+                    count: 0,
+                    size: 0,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn add(&mut self, fee_per_byte: f64, size: usize) {
+        let index = Self::BUCKETS.iter()
+            .rposition(|&min_fee_per_byte| fee_per_byte >= min_fee_per_byte)
+            .unwrap_or(0);
+        let bucket = &mut self.buckets[index];
+        bucket.count += 1;
+        bucket.size += size;
+    }
+
+    pub fn add_transaction(&mut self, transaction: &Transaction) {
+        self.add(transaction.fee_per_byte(), transaction.serialized_size());
+    }
+
+    pub fn buckets(&self) -> &[FeeHistogramBucket] {
+        &self.buckets
+    }
+
+    pub fn count(&self) -> usize {
+        self.buckets.iter().map(|bucket| bucket.count).sum()
+    }
+
+    pub fn size(&self) -> usize {
+        self.buckets.iter().map(|bucket| bucket.size).sum()
+    }
+
+    /// Returns the fee per byte a transaction of `size` bytes needs so that it is among the
+    /// first `capacity` bytes of transactions when ordered by fee per byte.
+    ///
+    /// Transactions within a bucket are not distinguished, so the result is always the lower
+    /// bound of the bucket above the last one that still fits entirely.
+    pub fn fee_per_byte_for_capacity(&self, capacity: usize, size: usize) -> f64 {
+        let mut accumulated = size;
+        for (index, bucket) in self.buckets.iter().enumerate().rev() {
+            accumulated += bucket.size;
+            if accumulated > capacity {
+                // The transaction has to outbid this bucket.
+                return self.buckets.get(index + 1)
+                    .map(|bucket| bucket.min_fee_per_byte)
+                    .unwrap_or(bucket.min_fee_per_byte * 2.0);
+            }
+        }
+        0.0
+    }
+}
+
+impl Default for FeeHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct BlockFees {
+    hash: Blake2bHash,
+    /// Accumulated serialized size of the block's transactions.
+    size: usize,
+    /// Lowest fee per byte of the block's transactions.
+    min_fee_per_byte: f64,
+}
+
+/// Fees paid by the transactions of recently included blocks.
+#[derive(Clone, Debug)]
+pub struct FeeHistory {
+    blocks: VecDeque<BlockFees>,
+    /// Number of bytes that are available for transactions in a block.
+    block_capacity: usize,
+}
+
+impl FeeHistory {
+    /// Number of blocks that are kept in the history.
+    pub const MAX_BLOCKS: usize = 20;
+
+    pub fn new(block_capacity: usize) -> Self {
+        FeeHistory {
+            blocks: VecDeque::with_capacity(Self::MAX_BLOCKS),
+            block_capacity,
+        }
+    }
+
+    pub fn push_block(&mut self, hash: Blake2bHash, transactions: &[Transaction]) {
+        let block_fees = BlockFees {
+            hash,
+            size: transactions.iter().map(Serialize::serialized_size).sum(),
+            min_fee_per_byte: transactions.iter()
+                .map(Transaction::fee_per_byte)
+                .fold(None, |min: Option<f64>, fee_per_byte| Some(min.map_or(fee_per_byte, |min| min.min(fee_per_byte))))
+                .unwrap_or(0.0),
+        };
+
+        if self.blocks.len() >= Self::MAX_BLOCKS {
+            self.blocks.pop_front();
+        }
+        self.blocks.push_back(block_fees);
+    }
+
+    /// Removes a block that was reverted by a rebranch.
+    pub fn revert_block(&mut self, hash: &Blake2bHash) {
+        self.blocks.retain(|block| &block.hash != hash);
+    }
+
+    pub fn block_capacity(&self) -> usize {
+        self.block_capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Returns the fee per byte a transaction of `size` bytes would have needed to be included
+    /// within `target_blocks` blocks, judging from the recent blocks.
+    ///
+    /// A block that had room left for the transaction did not require any fee, otherwise the
+    /// transaction had to pay at least the block's lowest fee per byte. The larger the target,
+    /// the lower the percentile of these fees that is returned (the median for the next block).
+    pub fn fee_per_byte_for_target(&self, size: usize, target_blocks: u32) -> f64 {
+        if self.blocks.is_empty() {
+            return 0.0;
+        }
+
+        let mut required_fees: Vec<f64> = self.blocks.iter()
+            .map(|block| if block.size + size > self.block_capacity {
+                block.min_fee_per_byte
+            } else {
+                0.0
+            })
+            .collect();
+        required_fees.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let percentile = 0.5 / f64::from(target_blocks.max(1));
+        let index = ((required_fees.len() - 1) as f64 * percentile).round() as usize;
+        required_fees[index]
+    }
+}
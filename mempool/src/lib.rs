@@ -26,14 +26,17 @@ use transaction::{Transaction, TransactionFlags};
 use utils::observer::{Notifier, weak_listener};
 use primitives::networks::NetworkId;
 
+use crate::fee::{FeeHistogram, FeeHistory};
 use crate::filter::{MempoolFilter, Rules};
 
+pub mod fee;
 pub mod filter;
 
 pub struct Mempool<B: AbstractBlockchain> {
     blockchain: Arc<B>,
     pub notifier: RwLock<Notifier<'static, MempoolEvent>>,
     state: RwLock<MempoolState>,
+    fee_history: RwLock<FeeHistory>,
     mut_lock: Mutex<()>,
 }
 
@@ -80,6 +83,7 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
                 transactions_sorted_fee: BTreeSet::new(),
                 filter: MempoolFilter::new(config.filter_rules, config.filter_limit),
             }),
+            fee_history: RwLock::new(FeeHistory::new(B::Block::MAX_TRANSACTIONS_SIZE)),
             mut_lock: Mutex::new(()),
         });
 
@@ -161,7 +165,11 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
 
             // Retrieve recipient account and check account type.
             // TODO Eliminate copy
-            let mut recipient_account = self.blockchain.get_account(&transaction.recipient);
+            // Transactions can't be verified without the state of the accounts.
+            let mut recipient_account = match self.blockchain.get_account(&transaction.recipient) {
+                Some(account) => account,
+                None => return ReturnCode::Invalid,
+            };
             let is_contract_creation = transaction.flags.contains(TransactionFlags::CONTRACT_CREATION);
             let is_type_change = recipient_account.account_type() != transaction.recipient_type;
             if is_contract_creation != is_type_change {
@@ -190,7 +198,10 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
 
             // Retrieve sender account and check account type.
             // TODO Eliminate copy
-            let mut sender_account = self.blockchain.get_account(&transaction.sender);
+            let mut sender_account = match self.blockchain.get_account(&transaction.sender) {
+                Some(account) => account,
+                None => return ReturnCode::Invalid,
+            };
             if sender_account.account_type() != transaction.sender_type {
                 return ReturnCode::Invalid;
             }
@@ -331,10 +342,13 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
                 if &tx.sender == validator_registry_address {
                     // Get copy of staking contract if required.
                     if validator_registry.is_none() {
-                        validator_registry = Some(self.blockchain.get_account(validator_registry_address));
+                        validator_registry = self.blockchain.get_account(validator_registry_address);
                     }
 
-                    let sender_account = validator_registry.as_mut().unwrap();
+                    let sender_account = match validator_registry.as_mut() {
+                        Some(account) => account,
+                        None => continue, // The state of the staking contract is unknown.
+                    };
                     match sender_account.commit_outgoing_transaction(&tx, block_height) {
                         Err(_) => continue, // Ignore transaction.
                         Ok(receipt) => outgoing_receipt = receipt,
@@ -345,10 +359,13 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
                 if &tx.recipient == validator_registry_address {
                     // Get copy of staking contract if required.
                     if validator_registry.is_none() {
-                        validator_registry = Some(self.blockchain.get_account(validator_registry_address));
+                        validator_registry = self.blockchain.get_account(validator_registry_address);
                     }
 
-                    let recipient_account = validator_registry.as_mut().unwrap();
+                    let recipient_account = match validator_registry.as_mut() {
+                        Some(account) => account,
+                        None => continue, // The state of the staking contract is unknown.
+                    };
                     if recipient_account.commit_incoming_transaction(&tx, block_height).is_err() {
                         // Potentially revert sender side and ignore transaction.
                         if &tx.sender == validator_registry_address {
@@ -395,6 +412,30 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
         txs
     }
 
+    /// Returns a histogram of the transactions in the mempool by fee per byte.
+    pub fn fee_histogram(&self) -> FeeHistogram {
+        let mut histogram = FeeHistogram::new();
+        for tx in self.state.read().transactions_sorted_fee.iter() {
+            histogram.add_transaction(tx);
+        }
+        histogram
+    }
+
+    /// Estimates the fee per byte a transaction of `size` bytes needs to be included within the
+    /// next `target_blocks` blocks.
+    ///
+    /// The estimate is the maximum of the fee needed to outbid enough of the current mempool
+    /// transactions and the fee that was needed in recent blocks. It is never lower than the
+    /// minimum fee for transactions not to be considered free.
+    pub fn estimate_fee_per_byte(&self, size: usize, target_blocks: u32) -> f64 {
+        let target_blocks = target_blocks.max(1);
+        let capacity = B::Block::MAX_TRANSACTIONS_SIZE.saturating_mul(target_blocks as usize);
+
+        let mempool_fee = self.fee_histogram().fee_per_byte_for_capacity(capacity, size);
+        let history_fee = self.fee_history.read().fee_per_byte_for_target(size, target_blocks);
+        mempool_fee.max(history_fee).max(TRANSACTION_RELAY_FEE_MIN)
+    }
+
     pub fn current_height(&self) -> u32 {
         self.blockchain.head_height()
     }
@@ -405,16 +446,39 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
 
     fn on_blockchain_event(&self, event: &BlockchainEvent<B::Block>) {
         match event {
-            BlockchainEvent::Extended(_) | BlockchainEvent::Finalized(_) => {
+            BlockchainEvent::Extended(hash) => {
+                if let Some(block) = self.blockchain.get_block(hash, true) {
+                    self.record_block_fees(&block);
+                }
+                self.evict_transactions()
+            },
+            BlockchainEvent::Finalized(_) => {
+                // Only macro blocks are finalized and they don't contain any transactions.
                 self.evict_transactions()
             },
-            BlockchainEvent::Rebranched(reverted_blocks, _) => {
+            BlockchainEvent::Rebranched(reverted_blocks, adopted_blocks) => {
+                {
+                    let mut fee_history = self.fee_history.write();
+                    for (hash, _) in reverted_blocks {
+                        fee_history.revert_block(hash);
+                    }
+                }
+                for (_, block) in adopted_blocks {
+                    self.record_block_fees(block);
+                }
                 self.restore_transactions(reverted_blocks);
                 self.evict_transactions();
             },
         }
     }
 
+    fn record_block_fees(&self, block: &B::Block) {
+        // Only micro blocks contain transactions.
+        if let Some(transactions) = block.transactions() {
+            self.fee_history.write().push_block(block.hash(), transactions);
+        }
+    }
+
     /// Evict all transactions from the pool that have become invalid due to changes in the
     /// account state (i.e. typically because the were included in a newly mined block). No need to re-check signatures.
     fn evict_transactions(&self) {
@@ -429,7 +493,14 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
 
             for (address, transactions) in state.transactions_by_sender.iter() {
                 // TODO Eliminate copy
-                let mut sender_account = self.blockchain.get_account(&address);
+                let mut sender_account = match self.blockchain.get_account(&address) {
+                    Some(account) => account,
+                    None => {
+                        // The transactions can't be verified anymore.
+                        txs_evicted.extend(transactions.iter().cloned());
+                        continue;
+                    },
+                };
                 for tx in transactions.iter().rev() {
                     // Check if the transaction has expired.
                     if !tx.is_valid_at(block_height) {
@@ -445,7 +516,13 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
 
                     // Check if transaction is still valid for recipient.
                     // TODO Eliminate copy
-                    let mut recipient_account = self.blockchain.get_account(&tx.recipient);
+                    let mut recipient_account = match self.blockchain.get_account(&tx.recipient) {
+                        Some(account) => account,
+                        None => {
+                            txs_evicted.push(tx.clone());
+                            continue;
+                        },
+                    };
                     if recipient_account.commit_incoming_transaction(&tx, block_height).is_err() {
                         txs_evicted.push(tx.clone());
                         continue;
@@ -519,7 +596,11 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
                 }
 
                 // TODO Eliminate copy
-                let mut recipient_account = self.blockchain.get_account(&tx.recipient);
+                let mut recipient_account = match self.blockchain.get_account(&tx.recipient) {
+                    Some(account) => account,
+                    // XXX The transaction is lost!
+                    None => continue,
+                };
                 if recipient_account.commit_incoming_transaction(&tx, block_height).is_err() {
                     // This transaction cannot be accepted by the recipient anymore.
                     // XXX The transaction is lost!
@@ -556,7 +637,11 @@ impl<B: AbstractBlockchain + 'static> Mempool<B> {
                 };
 
                 // TODO Eliminate copy.
-                let sender_account = self.blockchain.get_account(&sender);
+                let sender_account = match self.blockchain.get_account(&sender) {
+                    Some(account) => account,
+                    // XXX The transactions are lost!
+                    None => continue,
+                };
                 let (txs_to_add, txs_to_remove) = Self::merge_transactions(sender_account, block_height, existing_txs, &restored_txs);
                 for tx in txs_to_add {
                     let transaction = Arc::new(tx.clone());
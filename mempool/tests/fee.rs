@@ -0,0 +1,121 @@
+use std::convert::TryFrom;
+
+use beserial::Serialize;
+use nimiq_hash::{Blake2bHash, Blake2bHasher, Hasher};
+use nimiq_keys::Address;
+use nimiq_mempool::fee::{FeeHistogram, FeeHistory};
+use nimiq_primitives::coin::Coin;
+use nimiq_primitives::networks::NetworkId;
+use nimiq_transaction::Transaction;
+
+/// Number of bytes available for transactions in a block.
+const BLOCK_CAPACITY: usize = 100_000;
+
+fn transaction(fee: u64, validity_start_height: u32) -> Transaction {
+    Transaction::new_basic(
+        Address::from([1u8; Address::SIZE]),
+        Address::from([2u8; Address::SIZE]),
+        Coin::try_from(100).unwrap(),
+        Coin::try_from(fee).unwrap(),
+        validity_start_height,
+        NetworkId::Main,
+    )
+}
+
+/// Returns enough transactions with the given fee to fill a block.
+fn full_block(fee: u64) -> Vec<Transaction> {
+    let size = transaction(fee, 0).serialized_size();
+    (0..(BLOCK_CAPACITY / size) as u32)
+        .map(|i| transaction(fee, i))
+        .collect()
+}
+
+#[test]
+fn block_hash(i: u32) -> Blake2bHash {
+    Blake2bHasher::default().digest(&i.to_be_bytes())
+}
+
+#[test]
+fn it_can_build_fee_histograms() {
+    let mut histogram = FeeHistogram::new();
+    histogram.add(0.0, 100);
+    histogram.add(1.5, 200);
+    histogram.add(1.0, 300);
+    histogram.add(50000.0, 400);
+
+    assert_eq!(histogram.count(), 4);
+    assert_eq!(histogram.size(), 1000);
+
+    let buckets = histogram.buckets();
+    assert_eq!(buckets.len(), FeeHistogram::BUCKETS.len());
+    assert_eq!(buckets[0].count, 1);
+    assert_eq!(buckets[1].count, 2);
+    assert_eq!(buckets[1].size, 500);
+    assert_eq!(buckets[13].count, 1);
+}
+
+#[test]
+fn it_estimates_fees_from_histograms() {
+    let mut histogram = FeeHistogram::new();
+    assert_eq!(histogram.fee_per_byte_for_capacity(1000, 100), 0.0);
+
+    histogram.add(1.0, 400);
+    histogram.add(10.0, 400);
+
+    // Everything fits.
+    assert_eq!(histogram.fee_per_byte_for_capacity(1000, 100), 0.0);
+    // The transaction has to outbid the 1 Luna/byte bucket.
+    assert_eq!(histogram.fee_per_byte_for_capacity(800, 100), 2.0);
+    // The transaction has to outbid the 10 Luna/byte bucket.
+    assert_eq!(histogram.fee_per_byte_for_capacity(400, 100), 20.0);
+
+    histogram.add(20000.0, 400);
+    assert_eq!(histogram.fee_per_byte_for_capacity(400, 100), 20000.0 * 2.0);
+}
+
+#[test]
+fn it_estimates_fees_from_recent_blocks() {
+    let mut history = FeeHistory::new(BLOCK_CAPACITY);
+    assert!(history.is_empty());
+    assert_eq!(history.fee_per_byte_for_target(138, 1), 0.0);
+
+    // Blocks that are not full don't require any fee.
+    for i in 0..10 {
+        history.push_block(block_hash(i), &[transaction(1000, i)]);
+    }
+    assert_eq!(history.fee_per_byte_for_target(138, 1), 0.0);
+
+    // Full blocks require their lowest fee per byte.
+    let full_block = full_block(1000);
+    let min_fee_per_byte = full_block[0].fee_per_byte();
+    for i in 10..21 {
+        history.push_block(block_hash(i), &full_block);
+    }
+    assert_eq!(history.len(), FeeHistory::MAX_BLOCKS);
+    assert_eq!(history.fee_per_byte_for_target(138, 1), min_fee_per_byte);
+
+    // Waiting for more blocks is cheaper.
+    assert_eq!(history.fee_per_byte_for_target(138, 2), 0.0);
+}
+
+#[test]
+fn it_forgets_reverted_blocks() {
+    let mut history = FeeHistory::new(BLOCK_CAPACITY);
+    history.push_block(block_hash(1), &[transaction(1000, 1)]);
+
+    let full_block = full_block(1000);
+    let min_fee_per_byte = full_block[0].fee_per_byte();
+    history.push_block(block_hash(2), &full_block);
+    history.push_block(block_hash(3), &full_block);
+    assert_eq!(history.fee_per_byte_for_target(138, 1), min_fee_per_byte);
+
+    // The full blocks were reverted by a rebranch.
+    history.revert_block(&block_hash(3));
+    history.revert_block(&block_hash(2));
+    assert_eq!(history.len(), 1);
+    assert_eq!(history.fee_per_byte_for_target(138, 1), 0.0);
+
+    // Unknown blocks are ignored.
+    history.revert_block(&block_hash(4));
+    assert_eq!(history.len(), 1);
+}
@@ -103,6 +103,10 @@ pub enum MessageType {
     GetMacroBlocks = 123,
     GetEpochTransactions = 124,
     EpochTransactions = 125,
+    GetBlocksByNumber = 126,
+    Blocks = 127,
+    GetSlashRegistry = 128,
+    SlashRegistry = 129,
 }
 
 impl Display for MessageType {
@@ -158,6 +162,10 @@ impl Display for MessageType {
             Self::GetMacroBlocks  => write!(f, "get-macro-blocks"),
             Self::GetEpochTransactions  => write!(f, "get-epoch-transactions"),
             Self::EpochTransactions  => write!(f, "epoch-transactions"),
+            Self::GetBlocksByNumber  => write!(f, "get-blocks-by-number"),
+            Self::Blocks  => write!(f, "blocks"),
+            Self::GetSlashRegistry  => write!(f, "get-slash-registry"),
+            Self::SlashRegistry  => write!(f, "slash-registry"),
         }
     }
 }
@@ -215,6 +223,10 @@ pub enum Message {
     GetMacroBlocks(Box<GetBlocksMessage>),
     GetEpochTransactions(Box<GetEpochTransactionsMessage>),
     EpochTransactions(Box<EpochTransactionsMessage>),
+    GetBlocksByNumber(Box<GetBlocksByNumberMessage>),
+    Blocks(Box<BlocksMessage>),
+    GetSlashRegistry(Box<GetSlashRegistryMessage>),
+    SlashRegistry(Box<SlashRegistryMessage>),
 }
 
 impl Message {
@@ -265,6 +277,10 @@ impl Message {
             Message::GetMacroBlocks(_) => MessageType::GetMacroBlocks,
             Message::GetEpochTransactions(_) => MessageType::GetEpochTransactions,
             Message::EpochTransactions(_) => MessageType::EpochTransactions,
+            Message::GetBlocksByNumber(_) => MessageType::GetBlocksByNumber,
+            Message::Blocks(_) => MessageType::Blocks,
+            Message::GetSlashRegistry(_) => MessageType::GetSlashRegistry,
+            Message::SlashRegistry(_) => MessageType::SlashRegistry,
         }
     }
 
@@ -378,6 +394,10 @@ impl Deserialize for Message {
             MessageType::GetMacroBlocks => Message::GetMacroBlocks(Deserialize::deserialize(&mut crc32_reader)?),
             MessageType::GetEpochTransactions => Message::GetEpochTransactions(Deserialize::deserialize(&mut crc32_reader)?),
             MessageType::EpochTransactions => Message::EpochTransactions(Deserialize::deserialize(&mut crc32_reader)?),
+            MessageType::GetBlocksByNumber => Message::GetBlocksByNumber(Deserialize::deserialize(&mut crc32_reader)?),
+            MessageType::Blocks => Message::Blocks(Deserialize::deserialize(&mut crc32_reader)?),
+            MessageType::GetSlashRegistry => Message::GetSlashRegistry(Deserialize::deserialize(&mut crc32_reader)?),
+            MessageType::SlashRegistry => Message::SlashRegistry(Deserialize::deserialize(&mut crc32_reader)?),
         };
 
         // XXX Consume any leftover bytes in the message before computing the checksum.
@@ -450,6 +470,10 @@ impl Serialize for Message {
             Message::GetMacroBlocks(get_blocks_message) => get_blocks_message.serialize(&mut v)?,
             Message::GetEpochTransactions(get_epoch_transactions) => get_epoch_transactions.serialize(&mut v)?,
             Message::EpochTransactions(epoch_transactions) => epoch_transactions.serialize(&mut v)?,
+            Message::GetBlocksByNumber(get_blocks_by_number) => get_blocks_by_number.serialize(&mut v)?,
+            Message::Blocks(blocks) => blocks.serialize(&mut v)?,
+            Message::GetSlashRegistry(get_slash_registry) => get_slash_registry.serialize(&mut v)?,
+            Message::SlashRegistry(slash_registry) => slash_registry.serialize(&mut v)?,
         };
 
         // write checksum to placeholder
@@ -511,6 +535,10 @@ impl Serialize for Message {
             Message::GetMacroBlocks(get_blocks_message) => get_blocks_message.serialized_size(),
             Message::GetEpochTransactions(get_epoch_transactions) => get_epoch_transactions.serialized_size(),
             Message::EpochTransactions(epoch_transactions) => epoch_transactions.serialized_size(),
+            Message::GetBlocksByNumber(get_blocks_by_number) => get_blocks_by_number.serialized_size(),
+            Message::Blocks(blocks) => blocks.serialized_size(),
+            Message::GetSlashRegistry(get_slash_registry) => get_slash_registry.serialized_size(),
+            Message::SlashRegistry(slash_registry) => slash_registry.serialized_size(),
         };
         size
     }
@@ -563,6 +591,10 @@ pub struct MessageNotifier {
     pub get_macro_blocks: RwLock<PassThroughNotifier<'static, GetBlocksMessage>>,
     pub get_epoch_transactions: RwLock<PassThroughNotifier<'static, GetEpochTransactionsMessage>>,
     pub epoch_transactions: RwLock<PassThroughNotifier<'static, EpochTransactionsMessage>>,
+    pub get_blocks_by_number: RwLock<PassThroughNotifier<'static, GetBlocksByNumberMessage>>,
+    pub blocks: RwLock<PassThroughNotifier<'static, BlocksMessage>>,
+    pub get_slash_registry: RwLock<PassThroughNotifier<'static, GetSlashRegistryMessage>>,
+    pub slash_registry: RwLock<PassThroughNotifier<'static, SlashRegistryMessage>>,
 }
 
 impl MessageNotifier {
@@ -617,6 +649,10 @@ impl MessageNotifier {
             Message::GetMacroBlocks(msg) => self.get_macro_blocks.read().notify(*msg),
             Message::GetEpochTransactions(msg) => self.get_epoch_transactions.read().notify(*msg),
             Message::EpochTransactions(msg) => self.epoch_transactions.read().notify(*msg),
+            Message::GetBlocksByNumber(msg) => self.get_blocks_by_number.read().notify(*msg),
+            Message::Blocks(msg) => self.blocks.read().notify(*msg),
+            Message::GetSlashRegistry(msg) => self.get_slash_registry.read().notify(*msg),
+            Message::SlashRegistry(msg) => self.slash_registry.read().notify(*msg),
         }
     }
 }
@@ -1004,6 +1040,15 @@ pub struct GetAccountsProofMessage {
     pub addresses: Vec<Address>
 }
 
+impl GetAccountsProofMessage {
+    pub fn new(block_hash: Blake2bHash, addresses: Vec<Address>) -> Message {
+        Message::GetAccountsProof(Box::new(GetAccountsProofMessage {
+            block_hash,
+            addresses,
+        }))
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AccountsProofMessage {
     pub block_hash: Blake2bHash,
@@ -1210,3 +1255,61 @@ impl EpochTransactionsMessage {
         }))
     }
 }
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetBlocksByNumberMessage {
+    #[beserial(len_type(u16))]
+    pub block_numbers: Vec<u32>,
+}
+impl GetBlocksByNumberMessage {
+    pub const BLOCKS_MAX_COUNT: usize = 100;
+
+    pub fn new(block_numbers: Vec<u32>) -> Message {
+        Message::GetBlocksByNumber(Box::new(Self {
+            block_numbers,
+        }))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlocksMessage {
+    #[beserial(len_type(u16))]
+    pub blocks: Vec<BlockAlbatross>,
+}
+impl BlocksMessage {
+    pub fn new(blocks: Vec<BlockAlbatross>) -> Message {
+        Message::Blocks(Box::new(Self {
+            blocks,
+        }))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetSlashRegistryMessage {
+    pub block_hash: Blake2bHash,
+}
+impl GetSlashRegistryMessage {
+    pub fn new(block_hash: Blake2bHash) -> Message {
+        Message::GetSlashRegistry(Box::new(Self {
+            block_hash,
+        }))
+    }
+}
+
+/// The slash registry of an Albatross full node at a macro block. The state is serialized here,
+/// since the registry types live in the blockchain crate. An empty state means that the peer
+/// couldn't export the registry at the requested block.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SlashRegistryMessage {
+    pub block_hash: Blake2bHash,
+    #[beserial(len_type(u32))]
+    pub state: Vec<u8>,
+}
+impl SlashRegistryMessage {
+    pub fn new(block_hash: Blake2bHash, state: Vec<u8>) -> Message {
+        Message::SlashRegistry(Box::new(Self {
+            block_hash,
+            state,
+        }))
+    }
+}
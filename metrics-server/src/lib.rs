@@ -8,6 +8,11 @@ extern crate nimiq_mempool as mempool;
 extern crate nimiq_network as network;
 extern crate nimiq_block as block;
 extern crate nimiq_block_albatross as block_albatross;
+extern crate nimiq_utils as utils;
+#[cfg(feature = "validator")]
+extern crate nimiq_primitives as primitives;
+#[cfg(feature = "validator")]
+extern crate nimiq_validator as validator;
 
 use std::io;
 use std::io::Read;
@@ -23,11 +28,14 @@ use tokio::net::TcpListener;
 use tokio_tls::TlsAcceptor as TokioTlsAcceptor;
 
 use consensus::{Consensus, ConsensusProtocol};
+use utils::ip_allowlist::IpAllowlist;
 
 use crate::error::Error;
 use crate::metrics::mempool::MempoolMetrics;
 use crate::metrics::network::NetworkMetrics;
 pub use crate::metrics::chain::{AbstractChainMetrics, NimiqChainMetrics, AlbatrossChainMetrics};
+#[cfg(feature = "validator")]
+pub use crate::metrics::validator::ValidatorMetrics;
 
 macro_rules! attributes {
     // Empty attributes.
@@ -74,7 +82,7 @@ pub struct MetricsServer {
 }
 
 impl MetricsServer {
-    pub fn new<P, CM>(ip: IpAddr, port: u16, username: Option<String>, password: Option<String>, pkcs12_key_file: &str, pkcs12_passphrase: &str, consensus: Arc<Consensus<P>>) -> Result<MetricsServer, Error>
+    pub fn new<P, CM>(ip: IpAddr, port: u16, allowip: IpAllowlist, username: Option<String>, password: Option<String>, pkcs12_key_file: &str, pkcs12_passphrase: &str, consensus: Arc<Consensus<P>>, extra_metrics: Vec<Arc<dyn server::Metrics>>) -> Result<MetricsServer, Error>
         where P: ConsensusProtocol + 'static,
               CM: AbstractChainMetrics<P> + server::Metrics + 'static
     {
@@ -90,18 +98,34 @@ impl MetricsServer {
 
         let future = Box::new(Http::new()
             .serve_incoming(
-                srv.incoming().and_then(move |socket| {
-                    tls_cx
-                        .accept(socket)
-                        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
-                }),
+                srv.incoming()
+                    .filter(move |socket| {
+                        match socket.peer_addr() {
+                            Ok(address) if allowip.is_allowed(&address.ip()) => true,
+                            Ok(address) => {
+                                info!("Metrics server rejected connection from {}: IP not allowed", address);
+                                false
+                            },
+                            Err(e) => {
+                                warn!("Metrics server failed to get peer address: {}", e);
+                                false
+                            },
+                        }
+                    })
+                    .and_then(move |socket| {
+                        tls_cx
+                            .accept(socket)
+                            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+                    }),
                 move || {
+                    let mut metrics: Vec<Arc<dyn server::Metrics>> = vec![
+                        Arc::new(CM::new(consensus.blockchain.clone())),
+                        Arc::new(MempoolMetrics::new(consensus.mempool.clone())),
+                        Arc::new(NetworkMetrics::new(consensus.network.clone()))
+                    ];
+                    metrics.extend(extra_metrics.iter().cloned());
                     server::MetricsServer::new(
-                        vec![
-                            Arc::new(CM::new(consensus.blockchain.clone())),
-                            Arc::new(MempoolMetrics::new(consensus.mempool.clone())),
-                            Arc::new(NetworkMetrics::new(consensus.network.clone()))
-                        ],
+                        metrics,
                         attributes! { "peer" => consensus.network.network_config.peer_address() },
                         username.clone(),
                         password.clone())
@@ -0,0 +1,103 @@
+use std::io;
+use std::sync::Arc;
+
+use blockchain_albatross::Blockchain;
+use primitives::policy;
+use validator::metrics::ValidatorMetrics as ValidatorCounters;
+use validator::validator::{Validator, ValidatorStatus};
+
+use crate::server;
+use crate::server::SerializationType;
+
+pub struct ValidatorMetrics {
+    validator: Arc<Validator>,
+    blockchain: Arc<Blockchain>,
+}
+
+impl ValidatorMetrics {
+    pub fn new(validator: Arc<Validator>, blockchain: Arc<Blockchain>) -> Self {
+        ValidatorMetrics {
+            validator,
+            blockchain,
+        }
+    }
+
+    /// Serializes the counters kept by the validator itself.
+    fn serialize_counters(metrics: &ValidatorCounters, serializer: &mut server::MetricsSerializer<SerializationType>) -> Result<(), io::Error> {
+        serializer.metric_with_attributes("validator_micro_blocks", metrics.micro_block_produced_count(), attributes!{"action" => "produced"})?;
+        serializer.metric_with_attributes("validator_micro_blocks", metrics.micro_block_missed_count(), attributes!{"action" => "missed"})?;
+        serializer.metric_with_attributes("validator_view_changes", metrics.view_change_started_count(), attributes!{"action" => "started"})?;
+        serializer.metric_with_attributes("validator_view_changes", metrics.view_change_completed_count(), attributes!{"action" => "completed"})?;
+        serializer.metric("validator_fork_proofs", metrics.fork_proof_count())?;
+        serializer.metric_with_attributes("validator_pbft_votes", metrics.pbft_prepare_votes(), attributes!{"phase" => "prepare"})?;
+        serializer.metric_with_attributes("validator_pbft_votes", metrics.pbft_commit_votes(), attributes!{"phase" => "commit"})?;
+        serializer.metric("validator_pbft_rounds", metrics.pbft_completed_count())?;
+        serializer.metric("validator_pbft_latency_ms_sum", metrics.pbft_latency_ms_sum())?;
+        serializer.metric("validator_pbft_last_latency_ms", metrics.pbft_last_latency_ms())?;
+        Ok(())
+    }
+}
+
+impl server::Metrics for ValidatorMetrics {
+    fn metrics(&self, serializer: &mut server::MetricsSerializer<SerializationType>) -> Result<(), io::Error> {
+        let status = self.validator.status();
+        for &(other, str_status) in &[
+            (ValidatorStatus::None, "none"),
+            (ValidatorStatus::Synced, "synced"),
+            (ValidatorStatus::Potential, "potential"),
+            (ValidatorStatus::Active, "active"),
+        ] {
+            serializer.metric_with_attributes("validator_status", (status == other) as u8, attributes!{"status" => str_status})?;
+        }
+        serializer.metric("validator_slots", self.validator.num_slots().unwrap_or(0))?;
+        if let Some(pk_idx) = self.validator.pk_idx() {
+            serializer.metric("validator_index", pk_idx)?;
+        }
+
+        Self::serialize_counters(self.validator.metrics(), serializer)?;
+
+        serializer.metric_with_attributes("validator_slashed_slots", self.blockchain.current_slashed_set().len(), attributes!{"epoch" => "current"})?;
+        // There is no previous epoch during the first one.
+        if policy::epoch_at(self.blockchain.block_number()) > 0 {
+            serializer.metric_with_attributes("validator_slashed_slots", self.blockchain.last_slashed_set().len(), attributes!{"epoch" => "last"})?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::Chunk;
+
+    use crate::server::attributes::CachedAttributes;
+    use crate::server::MetricsSerializer;
+
+    use super::*;
+
+    #[test]
+    fn it_serializes_the_validator_counters() {
+        let metrics = ValidatorCounters::default();
+        metrics.note_micro_block_produced();
+        metrics.note_micro_block_missed();
+        metrics.note_micro_block_missed();
+        metrics.note_view_change_started();
+        metrics.note_pbft_votes(3, 2);
+
+        let mut serializer = MetricsSerializer::new(CachedAttributes::new(), Vec::new());
+        ValidatorMetrics::serialize_counters(&metrics, &mut serializer).unwrap();
+        let chunk: Chunk = serializer.into();
+        let output = String::from_utf8(chunk.to_vec()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines.len(), 10);
+        assert!(lines.contains(&"validator_micro_blocks{action=\"produced\"} 1"));
+        assert!(lines.contains(&"validator_micro_blocks{action=\"missed\"} 2"));
+        assert!(lines.contains(&"validator_view_changes{action=\"started\"} 1"));
+        assert!(lines.contains(&"validator_view_changes{action=\"completed\"} 0"));
+        assert!(lines.contains(&"validator_fork_proofs{} 0"));
+        assert!(lines.contains(&"validator_pbft_votes{phase=\"prepare\"} 3"));
+        assert!(lines.contains(&"validator_pbft_votes{phase=\"commit\"} 2"));
+        assert!(lines.contains(&"validator_pbft_rounds{} 0"));
+    }
+}
@@ -7,6 +7,8 @@ bitflags! {
         const NANO  = 0b0000_0001;
         const LIGHT = 0b0000_0010;
         const FULL  = 0b0000_0100;
+        // Node stores the full block history
+        const ARCHIVE = 0b0000_1000;
         // Node supports validator protocol
         const VALIDATOR  = 0b0100_0000_0000;
     }
@@ -25,6 +27,10 @@ impl ServiceFlags {
         self.contains(ServiceFlags::NANO)
     }
 
+    pub fn is_archive_node(self) -> bool {
+        self.contains(ServiceFlags::ARCHIVE)
+    }
+
     pub fn is_validator(self) -> bool { self.contains(ServiceFlags::VALIDATOR) }
 }
 
@@ -44,7 +50,14 @@ impl Services {
 
     pub fn full() -> Self {
         Services {
-            provided: ServiceFlags::FULL,
+            provided: ServiceFlags::FULL | ServiceFlags::ARCHIVE,
+            accepted: ServiceFlags::FULL,
+        }
+    }
+
+    pub fn light() -> Self {
+        Services {
+            provided: ServiceFlags::LIGHT,
             accepted: ServiceFlags::FULL,
         }
     }
@@ -44,6 +44,8 @@ pub enum CloseType {
     InvalidConnectionState = 40,
 
     GetEpochTransactionsTimeout = 41,
+    GetBlocksByNumberTimeout = 42,
+    GetSlashRegistryTimeout = 43,
 
     ManualPeerDisconnect = 90,
 
@@ -70,6 +72,7 @@ pub enum CloseType {
 
     UnexpectedEpochTransactions = 117,
     InvalidEpochTransactions = 118,
+    ReceivedInvalidAccountsTreeChunk = 119,
 
     RateLimitExceeded = 120,
 
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use beserial::{Deserialize, Serialize};
 use bls::bls12_381::CompressedPublicKey as BlsPublicKey;
 use keys::Address;
@@ -11,6 +13,12 @@ pub(super) struct InactiveStakeReceipt {
     retire_time: u32,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub(super) struct StakerRewardReceipt {
+    #[beserial(len_type(u32))]
+    rewards: BTreeMap<Address, Coin>,
+}
+
 /// Actions concerning a staker are:
 /// 1. Stake: Delegate stake from an outside address to a validator.
 /// 2. Retire: Remove stake from a validator and make it inactive
@@ -217,4 +225,38 @@ impl StakingContract {
         }
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Distributes the stakers' part of a validator's epoch reward.
+    /// The reward is split proportionally to the active stake and added to it.
+    pub(super) fn reward_stakers(&mut self, validator_key: &BlsPublicKey, reward: Coin) -> Result<StakerRewardReceipt, AccountError> {
+        let new_balance = Account::balance_add(self.balance, reward)?;
+
+        let mut entry = self.remove_validator(validator_key)
+            .ok_or(AccountError::InvalidInherent)?;
+        let rewards = entry.as_validator().split_stakers_reward(reward);
+        for (staker_address, &value) in rewards.iter() {
+            entry.try_add_stake(staker_address.clone(), value);
+        }
+        self.restore_validator(entry)?;
+
+        self.balance = new_balance;
+
+        Ok(StakerRewardReceipt { rewards })
+    }
+
+    /// Reverts the distribution of a stakers' reward.
+    pub(super) fn revert_reward_stakers(&mut self, validator_key: &BlsPublicKey, reward: Coin, receipt: StakerRewardReceipt) -> Result<(), AccountError> {
+        let new_balance = Account::balance_sub(self.balance, reward)?;
+
+        let mut entry = self.remove_validator(validator_key)
+            .ok_or(AccountError::InvalidInherent)?;
+        for (staker_address, &value) in receipt.rewards.iter() {
+            entry.try_sub_stake(staker_address, value, AccountError::InvalidReceipt);
+        }
+        self.restore_validator(entry)?;
+
+        self.balance = new_balance;
+
+        Ok(())
+    }
+}
@@ -7,6 +7,7 @@ use beserial::{Deserialize, Serialize};
 use bls::bls12_381::CompressedPublicKey as BlsPublicKey;
 use keys::Address;
 use primitives::coin::Coin;
+use primitives::policy;
 
 use crate::{Account, AccountError, StakingContract};
 use crate::staking_contract::{InactiveValidator, Validator};
@@ -21,6 +22,7 @@ pub(super) struct UnparkReceipt {
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub(super) struct UpdateValidatorReceipt {
     old_reward_address: Address,
+    old_pending_commission: Option<u16>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
@@ -32,6 +34,8 @@ pub(super) struct RetirementReceipt {
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub(super) struct DropValidatorReceipt {
     reward_address: Address,
+    commission: u16,
+    pending_commission: Option<u16>,
     #[beserial(len_type(u32))]
     retirement_by_address: BTreeMap<Address, RetirementReceipt>,
     retire_time: u32,
@@ -45,7 +49,8 @@ pub(super) struct InactiveValidatorReceipt {
 
 /// Actions concerning a validator are:
 /// 1. Create: Creates a validator entry.
-/// 2. Update: Updates reward address and key of the validator entry.
+/// 2. Update: Updates reward address, key and commission of the validator entry.
+///            A new commission only takes effect once the current epoch is finalized.
 /// 3. Retire: Inactivates a validator entry (also starts a cooldown period used for Drop).
 /// 4. Re-activate: Re-activates a validator entry.
 /// 5. Drop: Drops a validator entry (validator must have been inactive for the cooldown period).
@@ -75,17 +80,21 @@ impl StakingContract {
     /// Creates a new validator entry.
     /// The initial stake can only be retrieved by dropping the validator again.
     /// XXX This is public to fill the genesis staking contract
-    pub fn create_validator(&mut self, validator_key: BlsPublicKey, reward_address: Address, initial_stake: Coin) -> Result<(), AccountError> {
+    pub fn create_validator(&mut self, validator_key: BlsPublicKey, reward_address: Address, initial_stake: Coin, commission: u16) -> Result<(), AccountError> {
         if self.active_validators_by_key.contains_key(&validator_key)
             || self.inactive_validators_by_key.contains_key(&validator_key) {
             return Err(AccountError::InvalidForRecipient);
         }
 
+        if commission > policy::MAX_VALIDATOR_COMMISSION {
+            return Err(AccountError::InvalidForRecipient);
+        }
+
         self.balance = Account::balance_add(self.balance, initial_stake)?;
 
         // All checks passed, not allowed to fail from here on!
         let validator = Arc::new(
-            Validator::new(initial_stake, reward_address, validator_key.clone())
+            Validator::new(initial_stake, reward_address, validator_key.clone(), commission)
         );
 
         self.active_validators_sorted.insert(Arc::clone(&validator));
@@ -108,16 +117,18 @@ impl StakingContract {
 
     /// Update validator details.
     /// This can be used to update active and inactive validators.
-    pub(super) fn update_validator(&mut self, old_validator_key: &BlsPublicKey, new_validator_key: Option<BlsPublicKey>, new_reward_address: Option<Address>) -> Result<UpdateValidatorReceipt, AccountError> {
+    pub(super) fn update_validator(&mut self, old_validator_key: &BlsPublicKey, new_validator_key: Option<BlsPublicKey>, new_reward_address: Option<Address>, new_commission: Option<u16>) -> Result<UpdateValidatorReceipt, AccountError> {
         let mut entry = self.remove_validator(old_validator_key)
             .ok_or(AccountError::InvalidForRecipient)?;
 
         let old_reward_address = entry.as_validator().reward_address.clone();
-        entry.update_validator(new_reward_address, new_validator_key);
+        let old_pending_commission = entry.as_validator().pending_commission;
+        entry.update_validator(new_reward_address, new_validator_key, new_commission.or(old_pending_commission));
         self.restore_validator(entry)?;
 
         Ok(UpdateValidatorReceipt {
             old_reward_address,
+            old_pending_commission,
         })
     }
 
@@ -126,12 +137,31 @@ impl StakingContract {
         let mut entry = self.remove_validator(new_validator_key.as_ref().unwrap_or(&old_validator_key))
             .ok_or(AccountError::InvalidForRecipient)?;
 
-        entry.update_validator(Some(receipt.old_reward_address), Some(old_validator_key));
+        entry.update_validator(Some(receipt.old_reward_address), Some(old_validator_key), receipt.old_pending_commission);
         self.restore_validator(entry)?;
 
         Ok(())
     }
 
+    /// Applies the pending commissions of all validators.
+    /// This happens when an epoch is finalized and cannot be reverted.
+    pub(super) fn apply_pending_commissions(&mut self) -> Result<(), AccountError> {
+        let validator_keys: Vec<BlsPublicKey> = self.active_validators_by_key.values()
+            .chain(self.inactive_validators_by_key.values().map(|inactive_validator| &inactive_validator.validator))
+            .filter(|validator| validator.pending_commission.is_some())
+            .map(|validator| validator.validator_key.clone())
+            .collect();
+
+        for validator_key in validator_keys {
+            let mut entry = self.remove_validator(&validator_key)
+                .ok_or(AccountError::InvalidInherent)?;
+            entry.apply_pending_commission();
+            self.restore_validator(entry)?;
+        }
+
+        Ok(())
+    }
+
     /// Drops a validator entry.
     /// This can be used to drop inactive validators.
     /// The validator must have been inactive for at least one macro block.
@@ -169,6 +199,8 @@ impl StakingContract {
 
         Ok(DropValidatorReceipt {
             reward_address: validator.reward_address.clone(),
+            commission: validator.commission,
+            pending_commission: validator.pending_commission,
             retirement_by_address,
             retire_time: inactive_validator.retire_time,
             unpark_receipt,
@@ -195,6 +227,8 @@ impl StakingContract {
                 balance: total_value,
                 reward_address: receipt.reward_address,
                 validator_key,
+                commission: receipt.commission,
+                pending_commission: receipt.pending_commission,
                 active_stake_by_address: RwLock::new(active_stake_by_address),
             }),
             retire_time: receipt.retire_time,
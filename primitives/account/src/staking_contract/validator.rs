@@ -1,7 +1,7 @@
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::mem;
-use std::ops::{Deref, DerefMut};
+use std::ops::{Add, Deref, DerefMut};
 use std::sync::Arc;
 
 use parking_lot::RwLock;
@@ -10,6 +10,7 @@ use beserial::{Deserialize, DeserializeWithLength, ReadBytesExt, Serialize, Seri
 use bls::bls12_381::CompressedPublicKey as BlsPublicKey;
 use keys::Address;
 use primitives::coin::Coin;
+use primitives::policy;
 
 use crate::{Account, AccountError};
 
@@ -18,25 +19,47 @@ pub struct Validator {
     pub balance: Coin,
     pub reward_address: Address,
     pub validator_key: BlsPublicKey,
+    /// Commission on the stakers' rewards in basis points.
+    pub commission: u16,
+    /// Commission that takes effect when the current epoch is finalized.
+    pub pending_commission: Option<u16>,
     pub active_stake_by_address: RwLock<BTreeMap<Address, Coin>>,
 }
 
 impl Validator {
-    pub fn new(initial_balance: Coin, reward_address: Address, validator_key: BlsPublicKey) -> Self {
+    pub fn new(initial_balance: Coin, reward_address: Address, validator_key: BlsPublicKey, commission: u16) -> Self {
         Validator {
             balance: initial_balance,
             reward_address,
             validator_key,
+            commission,
+            pending_commission: None,
             active_stake_by_address: Default::default(),
         }
     }
 
-    pub fn update_validator(&self, new_reward_address: Option<Address>, new_validator_key: Option<BlsPublicKey>) -> Self {
+    /// Updates reward address and key and replaces the pending commission.
+    pub fn update_validator(&self, new_reward_address: Option<Address>, new_validator_key: Option<BlsPublicKey>, pending_commission: Option<u16>) -> Self {
         let active_stake_by_address = mem::take(self.active_stake_by_address.write().deref_mut());
         Validator {
             balance: self.balance,
             reward_address: new_reward_address.unwrap_or_else(|| self.reward_address.clone()),
             validator_key: new_validator_key.unwrap_or_else(|| self.validator_key.clone()),
+            commission: self.commission,
+            pending_commission,
+            active_stake_by_address: RwLock::new(active_stake_by_address),
+        }
+    }
+
+    /// Makes the pending commission the current one.
+    pub fn apply_pending_commission(&self) -> Self {
+        let active_stake_by_address = mem::take(self.active_stake_by_address.write().deref_mut());
+        Validator {
+            balance: self.balance,
+            reward_address: self.reward_address.clone(),
+            validator_key: self.validator_key.clone(),
+            commission: self.pending_commission.unwrap_or(self.commission),
+            pending_commission: None,
             active_stake_by_address: RwLock::new(active_stake_by_address),
         }
     }
@@ -47,6 +70,8 @@ impl Validator {
             balance,
             reward_address: self.reward_address.clone(),
             validator_key: self.validator_key.clone(),
+            commission: self.commission,
+            pending_commission: self.pending_commission,
             active_stake_by_address: RwLock::new(active_stake_by_address),
         }
     }
@@ -80,6 +105,61 @@ impl Validator {
         let validator = self.with_balance(new_balance);
         Ok(validator)
     }
+
+    /// Returns the sum of the active stake delegated to this validator.
+    pub fn total_active_stake(&self) -> Coin {
+        self.active_stake_by_address.read().values()
+            .cloned().fold(Coin::ZERO, Add::add)
+    }
+
+    /// Returns the part of a reward that belongs to the stakers of this validator.
+    /// It is proportional to the stakers' share of the validator's balance minus the validator's
+    /// commission and rounded down, i.e. the rounding remainder stays with the validator.
+    pub fn stakers_reward(&self, reward: Coin) -> Coin {
+        if self.balance.is_zero() {
+            return Coin::ZERO;
+        }
+
+        let total_stake = u128::from(u64::from(self.total_active_stake()));
+        let commission_base = u128::from(policy::VALIDATOR_COMMISSION_BASE);
+        let stakers_rate = commission_base - u128::from(self.commission.min(policy::VALIDATOR_COMMISSION_BASE));
+        let stakers_reward = u128::from(u64::from(reward)) * total_stake * stakers_rate
+            / (u128::from(u64::from(self.balance)) * commission_base);
+        Coin::from_u64_unchecked(stakers_reward as u64)
+    }
+
+    /// Splits a reward between the stakers of this validator proportionally to their active stake.
+    /// Each share is rounded down and the remaining units are handed out one by one in address order,
+    /// so the split is deterministic. Stakers with a zero share are omitted.
+    pub fn split_stakers_reward(&self, reward: Coin) -> BTreeMap<Address, Coin> {
+        let active_stake_by_address = self.active_stake_by_address.read();
+        let total_stake: u128 = active_stake_by_address.values()
+            .map(|&stake| u128::from(u64::from(stake)))
+            .sum();
+        if total_stake == 0 {
+            return BTreeMap::new();
+        }
+
+        let reward = u64::from(reward);
+        let mut remainder = reward;
+        let mut shares: Vec<(&Address, u64)> = active_stake_by_address.iter()
+            .map(|(staker_address, &stake)| {
+                let share = (u128::from(reward) * u128::from(u64::from(stake)) / total_stake) as u64;
+                remainder -= share;
+                (staker_address, share)
+            })
+            .collect();
+
+        // The remainder is smaller than the number of stakers.
+        for (_, share) in shares.iter_mut().take(remainder as usize) {
+            *share += 1;
+        }
+
+        shares.into_iter()
+            .filter(|&(_, share)| share > 0)
+            .map(|(staker_address, share)| (staker_address.clone(), Coin::from_u64_unchecked(share)))
+            .collect()
+    }
 }
 
 impl Serialize for Validator {
@@ -88,6 +168,8 @@ impl Serialize for Validator {
         size += Serialize::serialize(&self.balance, writer)?;
         size += Serialize::serialize(&self.reward_address, writer)?;
         size += Serialize::serialize(&self.validator_key, writer)?;
+        size += Serialize::serialize(&self.commission, writer)?;
+        size += Serialize::serialize(&self.pending_commission, writer)?;
         size += SerializeWithLength::serialize::<u32, _>(self.active_stake_by_address.read().deref(), writer)?;
         Ok(size)
     }
@@ -97,6 +179,8 @@ impl Serialize for Validator {
         size += Serialize::serialized_size(&self.balance);
         size += Serialize::serialized_size(&self.reward_address);
         size += Serialize::serialized_size(&self.validator_key);
+        size += Serialize::serialized_size(&self.commission);
+        size += Serialize::serialized_size(&self.pending_commission);
         size += SerializeWithLength::serialized_size::<u32>(self.active_stake_by_address.read().deref());
         size
     }
@@ -107,11 +191,15 @@ impl Deserialize for Validator {
         let balance = Deserialize::deserialize(reader)?;
         let reward_address = Deserialize::deserialize(reader)?;
         let validator_key = Deserialize::deserialize(reader)?;
+        let commission = Deserialize::deserialize(reader)?;
+        let pending_commission = Deserialize::deserialize(reader)?;
         let active_stake_by_address: BTreeMap<Address, Coin> = DeserializeWithLength::deserialize::<u32, _>(reader)?;
         Ok(Validator {
             balance,
             reward_address,
             validator_key,
+            commission,
+            pending_commission,
             active_stake_by_address: RwLock::new(active_stake_by_address),
         })
     }
@@ -146,6 +234,8 @@ impl Clone for Validator {
             balance: self.balance,
             reward_address: self.reward_address.clone(),
             validator_key: self.validator_key.clone(),
+            commission: self.commission,
+            pending_commission: self.pending_commission,
             active_stake_by_address: RwLock::new(self.active_stake_by_address.read().clone()),
         }
     }
@@ -215,9 +305,15 @@ impl ValidatorEntry {
         }
     }
 
-    pub fn update_validator(&mut self, new_reward_address: Option<Address>, new_validator_key: Option<BlsPublicKey>) {
+    pub fn update_validator(&mut self, new_reward_address: Option<Address>, new_validator_key: Option<BlsPublicKey>, pending_commission: Option<u16>) {
+        self.replace(Ok(Arc::new(
+            self.as_validator().update_validator(new_reward_address, new_validator_key, pending_commission)
+        )))
+    }
+
+    pub fn apply_pending_commission(&mut self) {
         self.replace(Ok(Arc::new(
-            self.as_validator().update_validator(new_reward_address, new_validator_key)
+            self.as_validator().apply_pending_commission()
         )))
     }
 
@@ -13,13 +13,14 @@ use nimiq_bls::SecureGenerate;
 use nimiq_keys::{Address, KeyPair, PrivateKey};
 use nimiq_primitives::coin::Coin;
 use nimiq_primitives::networks::NetworkId;
+use nimiq_primitives::policy;
 use nimiq_primitives::slot::{SlotCollection, SlotIndex};
 use nimiq_transaction::{SignatureProof, Transaction, TransactionError};
 use nimiq_transaction::account::AccountTransactionVerification;
 use nimiq_transaction::account::staking_contract::{IncomingStakingTransactionData, OutgoingStakingTransactionProof, SelfStakingTransactionData};
 
 const CONTRACT_1: &str = "00000000000000000000000000000000000000000000000000000000";
-const CONTRACT_2: &str = "0000000023c34600000000010000000023c3460003030303030303030303030303030303030303038dee007dd1af35c79b6abb901a787f1ee97d89cd4b6390987c9f6e2b9a135cdfb075cfc78d0cca37e2dd0eb37eac636d0d8f50c868a23eaca794f6af35213426d284dd6188b4679ab3881e80bcd318969959e60689ca40d1f41e02cd33d81609000000020202020202020202020202020202020202020202000000000bebc2005e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e0000000005f5e10000000000000000000000000000000000";
+const CONTRACT_2: &str = "0000000023c34600000000010000000023c3460003030303030303030303030303030303030303038dee007dd1af35c79b6abb901a787f1ee97d89cd4b6390987c9f6e2b9a135cdfb075cfc78d0cca37e2dd0eb37eac636d0d8f50c868a23eaca794f6af35213426d284dd6188b4679ab3881e80bcd318969959e60689ca40d1f41e02cd33d81609000000000000020202020202020202020202020202020202020202000000000bebc2005e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e5e0000000005f5e10000000000000000000000000000000000";
 const VALIDATOR_KEY: &str = "8dee007dd1af35c79b6abb901a787f1ee97d89cd4b6390987c9f6e2b9a135cdfb075cfc78d0cca37e2dd0eb37eac636d0d8f50c868a23eaca794f6af35213426d284dd6188b4679ab3881e80bcd318969959e60689ca40d1f41e02cd33d81609";
 const VALIDATOR_SECRET_KEY: &str = "49ea68eb6b8afdf4ca4d4c0a0b295c76ca85225293693bc30e755476492b707f";
 const STAKER_ADDRESS: &str = "9cd82948650d902d95d52ea2ec91eae6deb0c9fe";
@@ -39,7 +40,7 @@ const STAKER_PRIVATE_KEY: &str = "b410a7a583cbc13ef4f1cbddace30928bcb4f9c1372241
 //        previous_epoch_parking: Default::default(),
 //        inactive_stake_by_address: Default::default()
 //    };
-//    contract.create_validator(key_pair.public.compress(), Address::from([3u8; 20]), 300_000_000.try_into().unwrap());
+//    contract.create_validator(key_pair.public.compress(), Address::from([3u8; 20]), 300_000_000.try_into().unwrap(), 0);
 //    contract.stake(Address::from([2u8; 20]), 200_000_000.try_into().unwrap(), &key_pair.public.compress());
 //    contract.stake(Address::from([0x5eu8; 20]), 100_000_000.try_into().unwrap(), &key_pair.public.compress());
 //    assert_eq!(&hex::encode(contract.serialize_to_vec()), "");
@@ -117,6 +118,7 @@ fn it_can_verify_validator_and_staking_transaction() {
         validator_key: validator_key.clone(),
         proof_of_knowledge: keypair.sign(&validator_key.serialize_to_vec()).compress(),
         reward_address: Address::from([3u8; 20]),
+        commission: 0,
     }, 100_000_000);
 
     // Valid
@@ -133,6 +135,16 @@ fn it_can_verify_validator_and_staking_transaction() {
         validator_key: validator_key.clone(),
         proof_of_knowledge: invalid_pok.compress(),
         reward_address: Address::from([3u8; 20]),
+        commission: 0,
+    }, 100_000_000);
+    assert_eq!(AccountType::verify_incoming_transaction(&tx), Err(TransactionError::InvalidData));
+
+    // Commission above maximum
+    let tx = make_incoming_transaction(IncomingStakingTransactionData::CreateValidator {
+        validator_key: validator_key.clone(),
+        proof_of_knowledge: keypair.sign(&validator_key.serialize_to_vec()).compress(),
+        reward_address: Address::from([3u8; 20]),
+        commission: policy::MAX_VALIDATOR_COMMISSION + 1,
     }, 100_000_000);
     assert_eq!(AccountType::verify_incoming_transaction(&tx), Err(TransactionError::InvalidData));
 
@@ -164,6 +176,7 @@ fn it_can_apply_validator_and_staking_transaction() {
         validator_key: bls_pair.public.compress(),
         proof_of_knowledge: proof_of_knowledge.clone(),
         reward_address: Default::default(),
+        commission: 0,
     }, 150_000_000);
     assert_eq!(StakingContract::check_incoming_transaction(&tx_1, 2), Ok(()));
     assert_eq!(contract.commit_incoming_transaction(&tx_1, 2), Ok(None));
@@ -489,8 +502,202 @@ fn it_can_verify_inherent() {
         value: Coin::ZERO,
         data: Vec::new(),
     };
-    assert_eq!(contract.check_inherent(&inherent_1, 0), Err(AccountError::InvalidForTarget));
-    assert_eq!(contract.commit_inherent(&inherent_1, 0), Err(AccountError::InvalidForTarget));
+    assert_eq!(contract.check_inherent(&inherent_1, 0), Err(AccountError::InvalidInherent));
+    assert_eq!(contract.commit_inherent(&inherent_1, 0), Err(AccountError::InvalidInherent));
+
+    // Reward inherent for stakers
+    let inherent_2 = Inherent {
+        ty: InherentType::Reward,
+        target: Address::from([0u8; 20]),
+        value: Coin::from_u64_unchecked(10),
+        data: bls_pair.public.compress().serialize_to_vec(),
+    };
+    assert_eq!(contract.check_inherent(&inherent_2, 0), Ok(()));
+}
+
+#[test]
+fn it_rejects_invalid_reward_inherents() {
+    let bls_pair = bls_key_pair();
+    let key_pair = ed25519_key_pair();
+    let mut contract = make_sample_contract(&key_pair, &bls_pair);
+
+    // Invalid inherent
+    let mut inherent = Inherent {
+        ty: InherentType::Reward,
+        target: Default::default(),
+        value: Coin::from_u64_unchecked(10),
+        data: vec![1],
+    };
+
+    // Invalid data.
+    assert_eq!(contract.check_inherent(&inherent, 0), Err(AccountError::InvalidInherent));
+    assert_eq!(contract.commit_inherent(&inherent, 0), Err(AccountError::InvalidInherent));
+
+    // Unknown validator.
+    inherent.data = BlsKeyPair::generate(&mut thread_rng()).public.compress().serialize_to_vec();
+    assert_eq!(contract.check_inherent(&inherent, 0), Err(AccountError::InvalidInherent));
+    assert_eq!(contract.commit_inherent(&inherent, 0), Err(AccountError::InvalidInherent));
+
+    // Validator without stakers.
+    let validator_key = bls_pair.public.compress();
+    let mut contract = make_empty_contract();
+    contract.create_validator(validator_key.clone(), Address::from(&key_pair), Coin::from_u64_unchecked(150_000_000), 0).unwrap();
+    inherent.data = validator_key.serialize_to_vec();
+    assert_eq!(contract.check_inherent(&inherent, 0), Err(AccountError::InvalidInherent));
+    assert_eq!(contract.commit_inherent(&inherent, 0), Err(AccountError::InvalidInherent));
+    assert_eq!(contract.balance, Coin::from_u64_unchecked(150_000_000));
+}
+
+#[test]
+fn it_can_split_rewards_between_stakers() {
+    let bls_pair = bls_key_pair();
+    let key_pair = ed25519_key_pair();
+    let mut contract = make_sample_contract(&key_pair, &bls_pair);
+    let validator_key = bls_pair.public.compress();
+    let staker_address = Address::from(&key_pair);
+    let other_staker_address = Address::from([0u8; 20]);
+    contract.stake(other_staker_address.clone(), Coin::from_u64_unchecked(50_000_000), &validator_key).unwrap();
+
+    let validator = contract.get_validator(&validator_key).unwrap();
+    assert_eq!(validator.balance, Coin::from_u64_unchecked(350_000_000));
+    assert_eq!(validator.total_active_stake(), Coin::from_u64_unchecked(200_000_000));
+
+    // Stakers own 200/350 of the validator's balance, rounded down.
+    assert_eq!(validator.stakers_reward(Coin::from_u64_unchecked(350)), Coin::from_u64_unchecked(200));
+    assert_eq!(validator.stakers_reward(Coin::from_u64_unchecked(10)), Coin::from_u64_unchecked(5));
+    assert_eq!(validator.stakers_reward(Coin::ZERO), Coin::ZERO);
+
+    // Shares are proportional to the stake.
+    let rewards = validator.split_stakers_reward(Coin::from_u64_unchecked(200));
+    assert_eq!(rewards.len(), 2);
+    assert_eq!(rewards[&staker_address], Coin::from_u64_unchecked(150));
+    assert_eq!(rewards[&other_staker_address], Coin::from_u64_unchecked(50));
+
+    // The remainder is distributed deterministically in address order.
+    let rewards = validator.split_stakers_reward(Coin::from_u64_unchecked(5));
+    assert_eq!(rewards.len(), 2);
+    assert_eq!(rewards[&staker_address], Coin::from_u64_unchecked(3));
+    assert_eq!(rewards[&other_staker_address], Coin::from_u64_unchecked(2));
+    assert_eq!(validator.split_stakers_reward(Coin::from_u64_unchecked(5)), rewards);
+
+    // Stakers with a zero share are omitted.
+    let rewards = validator.split_stakers_reward(Coin::from_u64_unchecked(1));
+    assert_eq!(rewards.len(), 1);
+    assert_eq!(rewards[&other_staker_address], Coin::from_u64_unchecked(1));
+}
+
+#[test]
+fn it_can_apply_and_revert_reward_inherent() {
+    let bls_pair = bls_key_pair();
+    let key_pair = ed25519_key_pair();
+    let mut contract = make_sample_contract(&key_pair, &bls_pair);
+    let validator_key = bls_pair.public.compress();
+    let staker_address = Address::from(&key_pair);
+    let other_staker_address = Address::from([0u8; 20]);
+    contract.stake(other_staker_address.clone(), Coin::from_u64_unchecked(50_000_000), &validator_key).unwrap();
+
+    let reward = Inherent {
+        ty: InherentType::Reward,
+        target: Default::default(),
+        value: Coin::from_u64_unchecked(5),
+        data: validator_key.serialize_to_vec(),
+    };
+    assert_eq!(contract.check_inherent(&reward, 0), Ok(()));
+    let receipt = contract.commit_inherent(&reward, 0).unwrap();
+    assert!(receipt.is_some());
+    assert_eq!(contract.balance, Coin::from_u64_unchecked(350_000_005));
+    assert_eq!(contract.get_validator(&validator_key).unwrap().balance, Coin::from_u64_unchecked(350_000_005));
+    assert_eq!(contract.get_active_stake(&validator_key, &staker_address), Some(Coin::from_u64_unchecked(150_000_003)));
+    assert_eq!(contract.get_active_stake(&validator_key, &other_staker_address), Some(Coin::from_u64_unchecked(50_000_002)));
+    assert_eq!(contract.active_validators_sorted.len(), 1);
+
+    // Revert reward
+    assert_eq!(contract.revert_inherent(&reward, 0, None), Err(AccountError::InvalidReceipt));
+    assert_eq!(contract.revert_inherent(&reward, 0, receipt.as_ref()), Ok(()));
+    assert_eq!(contract.balance, Coin::from_u64_unchecked(350_000_000));
+    assert_eq!(contract.get_validator(&validator_key).unwrap().balance, Coin::from_u64_unchecked(350_000_000));
+    assert_eq!(contract.get_active_stake(&validator_key, &staker_address), Some(Coin::from_u64_unchecked(150_000_000)));
+    assert_eq!(contract.get_active_stake(&validator_key, &other_staker_address), Some(Coin::from_u64_unchecked(50_000_000)));
+
+    // Rewards are also paid to stakers of inactive validators.
+    let retire = make_signed_incoming_transaction(IncomingStakingTransactionData::RetireValidator {
+        validator_key: validator_key.clone(),
+        signature: Default::default(),
+    }, 0, &bls_pair);
+    assert_eq!(contract.commit_incoming_transaction(&retire, 2), Ok(None));
+    assert_eq!(contract.inactive_validators_by_key.len(), 1);
+    let receipt = contract.commit_inherent(&reward, 3).unwrap();
+    assert_eq!(contract.get_validator(&validator_key).unwrap().balance, Coin::from_u64_unchecked(350_000_005));
+    assert_eq!(contract.revert_inherent(&reward, 3, receipt.as_ref()), Ok(()));
+    assert_eq!(contract.get_validator(&validator_key).unwrap().balance, Coin::from_u64_unchecked(350_000_000));
+}
+
+#[test]
+fn it_can_apply_validator_commission() {
+    let bls_pair = bls_key_pair();
+    let key_pair = ed25519_key_pair();
+    let validator_key = bls_pair.public.compress();
+    let mut contract = make_empty_contract();
+    contract.create_validator(validator_key.clone(), Address::from(&key_pair), Coin::from_u64_unchecked(150_000_000), 1_000).unwrap();
+    contract.stake(Address::from(&key_pair), Coin::from_u64_unchecked(150_000_000), &validator_key).unwrap();
+
+    // Stakers own half of the validator's balance and pay a commission of 10%.
+    let validator = contract.get_validator(&validator_key).unwrap();
+    assert_eq!(validator.commission, 1_000);
+    assert_eq!(validator.pending_commission, None);
+    assert_eq!(validator.stakers_reward(Coin::from_u64_unchecked(1_000)), Coin::from_u64_unchecked(450));
+
+    // Commissions above the maximum are rejected.
+    let tx = make_signed_incoming_transaction(IncomingStakingTransactionData::UpdateValidator {
+        old_validator_key: validator_key.clone(),
+        new_validator_key: None,
+        new_proof_of_knowledge: None,
+        new_reward_address: None,
+        new_commission: Some(policy::MAX_VALIDATOR_COMMISSION + 1),
+        signature: Default::default(),
+    }, 0, &bls_pair);
+    assert_eq!(AccountType::verify_incoming_transaction(&tx), Err(TransactionError::InvalidData));
+
+    // Update the commission.
+    let tx = make_signed_incoming_transaction(IncomingStakingTransactionData::UpdateValidator {
+        old_validator_key: validator_key.clone(),
+        new_validator_key: None,
+        new_proof_of_knowledge: None,
+        new_reward_address: None,
+        new_commission: Some(2_000),
+        signature: Default::default(),
+    }, 0, &bls_pair);
+    assert_eq!(AccountType::verify_incoming_transaction(&tx), Ok(()));
+    assert_eq!(StakingContract::check_incoming_transaction(&tx, 2), Ok(()));
+    let receipt = contract.commit_incoming_transaction(&tx, 2).unwrap();
+    assert!(receipt.is_some());
+
+    // The new commission is pending until the epoch is finalized.
+    let validator = contract.get_validator(&validator_key).unwrap();
+    assert_eq!(validator.commission, 1_000);
+    assert_eq!(validator.pending_commission, Some(2_000));
+    assert_eq!(validator.stakers_reward(Coin::from_u64_unchecked(1_000)), Coin::from_u64_unchecked(450));
+
+    // Revert update.
+    let mut contract_copy = contract.clone();
+    assert_eq!(contract_copy.revert_incoming_transaction(&tx, 2, receipt.as_ref()), Ok(()));
+    let validator = contract_copy.get_validator(&validator_key).unwrap();
+    assert_eq!(validator.commission, 1_000);
+    assert_eq!(validator.pending_commission, None);
+
+    // Finalize epoch.
+    let finalize = Inherent {
+        ty: InherentType::FinalizeEpoch,
+        target: Default::default(),
+        value: Coin::ZERO,
+        data: vec![]
+    };
+    assert_eq!(contract.commit_inherent(&finalize, 3), Ok(None));
+    let validator = contract.get_validator(&validator_key).unwrap();
+    assert_eq!(validator.commission, 2_000);
+    assert_eq!(validator.pending_commission, None);
+    assert_eq!(validator.stakers_reward(Coin::from_u64_unchecked(1_000)), Coin::from_u64_unchecked(400));
+    assert_eq!(contract.active_validators_sorted.len(), 1);
 }
 
 #[test]
@@ -1030,7 +1237,8 @@ fn it_can_build_a_validator_set() {
     contract.create_validator(
         validator1.clone(),
         staker1.clone(),
-        Coin::from_u64_unchecked(100_000_000)
+        Coin::from_u64_unchecked(100_000_000),
+        0
     ).unwrap();
 
     let slots = contract.select_validators(&seed.compress().into());
@@ -1057,12 +1265,14 @@ fn it_can_build_a_validator_set() {
     contract.create_validator(
         validator2.clone(),
         staker2.clone(),
-        Coin::from_u64_unchecked(100_000_000)
+        Coin::from_u64_unchecked(100_000_000),
+        0
     ).unwrap();
     contract.create_validator(
         validator3.clone(),
         staker3.clone(),
-        Coin::from_u64_unchecked(100_000_000)
+        Coin::from_u64_unchecked(100_000_000),
+        0
     ).unwrap();
 
     contract.stake(
@@ -1129,6 +1339,7 @@ fn it_can_apply_validator_signalling() {
         validator_key: validator_key.clone(),
         proof_of_knowledge: bls_pair.sign(&validator_key.serialize_to_vec()).compress(),
         reward_address: Address::from([3u8; 20]),
+        commission: 0,
     }, 100_000_000);
 
     assert_eq!(AccountType::verify_incoming_transaction(&tx), Ok(()));
@@ -1174,7 +1385,8 @@ fn it_can_apply_validator_signalling() {
         new_validator_key: Some(validator_key2.clone()),
         new_proof_of_knowledge: Some(bls_pair2.sign(&validator_key.serialize_to_vec()).compress()),
         signature: Default::default(),
-        new_reward_address: None
+        new_reward_address: None,
+        new_commission: None,
     }, 0, &bls_pair);
     assert_eq!(AccountType::verify_incoming_transaction(&tx), Err(TransactionError::InvalidData));
 
@@ -1183,7 +1395,8 @@ fn it_can_apply_validator_signalling() {
         new_validator_key: Some(validator_key2.clone()),
         new_proof_of_knowledge: Some(bls_pair2.sign(&validator_key2.serialize_to_vec()).compress()),
         signature: Default::default(),
-        new_reward_address: None
+        new_reward_address: None,
+        new_commission: None,
     }, 0, &bls_pair);
     assert_eq!(AccountType::verify_incoming_transaction(&tx), Ok(()));
     assert_eq!(StakingContract::check_incoming_transaction(&tx, 3), Ok(()));
@@ -1267,7 +1480,8 @@ fn it_can_apply_validator_signalling() {
         new_validator_key: None,
         new_proof_of_knowledge: None,
         signature: Default::default(),
-        new_reward_address: Some(Address::from([4u8; 20]))
+        new_reward_address: Some(Address::from([4u8; 20])),
+        new_commission: None,
     }, 0, &bls_pair);
     assert_eq!(AccountType::verify_incoming_transaction(&tx), Ok(()));
     assert_eq!(StakingContract::check_incoming_transaction(&tx, 3), Ok(()));
@@ -1446,7 +1660,8 @@ fn it_can_manage_stake() {
     contract.create_validator(
         validator_key.clone(),
         Address::from([3u8; 20]),
-        Coin::from_u64_unchecked(100_000_000)
+        Coin::from_u64_unchecked(100_000_000),
+        0
     ).unwrap();
     let contract_backup = contract.clone();
 
@@ -1626,7 +1841,8 @@ fn it_can_manage_stake() {
     contract.create_validator(
         validator_key2.clone(),
         Address::from([3u8;20]),
-        Coin::from_u64_unchecked(100_000_000)
+        Coin::from_u64_unchecked(100_000_000),
+        0
     ).unwrap();
 
     // Re-activate stake to new validator.
@@ -2032,7 +2248,8 @@ fn it_can_manage_stake() {
     contract.create_validator(
         validator_key2.clone(),
         Address::from([3u8;20]),
-        Coin::from_u64_unchecked(100_000_000)
+        Coin::from_u64_unchecked(100_000_000),
+        0
     ).unwrap();
 
     // Re-activate stake to new validator.
@@ -2259,7 +2476,8 @@ fn make_sample_contract(key_pair: &KeyPair, bls_pair: &BlsKeyPair) -> StakingCon
     contract.create_validator(
         bls_pair.public.compress(),
         Address::from(key_pair),
-        Coin::from_u64_unchecked(150_000_000)
+        Coin::from_u64_unchecked(150_000_000),
+        0
     ).unwrap();
     contract.stake(
         Address::from(key_pair),
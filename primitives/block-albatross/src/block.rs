@@ -330,6 +330,9 @@ impl block_base::Block for Block {
     type Header = BlockHeader;
     type Error = BlockError;
 
+    // Only micro blocks contain transactions.
+    const MAX_TRANSACTIONS_SIZE: usize = MicroBlock::max_transactions_size(0, 0);
+
     fn hash(&self) -> Blake2bHash {
         self.hash()
     }
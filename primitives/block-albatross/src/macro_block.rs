@@ -76,6 +76,12 @@ impl MacroExtrinsics {
     }
 }
 
+impl MacroBlock {
+    /// Upper bound for the serialized size of a macro block, which is dominated by the validator
+    /// slots in its header.
+    pub const MAX_SIZE: usize = 100_000; // 100 KB
+}
+
 impl signed::Message for MacroHeader {
     const PREFIX: u8 = signed::PREFIX_PBFT_PROPOSAL;
 }
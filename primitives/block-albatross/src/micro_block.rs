@@ -55,6 +55,14 @@ pub struct MicroExtrinsics {
 impl MicroBlock {
     pub const MAX_SIZE: usize = 100_000; // 100 KB
 
+    /// Number of bytes that are available for transactions in a micro block with
+    /// `num_fork_proofs` fork proofs and `extra_data_size` bytes of extra data.
+    pub const fn max_transactions_size(num_fork_proofs: usize, extra_data_size: usize) -> usize {
+        Self::MAX_SIZE
+            - MicroHeader::SIZE
+            - MicroExtrinsics::get_metadata_size(num_fork_proofs, extra_data_size)
+    }
+
     pub fn verify(&self, network_id: NetworkId) -> Result<(), BlockError> {
         if let Some(ref extrinsics) = self.extrinsics {
             extrinsics.verify(self.header.block_number, network_id)?;
@@ -133,7 +141,7 @@ impl MicroExtrinsics {
         Ok(())
     }
 
-    pub fn get_metadata_size(num_fork_proofs: usize, extra_data_size: usize) -> usize {
+    pub const fn get_metadata_size(num_fork_proofs: usize, extra_data_size: usize) -> usize {
         /*fork_proofs size*/ 2
             + num_fork_proofs * ForkProof::SIZE
             + /*extra_data size*/ 1
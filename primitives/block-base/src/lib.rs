@@ -13,6 +13,9 @@ pub trait Block: Serialize + Deserialize + Send + Sync {
     type Header: BlockHeader;
     type Error: BlockError;
 
+    /// Number of bytes that are available for the transactions of a block
+    const MAX_TRANSACTIONS_SIZE: usize;
+
     fn hash(&self) -> Blake2bHash;
 
     fn prev_hash(&self) -> &Blake2bHash;
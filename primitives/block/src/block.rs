@@ -153,6 +153,9 @@ impl block_base::Block for Block {
     type Header = BlockHeader;
     type Error = BlockError;
 
+    // The interlink is ignored, it only takes up a few bytes.
+    const MAX_TRANSACTIONS_SIZE: usize = Block::MAX_SIZE - BlockHeader::SIZE - BlockBody::get_metadata_size(0);
+
     fn hash(&self) -> Blake2bHash {
         self.header.hash()
     }
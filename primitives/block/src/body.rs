@@ -132,7 +132,7 @@ impl BlockBody {
         }
     }
 
-    pub fn get_metadata_size(extra_data_size: usize) -> usize {
+    pub const fn get_metadata_size(extra_data_size: usize) -> usize {
         Address::SIZE
             + /*extra_data size*/ 1
             + extra_data_size
@@ -130,6 +130,12 @@ pub const MIN_STAKE: u64 = 1;
 /// Minimum initial_stake for validators in units
 pub const MIN_VALIDATOR_STAKE: u64 = 100_000_000;
 
+/// Denominator of validator commission rates, i.e. commissions are given in basis points
+pub const VALIDATOR_COMMISSION_BASE: u16 = 10_000;
+
+/// Maximum commission a validator can charge its stakers in basis points
+pub const MAX_VALIDATOR_COMMISSION: u16 = 5_000;
+
 /// Returns the height of the next macro block after given `block_height`
 #[inline]
 pub fn macro_block_after(block_number: u32) -> u32 {
@@ -1,6 +1,7 @@
 use beserial::{Deserialize, ReadBytesExt, Serialize, SerializingError, WriteBytesExt};
 use bls::bls12_381::{CompressedPublicKey as BlsPublicKey, CompressedSignature as BlsSignature};
 use keys::Address;
+use primitives::policy;
 
 use crate::{AccountType, Transaction, TransactionError};
 use crate::SignatureProof;
@@ -58,12 +59,16 @@ pub enum IncomingStakingTransactionData {
         validator_key: BlsPublicKey,
         proof_of_knowledge: BlsSignature,
         reward_address: Address,
+        // Commission on the stakers' rewards in basis points.
+        commission: u16,
     },
     UpdateValidator {
         old_validator_key: BlsPublicKey,
         new_validator_key: Option<BlsPublicKey>,
         new_proof_of_knowledge: Option<BlsSignature>,
         new_reward_address: Option<Address>,
+        // A new commission only takes effect in the following epoch.
+        new_commission: Option<u16>,
         signature: BlsSignature,
     },
     RetireValidator {
@@ -101,22 +106,29 @@ impl IncomingStakingTransactionData {
     pub fn verify(&self, transaction: &Transaction) -> Result<(), TransactionError> {
         match self {
             IncomingStakingTransactionData::CreateValidator {
-                validator_key, proof_of_knowledge, ..
+                validator_key, proof_of_knowledge, commission, ..
             } => {
                 // Check proof of knowledge.
                 verify_proof_of_knowledge(validator_key, proof_of_knowledge)?;
+
+                // Check commission.
+                verify_commission(*commission)?;
             },
             IncomingStakingTransactionData::UpdateValidator {
-                old_validator_key, new_validator_key, new_proof_of_knowledge, signature, new_reward_address
+                old_validator_key, new_validator_key, new_proof_of_knowledge, signature, new_reward_address, new_commission
             } => {
                 // Check signature and proof of knowledge.
                 verify_transaction_signature(transaction, old_validator_key, signature)?;
 
                 // Do not allow updates without any effect.
-                if new_validator_key.is_none() && new_reward_address.is_none() {
+                if new_validator_key.is_none() && new_reward_address.is_none() && new_commission.is_none() {
                     return Err(TransactionError::InvalidData);
                 }
 
+                if let Some(new_commission) = new_commission {
+                    verify_commission(*new_commission)?;
+                }
+
                 if let (Some(new_validator_key), Some(new_proof_of_knowledge)) = (new_validator_key, new_proof_of_knowledge) {
                     verify_proof_of_knowledge(new_validator_key, new_proof_of_knowledge)?;
                 }
@@ -186,20 +198,22 @@ impl Serialize for IncomingStakingTransactionData {
         let mut size = 0;
         match self {
             IncomingStakingTransactionData::CreateValidator {
-                validator_key, proof_of_knowledge, reward_address
+                validator_key, proof_of_knowledge, reward_address, commission
             } => {
                 size += Serialize::serialize(&IncomingStakingTransactionType::CreateValidator, writer)?;
                 size += Serialize::serialize(validator_key, writer)?;
                 size += Serialize::serialize(proof_of_knowledge, writer)?;
                 size += Serialize::serialize(reward_address, writer)?;
+                size += Serialize::serialize(commission, writer)?;
             },
             IncomingStakingTransactionData::UpdateValidator {
-                old_validator_key, new_validator_key, new_proof_of_knowledge, new_reward_address, signature
+                old_validator_key, new_validator_key, new_proof_of_knowledge, new_reward_address, new_commission, signature
             } => {
                 size += Serialize::serialize(&IncomingStakingTransactionType::UpdateValidator, writer)?;
                 size += Serialize::serialize(old_validator_key, writer)?;
                 size += Serialize::serialize(&new_validator_key.is_some(), writer)?;
                 size += Serialize::serialize(&new_reward_address.is_some(), writer)?;
+                size += Serialize::serialize(&new_commission.is_some(), writer)?;
                 if let (Some(new_validator_key), Some(new_proof_of_knowledge)) = (new_validator_key, new_proof_of_knowledge) {
                     size += Serialize::serialize(new_validator_key, writer)?;
                     size += Serialize::serialize(new_proof_of_knowledge, writer)?;
@@ -207,6 +221,9 @@ impl Serialize for IncomingStakingTransactionData {
                 if let Some(new_reward_address) = new_reward_address {
                     size += Serialize::serialize(new_reward_address, writer)?;
                 }
+                if let Some(new_commission) = new_commission {
+                    size += Serialize::serialize(new_commission, writer)?;
+                }
                 size += Serialize::serialize(signature, writer)?;
             },
             IncomingStakingTransactionData::RetireValidator {
@@ -245,20 +262,22 @@ impl Serialize for IncomingStakingTransactionData {
         let mut size = 0;
         match self {
             IncomingStakingTransactionData::CreateValidator {
-                validator_key, proof_of_knowledge, reward_address
+                validator_key, proof_of_knowledge, reward_address, commission
             } => {
                 size += Serialize::serialized_size(&IncomingStakingTransactionType::CreateValidator);
                 size += Serialize::serialized_size(validator_key);
                 size += Serialize::serialized_size(proof_of_knowledge);
                 size += Serialize::serialized_size(reward_address);
+                size += Serialize::serialized_size(commission);
             },
             IncomingStakingTransactionData::UpdateValidator {
-                old_validator_key, new_validator_key, new_proof_of_knowledge, new_reward_address, signature
+                old_validator_key, new_validator_key, new_proof_of_knowledge, new_reward_address, new_commission, signature
             } => {
                 size += Serialize::serialized_size(&IncomingStakingTransactionType::UpdateValidator);
                 size += Serialize::serialized_size(old_validator_key);
                 size += Serialize::serialized_size(&new_validator_key.is_some());
                 size += Serialize::serialized_size(&new_reward_address.is_some());
+                size += Serialize::serialized_size(&new_commission.is_some());
                 if let (Some(new_validator_key), Some(new_proof_of_knowledge)) = (new_validator_key, new_proof_of_knowledge) {
                     size += Serialize::serialized_size(new_validator_key);
                     size += Serialize::serialized_size(new_proof_of_knowledge);
@@ -266,6 +285,9 @@ impl Serialize for IncomingStakingTransactionData {
                 if let Some(new_reward_address) = new_reward_address {
                     size += Serialize::serialized_size(new_reward_address);
                 }
+                if let Some(new_commission) = new_commission {
+                    size += Serialize::serialized_size(new_commission);
+                }
                 size += Serialize::serialized_size(signature);
             },
             IncomingStakingTransactionData::RetireValidator {
@@ -310,16 +332,19 @@ impl Deserialize for IncomingStakingTransactionData {
                     validator_key: Deserialize::deserialize(reader)?,
                     proof_of_knowledge: Deserialize::deserialize(reader)?,
                     reward_address: Deserialize::deserialize(reader)?,
+                    commission: Deserialize::deserialize(reader)?,
                 })
             },
             IncomingStakingTransactionType::UpdateValidator => {
                 let old_validator_key: BlsPublicKey = Deserialize::deserialize(reader)?;
                 let updates_key: bool = Deserialize::deserialize(reader)?;
                 let updates_address: bool = Deserialize::deserialize(reader)?;
+                let updates_commission: bool = Deserialize::deserialize(reader)?;
 
                 let mut new_validator_key = None;
                 let mut new_proof_of_knowledge = None;
                 let mut new_reward_address = None;
+                let mut new_commission = None;
                 if updates_key {
                     new_validator_key = Some(Deserialize::deserialize(reader)?);
                     new_proof_of_knowledge = Some(Deserialize::deserialize(reader)?);
@@ -327,6 +352,9 @@ impl Deserialize for IncomingStakingTransactionData {
                 if updates_address {
                     new_reward_address = Some(Deserialize::deserialize(reader)?);
                 }
+                if updates_commission {
+                    new_commission = Some(Deserialize::deserialize(reader)?);
+                }
                 let signature = Deserialize::deserialize(reader)?;
 
                 Ok(IncomingStakingTransactionData::UpdateValidator {
@@ -334,6 +362,7 @@ impl Deserialize for IncomingStakingTransactionData {
                     new_validator_key,
                     new_proof_of_knowledge,
                     new_reward_address,
+                    new_commission,
                     signature,
                 })
             },
@@ -585,3 +614,11 @@ pub fn verify_proof_of_knowledge(validator_key: &BlsPublicKey, proof_of_knowledg
     }
     Ok(())
 }
+
+pub fn verify_commission(commission: u16) -> Result<(), TransactionError> {
+    if commission > policy::MAX_VALIDATOR_COMMISSION {
+        warn!("Validator commission above maximum");
+        return Err(TransactionError::InvalidData);
+    }
+    Ok(())
+}
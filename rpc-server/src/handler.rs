@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::net::IpAddr;
 use std::sync::Arc;
 
 use parking_lot::RwLock;
@@ -9,15 +10,17 @@ use crate::error::AuthenticationError;
 use crate::jsonrpc;
 use crate::handlers::Module;
 
+/// An RPC method. Clones of a method share the state of its module.
+#[derive(Clone)]
 pub struct Method {
-    f: Box<dyn Fn(&[JsonValue]) -> Result<JsonValue, JsonValue> + Send + Sync>
+    f: Arc<dyn Fn(&[JsonValue]) -> Result<JsonValue, JsonValue> + Send + Sync>
 }
 
 impl Method {
     pub fn new<F>(f: F) -> Self
         where F: Fn(&[JsonValue]) -> Result<JsonValue, JsonValue> + Send + Sync + 'static
     {
-        Self { f: Box::new(f) }
+        Self { f: Arc::new(f) }
     }
 
     pub fn call(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
@@ -77,5 +80,14 @@ impl jsonrpc::Handler for Handler {
             Err(AuthenticationError::IncorrectCredentials)
         }
     }
+
+    fn is_ip_allowed(&self, ip: &IpAddr) -> bool {
+        self.config.allowip.is_allowed(ip)
+    }
+
+    fn max_batch_size(&self) -> Option<usize> {
+        // A limit of 0 would reject every batch, so it means no limit.
+        self.config.max_batch_size.filter(|&max_batch_size| max_batch_size > 0)
+    }
 }
 
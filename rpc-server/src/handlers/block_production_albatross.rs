@@ -6,6 +6,8 @@ use std::string::ToString;
 
 use json::{JsonValue, object};
 
+use bls::bls12_381::CompressedSignature;
+
 use validator::validator::Validator;
 
 use crate::handler::Method;
@@ -23,35 +25,29 @@ impl BlockProductionAlbatrossHandler {
     }
 
     fn validator_key(&self, _params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
-        let key_pair = &self.validator.validator_key;
-
-        // Compute proof of knowledge.
-        // TODO: Do we need this at all? This is only needed to sign staking transactions, and
-        // that can be done with the mempool module.
-        let proof_of_knowledge = key_pair
-            .sign(&key_pair.public)
-            .compress();
+        let proof_of_knowledge = self.compute_proof_of_knowledge()?;
 
         Ok(object! {
-            "validatorKey" => self.validator.validator_key.public.to_string(),
+            "validatorKey" => self.validator.public_key().to_string(),
             "proofOfKnowledge" => proof_of_knowledge.to_string(),
         })
     }
 
     fn proof_of_knowledge(&self, _params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
-        let key_pair = &self.validator.validator_key;
-
-        // Compute proof of knowledge.
-        // TODO: Do we need this at all? This is only needed to sign staking transactions, and
-        // that can be done with the mempool module.
-        let proof_of_knowledge = key_pair
-            .sign(&key_pair.public)
-            .compress();
+        let proof_of_knowledge = self.compute_proof_of_knowledge()?;
 
         Ok(object! {
             "proofOfKnowledge" => proof_of_knowledge.to_string(),
         })
     }
+
+    fn compute_proof_of_knowledge(&self) -> Result<CompressedSignature, JsonValue> {
+        // Compute proof of knowledge.
+        // TODO: Do we need this at all? This is only needed to sign staking transactions, and
+        // that can be done with the mempool module.
+        self.validator.signer().sign_proof_of_knowledge()
+            .map_err(|e| object!{"message" => format!("Failed to compute proof of knowledge: {}", e)})
+    }
 }
 
 impl Module for BlockProductionAlbatrossHandler {
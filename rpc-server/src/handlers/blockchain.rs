@@ -3,6 +3,7 @@ use std::sync::Arc;
 
 use json::{Array, JsonValue, Null, object};
 
+use account::Account;
 use block_base::{Block, BlockHeader};
 use blockchain_base::AbstractBlockchain;
 use keys::Address;
@@ -174,10 +175,51 @@ impl<B: AbstractBlockchain + 'static> BlockchainHandler<B> {
             .and_then(|s| Address::from_any_str(s)
                 .map_err(|_| object!{"message" => "Invalid address"}))?;
 
-        let account = self.blockchain.get_account(&address);
+        let account = self.blockchain.get_account(&address)
+            .ok_or_else(|| object!{"message" => "Account state not available"})?;
         Ok(JsonValue::from(u64::from(account.balance())))
     }
 
+    /// Look up the account of an address, including the state of vesting and HTLC contracts.
+    /// Parameters:
+    /// - address (string)
+    ///
+    /// Returns the account object:
+    /// ```text
+    /// {
+    ///     address: string, // user friendly address
+    ///     type: string, // "basic", "vesting", "htlc" or "staking"
+    ///     balance: number,
+    ///
+    ///     // Vesting contracts:
+    ///     owner: string,
+    ///     vestingStart: number,
+    ///     vestingStepBlocks: number,
+    ///     vestingStepAmount: number,
+    ///     vestingTotalAmount: number,
+    ///     minCap: number, // amount that is still locked at the current height
+    ///
+    ///     // HTLCs:
+    ///     sender: string,
+    ///     recipient: string,
+    ///     hashAlgorithm: string,
+    ///     hashRoot: string,
+    ///     hashCount: number,
+    ///     timeout: number,
+    ///     totalAmount: number,
+    /// }
+    /// ```
+    pub(crate) fn get_account(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let address = params.get(0).and_then(JsonValue::as_str)
+            .ok_or_else(|| object!{"message" => "Invalid address"})
+            .and_then(|s| Address::from_any_str(s)
+                .map_err(|_| object!{"message" => "Invalid address"}))?;
+
+        let account = self.blockchain.get_account(&address)
+            .ok_or_else(|| object!{"message" => "Account state not available"})?;
+        Ok(account_to_obj(&address, &account, self.blockchain.head_height()))
+    }
+
     // Helper functions
 
     pub(crate) fn block_by_number(&self, number: &JsonValue) -> Result<B::Block, JsonValue> {
@@ -246,3 +288,45 @@ pub(crate) fn parse_hash(hash: &JsonValue) -> Result<Blake2bHash, JsonValue> {
         .and_then(|s| Blake2bHash::from_str(s)
             .map_err(|_| object!{"message" => "Invalid Blake2b hash"}))
 }
+
+pub(crate) fn account_to_obj(address: &Address, account: &Account, block_height: u32) -> JsonValue {
+    let mut obj = object!{
+        "address" => address.to_user_friendly_address(),
+        "balance" => u64::from(account.balance()),
+    };
+
+    match account {
+        Account::Basic(_) => {
+            obj["type"] = "basic".into();
+        },
+        Account::Vesting(contract) => {
+            let min_cap = if block_height < contract.start {
+                contract.total_amount
+            } else {
+                contract.min_cap(block_height)
+            };
+            obj["type"] = "vesting".into();
+            obj["owner"] = contract.owner.to_user_friendly_address().into();
+            obj["vestingStart"] = contract.start.into();
+            obj["vestingStepBlocks"] = contract.step_blocks.into();
+            obj["vestingStepAmount"] = u64::from(contract.step_amount).into();
+            obj["vestingTotalAmount"] = u64::from(contract.total_amount).into();
+            obj["minCap"] = u64::from(min_cap).into();
+        },
+        Account::HTLC(contract) => {
+            obj["type"] = "htlc".into();
+            obj["sender"] = contract.sender.to_user_friendly_address().into();
+            obj["recipient"] = contract.recipient.to_user_friendly_address().into();
+            obj["hashAlgorithm"] = contract.hash_algorithm.to_string().to_lowercase().into();
+            obj["hashRoot"] = contract.hash_root.to_hex().into();
+            obj["hashCount"] = contract.hash_count.into();
+            obj["timeout"] = contract.timeout.into();
+            obj["totalAmount"] = u64::from(contract.total_amount).into();
+        },
+        Account::Staking(_) => {
+            obj["type"] = "staking".into();
+        },
+    }
+
+    obj
+}
@@ -3,6 +3,7 @@ use std::convert::TryInto;
 use std::sync::Arc;
 
 use json::{JsonValue, Null, object};
+use parking_lot::Mutex;
 
 use account::Account;
 use account::staking_contract::{InactiveStake, InactiveValidator, Validator};
@@ -15,9 +16,10 @@ use keys::Address;
 use network_primitives::networks::NetworkInfo;
 use primitives::policy;
 use primitives::slot::{Slot, SlotBand, Slots};
+use utils::rate_limit::RateLimit;
 
 use crate::handler::Method;
-use crate::handlers::blockchain::BlockchainHandler;
+use crate::handlers::blockchain::{account_to_obj, BlockchainHandler};
 use crate::handlers::mempool::{transaction_to_obj, TransactionContext};
 use crate::handlers::Module;
 use crate::rpc_not_implemented;
@@ -26,13 +28,20 @@ use json::object::Object;
 pub struct BlockchainAlbatrossHandler {
     pub blockchain: Arc<Blockchain>,
     generic: BlockchainHandler<Blockchain>,
+    /// Limits the lookups of account states before the head, since each of them reverts the
+    /// accounts tree while blocking the blockchain from pushing blocks.
+    past_state_limit: Mutex<RateLimit>,
 }
 
 impl BlockchainAlbatrossHandler {
+    /// Maximum number of account lookups at past blocks per minute.
+    const PAST_STATE_LOOKUPS_PER_MINUTE: usize = 60;
+
     pub fn new(blockchain: Arc<Blockchain>) -> Self {
         BlockchainAlbatrossHandler {
             generic: BlockchainHandler::new(blockchain.clone()),
             blockchain,
+            past_state_limit: Mutex::new(RateLimit::new_per_minute(Self::PAST_STATE_LOOKUPS_PER_MINUTE)),
         }
     }
 
@@ -304,11 +313,44 @@ impl BlockchainAlbatrossHandler {
 
     // Accounts
 
-    // Lists all stakes
-    pub(crate) fn list_stakes(&self, _params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+    /// Look up the balance of an address.
+    /// Parameters:
+    /// - address (string)
+    /// - blockNumber (number, optional): Look up the balance at this block of the current epoch.
+    ///   Default is the head. Lookups before the head are limited to 60 per minute.
+    ///
+    /// Returns the amount in Luna (10000 = 1 NIM):
+    /// ```text
+    /// 1200000
+    /// ```
+    pub(crate) fn get_balance(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let address = Self::parse_address(params.get(0).unwrap_or(&Null))?;
+        let (account, _) = self.account_at(&address, params.get(1))?;
+        Ok(JsonValue::from(u64::from(account.balance())))
+    }
+
+    /// Look up the account of an address, including the state of vesting and HTLC contracts.
+    /// Parameters:
+    /// - address (string)
+    /// - blockNumber (number, optional): Look up the account at this block of the current epoch.
+    ///   Default is the head. Lookups before the head are limited to 60 per minute.
+    ///
+    /// Returns the same account object as the generic `getAccount`. The `minCap` of vesting
+    /// contracts refers to the requested block.
+    pub(crate) fn get_account(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let address = Self::parse_address(params.get(0).unwrap_or(&Null))?;
+        let (account, block_number) = self.account_at(&address, params.get(1))?;
+        Ok(account_to_obj(&address, &account, block_number))
+    }
+
+    /// Lists all stakes.
+    /// Parameters:
+    /// - blockNumber (number, optional): List the stakes at this block of the current epoch.
+    ///   Default is the head. Lookups before the head are limited to 60 per minute.
+    pub(crate) fn list_stakes(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
         let genesis_account = NetworkInfo::from_network_id(self.blockchain.network_id)
             .validator_registry_address().unwrap();
-        let account = self.blockchain.get_account(&genesis_account);
+        let (account, _) = self.account_at(genesis_account, params.get(0))?;
         let contract = match account {
             Account::Staking(c) => c,
             _ => return Err("No contract at staking contract address".into()),
@@ -334,6 +376,39 @@ impl BlockchainAlbatrossHandler {
 
     // Helper functions
 
+    fn parse_address(address: &JsonValue) -> Result<Address, JsonValue> {
+        address.as_str()
+            .ok_or_else(|| object!{"message" => "Invalid address"})
+            .and_then(|s| Address::from_any_str(s)
+                .map_err(|_| object!{"message" => "Invalid address"}))
+    }
+
+    /// Looks up an account at the given block number, or at the head if the block number is
+    /// missing or null. Returns the account together with the block number it was looked up at.
+    ///
+    /// Lookups before the head are rate-limited, see `PAST_STATE_LOOKUPS_PER_MINUTE`.
+    fn account_at(&self, address: &Address, block_number: Option<&JsonValue>) -> Result<(Account, u32), JsonValue> {
+        let block_number = match block_number {
+            Some(number) if !number.is_null() => Some(self.generic.parse_block_number(number)?),
+            _ => None,
+        };
+        match block_number {
+            Some(block_number) if block_number != self.blockchain.height() => {
+                if !self.past_state_limit.lock().note_single() {
+                    return Err(object!{"message" => "Too many lookups at past blocks - at most 60 per minute are allowed"});
+                }
+                let mut accounts = self.blockchain.get_accounts_at(block_number, &[address.clone()])
+                    .ok_or_else(|| object!{"message" => "Account state not available at this block - only blocks of the current epoch are supported"})?;
+                Ok((accounts.pop().unwrap(), block_number))
+            },
+            _ => {
+                let account = self.blockchain.get_account(address)
+                    .ok_or_else(|| object!{"message" => "Account state not available"})?;
+                Ok((account, self.blockchain.height()))
+            },
+        }
+    }
+
     fn proof_to_object<M: signed::Message>(proof: &signed::AggregateProof<M>) -> JsonValue {
         object!{
             "signature" => format!("{}", proof.signature),
@@ -469,6 +544,8 @@ impl BlockchainAlbatrossHandler {
             "publicKey" => hex::encode(&validator.validator_key),
             "balance" => u64::from(validator.balance),
             "rewardAddress" => validator.reward_address.to_user_friendly_address(),
+            "commission" => validator.commission,
+            "pendingCommission" => validator.pending_commission.map(JsonValue::from).unwrap_or(Null),
             "stakes" => JsonValue::Object(stakes),
         }
     }
@@ -510,7 +587,8 @@ impl Module for BlockchainAlbatrossHandler {
         "slotState" => slot_state,
 
         // Accounts
-        "getBalance" => generic.get_balance,
+        "getBalance" => get_balance,
+        "getAccount" => get_account,
         "listStakes" => list_stakes,
     }
 }
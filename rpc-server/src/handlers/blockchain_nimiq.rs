@@ -279,5 +279,6 @@ impl Module for BlockchainNimiqHandler {
 
         // Accounts
         "getBalance" => generic.get_balance,
+        "getAccount" => generic.get_account,
     }
 }
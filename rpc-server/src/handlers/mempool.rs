@@ -16,6 +16,7 @@ use primitives::account::AccountType;
 use primitives::coin::Coin;
 use primitives::networks::NetworkId;
 use transaction::{Transaction, TransactionFlags};
+use transaction_builder::TransactionProofBuilder;
 
 use crate::handler::Method;
 use crate::handlers::Module;
@@ -118,6 +119,7 @@ impl<P: ConsensusProtocol + 'static> MempoolHandler<P> {
     ///     flags: number|null,
     ///     data: string|null,
     ///     validityStartHeight: number|null,
+    ///     networkId: string|null,
     /// }
     /// ```
     /// Fields that can be null are optional.
@@ -153,24 +155,28 @@ impl<P: ConsensusProtocol + 'static> MempoolHandler<P> {
     ///     flags: number|null,
     ///     data: string|null,
     ///     validityStartHeight: number|null,
+    ///     networkId: string|null,
     /// }
     /// ```
     /// Fields that can be null are optional.
     pub(crate) fn send_transaction(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
-        let mut transaction = obj_to_transaction(params.get(0).unwrap_or(&Null), self.mempool.current_height(), self.mempool.network_id())?;
+        let transaction = obj_to_transaction(params.get(0).unwrap_or(&Null), self.mempool.current_height(), self.mempool.network_id())?;
 
-        if let Some(ref unlocked_wallets) = self.unlocked_wallets {
-            if transaction.sender_type == AccountType::Basic {
-                if let Some(wallet_account) = unlocked_wallets.read().get(&transaction.sender) {
-                    wallet_account.sign_transaction(&mut transaction);
-                } else {
-                    return Err(object! {"message" => "Sender account is locked"});
-                }
-            } else {
-                return Err(object! {"message" => "Sender account is not a basic account"});
-            }
+        let mut proof_builder = match TransactionProofBuilder::new(transaction) {
+            TransactionProofBuilder::Basic(proof_builder) => proof_builder,
+            _ => return Err(object! {"message" => "Sender account is not a basic account"}),
+        };
+
+        let unlocked_wallets = self.unlocked_wallets.as_ref()
+            .ok_or_else(|| object! {"message" => "No wallets"})?;
+        if let Some(wallet_account) = unlocked_wallets.read().get(&proof_builder.transaction.sender) {
+            proof_builder.sign_with_key_pair(&wallet_account.key_pair);
+        } else {
+            return Err(object! {"message" => "Sender account is locked"});
         }
 
+        let transaction = proof_builder.generate()
+            .ok_or_else(|| object! {"message" => "Failed to sign transaction"})?;
         self.push_transaction(transaction)
     }
 
@@ -215,6 +221,40 @@ impl<P: ConsensusProtocol + 'static> MempoolHandler<P> {
         }
     }
 
+    /// Estimates the fee for a transaction from the mempool contents and recent blocks.
+    /// Parameters:
+    /// - size (number, optional): Size of the transaction in bytes. Default is the size of a basic transaction.
+    /// - targetBlocks (number, optional): Number of blocks within which the transaction
+    ///     should be included. Default is `1`, i.e. the next block.
+    ///
+    /// The result has the following form:
+    /// ```text
+    /// {
+    ///     feePerByte: number,
+    ///     fee: number,
+    /// }
+    /// ```
+    pub(crate) fn estimate_fee(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let size = match params.get(0) {
+            None | Some(JsonValue::Null) => Transaction::MIN_SIZE,
+            Some(value) => value.as_usize()
+                .filter(|&size| size >= Transaction::MIN_SIZE)
+                .ok_or_else(|| object! {"message" => "Invalid transaction size"})?,
+        };
+        let target_blocks = match params.get(1) {
+            None | Some(JsonValue::Null) => 1,
+            Some(value) => value.as_u32()
+                .filter(|&target_blocks| target_blocks > 0)
+                .ok_or_else(|| object! {"message" => "Invalid target"})?,
+        };
+
+        let fee_per_byte = self.mempool.estimate_fee_per_byte(size, target_blocks);
+        Ok(object! {
+            "feePerByte" => fee_per_byte,
+            "fee" => (fee_per_byte * size as f64).ceil() as u64,
+        })
+    }
+
     // Helper functions
 
     pub(crate) fn push_transaction(&self, transaction: Transaction) -> Result<JsonValue, JsonValue> {
@@ -284,8 +324,7 @@ pub(crate) fn obj_to_transaction(obj: &JsonValue, current_height: u32, network_i
         .ok_or_else(|| object! {"message" => "Invalid transaction value"})?)
         .map_err(|_| object! {"message" => "Invalid transaction value"})?;
 
-    // FIXME Wrong fee
-    let fee = Coin::try_from(obj["value"].as_u64()
+    let fee = Coin::try_from(obj["fee"].as_u64()
         .ok_or_else(|| object! {"message" => "Invalid transaction fee"})?)
         .map_err(|_| object! {"message" => "Invalid transaction fee"})?;
 
@@ -304,6 +343,11 @@ pub(crate) fn obj_to_transaction(obj: &JsonValue, current_height: u32, network_i
         _ => None
     }.ok_or_else(|| object! {"message" => "Invalid validityStartHeight"})?;
 
+    let network_id = match &obj["networkId"] {
+        &JsonValue::Null => Some(network_id),
+        value => value.as_str().and_then(|s| NetworkId::from_str(s).ok()),
+    }.ok_or_else(|| object! {"message" => "Invalid networkId"})?;
+
     if to_type != AccountType::Basic
         && flags.contains(TransactionFlags::CONTRACT_CREATION)
         && to.is_none() {
@@ -332,5 +376,6 @@ impl<P: ConsensusProtocol + 'static> Module for MempoolHandler<P> {
         "mempoolContent" => mempool_content,
         "mempool" => mempool,
         "getMempoolTransaction" => get_transaction,
+        "estimateFee" => estimate_fee,
     }
 }
@@ -1,27 +1,48 @@
 use std::convert::{TryFrom, TryInto};
+use std::str::FromStr;
 use std::sync::Arc;
 
 use json::{JsonValue, Null, object};
 use parking_lot::RwLock;
 
-use beserial::{Deserialize, Serialize};
+use account::{Account, HashedTimeLockedContract};
+use beserial::Deserialize;
 use blockchain_albatross::Blockchain;
-use bls::bls12_381::{CompressedPublicKey, CompressedSignature};
+use blockchain_base::AbstractBlockchain;
+use bls::bls12_381::{CompressedPublicKey, CompressedSignature, PublicKey as BlsPublicKey};
 use consensus::AlbatrossConsensusProtocol;
-use keys::Address;
+use keys::{Address, KeyPair};
 use network_primitives::networks::NetworkInfo;
 use nimiq_mempool::Mempool;
 use primitives::account::AccountType;
 use primitives::coin::Coin;
-use transaction::account::staking_contract::{IncomingStakingTransactionData, OutgoingStakingTransactionProof, SelfStakingTransactionData};
-use transaction::Transaction;
+use primitives::networks::NetworkId;
+use primitives::policy;
+use transaction::{SignatureProof, Transaction};
+use transaction::account::htlc_contract::{AnyHash, HashAlgorithm};
+use transaction_builder::{Recipient, TransactionBuilder, TransactionBuilderError, TransactionProofBuilder};
+use transaction_builder::proof::BasicProofBuilder;
+use transaction_builder::proof::htlc_contract::HtlcProofBuilder;
+use transaction_builder::recipient::staking_contract::StakingRecipientBuilder;
 
 use crate::handler::Method;
 use crate::handlers::mempool::MempoolHandler;
 use crate::handlers::Module;
 use crate::handlers::wallet::UnlockedWalletManager;
 
+/// Builds, signs and pushes transfers, staking and contract transactions.
+///
+/// Transactions are signed with the accounts unlocked in the `UnlockedWalletManager`. This handler
+/// never signs with the BLS key of a validator: signalling transactions and transactions dropping
+/// a validator need a `validatorSignature` over the transaction content. If it is omitted, the
+/// transaction isn't sent and the content to sign is returned instead. Pass the returned
+/// `validityStartHeight` explicitly when sending the signed transaction.
+///
+/// All methods accept two optional trailing parameters:
+/// - validityStartHeight (number|null): Defaults to the current block height.
+/// - networkId (string|null): Defaults to the network of this node.
 pub struct MempoolAlbatrossHandler {
+    pub blockchain: Arc<Blockchain>,
     pub mempool: Arc<Mempool<Blockchain>>,
     pub unlocked_wallets: Option<Arc<RwLock<UnlockedWalletManager>>>,
     generic: MempoolHandler<AlbatrossConsensusProtocol>,
@@ -29,10 +50,12 @@ pub struct MempoolAlbatrossHandler {
 
 impl MempoolAlbatrossHandler {
     pub fn new(
+        blockchain: Arc<Blockchain>,
         mempool: Arc<Mempool<Blockchain>>,
         unlocked_wallets: Option<Arc<RwLock<UnlockedWalletManager>>>,
     ) -> Self {
         Self {
+            blockchain,
             mempool: Arc::clone(&mempool),
             unlocked_wallets: unlocked_wallets.as_ref().map(Arc::clone),
             generic: MempoolHandler::new(mempool, unlocked_wallets),
@@ -46,70 +69,284 @@ impl MempoolAlbatrossHandler {
                 .map_err(|_| object! {"message" => format!("Invalid {} address", kind)}))
     }
 
-    /// Create validator
-    /// Parameters:
-    /// - sender_address: NIM address used to create this transaction
-    /// - validator_key: Public key of validator (BLS)
-    /// - proof_of_knowledge: Proof of knowledge of validator key
-    /// - reward_address: NIM address used for the reward
-    /// - amount: Initial staking amount in Luna
-    /// - fee: Fee for transaction in Luna
-    pub(crate) fn create_validator(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
-        let sender_address = Self::parse_address(params.get(0).unwrap_or(&Null), "reward")?;
-        let validator_key = params.get(1)
-            .and_then(JsonValue::as_str)
+    fn parse_validator_key(value: &JsonValue) -> Result<BlsPublicKey, JsonValue> {
+        value.as_str()
             .ok_or_else(|| object! {"message" => "Invalid validator key"})
             .and_then(|it| hex::decode(it)
                 .map_err(|_| object! {"message" => "Validator key must be hex-encoded"}))
             .and_then(|it| CompressedPublicKey::deserialize_from_vec(&it)
-                .map_err(|_| object! {"message" => "Invalid public key"}))?;
-        let proof_of_knowledge = params.get(2)
-            .and_then(JsonValue::as_str)
-            .ok_or_else(|| object! {"message" => "Invalid proof of knowledge"})
+                .map_err(|_| object! {"message" => "Invalid public key"}))
+            .and_then(|it| it.uncompress()
+                .map_err(|_| object! {"message" => "Invalid public key"}))
+    }
+
+    fn parse_bls_signature(value: &JsonValue, kind: &str) -> Result<CompressedSignature, JsonValue> {
+        value.as_str()
+            .ok_or_else(|| object! {"message" => format!("Invalid {}", kind)})
             .and_then(|it| hex::decode(it)
-                .map_err(|_| object! {"message" => "Proof of knowledge must be hex-encoded"}))
+                .map_err(|_| object! {"message" => format!("Invalid {}: Must be hex-encoded", kind)}))
             .and_then(|it| CompressedSignature::deserialize_from_vec(&it)
-                .map_err(|_| object! {"message" => "Invalid proof of knowledge"}))?;
-        let reward_address = Self::parse_address(params.get(3).unwrap_or(&Null), "reward")?;
-        let amount = params.get(4)
-            .and_then(JsonValue::as_u64)
+                .map_err(|_| object! {"message" => format!("Invalid {}", kind)}))
+    }
+
+    /// Parses the optional `validatorSignature` parameter.
+    fn parse_validator_signature(value: Option<&JsonValue>) -> Result<Option<CompressedSignature>, JsonValue> {
+        match value {
+            None | Some(JsonValue::Null) => Ok(None),
+            Some(value) => Self::parse_bls_signature(value, "validator signature").map(Some),
+        }
+    }
+
+    fn parse_amount(value: &JsonValue) -> Result<Coin, JsonValue> {
+        value.as_u64()
             .ok_or_else(|| object! {"message" => "Invalid amount"})
             .and_then(|it| Coin::try_from(it)
-                .map_err(|e| object! {"message" => format!("Invalid amount: {}", e)}))?;
-        let fee = params.get(5)
-            .and_then(JsonValue::as_u64)
+                .map_err(|e| object! {"message" => format!("Invalid amount: {}", e)}))
+    }
+
+    fn parse_fee(value: Option<&JsonValue>) -> Result<Coin, JsonValue> {
+        value.and_then(JsonValue::as_u64)
             .unwrap_or(0)
             .try_into()
-            .map_err(|e| object! {"message" => format!("Invalid fee: {}", e)})?;
-
-        let network_id = self.mempool.network_id();
-        let staking_contract = NetworkInfo::from_network_id(network_id)
-            .validator_registry_address().unwrap();
-        let staking_data = IncomingStakingTransactionData::CreateValidator {
-            validator_key,
-            reward_address,
-            proof_of_knowledge,
+            .map_err(|e| object! {"message" => format!("Invalid fee: {}", e)})
+    }
+
+    /// Parses the optional `validityStartHeight` and `networkId` parameters starting at `index`.
+    fn parse_validity(&self, params: &[JsonValue], index: usize) -> Result<(u32, NetworkId), JsonValue> {
+        let validity_start_height = match params.get(index) {
+            None | Some(JsonValue::Null) => self.mempool.current_height(),
+            Some(value) => value.as_u32()
+                .ok_or_else(|| object! {"message" => "Invalid validityStartHeight"})?,
+        };
+        let network_id = match params.get(index + 1) {
+            None | Some(JsonValue::Null) => self.mempool.network_id(),
+            Some(value) => value.as_str()
+                .and_then(|it| NetworkId::from_str(it).ok())
+                .ok_or_else(|| object! {"message" => "Invalid networkId"})?,
         };
+        Ok((validity_start_height, network_id))
+    }
 
-        let mut tx = Transaction::new_extended(
-            sender_address, AccountType::Basic,    // sender
-            staking_contract.clone(), AccountType::Staking, // recipient
-            amount, fee,    // amount, fee
-            staking_data.serialize_to_vec(),       // data
-            self.mempool.current_height(),         // validity_start_height
-            network_id,                            // network_id
-        );
+    fn staking_contract(network_id: NetworkId) -> Result<Address, JsonValue> {
+        NetworkInfo::from_network_id(network_id)
+            .validator_registry_address()
+            .cloned()
+            .ok_or_else(|| object! {"message" => format!("No staking contract on network {}", network_id)})
+    }
 
-        debug!("Transaction data: {:#?}", staking_data);
+    fn build_error(e: TransactionBuilderError) -> JsonValue {
+        object! {"message" => format!("Failed to build transaction: {}", e)}
+    }
 
+    /// Returns the content of `transaction` that the validator has to sign.
+    fn content_to_sign(transaction: &Transaction) -> JsonValue {
+        object! {
+            "content" => hex::encode(transaction.serialize_content()),
+            "validityStartHeight" => transaction.validity_start_height,
+        }
+    }
+
+    /// Calls `f` with the key pair of the unlocked wallet account `address`.
+    fn with_key_pair<F, R>(&self, address: &Address, f: F) -> Result<R, JsonValue>
+        where F: FnOnce(&KeyPair) -> R
+    {
         let unlocked_wallets = self.unlocked_wallets.as_ref()
             .ok_or_else(|| object! {"message" => "No wallets"})?;
         let unlocked_wallets = unlocked_wallets.read();
-        let wallet_account = unlocked_wallets.get(&tx.sender)
-            .ok_or_else(|| object! {"message" => "Sender account is locked"})?;
-        wallet_account.sign_transaction(&mut tx);
+        let wallet_account = unlocked_wallets.get(address)
+            .ok_or_else(|| object! {"message" => "Account is locked"})?;
+        Ok(f(&wallet_account.key_pair))
+    }
+
+    /// Signs the transaction in `proof_builder` with the unlocked wallet account `signer` and
+    /// pushes it to the mempool.
+    fn sign_and_push(&self, signer: &Address, mut proof_builder: BasicProofBuilder) -> Result<JsonValue, JsonValue> {
+        self.with_key_pair(signer, |key_pair| {
+            proof_builder.sign_with_key_pair(key_pair);
+        })?;
+        let transaction = proof_builder.generate()
+            .ok_or_else(|| object! {"message" => "Failed to sign transaction"})?;
+        self.generic.push_transaction(transaction)
+    }
+
+    /// Builds an incoming staking transaction from `sender`.
+    fn build_incoming_staking_transaction(
+        sender: Address,
+        recipient: StakingRecipientBuilder,
+        value: Coin,
+        fee: Coin,
+        validity_start_height: u32,
+        network_id: NetworkId,
+    ) -> Result<TransactionProofBuilder, JsonValue> {
+        let recipient = recipient.generate()
+            .ok_or_else(|| object! {"message" => "Invalid staking transaction"})?;
+
+        let mut builder = TransactionBuilder::new();
+        builder.with_sender(sender)
+            .with_recipient(recipient)
+            .with_value(value)
+            .with_fee(fee)
+            .with_validity_start_height(validity_start_height)
+            .with_network_id(network_id);
+        builder.generate().map_err(Self::build_error)
+    }
+
+    /// Creates a validator.
+    /// Parameters:
+    /// - sender_address: NIM address used to create this transaction
+    /// - validator_key: Public key of the validator (BLS)
+    /// - proof_of_knowledge: Hex-encoded signature of the validator key over its public key
+    /// - reward_address: NIM address used for the reward
+    /// - amount: Initial staking amount in Luna
+    /// - fee: Fee for transaction in Luna
+    /// - commission: Commission on the stakers' rewards in basis points (optional, defaults to 0)
+    pub(crate) fn create_validator(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let sender_address = Self::parse_address(params.get(0).unwrap_or(&Null), "sender")?;
+        let validator_key = Self::parse_validator_key(params.get(1).unwrap_or(&Null))?;
+        let proof_of_knowledge = Self::parse_bls_signature(params.get(2).unwrap_or(&Null), "proof of knowledge")?;
+        let reward_address = Self::parse_address(params.get(3).unwrap_or(&Null), "reward")?;
+        let amount = Self::parse_amount(params.get(4).unwrap_or(&Null))?;
+        let fee = Self::parse_fee(params.get(5))?;
+        let commission = match params.get(6) {
+            None | Some(JsonValue::Null) => 0,
+            Some(value) => value.as_u16()
+                .filter(|&commission| commission <= policy::MAX_VALIDATOR_COMMISSION)
+                .ok_or_else(|| object! {"message" => "Invalid commission"})?,
+        };
+        let (validity_start_height, network_id) = self.parse_validity(params, 7)?;
+
+        let mut recipient = Recipient::new_staking_builder(Self::staking_contract(network_id)?);
+        recipient.create_validator_with_proof_of_knowledge(&validator_key, proof_of_knowledge, reward_address, commission);
 
-        self.generic.push_transaction(tx)
+        let proof_builder = Self::build_incoming_staking_transaction(sender_address.clone(), recipient, amount, fee, validity_start_height, network_id)?;
+        self.sign_and_push(&sender_address, proof_builder.unwrap_basic())
+    }
+
+    /// Updates the reward address of a validator.
+    /// Parameters:
+    /// - sender_address: NIM address paying the fee
+    /// - validator_key: Public key of the validator (BLS)
+    /// - reward_address: New NIM address used for the reward
+    /// - fee: Fee for transaction in Luna
+    /// - validator_signature: Hex-encoded signature of the validator key over the transaction content (optional)
+    pub(crate) fn update_validator(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let reward_address = Self::parse_address(params.get(2).unwrap_or(&Null), "reward")?;
+        self.signal_validator(params, 3, |recipient, validator_key| {
+            recipient.update_validator(validator_key, None, Some(reward_address))
+        })
+    }
+
+    /// Updates the commission of a validator.
+    /// Parameters:
+    /// - sender_address: NIM address paying the fee
+    /// - validator_key: Public key of the validator (BLS)
+    /// - commission: New commission on the stakers' rewards in basis points
+    /// - fee: Fee for transaction in Luna
+    /// - validator_signature: Hex-encoded signature of the validator key over the transaction content (optional)
+    pub(crate) fn update_validator_commission(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let commission = params.get(2)
+            .and_then(JsonValue::as_u16)
+            .filter(|&commission| commission <= policy::MAX_VALIDATOR_COMMISSION)
+            .ok_or_else(|| object! {"message" => "Invalid commission"})?;
+        self.signal_validator(params, 3, |recipient, validator_key| {
+            recipient.update_validator_commission(validator_key, commission)
+        })
+    }
+
+    /// Retires a validator.
+    /// Parameters:
+    /// - sender_address: NIM address paying the fee
+    /// - validator_key: Public key of the validator (BLS)
+    /// - fee: Fee for transaction in Luna
+    /// - validator_signature: Hex-encoded signature of the validator key over the transaction content (optional)
+    pub(crate) fn retire_validator(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        self.signal_validator(params, 2, StakingRecipientBuilder::retire_validator)
+    }
+
+    /// Re-activates a validator.
+    /// Parameters:
+    /// - sender_address: NIM address paying the fee
+    /// - validator_key: Public key of the validator (BLS)
+    /// - fee: Fee for transaction in Luna
+    /// - validator_signature: Hex-encoded signature of the validator key over the transaction content (optional)
+    pub(crate) fn reactivate_validator(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        self.signal_validator(params, 2, StakingRecipientBuilder::reactivate_validator)
+    }
+
+    /// Unparks a validator.
+    /// Parameters:
+    /// - sender_address: NIM address paying the fee
+    /// - validator_key: Public key of the validator (BLS)
+    /// - fee: Fee for transaction in Luna
+    /// - validator_signature: Hex-encoded signature of the validator key over the transaction content (optional)
+    pub(crate) fn unpark_validator(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        self.signal_validator(params, 2, StakingRecipientBuilder::unpark_validator)
+    }
+
+    /// Builds a signalling transaction from `sender_address` (`params[0]`) for the validator
+    /// `validator_key` (`params[1]`). The fee and the optional validator signature are read from
+    /// `params[fee_index]` and the following parameter.
+    fn signal_validator<F>(&self, params: &[JsonValue], fee_index: usize, signal: F) -> Result<JsonValue, JsonValue>
+        where F: for<'a> FnOnce(&'a mut StakingRecipientBuilder, &BlsPublicKey) -> &'a mut StakingRecipientBuilder
+    {
+        let sender_address = Self::parse_address(params.get(0).unwrap_or(&Null), "sender")?;
+        let validator_key = Self::parse_validator_key(params.get(1).unwrap_or(&Null))?;
+        let fee = Self::parse_fee(params.get(fee_index))?;
+        let validator_signature = Self::parse_validator_signature(params.get(fee_index + 1))?;
+        let (validity_start_height, network_id) = self.parse_validity(params, fee_index + 2)?;
+
+        let mut recipient = Recipient::new_staking_builder(Self::staking_contract(network_id)?);
+        signal(&mut recipient, &validator_key);
+
+        let mut proof_builder = Self::build_incoming_staking_transaction(sender_address.clone(), recipient, Coin::ZERO, fee, validity_start_height, network_id)?
+            .unwrap_signalling();
+        let validator_signature = match validator_signature {
+            Some(validator_signature) => validator_signature,
+            None => return Ok(Self::content_to_sign(&proof_builder.transaction)),
+        };
+        proof_builder.set_validator_signature(validator_signature);
+        let proof_builder = proof_builder.generate()
+            .ok_or_else(|| object! {"message" => "Invalid validator signature"})?
+            .unwrap_basic();
+
+        self.sign_and_push(&sender_address, proof_builder)
+    }
+
+    /// Drops a retired validator and pays out its initial stake.
+    /// Parameters:
+    /// - validator_key: Public key of the validator (BLS)
+    /// - recipient_address: NIM address receiving the initial stake
+    /// - amount: Initial stake of the validator in Luna
+    /// - fee: Fee for transaction in Luna, deducted from the initial stake
+    /// - validator_signature: Hex-encoded signature of the validator key over the transaction content (optional)
+    pub(crate) fn drop_validator(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let validator_key = Self::parse_validator_key(params.get(0).unwrap_or(&Null))?;
+        let recipient_address = Self::parse_address(params.get(1).unwrap_or(&Null), "recipient")?;
+        let amount = Self::parse_amount(params.get(2).unwrap_or(&Null))?;
+        let fee = Self::parse_fee(params.get(3))?;
+        let validator_signature = Self::parse_validator_signature(params.get(4))?;
+        let (validity_start_height, network_id) = self.parse_validity(params, 5)?;
+        let value = amount.checked_sub(fee)
+            .ok_or_else(|| object! {"message" => "Fee exceeds amount"})?;
+
+        let mut builder = TransactionBuilder::new();
+        builder.with_sender(Self::staking_contract(network_id)?)
+            .with_sender_type(AccountType::Staking)
+            .with_recipient(Recipient::new_basic(recipient_address))
+            .with_value(value)
+            .with_fee(fee)
+            .with_validity_start_height(validity_start_height)
+            .with_network_id(network_id);
+        let mut proof_builder = builder.generate().map_err(Self::build_error)?.unwrap_staking();
+        let validator_signature = match validator_signature {
+            Some(validator_signature) => validator_signature,
+            None => return Ok(Self::content_to_sign(&proof_builder.transaction)),
+        };
+        proof_builder.set_drop_validator_signature(&validator_key, validator_signature);
+        let transaction = proof_builder.generate()
+            .ok_or_else(|| object! {"message" => "Invalid validator signature"})?;
+
+        self.generic.push_transaction(transaction)
     }
 
     /// Stakes NIM
@@ -121,54 +358,20 @@ impl MempoolAlbatrossHandler {
     /// - staker_address: NIM address used to stake (optional, default is sender)
     pub(crate) fn stake(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
         let sender_address = Self::parse_address(params.get(0).unwrap_or(&Null), "sender")?;
-        let validator_key = params.get(1)
-            .and_then(JsonValue::as_str)
-            .ok_or_else(|| object! {"message" => "Invalid validator key"})
-            .and_then(|it| hex::decode(it)
-                .map_err(|_| object! {"message" => "Validator key must be hex-encoded"}))
-            .and_then(|it| CompressedPublicKey::deserialize_from_vec(&it)
-                .map_err(|_| object! {"message" => "Invalid public key"}))?;
-        let amount = params.get(2)
-            .and_then(JsonValue::as_u64)
-            .ok_or_else(|| object! {"message" => "Invalid amount"})
-            .and_then(|it| Coin::try_from(it)
-                .map_err(|e| object! {"message" => format!("Invalid amount: {}", e)}))?;
-        let fee = params.get(3)
-            .and_then(JsonValue::as_u64)
-            .unwrap_or(0)
-            .try_into()
-            .map_err(|e| object! {"message" => format!("Invalid fee: {}", e)})?;
-        let staker_address = if let Some(value) = params.get(4) {
-            Some(Self::parse_address(value, "staker")?)
-        } else { None };
-
-        let network_id = self.mempool.network_id();
-        let staking_contract = NetworkInfo::from_network_id(network_id)
-            .validator_registry_address().unwrap();
-        let staking_data = IncomingStakingTransactionData::Stake {
-            validator_key,
-            staker_address,
+        let validator_key = Self::parse_validator_key(params.get(1).unwrap_or(&Null))?;
+        let amount = Self::parse_amount(params.get(2).unwrap_or(&Null))?;
+        let fee = Self::parse_fee(params.get(3))?;
+        let staker_address = match params.get(4) {
+            None | Some(JsonValue::Null) => None,
+            Some(value) => Some(Self::parse_address(value, "staker")?),
         };
+        let (validity_start_height, network_id) = self.parse_validity(params, 5)?;
 
-        let mut tx = Transaction::new_extended(
-            sender_address, AccountType::Basic,    // sender
-            staking_contract.clone(), AccountType::Staking, // recipient
-            amount, fee,    // amount, fee
-            staking_data.serialize_to_vec(),       // data
-            self.mempool.current_height(),         // validity_start_height
-            network_id,                            // network_id
-        );
-
-        debug!("Transaction data: {:#?}", staking_data);
-
-        let unlocked_wallets = self.unlocked_wallets.as_ref()
-            .ok_or_else(|| object! {"message" => "No wallets"})?;
-        let unlocked_wallets = unlocked_wallets.read();
-        let wallet_account = unlocked_wallets.get(&tx.sender)
-            .ok_or_else(|| object! {"message" => "Sender account is locked"})?;
-        wallet_account.sign_transaction(&mut tx);
+        let mut recipient = Recipient::new_staking_builder(Self::staking_contract(network_id)?);
+        recipient.stake(&validator_key, staker_address);
 
-        self.generic.push_transaction(tx)
+        let proof_builder = Self::build_incoming_staking_transaction(sender_address.clone(), recipient, amount, fee, validity_start_height, network_id)?;
+        self.sign_and_push(&sender_address, proof_builder.unwrap_basic())
     }
 
     /// Retires staked NIM
@@ -178,87 +381,373 @@ impl MempoolAlbatrossHandler {
     /// - amount: Amount to retire
     /// - fee: Fee for transaction in Luna
     pub(crate) fn retire(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
-        let validator_key = params.get(0)
-            .and_then(JsonValue::as_str)
-            .ok_or_else(|| object! {"message" => "Invalid validator key"})
-            .and_then(|it| hex::decode(it)
-                .map_err(|_| object! {"message" => "Validator key must be hex-encoded"}))
-            .and_then(|it| CompressedPublicKey::deserialize_from_vec(&it)
-                .map_err(|_| object! {"message" => "Invalid public key"}))?;
+        let validator_key = Self::parse_validator_key(params.get(0).unwrap_or(&Null))?;
         let staker_address = Self::parse_address(params.get(1).unwrap_or(&Null), "staker")?;
-        let amount = params.get(2)
-            .and_then(JsonValue::as_u64)
-            .ok_or_else(|| object! {"message" => "Invalid amount"})
-            .and_then(|it| Coin::try_from(it)
-                .map_err(|e| object! {"message" => format!("Invalid amount: {}", e)}))?;
-        let fee = params.get(3)
-            .and_then(JsonValue::as_u64)
-            .unwrap_or(0)
-            .try_into()
-            .map_err(|e| object! {"message" => format!("Invalid fee: {}", e)})?;
+        let amount = Self::parse_amount(params.get(2).unwrap_or(&Null))?;
+        let fee = Self::parse_fee(params.get(3))?;
+        let (validity_start_height, network_id) = self.parse_validity(params, 4)?;
+        let staking_contract = Self::staking_contract(network_id)?;
 
-        let network_id = self.mempool.network_id();
-        let genesis_account = NetworkInfo::from_network_id(network_id)
-            .validator_registry_address().unwrap();
+        let transaction = self.with_key_pair(&staker_address, |key_pair| {
+            TransactionBuilder::new_retire(staking_contract, key_pair, &validator_key, amount, fee, validity_start_height, network_id)
+        })?;
 
-        let staking_data = SelfStakingTransactionData::RetireStake(validator_key);
+        self.generic.push_transaction(transaction)
+    }
 
-        let mut tx = Transaction::new_extended(
-            genesis_account.clone(), AccountType::Staking,
-            genesis_account.clone(), AccountType::Staking,
-            amount, fee, // amount, fee
-            staking_data.serialize_to_vec(),       // data
-            self.mempool.current_height(),      // validity_start_height
-            network_id,                         // network_id
-        );
+    /// Re-activates retired NIM and assigns them to a validator
+    /// Parameters:
+    /// - validator_key: Public key of the new validator (BLS)
+    /// - staker_address: NIM address used to stake
+    /// - amount: Amount to re-activate
+    /// - fee: Fee for transaction in Luna
+    pub(crate) fn reactivate(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let validator_key = Self::parse_validator_key(params.get(0).unwrap_or(&Null))?;
+        let staker_address = Self::parse_address(params.get(1).unwrap_or(&Null), "staker")?;
+        let amount = Self::parse_amount(params.get(2).unwrap_or(&Null))?;
+        let fee = Self::parse_fee(params.get(3))?;
+        let (validity_start_height, network_id) = self.parse_validity(params, 4)?;
+        let staking_contract = Self::staking_contract(network_id)?;
 
-        let unlocked_wallets = self.unlocked_wallets.as_ref()
-            .ok_or_else(|| object! {"message" => "No wallets"})?;
-        let unlocked_wallets = unlocked_wallets.read();
-        let wallet_account = unlocked_wallets.get(&staker_address)
-            .ok_or_else(|| object! {"message" => "Sender account is locked"})?;
-        wallet_account.sign_transaction(&mut tx);
+        let transaction = self.with_key_pair(&staker_address, |key_pair| {
+            TransactionBuilder::new_reactivate(staking_contract, key_pair, &validator_key, amount, fee, validity_start_height, network_id)
+        })?;
 
-        self.generic.push_transaction(tx)
+        self.generic.push_transaction(transaction)
     }
 
     /// Unstakes NIM
     /// Parameters:
     /// - staker_address: NIM address used to stake
     /// - amount: Amount to unstake
+    /// - fee: Fee for transaction in Luna, deducted from the unstaked amount (optional, defaults to 0)
+    /// - recipient_address: NIM address receiving the funds (optional, default is staker)
     pub(crate) fn unstake(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
         let staker_address = Self::parse_address(params.get(0).unwrap_or(&Null), "staker")?;
-        let amount = params.get(1)
-            .and_then(JsonValue::as_u64)
-            .ok_or_else(|| object! {"message" => "Invalid amount"})
-            .and_then(|it| Coin::try_from(it)
-                .map_err(|e| object! {"message" => format!("Invalid amount: {}", e)}))?;
+        let amount = Self::parse_amount(params.get(1).unwrap_or(&Null))?;
+        let fee = Self::parse_fee(params.get(2))?;
+        let recipient_address = match params.get(3) {
+            None | Some(JsonValue::Null) => staker_address.clone(),
+            Some(value) => Self::parse_address(value, "recipient")?,
+        };
+        let (validity_start_height, network_id) = self.parse_validity(params, 4)?;
+        let staking_contract = Self::staking_contract(network_id)?;
+        let value = amount.checked_sub(fee)
+            .ok_or_else(|| object! {"message" => "Fee exceeds amount"})?;
 
-        let network_id = self.mempool.network_id();
-        let staking_contract = NetworkInfo::from_network_id(network_id)
-            .validator_registry_address().unwrap();
+        let transaction = self.with_key_pair(&staker_address, |key_pair| {
+            TransactionBuilder::new_unstake(staking_contract, key_pair, recipient_address, value, fee, validity_start_height, network_id)
+        })?;
 
-        let mut tx = Transaction::new_extended(
-            staking_contract.clone(), AccountType::Staking, // sender
-            staker_address, AccountType::Basic, // recipient
-            amount, Coin::try_from(0).unwrap(), // amount, fee
-            vec![],                             // data
-            self.mempool.current_height(),      // validity_start_height
-            network_id,                         // network_id
-        );
+        self.generic.push_transaction(transaction)
+    }
 
-        let unlocked_wallets = self.unlocked_wallets.as_ref()
-            .ok_or_else(|| object! {"message" => "No wallets"})?;
-        let unlocked_wallets = unlocked_wallets.read();
-        let wallet_account = unlocked_wallets.get(&tx.recipient)
-            .ok_or_else(|| object! {"message" => "Sender account is locked"})?;
+    /// Creates a HTLC.
+    /// Parameters:
+    /// - sender_address: NIM address used to fund the HTLC
+    /// - htlc_sender: NIM address that can resolve the HTLC after the timeout (null defaults to sender)
+    /// - htlc_recipient: NIM address that can redeem the HTLC with the pre-image
+    /// - hash_root: Hex-encoded hash root
+    /// - hash_count: Number of times the pre-image is hashed to yield the hash root
+    /// - hash_algorithm: "blake2b" or "sha256"
+    /// - timeout: Block height after which the HTLC can be resolved by the HTLC sender
+    /// - value: Amount in Luna to lock
+    /// - fee: Fee for transaction in Luna
+    ///
+    /// Returns the transaction hash and the address of the new contract.
+    pub(crate) fn create_htlc(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let sender_address = Self::parse_address(params.get(0).unwrap_or(&Null), "sender")?;
+        let htlc_sender = match params.get(1) {
+            None | Some(JsonValue::Null) => sender_address.clone(),
+            Some(value) => Self::parse_address(value, "HTLC sender")?,
+        };
+        let htlc_recipient = Self::parse_address(params.get(2).unwrap_or(&Null), "HTLC recipient")?;
+        let hash_root = Self::parse_any_hash(params.get(3).unwrap_or(&Null), "hash root")?;
+        let hash_count = params.get(4)
+            .and_then(JsonValue::as_u8)
+            .filter(|&hash_count| hash_count > 0)
+            .ok_or_else(|| object! {"message" => "Invalid hash count"})?;
+        let hash_algorithm = Self::parse_hash_algorithm(params.get(5).unwrap_or(&Null))?;
+        let timeout = params.get(6)
+            .and_then(JsonValue::as_u32)
+            .ok_or_else(|| object! {"message" => "Invalid timeout"})?;
+        let value = Self::parse_amount(params.get(7).unwrap_or(&Null))?;
+        let fee = Self::parse_fee(params.get(8))?;
+        let (validity_start_height, network_id) = self.parse_validity(params, 9)?;
+
+        let mut recipient = Recipient::new_htlc_builder();
+        recipient.with_sender(htlc_sender)
+            .with_recipient(htlc_recipient)
+            .with_hash(hash_root, hash_count, hash_algorithm)
+            .with_timeout_block(timeout);
+        let recipient = recipient.generate()
+            .map_err(|e| object! {"message" => format!("Invalid HTLC: {}", e)})?;
+
+        self.push_contract_creation(sender_address, recipient, value, fee, validity_start_height, network_id)
+    }
+
+    /// Creates a vesting contract.
+    /// Parameters:
+    /// - sender_address: NIM address used to fund the vesting contract
+    /// - owner: NIM address that can withdraw the vested funds (null defaults to sender)
+    /// - value: Total amount in Luna to vest
+    /// - start: Block height at which the vesting starts
+    /// - step_blocks: Number of blocks between two vesting steps
+    /// - step_amount: Amount in Luna released at each step (optional, defaults to value)
+    /// - fee: Fee for transaction in Luna
+    ///
+    /// Returns the transaction hash and the address of the new contract.
+    pub(crate) fn create_vesting_contract(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let sender_address = Self::parse_address(params.get(0).unwrap_or(&Null), "sender")?;
+        let owner = match params.get(1) {
+            None | Some(JsonValue::Null) => sender_address.clone(),
+            Some(value) => Self::parse_address(value, "owner")?,
+        };
+        let value = Self::parse_amount(params.get(2).unwrap_or(&Null))?;
+        let start = params.get(3)
+            .and_then(JsonValue::as_u32)
+            .ok_or_else(|| object! {"message" => "Invalid vesting start"})?;
+        let step_blocks = params.get(4)
+            .and_then(JsonValue::as_u32)
+            .ok_or_else(|| object! {"message" => "Invalid vesting step blocks"})?;
+        let step_amount = match params.get(5) {
+            None | Some(JsonValue::Null) => value,
+            Some(step_amount) => Self::parse_amount(step_amount)?,
+        };
+        let fee = Self::parse_fee(params.get(6))?;
+        let (validity_start_height, network_id) = self.parse_validity(params, 7)?;
+
+        let mut recipient = Recipient::new_vesting_builder(owner);
+        recipient.with_start_block(start)
+            .with_step_distance(step_blocks)
+            .with_step_amount(step_amount)
+            .with_total_amount(value);
+        let recipient = recipient.generate()
+            .map_err(|e| object! {"message" => format!("Invalid vesting contract: {}", e)})?;
+
+        self.push_contract_creation(sender_address, recipient, value, fee, validity_start_height, network_id)
+    }
+
+    /// Redeems a HTLC by presenting the pre-image (regular transfer).
+    /// The HTLC recipient account must be unlocked.
+    /// Parameters:
+    /// - contract_address: Address of the HTLC
+    /// - recipient_address: NIM address receiving the funds (null defaults to the HTLC recipient)
+    /// - pre_image: Hex-encoded pre-image
+    /// - fee: Fee for transaction in Luna
+    /// - value: Amount in Luna to redeem (optional, defaults to the contract balance minus the fee)
+    /// - hash_depth: Number of times the pre-image is hashed to yield the hash root
+    ///     (optional, defaults to the hash count of the contract)
+    pub(crate) fn redeem_htlc(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let contract_address = Self::parse_address(params.get(0).unwrap_or(&Null), "contract")?;
+        let contract = self.get_htlc(&contract_address)?;
+        let recipient_address = match params.get(1) {
+            None | Some(JsonValue::Null) => contract.recipient.clone(),
+            Some(value) => Self::parse_address(value, "recipient")?,
+        };
+        let pre_image = Self::parse_any_hash(params.get(2).unwrap_or(&Null), "pre-image")?;
+        let fee = Self::parse_fee(params.get(3))?;
+        let value = Self::parse_htlc_value(params.get(4), &contract, fee)?;
+        let hash_depth = match params.get(5) {
+            None | Some(JsonValue::Null) => contract.hash_count,
+            Some(value) => value.as_u8()
+                .filter(|&hash_depth| hash_depth <= contract.hash_count)
+                .ok_or_else(|| object! {"message" => "Invalid hash depth"})?,
+        };
+        let (validity_start_height, network_id) = self.parse_validity(params, 6)?;
 
-        let proof = OutgoingStakingTransactionProof::Unstake(
-            wallet_account.create_signature_proof(&tx)
-        );
-        tx.proof = proof.serialize_to_vec();
+        let mut proof_builder = self.build_htlc_transaction(contract_address, recipient_address, value, fee, validity_start_height, network_id)?;
+        let recipient_signature = self.with_key_pair(&contract.recipient, |key_pair| {
+            proof_builder.signature_with_key_pair(key_pair)
+        })?;
+        proof_builder.regular_transfer(contract.hash_algorithm, pre_image, hash_depth, contract.hash_root.clone(), recipient_signature);
+        let transaction = proof_builder.generate()
+            .ok_or_else(|| object! {"message" => "Failed to sign transaction"})?;
 
-        self.generic.push_transaction(tx)
+        self.generic.push_transaction(transaction)
+    }
+
+    /// Resolves a HTLC before the timeout with the signatures of both the HTLC sender and
+    /// recipient (early resolve). The HTLC recipient account must be unlocked.
+    /// Parameters:
+    /// - contract_address: Address of the HTLC
+    /// - recipient_address: NIM address receiving the funds
+    /// - fee: Fee for transaction in Luna
+    /// - value: Amount in Luna to redeem (optional, defaults to the contract balance minus the fee)
+    /// - sender_signature: Hex-encoded signature proof of the HTLC sender for this transaction
+    ///     (optional, by default the transaction is signed with the unlocked HTLC sender account)
+    pub(crate) fn redeem_htlc_early(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let contract_address = Self::parse_address(params.get(0).unwrap_or(&Null), "contract")?;
+        let contract = self.get_htlc(&contract_address)?;
+        let recipient_address = Self::parse_address(params.get(1).unwrap_or(&Null), "recipient")?;
+        let fee = Self::parse_fee(params.get(2))?;
+        let value = Self::parse_htlc_value(params.get(3), &contract, fee)?;
+        let sender_signature = match params.get(4) {
+            None | Some(JsonValue::Null) => None,
+            Some(value) => Some(value.as_str()
+                .and_then(|it| hex::decode(it).ok())
+                .and_then(|it| SignatureProof::deserialize_from_vec(&it).ok())
+                .ok_or_else(|| object! {"message" => "Invalid sender signature"})?),
+        };
+        let (validity_start_height, network_id) = self.parse_validity(params, 5)?;
+
+        let mut proof_builder = self.build_htlc_transaction(contract_address, recipient_address, value, fee, validity_start_height, network_id)?;
+        let sender_signature = match sender_signature {
+            Some(sender_signature) => sender_signature,
+            None => self.with_key_pair(&contract.sender, |key_pair| {
+                proof_builder.signature_with_key_pair(key_pair)
+            })?,
+        };
+        let recipient_signature = self.with_key_pair(&contract.recipient, |key_pair| {
+            proof_builder.signature_with_key_pair(key_pair)
+        })?;
+        proof_builder.early_resolve(sender_signature, recipient_signature);
+        let transaction = proof_builder.generate()
+            .ok_or_else(|| object! {"message" => "Failed to sign transaction"})?;
+
+        self.generic.push_transaction(transaction)
+    }
+
+    /// Resolves a HTLC after the timeout (timeout resolve).
+    /// The HTLC sender account must be unlocked.
+    /// Parameters:
+    /// - contract_address: Address of the HTLC
+    /// - recipient_address: NIM address receiving the funds (null defaults to the HTLC sender)
+    /// - fee: Fee for transaction in Luna
+    /// - value: Amount in Luna to redeem (optional, defaults to the contract balance minus the fee)
+    pub(crate) fn redeem_htlc_timeout(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let contract_address = Self::parse_address(params.get(0).unwrap_or(&Null), "contract")?;
+        let contract = self.get_htlc(&contract_address)?;
+        let recipient_address = match params.get(1) {
+            None | Some(JsonValue::Null) => contract.sender.clone(),
+            Some(value) => Self::parse_address(value, "recipient")?,
+        };
+        let fee = Self::parse_fee(params.get(2))?;
+        let value = Self::parse_htlc_value(params.get(3), &contract, fee)?;
+        let (validity_start_height, network_id) = self.parse_validity(params, 4)?;
+
+        let mut proof_builder = self.build_htlc_transaction(contract_address, recipient_address, value, fee, validity_start_height, network_id)?;
+        let sender_signature = self.with_key_pair(&contract.sender, |key_pair| {
+            proof_builder.signature_with_key_pair(key_pair)
+        })?;
+        proof_builder.timeout_resolve(sender_signature);
+        let transaction = proof_builder.generate()
+            .ok_or_else(|| object! {"message" => "Failed to sign transaction"})?;
+
+        self.generic.push_transaction(transaction)
+    }
+
+    /// Withdraws vested funds from a vesting contract.
+    /// The owner account must be unlocked.
+    /// Parameters:
+    /// - contract_address: Address of the vesting contract
+    /// - recipient_address: NIM address receiving the funds (null defaults to the owner)
+    /// - value: Amount in Luna to withdraw
+    /// - fee: Fee for transaction in Luna
+    pub(crate) fn withdraw_vesting(&self, params: &[JsonValue]) -> Result<JsonValue, JsonValue> {
+        let contract_address = Self::parse_address(params.get(0).unwrap_or(&Null), "contract")?;
+        let contract = match self.blockchain.get_account(&contract_address) {
+            Some(Account::Vesting(contract)) => contract,
+            _ => return Err(object! {"message" => "No vesting contract at this address"}),
+        };
+        let recipient_address = match params.get(1) {
+            None | Some(JsonValue::Null) => contract.owner.clone(),
+            Some(value) => Self::parse_address(value, "recipient")?,
+        };
+        let value = Self::parse_amount(params.get(2).unwrap_or(&Null))?;
+        let fee = Self::parse_fee(params.get(3))?;
+        let (validity_start_height, network_id) = self.parse_validity(params, 4)?;
+
+        let mut builder = TransactionBuilder::new();
+        builder.with_sender(contract_address)
+            .with_sender_type(AccountType::Vesting)
+            .with_recipient(Recipient::new_basic(recipient_address))
+            .with_value(value)
+            .with_fee(fee)
+            .with_validity_start_height(validity_start_height)
+            .with_network_id(network_id);
+        let proof_builder = builder.generate().map_err(Self::build_error)?.unwrap_basic();
+
+        self.sign_and_push(&contract.owner, proof_builder)
+    }
+
+    fn parse_any_hash(value: &JsonValue, kind: &str) -> Result<AnyHash, JsonValue> {
+        value.as_str()
+            .and_then(|it| AnyHash::from_str(it).ok())
+            .ok_or_else(|| object! {"message" => format!("Invalid {}", kind)})
+    }
+
+    fn parse_hash_algorithm(value: &JsonValue) -> Result<HashAlgorithm, JsonValue> {
+        match value.as_str().map(str::to_lowercase).as_ref().map(String::as_str) {
+            Some("blake2b") => Ok(HashAlgorithm::Blake2b),
+            Some("sha256") => Ok(HashAlgorithm::Sha256),
+            _ => Err(object! {"message" => "Invalid hash algorithm"}),
+        }
+    }
+
+    /// Parses the optional value of a HTLC transaction, which defaults to the whole contract
+    /// balance minus the fee.
+    fn parse_htlc_value(value: Option<&JsonValue>, contract: &HashedTimeLockedContract, fee: Coin) -> Result<Coin, JsonValue> {
+        match value {
+            None | Some(JsonValue::Null) => contract.balance.checked_sub(fee)
+                .ok_or_else(|| object! {"message" => "Fee exceeds contract balance"}),
+            Some(value) => Self::parse_amount(value),
+        }
+    }
+
+    fn get_htlc(&self, address: &Address) -> Result<HashedTimeLockedContract, JsonValue> {
+        match self.blockchain.get_account(address) {
+            Some(Account::HTLC(contract)) => Ok(contract),
+            _ => Err(object! {"message" => "No HTLC at this address"}),
+        }
+    }
+
+    fn build_htlc_transaction(
+        &self,
+        contract_address: Address,
+        recipient_address: Address,
+        value: Coin,
+        fee: Coin,
+        validity_start_height: u32,
+        network_id: NetworkId,
+    ) -> Result<HtlcProofBuilder, JsonValue> {
+        let mut builder = TransactionBuilder::new();
+        builder.with_sender(contract_address)
+            .with_sender_type(AccountType::HTLC)
+            .with_recipient(Recipient::new_basic(recipient_address))
+            .with_value(value)
+            .with_fee(fee)
+            .with_validity_start_height(validity_start_height)
+            .with_network_id(network_id);
+        Ok(builder.generate().map_err(Self::build_error)?.unwrap_htlc())
+    }
+
+    /// Signs and pushes a contract creation transaction from the unlocked wallet account
+    /// `sender`. Returns the transaction hash and the address of the new contract.
+    fn push_contract_creation(
+        &self,
+        sender: Address,
+        recipient: Recipient,
+        value: Coin,
+        fee: Coin,
+        validity_start_height: u32,
+        network_id: NetworkId,
+    ) -> Result<JsonValue, JsonValue> {
+        let mut builder = TransactionBuilder::new();
+        builder.with_sender(sender.clone())
+            .with_recipient(recipient)
+            .with_value(value)
+            .with_fee(fee)
+            .with_validity_start_height(validity_start_height)
+            .with_network_id(network_id);
+        let proof_builder = builder.generate().map_err(Self::build_error)?.unwrap_basic();
+        let contract_address = proof_builder.transaction.contract_creation_address();
+
+        let hash = self.sign_and_push(&sender, proof_builder)?;
+        Ok(object! {
+            "hash" => hash,
+            "address" => contract_address.to_user_friendly_address(),
+        })
     }
 }
 
@@ -270,10 +759,30 @@ impl Module for MempoolAlbatrossHandler {
         "sendTransaction" => generic.send_transaction,
         "mempoolContent" => generic.mempool_content,
         "mempool" => generic.mempool,
+        "getTransaction" => generic.get_transaction,
+        "estimateFee" => generic.estimate_fee,
+
+        // Validators
         "createValidator" => create_validator,
+        "updateValidator" => update_validator,
+        "updateValidatorCommission" => update_validator_commission,
+        "retireValidator" => retire_validator,
+        "reactivateValidator" => reactivate_validator,
+        "unparkValidator" => unpark_validator,
+        "dropValidator" => drop_validator,
+
+        // Stakers
         "stake" => stake,
         "retire" => retire,
+        "reactivate" => reactivate,
         "unstake" => unstake,
-        "getTransaction" => generic.get_transaction,
+
+        // Contracts
+        "createHtlc" => create_htlc,
+        "redeemHtlc" => redeem_htlc,
+        "redeemHtlcEarly" => redeem_htlc_early,
+        "redeemHtlcTimeout" => redeem_htlc_timeout,
+        "createVestingContract" => create_vesting_contract,
+        "withdrawVesting" => withdraw_vesting,
     }
 }
@@ -280,7 +280,6 @@ impl Module for WalletHandler {
         "lockAccount" => lock_account,
         "createAccount" => create_account,
         "unlockAccount" => unlock_account,
-//        "sendTransaction" => send_transaction,
         "sign" => sign,
         "verifySignature" => verify_signature,
     }
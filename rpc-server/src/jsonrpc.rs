@@ -1,9 +1,10 @@
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 
 use futures::{future, Future, IntoFuture, stream::Stream};
 use hyper::{Body, Method, Request, Response, StatusCode};
 use hyper::header::HeaderValue;
-use json::{Array, JsonValue, Null, array, object};
+use json::{Array, JsonValue, Null, object};
 
 use crate::error::AuthenticationError;
 
@@ -12,16 +13,25 @@ pub trait Handler: Send + Sync {
     fn authorize(&self, _username: &str, _password: &str) -> Result<(), AuthenticationError> {
         Ok(())
     }
+    fn is_ip_allowed(&self, _ip: &IpAddr) -> bool {
+        true
+    }
+    /// Maximum number of requests in a batch, or `None` for no limit
+    fn max_batch_size(&self) -> Option<usize> {
+        None
+    }
 }
 
 pub struct Service<H> where H: Handler {
-    handler: Arc<H>
+    handler: Arc<H>,
+    remote_addr: SocketAddr,
 }
 
 impl<H> Service<H> where H: Handler {
-    pub fn new(handler: Arc<H>) -> Self {
+    pub fn new(handler: Arc<H>, remote_addr: SocketAddr) -> Self {
         Service {
             handler,
+            remote_addr,
         }
     }
 }
@@ -42,109 +52,126 @@ impl std::fmt::Display for Never {
     }
 }
 
-fn handle_request<H>(handler: Arc<H>, str_o: Result<&str, std::str::Utf8Error>) -> Response<Body> where H: Handler {
-    let mut builder = Response::builder();
-    builder.header("Content-Type", "application/json");
-    if str_o.is_err() {
-        return builder
-            .status(StatusCode::BAD_REQUEST)
-            .body(Body::from(json::stringify(object! {
-                "jsonrpc" => "2.0",
-                "id" => Null,
-                "error" => object!{
-                    "code" => -32600,
-                    "message" => "Invalid encoding"
-                }
-            })))
-            .unwrap();
-    }
-    let json_o = json::parse(str_o.unwrap());
-    if json_o.is_err() {
-        return builder
-            .status(StatusCode::BAD_REQUEST)
-            .body(Body::from(json::stringify(object! {
-                "jsonrpc" => "2.0",
-                "id" => Null,
-                "error" => object!{
-                    "code" => -32600,
-                    "message" => "Invalid JSON"
-                }
-            })))
-            .unwrap();
-    }
-    let mut json = json_o.unwrap();
-    let single = json.is_object();
-    if single {
-        json = array![json];
-    }
-    if !json.is_array() {
-        return builder
-            .status(StatusCode::BAD_REQUEST)
-            .body(Body::from(json::stringify(object! {
-                "jsonrpc" => "2.0",
-                "id" => Null,
-                "error" => object!{
-                    "code" => -32600,
-                    "message" => "Invalid request"
-                }
-            })))
-            .unwrap();
-    }
-    let mut results = vec![];
-    for msg in json.members() {
-        if msg["jsonrpc"] != "2.0" || !msg.has_key("method") || !msg["method"].is_string() {
-            results.push(object! {
-                "jsonrpc" => "2.0",
-                "id" => msg["id"].clone(),
-                "error" => object!{
-                    "code" => -32600,
-                    "message" => "Invalid request"
-                }
-            });
-            continue;
+fn error_response(status: StatusCode, code: i32, message: &str) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(json::stringify(error_object(Null, code, message))))
+        .unwrap()
+}
+
+/// Builds the response object of a failed request
+pub fn error_object(id: JsonValue, code: i32, message: &str) -> JsonValue {
+    object! {
+        "jsonrpc" => "2.0",
+        "id" => id,
+        "error" => object!{
+            "code" => code,
+            "message" => message
         }
+    }
+}
+
+fn is_valid_request(msg: &JsonValue) -> bool {
+    msg.is_object() && msg["jsonrpc"] == "2.0" && msg.has_key("method") && msg["method"].is_string()
+}
+
+/// Handles a single request object of a (possibly batched) call.
+/// Returns `None` if the request is a notification, i.e. it has no `id`.
+pub fn handle_message<H>(handler: &H, msg: &JsonValue) -> Option<JsonValue> where H: Handler {
+    if !is_valid_request(msg) {
+        // Invalid requests are answered even if they lack an id.
+        return Some(error_object(msg["id"].clone(), -32600, "Invalid request"));
+    }
+    let response = call(handler, msg);
+    if msg.has_key("id") {
+        Some(response)
+    } else {
+        None
+    }
+}
+
+/// Calls the method of a single request object and builds its response.
+fn call<H>(handler: &H, msg: &JsonValue) -> JsonValue where H: Handler {
+    if !is_valid_request(msg) {
+        return error_object(msg["id"].clone(), -32600, "Invalid request");
+    }
+
+    let params = msg["params"].clone();
+    let params_array = match params {
+        JsonValue::Array(a) => a,
+        _ => vec![params]
+    };
 
-        let params = msg["params"].clone();
-        let params_array = match params {
-            JsonValue::Array(a) => a,
-            _ => vec![params]
-        };
-
-        let result_o = handler.call_method(
-            msg["method"].as_str().unwrap(),
-            params_array,
-        );
-        if result_o.is_none() {
+    let result_o = handler.call_method(
+        msg["method"].as_str().unwrap(),
+        params_array,
+    );
+    match result_o {
+        None => {
             warn!("Unknown method called: {}", msg["method"]);
-            results.push(object! {
-                "jsonrpc" => "2.0",
-                "id" => msg["id"].clone(),
-                "error" => object!{
-                    "code" => -32601,
-                    "message" => "Method not found"
-                }
-            });
-            continue;
+            error_object(msg["id"].clone(), -32601, "Method not found")
+        },
+        Some(Ok(result)) => object! {
+            "jsonrpc" => "2.0",
+            "id" => msg["id"].clone(),
+            "result" => result
+        },
+        Some(Err(error)) => object! {
+            "jsonrpc" => "2.0",
+            "id" => msg["id"].clone(),
+            "error" => error
         }
+    }
+}
 
-        results.push(match result_o.unwrap() {
-            Ok(result) => object! {
-                "jsonrpc" => "2.0",
-                "id" => msg["id"].clone(),
-                "result" => result
-            },
-            Err(error) => object! {
-                "jsonrpc" => "2.0",
-                "id" => msg["id"].clone(),
-                "error" => error
+fn handle_request<H>(handler: Arc<H>, str_o: Result<&str, std::str::Utf8Error>) -> Response<Body> where H: Handler {
+    let str = match str_o {
+        Ok(str) => str,
+        Err(_) => return error_response(StatusCode::BAD_REQUEST, -32600, "Invalid encoding"),
+    };
+    let json = match json::parse(str) {
+        Ok(json) => json,
+        Err(_) => return error_response(StatusCode::BAD_REQUEST, -32700, "Invalid JSON"),
+    };
+
+    let response = if json.is_object() {
+        // A single request is always answered, even if it is a notification. HTTP clients
+        // expect a response body and the server answered single notifications before batches
+        // were supported.
+        Some(call(handler.as_ref(), &json))
+    } else if json.is_array() {
+        if json.is_empty() {
+            return error_response(StatusCode::BAD_REQUEST, -32600, "Invalid request");
+        }
+        if let Some(max_batch_size) = handler.max_batch_size() {
+            if json.len() > max_batch_size {
+                return error_response(StatusCode::BAD_REQUEST, -32600, "Batch size limit exceeded");
             }
-        });
-    }
+        }
 
-    if single {
-        builder.body(Body::from(results.pop().map(json::stringify).unwrap_or_else(String::new))).unwrap()
+        let results: Vec<JsonValue> = json.members()
+            .filter_map(|msg| handle_message(handler.as_ref(), msg))
+            .collect();
+        if results.is_empty() {
+            None
+        } else {
+            Some(JsonValue::Array(results))
+        }
     } else {
-        builder.body(Body::from(json::stringify(JsonValue::Array(results)))).unwrap()
+        return error_response(StatusCode::BAD_REQUEST, -32600, "Invalid request");
+    };
+
+    match response {
+        Some(response) => Response::builder()
+            .header("Content-Type", "application/json")
+            .body(Body::from(json::stringify(response)))
+            .unwrap(),
+        // A batch of notifications only, nothing to respond with.
+        None => Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(Body::empty())
+            .unwrap(),
     }
 }
 
@@ -188,9 +215,14 @@ impl<H> hyper::service::Service for Service<H> where H: Handler + 'static {
 
     fn call(&mut self, req: Request<<Self as hyper::service::Service>::ReqBody>) -> <Self as hyper::service::Service>::Future {
         let handler = Arc::clone(&self.handler);
+        if !handler.is_ip_allowed(&self.remote_addr.ip()) {
+            info!("Rejected RPC request from {}: IP not allowed", self.remote_addr);
+            return Box::new(future::ok(error_response(StatusCode::FORBIDDEN, -32001, "IP not allowed")));
+        }
         match *req.method() {
             Method::GET => Box::new(future::ok(Response::new(Body::from("Nimiq JSON-RPC Server")))),
             Method::POST => {
+                // Authentication is checked once for the whole (possibly batched) request.
                 if let Err(e) = check_authentication(Arc::clone(&handler), req.headers().get("Authorization")) {
                     info!("Authentication failed: {}", e);
                     //return Box::new(future::ok(Response::new(Body::from(json::stringify(e)))));
@@ -206,3 +238,92 @@ impl<H> hyper::service::Service for Service<H> where H: Handler + 'static {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestHandler {
+        max_batch_size: Option<usize>,
+    }
+
+    impl Handler for TestHandler {
+        fn call_method(&self, name: &str, params: Array) -> Option<Result<JsonValue, JsonValue>> {
+            match name {
+                "echo" => Some(Ok(params.into_iter().next().unwrap_or(Null))),
+                "fail" => Some(Err(object!{"message" => "failed"})),
+                _ => None,
+            }
+        }
+
+        fn max_batch_size(&self) -> Option<usize> {
+            self.max_batch_size
+        }
+    }
+
+    fn request(handler: TestHandler, body: &str) -> (StatusCode, JsonValue) {
+        let response = handle_request(Arc::new(handler), Ok(body));
+        let status = response.status();
+        let body = response.into_body().concat2().wait().unwrap();
+        let body = std::str::from_utf8(&body).unwrap();
+        (status, if body.is_empty() { Null } else { json::parse(body).unwrap() })
+    }
+
+    #[test]
+    fn it_answers_batches() {
+        let (status, response) = request(TestHandler { max_batch_size: None }, r#"[
+            {"jsonrpc": "2.0", "id": 1, "method": "echo", "params": [42]},
+            {"jsonrpc": "2.0", "id": 2, "method": "fail"},
+            {"jsonrpc": "2.0", "id": 3, "method": "unknown"},
+            {"jsonrpc": "2.0", "method": "echo", "params": [23]},
+            {"id": 4}
+        ]"#);
+        assert_eq!(status, StatusCode::OK);
+        // The notification isn't answered
+        assert_eq!(response.len(), 4);
+        assert_eq!(response[0]["id"], 1);
+        assert_eq!(response[0]["result"], 42);
+        assert_eq!(response[1]["error"]["message"], "failed");
+        assert_eq!(response[2]["error"]["code"], -32601);
+        assert_eq!(response[3]["id"], 4);
+        assert_eq!(response[3]["error"]["code"], -32600);
+    }
+
+    #[test]
+    fn it_limits_the_batch_size() {
+        let batch = r#"[
+            {"jsonrpc": "2.0", "id": 1, "method": "echo"},
+            {"jsonrpc": "2.0", "id": 2, "method": "echo"}
+        ]"#;
+
+        let (status, response) = request(TestHandler { max_batch_size: Some(1) }, batch);
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(response["error"]["code"], -32600);
+
+        let (status, response) = request(TestHandler { max_batch_size: Some(2) }, batch);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(response.len(), 2);
+
+        let (status, _) = request(TestHandler { max_batch_size: None }, "[]");
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn it_answers_single_notifications() {
+        let (status, response) = request(TestHandler { max_batch_size: None },
+                                         r#"{"jsonrpc": "2.0", "method": "echo", "params": [42]}"#);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(response["id"], Null);
+        assert_eq!(response["result"], 42);
+    }
+
+    #[test]
+    fn it_does_not_answer_batches_of_notifications() {
+        let (status, response) = request(TestHandler { max_batch_size: None }, r#"[
+            {"jsonrpc": "2.0", "method": "echo", "params": [1]},
+            {"jsonrpc": "2.0", "method": "echo", "params": [2]}
+        ]"#);
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(response, Null);
+    }
+}
@@ -14,6 +14,7 @@ extern crate nimiq_keys as keys;
 extern crate nimiq_network_primitives as network_primitives;
 extern crate nimiq_primitives as primitives;
 extern crate nimiq_transaction as transaction;
+extern crate nimiq_transaction_builder as transaction_builder;
 extern crate nimiq_utils as utils;
 #[cfg(feature="validator")]
 extern crate nimiq_validator as validator;
@@ -24,8 +25,12 @@ use std::sync::Arc;
 
 use futures::future::Future;
 use hyper::Server;
+use hyper::server::conn::AddrStream;
+use hyper::service::make_service_fn;
 use json::{JsonValue, object};
 
+use utils::ip_allowlist::IpAllowlist;
+
 use crate::error::Error;
 pub use crate::handler::Handler;
 use futures::IntoFuture;
@@ -45,8 +50,11 @@ pub struct JsonRpcConfig {
     pub username: Option<String>,
     pub password: Option<String>,
     pub methods: HashSet<String>,
-    pub allowip: (),
+    /// Only allow requests from these IP addresses and ranges (all if empty).
+    pub allowip: IpAllowlist,
     pub corsdomain: Vec<String>,
+    /// Maximum number of requests in a batch (no limit if `None` or 0).
+    pub max_batch_size: Option<usize>,
 }
 
 
@@ -63,9 +71,9 @@ impl RpcServer {
 
         let handler2 = Arc::clone(&handler);
         let future = Box::new(Server::try_bind(&SocketAddr::new(ip, port))?
-            .serve(move || {
-                jsonrpc::Service::new(Arc::clone(&handler2))
-            })
+            .serve(make_service_fn(move |socket: &AddrStream| {
+                jsonrpc::Service::new(Arc::clone(&handler2), socket.remote_addr())
+            }))
             .map_err(|e| error!("RPC server failed: {}", e)));
 
         Ok(RpcServer {
@@ -0,0 +1,257 @@
+extern crate nimiq_bls as bls;
+extern crate nimiq_database as database;
+extern crate nimiq_primitives as primitives;
+extern crate nimiq_utils as utils;
+extern crate nimiq_validator as validator;
+
+use std::path::Path;
+use std::process::exit;
+use std::sync::Arc;
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand, crate_version, crate_authors};
+use failure::{Error, Fail};
+
+use bls::bls12_381::KeyPair;
+use database::lmdb::{LmdbEnvironment, open as LmdbFlags};
+use primitives::networks::NetworkId;
+use utils::key_store::KeyStore;
+use validator::signer::{AuthKey, LocalSigner, SignerAddress, SignerServer};
+use validator::slashing_protection::SlashingProtection;
+
+
+fn key_file_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("key_file")
+        .short("k")
+        .long("key-file")
+        .value_name("FILE")
+        .help("Validator key file, as written by the client.")
+        .takes_value(true)
+        .required(true)
+}
+
+fn slashing_protection_db_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("slashing_protection_db")
+        .short("d")
+        .long("slashing-protection-db")
+        .value_name("PATH")
+        .help("Directory of the slashing protection database.")
+        .takes_value(true)
+        .required(true)
+}
+
+/// Subcommand moving the slashing protection database from or to an interchange file, in the same
+/// format as the client's `export-slashing-protection` and `import-slashing-protection` commands.
+fn interchange_subcommand<'a, 'b>(name: &'a str, about: &'a str) -> App<'a, 'b> {
+    SubCommand::with_name(name)
+        .about(about)
+        .arg(key_file_arg())
+        .arg(slashing_protection_db_arg())
+        .arg(Arg::with_name("network")
+            .short("n")
+            .long("network")
+            .value_name("NETWORK")
+            .help("Network the validator is on, e.g. devalbatross.")
+            .takes_value(true)
+            .required(true))
+        .arg(Arg::with_name("file")
+            .value_name("FILE")
+            .help("Interchange file")
+            .required(true))
+}
+
+fn run_app() -> Result<(), Error> {
+    let matches = App::new("Nimiq remote signer")
+        .version(crate_version!())
+        .author(crate_authors!())
+        .about("Holds a validator key and signs for a remote validator")
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .arg(key_file_arg())
+        .arg(Arg::with_name("auth_key_file")
+            .short("a")
+            .long("auth-key-file")
+            .value_name("FILE")
+            .help("File with the hex encoded key shared with the validator.")
+            .takes_value(true)
+            .required(true))
+        .arg(Arg::with_name("generate_auth_key")
+            .long("generate-auth-key")
+            .help("Generate the authentication key file if it doesn't exist.")
+            .takes_value(false))
+        .arg(Arg::with_name("listen")
+            .short("l")
+            .long("listen")
+            .value_name("ADDRESS")
+            .help("Listen on ADDRESS, either tcp://<host>:<port> or unix:<path>.")
+            .takes_value(true)
+            .required(true))
+        .arg(slashing_protection_db_arg())
+        .subcommand(interchange_subcommand("export", "Exports the slashing protection database to an interchange file"))
+        .subcommand(interchange_subcommand("import", "Merges an interchange file into the slashing protection database. This must be done before the signer is started with the validator key."))
+        .get_matches();
+
+    simple_logger::init_with_level(log::Level::Info)?;
+
+    match matches.subcommand() {
+        ("export", Some(matches)) => {
+            let exported = export_slashing_protection(&interchange_params(matches)?)?;
+            log::info!("Exported {} slashing protection records", exported);
+            Ok(())
+        },
+        ("import", Some(matches)) => {
+            let (updated, total) = import_slashing_protection(&interchange_params(matches)?)?;
+            log::info!("Imported {} of {} slashing protection records", updated, total);
+            Ok(())
+        },
+        _ => run_signer(&matches),
+    }
+}
+
+fn run_signer(matches: &ArgMatches) -> Result<(), Error> {
+    // Unwraps are safe, since these arguments are required.
+    let key_pair = load_key(matches.value_of("key_file").unwrap())?;
+
+    let auth_key_file = Path::new(matches.value_of("auth_key_file").unwrap());
+    let auth_key = if !auth_key_file.exists() && matches.is_present("generate_auth_key") {
+        let auth_key = AuthKey::generate();
+        auth_key.save(auth_key_file)?;
+        log::info!("Generated authentication key: {}", auth_key_file.display());
+        auth_key
+    } else {
+        AuthKey::load(auth_key_file)?
+    };
+
+    let address: SignerAddress = matches.value_of("listen").unwrap().parse()?;
+    let slashing_protection = open_slashing_protection(matches.value_of("slashing_protection_db").unwrap())?;
+
+    log::info!("Signing with validator key {}", key_pair.public.compress());
+    log::info!("Listening on {}", address);
+
+    let signer = LocalSigner::new(key_pair, slashing_protection);
+    SignerServer::new(Arc::new(signer), auth_key).run(&address)?;
+    Ok(())
+}
+
+fn load_key(key_file: &str) -> Result<KeyPair, AppError> {
+    KeyStore::new(key_file.to_string())
+        .load_key()
+        .map_err(|e| AppError::KeyFile(e.to_string()))
+}
+
+fn open_slashing_protection(path: &str) -> Result<SlashingProtection, Error> {
+    // Don't relax syncing here: A record must be on disk before the message is signed.
+    let env = LmdbEnvironment::new(path, 1024 * 1024, 1, LmdbFlags::Flags::empty())?;
+    Ok(SlashingProtection::new(env))
+}
+
+struct InterchangeParams {
+    key_file: String,
+    slashing_protection_db: String,
+    network_id: NetworkId,
+    file: String,
+}
+
+fn interchange_params(matches: &ArgMatches) -> Result<InterchangeParams, Error> {
+    // Unwraps are safe, since these arguments are required.
+    Ok(InterchangeParams {
+        key_file: matches.value_of("key_file").unwrap().to_string(),
+        slashing_protection_db: matches.value_of("slashing_protection_db").unwrap().to_string(),
+        network_id: matches.value_of("network").unwrap().parse()?,
+        file: matches.value_of("file").unwrap().to_string(),
+    })
+}
+
+/// Returns the number of exported records.
+fn export_slashing_protection(params: &InterchangeParams) -> Result<usize, Error> {
+    let public_key = load_key(&params.key_file)?.public.compress();
+    let exported = open_slashing_protection(&params.slashing_protection_db)?
+        .export_to_file(&params.file, params.network_id, public_key)?;
+    Ok(exported)
+}
+
+/// Returns the number of updated records and the number of records in the file.
+fn import_slashing_protection(params: &InterchangeParams) -> Result<(usize, usize), Error> {
+    let public_key = load_key(&params.key_file)?.public.compress();
+    let imported = open_slashing_protection(&params.slashing_protection_db)?
+        .import_from_file(&params.file, params.network_id, &public_key)?;
+    Ok(imported)
+}
+
+fn main() {
+    exit(match run_app() {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    });
+}
+
+
+#[derive(Debug, Fail)]
+enum AppError {
+    #[fail(display = "Failed to load validator key: {}", _0)]
+    KeyFile(String),
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+
+    use bls::SecureGenerate;
+    use nimiq_hash::{Blake2bHasher, Hasher};
+    use validator::slashing_protection::{SignedMessageKind, SigningRecord};
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("nimiq-signer-{}-{}", name, process::id()));
+        fs::remove_dir_all(&path).unwrap_or(());
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    fn params(dir: &Path, db: &str, file: &Path) -> InterchangeParams {
+        InterchangeParams {
+            key_file: dir.join("validator_key.dat").to_str().unwrap().to_string(),
+            slashing_protection_db: dir.join(db).to_str().unwrap().to_string(),
+            network_id: NetworkId::DevAlbatross,
+            file: file.to_str().unwrap().to_string(),
+        }
+    }
+
+    #[test]
+    fn it_exports_and_imports_slashing_protection() {
+        let dir = temp_dir("interchange");
+        let key_file = dir.join("validator_key.dat");
+        KeyStore::new(key_file.to_str().unwrap().to_string())
+            .save_key(&KeyPair::generate_default_csprng())
+            .unwrap();
+        let file = dir.join("slashing_protection.dat");
+
+        // The signer signed a pBFT prepare on the old machine.
+        let record = SigningRecord::new(SignedMessageKind::PbftPrepare, 128, 0, Blake2bHasher::default().digest(b"a"));
+        let old_params = params(&dir, "old", &file);
+        open_slashing_protection(&old_params.slashing_protection_db).unwrap()
+            .check_and_record(record.clone())
+            .unwrap();
+        assert_eq!(export_slashing_protection(&old_params).unwrap(), 1);
+
+        // On the new machine, it must not sign another prepare for that block and view.
+        let new_params = params(&dir, "new", &file);
+        assert_eq!(import_slashing_protection(&new_params).unwrap(), (1, 1));
+        let slashing_protection = open_slashing_protection(&new_params.slashing_protection_db).unwrap();
+        assert_eq!(slashing_protection.get(SignedMessageKind::PbftPrepare), Some(record));
+        assert!(slashing_protection.check_and_record(SigningRecord::new(SignedMessageKind::PbftPrepare, 128, 0, Blake2bHasher::default().digest(b"b"))).is_err());
+        drop(slashing_protection);
+
+        // The file is bound to the network.
+        let wrong_network = InterchangeParams { network_id: NetworkId::TestAlbatross, ..params(&dir, "other", &file) };
+        assert!(import_slashing_protection(&wrong_network).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,5 +1,5 @@
 use beserial::{Deserialize, Serialize};
-use bls::bls12_381::KeyPair as BlsKeyPair;
+use bls::bls12_381::{CompressedSignature, KeyPair as BlsKeyPair, PublicKey as BlsPublicKey};
 use keys::KeyPair;
 use transaction::account::staking_contract::{
     IncomingStakingTransactionData, OutgoingStakingTransactionProof,
@@ -32,12 +32,17 @@ impl SignallingProofBuilder {
     /// This method sets the required signalling `signature` proof by signing the transaction
     /// using a BLS key pair `validator_key_pair`.
     pub fn sign_with_validator_key_pair(&mut self, validator_key_pair: &BlsKeyPair) -> &mut Self {
-        // Set validator signature first.
-        let mut data: IncomingStakingTransactionData =
-            Deserialize::deserialize_from_vec(&self.transaction.data[..]).unwrap();
         let validator_signature =
             validator_key_pair.sign(&self.transaction.serialize_content().as_slice());
-        data.set_validator_signature(validator_signature.compress());
+        self.set_validator_signature(validator_signature.compress())
+    }
+
+    /// This method sets the required signalling `signature` proof from a signature over the
+    /// transaction content that was created elsewhere, e.g. by a remote signer.
+    pub fn set_validator_signature(&mut self, signature: CompressedSignature) -> &mut Self {
+        let mut data: IncomingStakingTransactionData =
+            Deserialize::deserialize_from_vec(&self.transaction.data[..]).unwrap();
+        data.set_validator_signature(signature);
         self.data = Some(data);
         self
     }
@@ -81,6 +86,17 @@ impl StakingProofBuilder {
         self
     }
 
+    /// This methods sets the action to drop the validator with public key `validator_key` from
+    /// a `signature` over the transaction content that was created elsewhere, e.g. by a remote
+    /// signer.
+    pub fn set_drop_validator_signature(&mut self, validator_key: &BlsPublicKey, signature: CompressedSignature) -> &mut Self {
+        self.proof = Some(OutgoingStakingTransactionProof::DropValidator {
+            validator_key: validator_key.compress(),
+            signature,
+        });
+        self
+    }
+
     /// This methods sets the action to unstake and builds the corresponding proof
     /// from a staker's `key_pair`.
     pub fn unstake(&mut self, key_pair: &KeyPair) -> &mut Self {
@@ -93,12 +93,43 @@ impl StakingRecipientBuilder {
 
     /// This method allows to create a new validator entry using a BLS key pair `key_pair`.
     /// All rewards for this validator will be paid out to its `reward_address`.
+    /// The validator does not charge a commission on its stakers' rewards.
     pub fn create_validator(&mut self, key_pair: &KeyPair, reward_address: Address) -> &mut Self {
+        self.create_validator_with_commission(key_pair, reward_address, 0)
+    }
+
+    /// This method allows to create a new validator entry using a BLS key pair `key_pair`.
+    /// All rewards for this validator will be paid out to its `reward_address`.
+    /// The validator keeps a `commission` (in basis points) of its stakers' rewards.
+    pub fn create_validator_with_commission(&mut self, key_pair: &KeyPair, reward_address: Address, commission: u16) -> &mut Self {
         self.staking_data = Some(StakingTransaction::IncomingTransaction(
             IncomingStakingTransactionData::CreateValidator {
                 validator_key: key_pair.public.compress(),
                 proof_of_knowledge: StakingRecipientBuilder::generate_proof_of_knowledge(&key_pair),
                 reward_address,
+                commission,
+            },
+        ));
+        self
+    }
+
+    /// This method allows to create a new validator entry from its public key `validator_key`
+    /// and a `proof_of_knowledge` of the corresponding secret key, which have been generated
+    /// elsewhere, e.g. by the validator itself.
+    /// The validator keeps a `commission` (in basis points) of its stakers' rewards.
+    pub fn create_validator_with_proof_of_knowledge(
+        &mut self,
+        validator_key: &PublicKey,
+        proof_of_knowledge: CompressedSignature,
+        reward_address: Address,
+        commission: u16,
+    ) -> &mut Self {
+        self.staking_data = Some(StakingTransaction::IncomingTransaction(
+            IncomingStakingTransactionData::CreateValidator {
+                validator_key: validator_key.compress(),
+                proof_of_knowledge,
+                reward_address,
+                commission,
             },
         ));
         self
@@ -131,6 +162,24 @@ impl StakingRecipientBuilder {
                 new_proof_of_knowledge: new_key_pair
                     .map(|key| StakingRecipientBuilder::generate_proof_of_knowledge(&key)),
                 new_reward_address,
+                new_commission: None,
+                signature: Default::default(),
+            },
+        ));
+        self
+    }
+
+    /// This method allows to change the commission (in basis points) of an existing validator
+    /// entry with the public key `validator_key`.
+    /// The new commission only takes effect starting in the following epoch.
+    pub fn update_validator_commission(&mut self, validator_key: &PublicKey, new_commission: u16) -> &mut Self {
+        self.staking_data = Some(StakingTransaction::IncomingTransaction(
+            IncomingStakingTransactionData::UpdateValidator {
+                old_validator_key: validator_key.compress(),
+                new_validator_key: None,
+                new_proof_of_knowledge: None,
+                new_reward_address: None,
+                new_commission: Some(new_commission),
                 signature: Default::default(),
             },
         ));
@@ -105,6 +105,7 @@ fn it_can_verify_validator_transactions() {
             validator_key: bls_pair.public.compress(),
             proof_of_knowledge: bls_pair.sign(&bls_pair.public).compress(),
             reward_address: Address::from_any_str(STAKER_ADDRESS).unwrap(),
+            commission: 0,
         },
         150_000_000,
         &bls_pair,
@@ -135,6 +136,7 @@ fn it_can_verify_validator_transactions() {
             new_validator_key: None,
             new_proof_of_knowledge: None,
             new_reward_address: Some(Address::from([1u8; 20])),
+            new_commission: None,
             signature: Default::default(),
         },
         0,
@@ -161,6 +163,40 @@ fn it_can_verify_validator_transactions() {
 
     assert_eq!(tx2, tx);
 
+    // Update commission
+    let tx = make_signed_incoming_transaction(
+        IncomingStakingTransactionData::UpdateValidator {
+            old_validator_key: bls_pair.public.compress(),
+            new_validator_key: None,
+            new_proof_of_knowledge: None,
+            new_reward_address: None,
+            new_commission: Some(500),
+            signature: Default::default(),
+        },
+        0,
+        &bls_pair,
+        &key_pair,
+    );
+
+    let mut recipient = Recipient::new_staking_builder(Address::from([1u8; 20]));
+    recipient.update_validator_commission(&bls_pair.public, 500);
+
+    let mut tx_builder = TransactionBuilder::new();
+    tx_builder
+        .with_sender(Address::from_any_str(STAKER_ADDRESS).unwrap())
+        .with_value(0.try_into().unwrap())
+        .with_fee(100.try_into().unwrap())
+        .with_network_id(NetworkId::Dummy)
+        .with_validity_start_height(1)
+        .with_recipient(recipient.generate().unwrap());
+    let mut proof_builder = tx_builder.generate().unwrap().unwrap_signalling();
+    proof_builder.sign_with_validator_key_pair(&bls_pair);
+    let mut proof_builder = proof_builder.generate().unwrap().unwrap_basic();
+    proof_builder.sign_with_key_pair(&key_pair);
+    let tx2 = proof_builder.generate().unwrap();
+
+    assert_eq!(tx2, tx);
+
     // Retire
     let tx = make_signed_incoming_transaction(
         IncomingStakingTransactionData::RetireValidator {
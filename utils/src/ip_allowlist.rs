@@ -0,0 +1,169 @@
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+/// A range of IP addresses in CIDR notation, e.g. `10.0.0.0/8` or `fd00::/8`.
+/// A plain IP address is treated as a range containing only this address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IpNetwork {
+    address: IpAddr,
+    prefix_len: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IpNetworkParseError {
+    InvalidAddress(String),
+    InvalidPrefixLength(String),
+}
+
+impl fmt::Display for IpNetworkParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IpNetworkParseError::InvalidAddress(s) => write!(f, "Invalid IP address: {}", s),
+            IpNetworkParseError::InvalidPrefixLength(s) => write!(f, "Invalid prefix length: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for IpNetworkParseError {}
+
+impl IpNetwork {
+    /// Creates a new network from an address and a prefix length.
+    /// Host bits of the address are cleared.
+    pub fn new(address: IpAddr, prefix_len: u8) -> Result<Self, IpNetworkParseError> {
+        if prefix_len > max_prefix_len(&address) {
+            return Err(IpNetworkParseError::InvalidPrefixLength(prefix_len.to_string()));
+        }
+
+        Ok(IpNetwork {
+            address: mask(&address, prefix_len),
+            prefix_len,
+        })
+    }
+
+    pub fn address(&self) -> IpAddr {
+        self.address
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Checks whether `ip` is part of this network.
+    /// IPv4-mapped IPv6 addresses (`::ffff:a.b.c.d`) are matched against IPv4 networks.
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        let ip = normalize(ip);
+        match (&self.address, &ip) {
+            (IpAddr::V4(_), IpAddr::V4(_)) | (IpAddr::V6(_), IpAddr::V6(_)) => {
+                mask(&ip, self.prefix_len) == self.address
+            },
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = IpNetworkParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (address, prefix_len) = match s.find('/') {
+            Some(index) => (&s[..index], Some(&s[index + 1..])),
+            None => (s, None),
+        };
+
+        let address: IpAddr = address.parse()
+            .map_err(|_| IpNetworkParseError::InvalidAddress(address.to_string()))?;
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => prefix_len.parse()
+                .map_err(|_| IpNetworkParseError::InvalidPrefixLength(prefix_len.to_string()))?,
+            None => max_prefix_len(&address),
+        };
+
+        IpNetwork::new(address, prefix_len)
+    }
+}
+
+impl fmt::Display for IpNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.address, self.prefix_len)
+    }
+}
+
+impl From<IpAddr> for IpNetwork {
+    fn from(address: IpAddr) -> Self {
+        IpNetwork {
+            address,
+            prefix_len: max_prefix_len(&address),
+        }
+    }
+}
+
+/// A list of IP networks that are allowed to connect to a server.
+/// An empty allowlist allows all addresses.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IpAllowlist {
+    networks: Vec<IpNetwork>,
+}
+
+impl IpAllowlist {
+    pub fn new(networks: Vec<IpNetwork>) -> Self {
+        IpAllowlist { networks }
+    }
+
+    /// Parses a list of IP addresses and CIDR ranges.
+    pub fn parse<S: AsRef<str>>(networks: &[S]) -> Result<Self, IpNetworkParseError> {
+        let networks = networks.iter()
+            .map(|s| s.as_ref().parse())
+            .collect::<Result<Vec<IpNetwork>, IpNetworkParseError>>()?;
+        Ok(IpAllowlist { networks })
+    }
+
+    pub fn networks(&self) -> &[IpNetwork] {
+        &self.networks
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.networks.is_empty()
+    }
+
+    pub fn is_allowed(&self, ip: &IpAddr) -> bool {
+        self.networks.is_empty() || self.networks.iter().any(|network| network.contains(ip))
+    }
+}
+
+fn max_prefix_len(address: &IpAddr) -> u8 {
+    match address {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+fn mask(address: &IpAddr, prefix_len: u8) -> IpAddr {
+    match address {
+        IpAddr::V4(ip) => {
+            let mask = u32::max_value().checked_shl(32 - u32::from(prefix_len)).unwrap_or(0);
+            IpAddr::V4(Ipv4Addr::from(u32::from(*ip) & mask))
+        },
+        IpAddr::V6(ip) => {
+            let mask = u128::max_value().checked_shl(128 - u32::from(prefix_len)).unwrap_or(0);
+            IpAddr::V6(Ipv6Addr::from(u128::from(*ip) & mask))
+        },
+    }
+}
+
+fn normalize(address: &IpAddr) -> IpAddr {
+    match address {
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            if segments[..6] == [0, 0, 0, 0, 0, 0xffff] {
+                let [a, b] = segments[6].to_be_bytes();
+                let [c, d] = segments[7].to_be_bytes();
+                IpAddr::V4(Ipv4Addr::new(a, b, c, d))
+            } else {
+                *address
+            }
+        },
+        _ => *address,
+    }
+}
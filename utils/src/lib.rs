@@ -43,3 +43,5 @@ pub mod math;
 pub mod key_rng;
 #[cfg(feature = "hash-rng")]
 pub mod hash_rng;
+#[cfg(feature = "ip-allowlist")]
+pub mod ip_allowlist;
@@ -0,0 +1,83 @@
+use std::net::IpAddr;
+
+use nimiq_utils::ip_allowlist::*;
+
+fn ip(s: &str) -> IpAddr {
+    s.parse().unwrap()
+}
+
+#[test]
+fn it_can_parse_networks() {
+    let network: IpNetwork = "192.168.1.7/24".parse().unwrap();
+    assert_eq!(network.address(), ip("192.168.1.0"));
+    assert_eq!(network.prefix_len(), 24);
+    assert_eq!(network.to_string(), "192.168.1.0/24");
+
+    let network: IpNetwork = "10.0.0.1".parse().unwrap();
+    assert_eq!(network.address(), ip("10.0.0.1"));
+    assert_eq!(network.prefix_len(), 32);
+
+    let network: IpNetwork = "fd00:1234::1/16".parse().unwrap();
+    assert_eq!(network.address(), ip("fd00::"));
+    assert_eq!(network.prefix_len(), 16);
+
+    let network: IpNetwork = "::1".parse().unwrap();
+    assert_eq!(network.prefix_len(), 128);
+
+    let network: IpNetwork = "0.0.0.0/0".parse().unwrap();
+    assert_eq!(network.prefix_len(), 0);
+
+    assert!("10.0.0.1/33".parse::<IpNetwork>().is_err());
+    assert!("::1/129".parse::<IpNetwork>().is_err());
+    assert!("10.0.0.1/a".parse::<IpNetwork>().is_err());
+    assert!("10.0.0/8".parse::<IpNetwork>().is_err());
+    assert!("localhost".parse::<IpNetwork>().is_err());
+}
+
+#[test]
+fn it_matches_ipv4_networks() {
+    let network: IpNetwork = "192.168.1.0/24".parse().unwrap();
+    assert!(network.contains(&ip("192.168.1.0")));
+    assert!(network.contains(&ip("192.168.1.255")));
+    assert!(!network.contains(&ip("192.168.2.1")));
+    assert!(!network.contains(&ip("::1")));
+
+    // IPv4-mapped IPv6 addresses are matched against IPv4 networks.
+    assert!(network.contains(&ip("::ffff:192.168.1.42")));
+    assert!(!network.contains(&ip("::ffff:192.168.2.42")));
+
+    let network: IpNetwork = "0.0.0.0/0".parse().unwrap();
+    assert!(network.contains(&ip("1.2.3.4")));
+    assert!(!network.contains(&ip("fd00::1")));
+}
+
+#[test]
+fn it_matches_ipv6_networks() {
+    let network: IpNetwork = "fd00::/8".parse().unwrap();
+    assert!(network.contains(&ip("fd00::1")));
+    assert!(network.contains(&ip("fdff:ffff::1")));
+    assert!(!network.contains(&ip("fe80::1")));
+    assert!(!network.contains(&ip("127.0.0.1")));
+
+    let network: IpNetwork = "::1".parse().unwrap();
+    assert!(network.contains(&ip("::1")));
+    assert!(!network.contains(&ip("::2")));
+}
+
+#[test]
+fn it_can_check_allowlists() {
+    let allowlist = IpAllowlist::default();
+    assert!(allowlist.is_empty());
+    assert!(allowlist.is_allowed(&ip("1.2.3.4")));
+    assert!(allowlist.is_allowed(&ip("::1")));
+
+    let allowlist = IpAllowlist::parse(&["127.0.0.1", "10.0.0.0/8", "fd00::/8"]).unwrap();
+    assert_eq!(allowlist.networks().len(), 3);
+    assert!(allowlist.is_allowed(&ip("127.0.0.1")));
+    assert!(allowlist.is_allowed(&ip("10.20.30.40")));
+    assert!(allowlist.is_allowed(&ip("fd12::1")));
+    assert!(!allowlist.is_allowed(&ip("127.0.0.2")));
+    assert!(!allowlist.is_allowed(&ip("::1")));
+
+    assert!(IpAllowlist::parse(&["127.0.0.1", "300.0.0.1"]).is_err());
+}
@@ -17,4 +17,6 @@ pub mod rate_limit;
 #[cfg(feature = "unique-id")]
 pub mod unique_id;
 #[cfg(feature = "otp")]
-pub mod otp;
\ No newline at end of file
+pub mod otp;
+#[cfg(feature = "ip-allowlist")]
+pub mod ip_allowlist;
\ No newline at end of file
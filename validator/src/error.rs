@@ -4,6 +4,8 @@ use blockchain_base::BlockchainError;
 use consensus::Error as ConsensusError;
 use utils::key_store::Error as KeyStoreError;
 
+use crate::signer::SignerError;
+
 
 #[derive(Fail, Debug)]
 pub enum Error {
@@ -13,6 +15,8 @@ pub enum Error {
     ConsensusError(#[cause] ConsensusError),
     #[fail(display = "{}", _0)]
     KeyStoreError(#[cause] KeyStoreError),
+    #[fail(display = "{}", _0)]
+    SignerError(#[cause] SignerError),
 }
 
 impl From<ConsensusError> for Error {
@@ -32,3 +36,9 @@ impl From<BlockchainError> for Error {
         Error::BlockchainError(e)
     }
 }
+
+impl From<SignerError> for Error {
+    fn from(e: SignerError) -> Self {
+        Error::SignerError(e)
+    }
+}
@@ -2,6 +2,8 @@
 
 #[macro_use]
 extern crate log;
+#[macro_use]
+extern crate beserial_derive;
 extern crate nimiq_macros as macros;
 extern crate nimiq_handel as handel;
 
@@ -22,13 +24,20 @@ extern crate nimiq_blockchain_albatross as blockchain_albatross;
 extern crate nimiq_blockchain_base as blockchain_base;
 extern crate nimiq_block_production_albatross as block_production_albatross;
 extern crate nimiq_keys as keys;
+extern crate nimiq_transaction as transaction;
 extern crate nimiq_transaction_builder as transaction_builder;
+extern crate nimiq_vrf as vrf;
+extern crate tokio_threadpool;
 
 pub mod validator;
 pub mod validator_network;
 pub mod validator_agent;
 pub mod error;
 pub mod slash;
+pub mod slashing_protection;
+pub mod signer;
 pub mod signature_aggregation;
 pub mod pool;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 
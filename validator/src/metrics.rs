@@ -0,0 +1,133 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use parking_lot::Mutex;
+
+use crate::validator_network::ValidatorNetworkEvent;
+
+
+#[derive(Default)]
+pub struct ValidatorMetrics {
+    micro_block_produced_count: AtomicUsize,
+    micro_block_missed_count: AtomicUsize,
+    view_change_started_count: AtomicUsize,
+    view_change_completed_count: AtomicUsize,
+    fork_proof_count: AtomicUsize,
+    pbft_prepare_votes: AtomicUsize,
+    pbft_commit_votes: AtomicUsize,
+    pbft_completed_count: AtomicUsize,
+    pbft_latency_ms_sum: AtomicUsize,
+    pbft_last_latency_ms: AtomicUsize,
+    /// Start of the current pBFT round, i.e. when its proposal was received.
+    pbft_round_start: Mutex<Option<Instant>>,
+}
+
+impl ValidatorMetrics {
+    pub fn note_validator_network_event(&self, event: &ValidatorNetworkEvent) {
+        match event {
+            ValidatorNetworkEvent::ForkProof(_) => self.note_fork_proof(),
+            ValidatorNetworkEvent::ViewChangeComplete(_) => self.note_view_change_completed(),
+            ValidatorNetworkEvent::PbftProposal(_) => self.note_pbft_proposal(),
+            ValidatorNetworkEvent::PbftUpdate(event) => self.note_pbft_votes(event.prepare_votes, event.commit_votes),
+            ValidatorNetworkEvent::PbftComplete(_) => self.note_pbft_completed(),
+            _ => {},
+        }
+    }
+
+    #[inline]
+    pub fn note_micro_block_produced(&self) {
+        self.micro_block_produced_count.fetch_add(1, Ordering::Release);
+    }
+
+    #[inline]
+    pub fn micro_block_produced_count(&self) -> usize {
+        self.micro_block_produced_count.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub fn note_micro_block_missed(&self) {
+        self.micro_block_missed_count.fetch_add(1, Ordering::Release);
+    }
+
+    #[inline]
+    pub fn micro_block_missed_count(&self) -> usize {
+        self.micro_block_missed_count.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub fn note_view_change_started(&self) {
+        self.view_change_started_count.fetch_add(1, Ordering::Release);
+    }
+
+    #[inline]
+    pub fn view_change_started_count(&self) -> usize {
+        self.view_change_started_count.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub fn note_view_change_completed(&self) {
+        self.view_change_completed_count.fetch_add(1, Ordering::Release);
+    }
+
+    #[inline]
+    pub fn view_change_completed_count(&self) -> usize {
+        self.view_change_completed_count.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub fn note_fork_proof(&self) {
+        self.fork_proof_count.fetch_add(1, Ordering::Release);
+    }
+
+    #[inline]
+    pub fn fork_proof_count(&self) -> usize {
+        self.fork_proof_count.load(Ordering::Acquire)
+    }
+
+    /// Starts a new pBFT round.
+    pub fn note_pbft_proposal(&self) {
+        *self.pbft_round_start.lock() = Some(Instant::now());
+        self.note_pbft_votes(0, 0);
+    }
+
+    #[inline]
+    pub fn note_pbft_votes(&self, prepare_votes: usize, commit_votes: usize) {
+        self.pbft_prepare_votes.store(prepare_votes, Ordering::Release);
+        self.pbft_commit_votes.store(commit_votes, Ordering::Release);
+    }
+
+    #[inline]
+    pub fn pbft_prepare_votes(&self) -> usize {
+        self.pbft_prepare_votes.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub fn pbft_commit_votes(&self) -> usize {
+        self.pbft_commit_votes.load(Ordering::Acquire)
+    }
+
+    /// Completes the current pBFT round and records its latency.
+    pub fn note_pbft_completed(&self) {
+        self.pbft_completed_count.fetch_add(1, Ordering::Release);
+        if let Some(start) = self.pbft_round_start.lock().take() {
+            let latency_ms = start.elapsed().as_millis() as usize;
+            self.pbft_latency_ms_sum.fetch_add(latency_ms, Ordering::Release);
+            self.pbft_last_latency_ms.store(latency_ms, Ordering::Release);
+        }
+    }
+
+    #[inline]
+    pub fn pbft_completed_count(&self) -> usize {
+        self.pbft_completed_count.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub fn pbft_latency_ms_sum(&self) -> usize {
+        self.pbft_latency_ms_sum.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub fn pbft_last_latency_ms(&self) -> usize {
+        self.pbft_last_latency_ms.load(Ordering::Acquire)
+    }
+}
@@ -4,3 +4,21 @@
 pub mod voting;
 pub mod view_change;
 pub mod pbft;
+
+
+use handel::config::Config;
+
+
+/// Handel configurations of the aggregations a validator takes part in
+#[derive(Clone, Debug, Default)]
+pub struct AggregationConfig {
+    /// Configuration of view change aggregations
+    pub view_change: Config,
+
+    /// Configuration of pBFT prepare aggregations
+    pub pbft_prepare: Config,
+
+    /// Configuration of pBFT commit aggregations. Commits complete later than prepares, so they
+    /// need their own timeout strategy instance, even if both are configured the same way.
+    pub pbft_commit: Config,
+}
@@ -17,7 +17,7 @@ use handel::store::{SignatureStore, ReplaceStore};
 use handel::aggregation::Aggregation;
 use handel::config::Config;
 use handel::partitioner::BinomialPartitioner;
-use handel::verifier::MultithreadedVerifier;
+use handel::verifier::BatchVerifier;
 use handel::multisig::{Signature, MultiSignature, IndividualSignature};
 use handel::identity::WeightRegistry;
 
@@ -89,7 +89,7 @@ pub struct PbftCommitProtocol {
 
     /// The verifier for the commit phase. The difference from the prepare verifier is only the message
     /// hash, which uses a different prefix.
-    verifier: Arc<MultithreadedVerifier<ValidatorRegistry>>,
+    verifier: Arc<BatchVerifier<ValidatorRegistry>>,
 
     sender: Arc<VotingSender<PbftCommitMessage>>,
 
@@ -98,7 +98,7 @@ pub struct PbftCommitProtocol {
 
 impl Protocol for PbftCommitProtocol {
     type Registry = ValidatorRegistry;
-    type Verifier = MultithreadedVerifier<ValidatorRegistry>;
+    type Verifier = BatchVerifier<ValidatorRegistry>;
     type Store = ReplaceStore<BinomialPartitioner>;
     type Evaluator = PbftCommitEvaluator;
     type Partitioner = BinomialPartitioner;
@@ -139,7 +139,7 @@ impl PbftCommitProtocol {
 
         let tag = PbftCommitMessage::from(prepare_protocol.tag.block_hash.clone());
         let registry = Arc::clone(&prepare_protocol.registry());
-        let verifier = Arc::new(MultithreadedVerifier::shared(
+        let verifier = Arc::new(BatchVerifier::shared(
             tag.hash_with_prefix(),
             Arc::clone(&registry),
         ));
@@ -193,8 +193,7 @@ pub struct PbftAggregation {
 
 
 impl PbftAggregation {
-    pub fn new(proposal_hash: Blake2bHash, node_id: usize, validators: Arc<RwLock<ValidatorPool>>, config: Option<Config>) -> Self {
-        let config = config.unwrap_or_default();
+    pub fn new(proposal_hash: Blake2bHash, node_id: usize, validators: Arc<RwLock<ValidatorPool>>, prepare_config: Option<Config>, commit_config: Option<Config>) -> Self {
 
         // create prepare aggregation
         let prepare_protocol = PbftPrepareProtocol::new(
@@ -202,11 +201,11 @@ impl PbftAggregation {
             node_id,
             validators,
         );
-        let prepare_aggregation = Aggregation::new(prepare_protocol, config.clone());
+        let prepare_aggregation = Aggregation::new(prepare_protocol, prepare_config.unwrap_or_default());
 
         // create commit aggregation
         let commit_protocol = PbftCommitProtocol::new(Arc::clone(&prepare_aggregation));
-        let commit_aggregation = Aggregation::new(commit_protocol, config);
+        let commit_aggregation = Aggregation::new(commit_protocol, commit_config.unwrap_or_default());
 
         Self {
             prepare_aggregation,
@@ -16,7 +16,7 @@ use collections::bitset::BitSet;
 use handel::protocol::Protocol;
 use handel::multisig::{IndividualSignature, Signature};
 use handel::identity::{IdentityRegistry, WeightRegistry};
-use handel::verifier::MultithreadedVerifier;
+use handel::verifier::BatchVerifier;
 use handel::config::Config;
 use handel::store::ReplaceStore;
 use handel::partitioner::BinomialPartitioner;
@@ -122,7 +122,7 @@ pub struct VotingProtocol<T: Tag> {
 
     // TODO: This should not be part of the protocol. Except that it holds the hash which depends
     // on the *specific* item being hashed (including prefix)
-    verifier: Arc<MultithreadedVerifier<ValidatorRegistry>>,
+    verifier: Arc<BatchVerifier<ValidatorRegistry>>,
 
     partitioner: Arc<BinomialPartitioner>,
     store: Arc<RwLock<ReplaceStore<BinomialPartitioner>>>,
@@ -146,7 +146,7 @@ impl<T: Tag> VotingProtocol<T> {
         }
 
         let registry = Arc::new(ValidatorRegistry::new(Arc::clone(&validators)));
-        let verifier = Arc::new(MultithreadedVerifier::shared(
+        let verifier = Arc::new(BatchVerifier::shared(
             tag.hash_with_prefix(),
             Arc::clone(&registry),
         ));
@@ -195,7 +195,7 @@ impl<T: Tag> fmt::Debug for VotingProtocol<T> {
 
 impl<T: Tag> Protocol for VotingProtocol<T> {
     type Registry = ValidatorRegistry;
-    type Verifier = MultithreadedVerifier<ValidatorRegistry>;
+    type Verifier = BatchVerifier<ValidatorRegistry>;
     type Store = ReplaceStore<BinomialPartitioner>;
     type Evaluator = VotingEvaluator;
     type Partitioner = BinomialPartitioner;
@@ -0,0 +1,36 @@
+use bls::bls12_381::{KeyPair, PublicKey, Signature};
+
+use crate::slashing_protection::SlashingProtection;
+
+use super::{Signer, SignerError, SigningRequest};
+
+
+/// Signs with a key pair held in this process.
+pub struct LocalSigner {
+    key_pair: KeyPair,
+    slashing_protection: SlashingProtection,
+}
+
+impl LocalSigner {
+    pub fn new(key_pair: KeyPair, slashing_protection: SlashingProtection) -> Self {
+        LocalSigner { key_pair, slashing_protection }
+    }
+}
+
+impl Signer for LocalSigner {
+    fn public_key(&self) -> &PublicKey {
+        &self.key_pair.public
+    }
+
+    fn sign(&self, request: &SigningRequest) -> Result<Signature, SignerError> {
+        // The record is persisted before we sign.
+        if let Some(record) = request.signing_record() {
+            self.slashing_protection.check_and_record(record)?;
+        }
+        Ok(self.key_pair.sign_hash(request.sig_hash(&self.key_pair.public)))
+    }
+
+    fn key_pair(&self) -> Option<&KeyPair> {
+        Some(&self.key_pair)
+    }
+}
@@ -0,0 +1,217 @@
+use std::io;
+
+use failure::Fail;
+
+use beserial::{Deserialize, Serialize, SerializingError};
+use block_albatross::{
+    MicroHeader,
+    PbftCommitMessage,
+    PbftPrepareMessage,
+    PbftProposal,
+    SignedPbftCommitMessage,
+    SignedPbftPrepareMessage,
+    SignedPbftProposal,
+    SignedViewChange,
+    ViewChange,
+};
+use block_albatross::signed::{Message, SignedMessage};
+use bls::bls12_381::{CompressedSignature, KeyPair, PublicKey, Signature};
+use hash::{Blake2bHash, Hash};
+use network_primitives::validator_info::{SignedValidatorInfo, ValidatorInfo};
+use transaction::Transaction;
+use vrf::VrfSeed;
+
+use crate::slashing_protection::{SignedMessageKind, SigningRecord, SlashingProtectionError};
+
+pub mod local;
+pub mod remote;
+mod protocol;
+
+pub use self::local::LocalSigner;
+pub use self::protocol::AuthKey;
+pub use self::remote::{RemoteSigner, SignerAddress, SignerServer};
+
+
+#[derive(Debug, Fail)]
+pub enum SignerError {
+    #[fail(display = "{}", _0)]
+    SlashingProtection(#[cause] SlashingProtectionError),
+    #[fail(display = "Signer refused request: {}", _0)]
+    Refused(String),
+    #[fail(display = "Signer returned an invalid signature")]
+    InvalidSignature,
+    #[fail(display = "Signer authentication failed")]
+    AuthenticationFailed,
+    #[fail(display = "Unsupported signer protocol version: {}", _0)]
+    UnsupportedVersion(u8),
+    #[fail(display = "Unexpected message from signer")]
+    UnexpectedMessage,
+    #[fail(display = "Invalid signer address: {}", _0)]
+    InvalidAddress(String),
+    #[fail(display = "Invalid authentication key")]
+    InvalidAuthKey,
+    #[fail(display = "I/O error: {}", _0)]
+    Io(#[cause] io::Error),
+    #[fail(display = "Serialization error: {}", _0)]
+    Serializing(#[cause] SerializingError),
+}
+
+impl From<SlashingProtectionError> for SignerError {
+    fn from(e: SlashingProtectionError) -> Self {
+        SignerError::SlashingProtection(e)
+    }
+}
+
+impl From<io::Error> for SignerError {
+    fn from(e: io::Error) -> Self {
+        SignerError::Io(e)
+    }
+}
+
+impl From<SerializingError> for SignerError {
+    fn from(e: SerializingError) -> Self {
+        SignerError::Serializing(e)
+    }
+}
+
+/// Everything a validator signs with its BLS key. Each request carries the block and view number
+/// it belongs to, so that the signer can enforce slashing protection without following the
+/// chain.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum SigningRequest {
+    /// The seed of the block at the given position, derived from the previous seed.
+    Seed {
+        block_number: u32,
+        view_number: u32,
+        prev_seed: VrfSeed,
+    },
+    /// The header of a micro block we produce.
+    MicroHeader(MicroHeader),
+    ViewChange(ViewChange),
+    PbftProposal(PbftProposal),
+    PbftPrepare {
+        block_number: u32,
+        view_number: u32,
+        message: PbftPrepareMessage,
+    },
+    PbftCommit {
+        block_number: u32,
+        view_number: u32,
+        message: PbftCommitMessage,
+    },
+    ValidatorInfo(ValidatorInfo),
+    /// The proof of knowledge of the secret key, i.e. the signature of the public key.
+    ProofOfKnowledge,
+    /// A signalling transaction to the staking contract, e.g. to unpark the validator.
+    StakingSignal(Transaction),
+}
+
+impl SigningRequest {
+    /// The hash that is signed for this request.
+    pub fn sig_hash(&self, public_key: &PublicKey) -> Blake2bHash {
+        match self {
+            SigningRequest::Seed { prev_seed, .. } => prev_seed.next_hash(),
+            SigningRequest::MicroHeader(header) => header.hash(),
+            SigningRequest::ViewChange(view_change) => view_change.hash_with_prefix(),
+            SigningRequest::PbftProposal(proposal) => proposal.hash_with_prefix(),
+            SigningRequest::PbftPrepare { message, .. } => message.hash_with_prefix(),
+            SigningRequest::PbftCommit { message, .. } => message.hash_with_prefix(),
+            SigningRequest::ValidatorInfo(info) => info.hash_with_prefix(),
+            SigningRequest::ProofOfKnowledge => public_key.hash(),
+            SigningRequest::StakingSignal(transaction) => transaction.serialize_content().as_slice().hash(),
+        }
+    }
+
+    /// The record to check against the slashing protection store, if signing this request twice
+    /// for the same block and view can be slashed.
+    pub fn signing_record(&self) -> Option<SigningRecord> {
+        match self {
+            SigningRequest::MicroHeader(header) => Some(SigningRecord::new(
+                SignedMessageKind::MicroBlock, header.block_number, header.view_number, header.hash())),
+            SigningRequest::ViewChange(view_change) => Some(SigningRecord::new(
+                SignedMessageKind::ViewChange, view_change.block_number, view_change.new_view_number, view_change.hash())),
+            SigningRequest::PbftPrepare { block_number, view_number, message } => Some(SigningRecord::new(
+                SignedMessageKind::PbftPrepare, *block_number, *view_number, message.block_hash.clone())),
+            SigningRequest::PbftCommit { block_number, view_number, message } => Some(SigningRecord::new(
+                SignedMessageKind::PbftCommit, *block_number, *view_number, message.block_hash.clone())),
+            _ => None,
+        }
+    }
+}
+
+/// Holds the validator's BLS key and signs on its behalf. Implementations must refuse requests
+/// that conflict with earlier ones.
+pub trait Signer: Send + Sync {
+    /// The public key of the validator key.
+    fn public_key(&self) -> &PublicKey;
+
+    /// Signs the request.
+    fn sign(&self, request: &SigningRequest) -> Result<Signature, SignerError>;
+
+    /// The key pair, if it is held in this process. Staking transactions that manage the
+    /// validator can only be signed with it.
+    fn key_pair(&self) -> Option<&KeyPair> {
+        None
+    }
+
+    fn sign_seed(&self, block_number: u32, view_number: u32, prev_seed: &VrfSeed) -> Result<VrfSeed, SignerError> {
+        let signature = self.sign(&SigningRequest::Seed { block_number, view_number, prev_seed: prev_seed.clone() })?;
+        Ok(signature.compress().into())
+    }
+
+    fn sign_micro_header(&self, header: &MicroHeader) -> Result<CompressedSignature, SignerError> {
+        Ok(self.sign(&SigningRequest::MicroHeader(header.clone()))?.compress())
+    }
+
+    fn sign_view_change(&self, view_change: ViewChange, signer_idx: u16) -> Result<SignedViewChange, SignerError> {
+        let signature = self.sign(&SigningRequest::ViewChange(view_change.clone()))?;
+        Ok(signed_message(view_change, signer_idx, signature))
+    }
+
+    fn sign_pbft_proposal(&self, proposal: PbftProposal, signer_idx: u16) -> Result<SignedPbftProposal, SignerError> {
+        let signature = self.sign(&SigningRequest::PbftProposal(proposal.clone()))?;
+        Ok(signed_message(proposal, signer_idx, signature))
+    }
+
+    fn sign_pbft_prepare(&self, proposal: &PbftProposal, signer_idx: u16) -> Result<SignedPbftPrepareMessage, SignerError> {
+        let message = PbftPrepareMessage { block_hash: proposal.header.hash() };
+        let signature = self.sign(&SigningRequest::PbftPrepare {
+            block_number: proposal.header.block_number,
+            view_number: proposal.header.view_number,
+            message: message.clone(),
+        })?;
+        Ok(signed_message(message, signer_idx, signature))
+    }
+
+    fn sign_pbft_commit(&self, proposal: &PbftProposal, signer_idx: u16) -> Result<SignedPbftCommitMessage, SignerError> {
+        let message = PbftCommitMessage { block_hash: proposal.header.hash() };
+        let signature = self.sign(&SigningRequest::PbftCommit {
+            block_number: proposal.header.block_number,
+            view_number: proposal.header.view_number,
+            message: message.clone(),
+        })?;
+        Ok(signed_message(message, signer_idx, signature))
+    }
+
+    fn sign_validator_info(&self, info: ValidatorInfo) -> Result<SignedValidatorInfo, SignerError> {
+        let signature = self.sign(&SigningRequest::ValidatorInfo(info.clone()))?;
+        Ok(signed_message(info, 0, signature))
+    }
+
+    fn sign_proof_of_knowledge(&self) -> Result<CompressedSignature, SignerError> {
+        Ok(self.sign(&SigningRequest::ProofOfKnowledge)?.compress())
+    }
+
+    fn sign_staking_signal(&self, transaction: &Transaction) -> Result<CompressedSignature, SignerError> {
+        Ok(self.sign(&SigningRequest::StakingSignal(transaction.clone()))?.compress())
+    }
+}
+
+fn signed_message<M: Message>(message: M, signer_idx: u16, signature: Signature) -> SignedMessage<M> {
+    SignedMessage {
+        message,
+        signer_idx,
+        signature,
+    }
+}
@@ -0,0 +1,248 @@
+//! The protocol between a validator and a remote signer.
+//!
+//! Both sides share a secret authentication key. On connect, each side sends the protocol
+//! version and a random nonce. The session key is derived from the authentication key and both
+//! nonces, and each side proves that it knows it. Afterwards, every message is sent as a length
+//! prefixed frame followed by a MAC over the session key, the direction, a message counter and
+//! the payload. Messages are authenticated, but not encrypted.
+
+use std::fmt;
+use std::fs;
+use std::io::{Read, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::Path;
+
+use rand::RngCore;
+use rand::rngs::OsRng;
+
+use beserial::{Deserialize, Serialize};
+use bls::bls12_381::{PublicKey, Signature};
+use hash::{Blake2bHash, Blake2bHasher, Hasher};
+
+use super::{SignerError, SigningRequest};
+
+
+const VERSION: u8 = 1;
+const NONCE_SIZE: usize = 32;
+const MAX_FRAME_SIZE: usize = 1024 * 1024;
+
+
+/// The shared secret between a validator and its remote signer.
+#[derive(Clone)]
+pub struct AuthKey([u8; 32]);
+
+impl AuthKey {
+    pub const SIZE: usize = 32;
+
+    pub fn generate() -> Self {
+        let mut key = [0u8; AuthKey::SIZE];
+        OsRng.fill_bytes(&mut key);
+        AuthKey(key)
+    }
+
+    /// Loads a hex encoded key from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SignerError> {
+        let bytes = hex::decode(fs::read_to_string(path)?.trim())
+            .map_err(|_| SignerError::InvalidAuthKey)?;
+        if bytes.len() != AuthKey::SIZE {
+            return Err(SignerError::InvalidAuthKey);
+        }
+        let mut key = [0u8; AuthKey::SIZE];
+        key.copy_from_slice(&bytes);
+        Ok(AuthKey(key))
+    }
+
+    /// Saves the key hex encoded to a file, which only the owner can read and write.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SignerError> {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(path)?;
+        // The mode only applies to newly created files.
+        #[cfg(unix)]
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        file.write_all(hex::encode(&self.0).as_bytes())?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for AuthKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("AuthKey(..)")
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[repr(u8)]
+pub(crate) enum SignerRequest {
+    PublicKey,
+    Sign(SigningRequest),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[repr(u8)]
+pub(crate) enum SignerResponse {
+    PublicKey(PublicKey),
+    Signature(Signature),
+    Refused(#[beserial(len_type(u16))] String),
+}
+
+/// The role of a side of the connection, used to separate the MACs of both directions.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum Role {
+    Validator = 1,
+    Signer = 2,
+}
+
+/// An authenticated connection between a validator and a remote signer.
+pub(crate) struct Connection<S> {
+    stream: S,
+    session_key: Blake2bHash,
+    role: Role,
+    sent: u64,
+    received: u64,
+}
+
+impl<S: Read + Write> Connection<S> {
+    /// Opens the connection on the validator side.
+    pub fn connect(stream: S, auth_key: &AuthKey) -> Result<Self, SignerError> {
+        Self::handshake(stream, auth_key, Role::Validator)
+    }
+
+    /// Accepts the connection on the signer side.
+    pub fn accept(stream: S, auth_key: &AuthKey) -> Result<Self, SignerError> {
+        Self::handshake(stream, auth_key, Role::Signer)
+    }
+
+    fn handshake(mut stream: S, auth_key: &AuthKey, role: Role) -> Result<Self, SignerError> {
+        let mut nonce = [0u8; NONCE_SIZE];
+        OsRng.fill_bytes(&mut nonce);
+        stream.write_all(&[VERSION])?;
+        stream.write_all(&nonce)?;
+        stream.flush()?;
+
+        let mut version = [0u8; 1];
+        stream.read_exact(&mut version)?;
+        if version[0] != VERSION {
+            return Err(SignerError::UnsupportedVersion(version[0]));
+        }
+        let mut peer_nonce = [0u8; NONCE_SIZE];
+        stream.read_exact(&mut peer_nonce)?;
+
+        let (validator_nonce, signer_nonce) = match role {
+            Role::Validator => (&nonce, &peer_nonce),
+            Role::Signer => (&peer_nonce, &nonce),
+        };
+        let session_key = mac(&auth_key.0, &[validator_nonce, signer_nonce]);
+
+        // Prove that we know the key and check that the other side does too.
+        let peer_role = match role {
+            Role::Validator => Role::Signer,
+            Role::Signer => Role::Validator,
+        };
+        stream.write_all(mac(session_key.as_bytes(), &[&[role as u8]]).as_bytes())?;
+        stream.flush()?;
+        let mut peer_proof = [0u8; Blake2bHash::SIZE];
+        stream.read_exact(&mut peer_proof)?;
+        if !mac_eq(&peer_proof, &mac(session_key.as_bytes(), &[&[peer_role as u8]])) {
+            return Err(SignerError::AuthenticationFailed);
+        }
+
+        Ok(Connection {
+            stream,
+            session_key,
+            role,
+            sent: 0,
+            received: 0,
+        })
+    }
+
+    pub fn send<T: Serialize>(&mut self, message: &T) -> Result<(), SignerError> {
+        let payload = message.serialize_to_vec();
+        let tag = self.frame_mac(self.role, self.sent, &payload);
+        self.sent += 1;
+
+        self.stream.write_all(&(payload.len() as u32).to_be_bytes())?;
+        self.stream.write_all(&payload)?;
+        self.stream.write_all(tag.as_bytes())?;
+        self.stream.flush()?;
+        Ok(())
+    }
+
+    pub fn receive<T: Deserialize>(&mut self) -> Result<T, SignerError> {
+        let mut size = [0u8; 4];
+        self.stream.read_exact(&mut size)?;
+        let size = u32::from_be_bytes(size);
+        if size as usize > MAX_FRAME_SIZE {
+            return Err(SignerError::UnexpectedMessage);
+        }
+        let mut payload = vec![0u8; size as usize];
+        self.stream.read_exact(&mut payload)?;
+        let mut tag = [0u8; Blake2bHash::SIZE];
+        self.stream.read_exact(&mut tag)?;
+
+        let peer_role = match self.role {
+            Role::Validator => Role::Signer,
+            Role::Signer => Role::Validator,
+        };
+        if !mac_eq(&tag, &self.frame_mac(peer_role, self.received, &payload)) {
+            return Err(SignerError::AuthenticationFailed);
+        }
+        self.received += 1;
+
+        Ok(Deserialize::deserialize_from_vec(&payload)?)
+    }
+
+    fn frame_mac(&self, sender: Role, counter: u64, payload: &[u8]) -> Blake2bHash {
+        mac(self.session_key.as_bytes(), &[&[sender as u8], &counter.to_be_bytes(), payload])
+    }
+}
+
+/// Compares a received MAC with the expected one in constant time.
+fn mac_eq(received: &[u8; Blake2bHash::SIZE], expected: &Blake2bHash) -> bool {
+    received.iter().zip(expected.as_bytes())
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Blake2b over the key followed by the parts. All parts but the last have a fixed size.
+fn mac(key: &[u8], parts: &[&[u8]]) -> Blake2bHash {
+    let mut hasher = Blake2bHasher::new();
+    hasher.write_all(key).unwrap();
+    for part in parts {
+        hasher.write_all(part).unwrap();
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_compares_macs() {
+        let expected = mac(&[1u8; AuthKey::SIZE], &[b"payload"]);
+        let mut received = [0u8; Blake2bHash::SIZE];
+        received.copy_from_slice(expected.as_bytes());
+        assert!(mac_eq(&received, &expected));
+
+        received[Blake2bHash::SIZE - 1] ^= 1;
+        assert!(!mac_eq(&received, &expected));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_saves_the_auth_key_for_the_owner_only() {
+        let path = std::env::temp_dir().join(format!("nimiq-signer-auth-key-{}", std::process::id()));
+        fs::write(&path, "").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        let key = AuthKey::generate();
+        key.save(&path).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(AuthKey::load(&path).unwrap().0, key.0);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,316 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use futures::Async;
+use parking_lot::Mutex;
+
+use bls::bls12_381::{PublicKey, Signature};
+
+use super::{Signer, SignerError, SigningRequest};
+use super::protocol::{AuthKey, Connection, SignerRequest, SignerResponse};
+
+
+/// Where a remote signer listens, either `tcp://<host>:<port>` or `unix:<path>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SignerAddress {
+    Tcp(SocketAddr),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl FromStr for SignerAddress {
+    type Err = SignerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("tcp://") {
+            let address = s["tcp://".len()..].to_socket_addrs()
+                .ok()
+                .and_then(|mut addresses| addresses.next())
+                .ok_or_else(|| SignerError::InvalidAddress(s.to_string()))?;
+            return Ok(SignerAddress::Tcp(address));
+        }
+        #[cfg(unix)] {
+            if s.starts_with("unix:") {
+                return Ok(SignerAddress::Unix(PathBuf::from(&s["unix:".len()..])));
+            }
+        }
+        Err(SignerError::InvalidAddress(s.to_string()))
+    }
+}
+
+impl fmt::Display for SignerAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SignerAddress::Tcp(address) => write!(f, "tcp://{}", address),
+            #[cfg(unix)]
+            SignerAddress::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+trait Stream: Read + Write + Send {}
+
+impl<S: Read + Write + Send> Stream for S {}
+
+/// Signs by sending requests to a signer in another process, e.g. a `nimiq-signer` daemon.
+pub struct RemoteSigner {
+    address: SignerAddress,
+    auth_key: AuthKey,
+    public_key: PublicKey,
+    connection: Mutex<Option<Connection<Box<dyn Stream>>>>,
+}
+
+impl RemoteSigner {
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Connects to the signer and asks it for the public key of the validator key.
+    pub fn connect(address: SignerAddress, auth_key: AuthKey) -> Result<Self, SignerError> {
+        let mut connection = Self::open(&address, &auth_key)?;
+        connection.send(&SignerRequest::PublicKey)?;
+        let public_key = match connection.receive()? {
+            SignerResponse::PublicKey(public_key) => public_key,
+            SignerResponse::Refused(reason) => return Err(SignerError::Refused(reason)),
+            _ => return Err(SignerError::UnexpectedMessage),
+        };
+
+        info!("Connected to remote signer at {} with key {}", address, public_key.compress());
+        Ok(RemoteSigner {
+            address,
+            auth_key,
+            public_key,
+            connection: Mutex::new(Some(connection)),
+        })
+    }
+
+    fn open(address: &SignerAddress, auth_key: &AuthKey) -> Result<Connection<Box<dyn Stream>>, SignerError> {
+        let stream: Box<dyn Stream> = match address {
+            SignerAddress::Tcp(address) => {
+                let stream = TcpStream::connect_timeout(address, Self::TIMEOUT)?;
+                stream.set_read_timeout(Some(Self::TIMEOUT))?;
+                stream.set_write_timeout(Some(Self::TIMEOUT))?;
+                stream.set_nodelay(true)?;
+                Box::new(stream)
+            },
+            #[cfg(unix)]
+            SignerAddress::Unix(path) => {
+                let stream = UnixStream::connect(path)?;
+                stream.set_read_timeout(Some(Self::TIMEOUT))?;
+                stream.set_write_timeout(Some(Self::TIMEOUT))?;
+                Box::new(stream)
+            },
+        };
+        Connection::connect(stream, auth_key)
+    }
+
+    /// Sends a request and waits for the response. This blocks on I/O, so on a worker of the
+    /// tokio thread pool, its other tasks are handed off to another thread first.
+    fn request(&self, request: &SignerRequest) -> Result<SignerResponse, SignerError> {
+        let mut request_fn = Some(|| self.request_blocking(request));
+        match tokio_threadpool::blocking(|| request_fn.take().unwrap()()) {
+            Ok(Async::Ready(result)) => result,
+            // Not on the thread pool or no blocking capacity left, so block right here.
+            Ok(Async::NotReady) | Err(_) => request_fn.take().unwrap()(),
+        }
+    }
+
+    fn request_blocking(&self, request: &SignerRequest) -> Result<SignerResponse, SignerError> {
+        let mut connection = self.connection.lock();
+
+        // Reconnect once if the connection broke, e.g. because the signer was restarted.
+        for attempt in 0..2 {
+            if connection.is_none() {
+                *connection = Some(Self::open(&self.address, &self.auth_key)?);
+            }
+            let open = connection.as_mut().unwrap();
+            let result = open.send(request).and_then(|_| open.receive());
+            match result {
+                Ok(response) => return Ok(response),
+                Err(SignerError::Io(e)) if attempt == 0 => {
+                    debug!("Connection to remote signer failed, reconnecting: {}", e);
+                    *connection = None;
+                },
+                Err(e) => {
+                    *connection = None;
+                    return Err(e);
+                },
+            }
+        }
+        unreachable!()
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    fn sign(&self, request: &SigningRequest) -> Result<Signature, SignerError> {
+        let signature = match self.request(&SignerRequest::Sign(request.clone()))? {
+            SignerResponse::Signature(signature) => signature,
+            SignerResponse::Refused(reason) => return Err(SignerError::Refused(reason)),
+            _ => return Err(SignerError::UnexpectedMessage),
+        };
+
+        // Don't trust the signer to sign the right thing.
+        if !self.public_key.verify_hash(request.sig_hash(&self.public_key), &signature) {
+            return Err(SignerError::InvalidSignature);
+        }
+        Ok(signature)
+    }
+}
+
+/// Serves signing requests of remote validators with another signer, usually a `LocalSigner`.
+pub struct SignerServer {
+    signer: Arc<dyn Signer>,
+    auth_key: AuthKey,
+}
+
+impl SignerServer {
+    pub fn new(signer: Arc<dyn Signer>, auth_key: AuthKey) -> Self {
+        SignerServer { signer, auth_key }
+    }
+
+    /// Accepts connections on the given address and serves each of them in its own thread.
+    pub fn run(self, address: &SignerAddress) -> Result<(), SignerError> {
+        let server = Arc::new(self);
+        match address {
+            SignerAddress::Tcp(address) => {
+                let listener = TcpListener::bind(address)?;
+                for stream in listener.incoming() {
+                    let stream = stream?;
+                    stream.set_nodelay(true)?;
+                    server.spawn(stream);
+                }
+            },
+            #[cfg(unix)]
+            SignerAddress::Unix(path) => {
+                let listener = UnixListener::bind(path)?;
+                for stream in listener.incoming() {
+                    server.spawn(stream?);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    fn spawn<S: Read + Write + Send + 'static>(self: &Arc<Self>, stream: S) {
+        let server = Arc::clone(self);
+        thread::spawn(move || {
+            match server.serve_connection(stream) {
+                Ok(()) => debug!("Validator disconnected"),
+                Err(e) => warn!("Connection to validator failed: {}", e),
+            }
+        });
+    }
+
+    /// Serves a single connection until it is closed.
+    pub fn serve_connection<S: Read + Write>(&self, stream: S) -> Result<(), SignerError> {
+        let mut connection = Connection::accept(stream, &self.auth_key)?;
+        debug!("Validator connected");
+
+        loop {
+            let request = match connection.receive() {
+                Ok(request) => request,
+                Err(SignerError::Io(ref e)) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e),
+            };
+
+            let response = match request {
+                SignerRequest::PublicKey => SignerResponse::PublicKey(self.signer.public_key().clone()),
+                SignerRequest::Sign(request) => match self.signer.sign(&request) {
+                    Ok(signature) => SignerResponse::Signature(signature),
+                    Err(e) => {
+                        warn!("Refusing signing request: {}", e);
+                        SignerResponse::Refused(e.to_string())
+                    },
+                },
+            };
+            connection.send(&response)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(unix)]
+    use std::os::unix::net::UnixStream;
+
+    use block_albatross::ViewChange;
+    use bls::bls12_381::KeyPair;
+    use database::volatile::VolatileEnvironment;
+    use vrf::VrfSeed;
+
+    use crate::signer::LocalSigner;
+    use crate::slashing_protection::SlashingProtection;
+
+    use super::*;
+
+    #[test]
+    fn it_parses_signer_addresses() {
+        assert_eq!("tcp://127.0.0.1:8650".parse::<SignerAddress>().unwrap(), SignerAddress::Tcp("127.0.0.1:8650".parse().unwrap()));
+        #[cfg(unix)]
+        assert_eq!("unix:/run/nimiq/signer.sock".parse::<SignerAddress>().unwrap(), SignerAddress::Unix(PathBuf::from("/run/nimiq/signer.sock")));
+        assert!("127.0.0.1:8650".parse::<SignerAddress>().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_signs_through_the_protocol() {
+        let key_pair = KeyPair::generate_default_csprng();
+        let public_key = key_pair.public.clone();
+        let slashing_protection = SlashingProtection::new(VolatileEnvironment::new(1).unwrap());
+        let server = SignerServer::new(Arc::new(LocalSigner::new(key_pair, slashing_protection)), AuthKey::generate());
+        let auth_key = server.auth_key.clone();
+
+        let (validator_stream, signer_stream) = UnixStream::pair().unwrap();
+        let handle = thread::spawn(move || server.serve_connection(signer_stream));
+
+        let mut connection = Connection::connect(validator_stream, &auth_key).unwrap();
+        connection.send(&SignerRequest::PublicKey).unwrap();
+        match connection.receive().unwrap() {
+            SignerResponse::PublicKey(key) => assert_eq!(key, public_key),
+            response => panic!("Unexpected response: {:?}", response),
+        }
+
+        let view_change = |prev_seed: VrfSeed| ViewChange { block_number: 10, new_view_number: 1, prev_seed };
+        let request = SigningRequest::ViewChange(view_change(VrfSeed::default()));
+        connection.send(&SignerRequest::Sign(request.clone())).unwrap();
+        match connection.receive().unwrap() {
+            SignerResponse::Signature(signature) => assert!(public_key.verify_hash(request.sig_hash(&public_key), &signature)),
+            response => panic!("Unexpected response: {:?}", response),
+        }
+
+        // A conflicting view change is refused by the slashing protection of the signer.
+        let other_seed = VrfSeed::default().sign_next(&KeyPair::generate_default_csprng().secret);
+        connection.send(&SignerRequest::Sign(SigningRequest::ViewChange(view_change(other_seed)))).unwrap();
+        match connection.receive().unwrap() {
+            SignerResponse::Refused(_) => {},
+            response => panic!("Unexpected response: {:?}", response),
+        }
+
+        drop(connection);
+        handle.join().unwrap().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_rejects_a_wrong_auth_key() {
+        let slashing_protection = SlashingProtection::new(VolatileEnvironment::new(1).unwrap());
+        let server = SignerServer::new(Arc::new(LocalSigner::new(KeyPair::generate_default_csprng(), slashing_protection)), AuthKey::generate());
+
+        let (validator_stream, signer_stream) = UnixStream::pair().unwrap();
+        let handle = thread::spawn(move || server.serve_connection(signer_stream));
+
+        assert!(Connection::connect(validator_stream, &AuthKey::generate()).is_err());
+        assert!(handle.join().unwrap().is_err());
+    }
+}
@@ -0,0 +1,318 @@
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use failure::Fail;
+
+use beserial::{Deserialize, Serialize, SerializingError};
+use bls::bls12_381::CompressedPublicKey;
+use database::{Database, Environment, FromDatabaseValue, IntoDatabaseValue, ReadTransaction, WriteTransaction};
+use hash::Blake2bHash;
+use primitives::networks::NetworkId;
+
+
+#[derive(Debug, Fail)]
+pub enum SlashingProtectionError {
+    #[fail(display = "Refusing to sign {}, already signed {}", _0, _1)]
+    Conflict(SigningRecord, SigningRecord),
+    #[fail(display = "Interchange file is for network {}, but validator is on {}", _0, _1)]
+    WrongNetwork(NetworkId, NetworkId),
+    #[fail(display = "Interchange file is for another validator key")]
+    WrongPublicKey,
+    #[fail(display = "Unsupported interchange file version: {}", _0)]
+    UnsupportedVersion(u8),
+    #[fail(display = "Serialization error: {}", _0)]
+    Serializing(#[cause] SerializingError),
+    #[fail(display = "I/O error: {}", _0)]
+    Io(#[cause] io::Error),
+}
+
+impl From<SerializingError> for SlashingProtectionError {
+    fn from(e: SerializingError) -> Self {
+        SlashingProtectionError::Serializing(e)
+    }
+}
+
+impl From<io::Error> for SlashingProtectionError {
+    fn from(e: io::Error) -> Self {
+        SlashingProtectionError::Io(e)
+    }
+}
+
+/// The kinds of messages that a validator must never sign twice for the same block and view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum SignedMessageKind {
+    MicroBlock = 0,
+    ViewChange = 1,
+    PbftPrepare = 2,
+    PbftCommit = 3,
+}
+
+impl SignedMessageKind {
+    pub const ALL: [SignedMessageKind; 4] = [
+        SignedMessageKind::MicroBlock,
+        SignedMessageKind::ViewChange,
+        SignedMessageKind::PbftPrepare,
+        SignedMessageKind::PbftCommit,
+    ];
+
+    fn db_key(self) -> &'static str {
+        match self {
+            SignedMessageKind::MicroBlock => "MicroBlock",
+            SignedMessageKind::ViewChange => "ViewChange",
+            SignedMessageKind::PbftPrepare => "PbftPrepare",
+            SignedMessageKind::PbftCommit => "PbftCommit",
+        }
+    }
+}
+
+impl fmt::Display for SignedMessageKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SignedMessageKind::MicroBlock => write!(f, "micro block"),
+            SignedMessageKind::ViewChange => write!(f, "view change"),
+            SignedMessageKind::PbftPrepare => write!(f, "pBFT prepare"),
+            SignedMessageKind::PbftCommit => write!(f, "pBFT commit"),
+        }
+    }
+}
+
+/// A message that was signed (or is about to be signed) by the validator.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SigningRecord {
+    pub kind: SignedMessageKind,
+    pub block_number: u32,
+    pub view_number: u32,
+    /// The hash of the signed message, i.e. the block hash for micro blocks and pBFT messages
+    /// and the hash of the `ViewChange` for view changes.
+    pub message_hash: Blake2bHash,
+}
+
+impl SigningRecord {
+    pub fn new(kind: SignedMessageKind, block_number: u32, view_number: u32, message_hash: Blake2bHash) -> Self {
+        SigningRecord { kind, block_number, view_number, message_hash }
+    }
+
+    /// A message may only be signed if it is at a later block and view than the last signed
+    /// message of its kind, or if it is exactly that message.
+    fn may_follow(&self, recorded: &SigningRecord) -> bool {
+        let position = (self.block_number, self.view_number);
+        let recorded_position = (recorded.block_number, recorded.view_number);
+        position > recorded_position
+            || (position == recorded_position && self.message_hash == recorded.message_hash)
+    }
+}
+
+impl fmt::Display for SigningRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} #{}.{} ({})", self.kind, self.block_number, self.view_number, self.message_hash)
+    }
+}
+
+impl IntoDatabaseValue for SigningRecord {
+    fn database_byte_size(&self) -> usize {
+        self.serialized_size()
+    }
+
+    fn copy_into_database(&self, mut bytes: &mut [u8]) {
+        Serialize::serialize(&self, &mut bytes).unwrap();
+    }
+}
+
+impl FromDatabaseValue for SigningRecord {
+    fn copy_from_database(bytes: &[u8]) -> io::Result<Self> where Self: Sized {
+        let mut cursor = io::Cursor::new(bytes);
+        Ok(Deserialize::deserialize(&mut cursor)?)
+    }
+}
+
+/// The content of a slashing protection interchange file, which is used to move a validator
+/// to another machine.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SlashingProtectionInterchange {
+    pub version: u8,
+    pub network_id: NetworkId,
+    pub public_key: CompressedPublicKey,
+    #[beserial(len_type(u8))]
+    pub records: Vec<SigningRecord>,
+}
+
+impl SlashingProtectionInterchange {
+    pub const VERSION: u8 = 1;
+
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, SlashingProtectionError> {
+        Ok(Deserialize::deserialize(reader)?)
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), SlashingProtectionError> {
+        Serialize::serialize(self, writer)?;
+        Ok(())
+    }
+}
+
+/// Persistent store of the last message of each kind that the validator signed. It must be kept
+/// separately from the chain database, so that it survives a resync.
+#[derive(Debug)]
+pub struct SlashingProtection {
+    env: Environment,
+    db: Database,
+}
+
+impl SlashingProtection {
+    const DB_NAME: &'static str = "SlashingProtection";
+
+    pub fn new(env: Environment) -> Self {
+        let db = env.open_database(Self::DB_NAME.to_string());
+        SlashingProtection { env, db }
+    }
+
+    /// Returns the last signed message of the given kind.
+    pub fn get(&self, kind: SignedMessageKind) -> Option<SigningRecord> {
+        ReadTransaction::new(&self.env).get(&self.db, kind.db_key())
+    }
+
+    /// Checks that signing the given message can't conflict with anything signed before and
+    /// records it. The record is persisted before this returns, so the message may only be
+    /// signed if this succeeds.
+    pub fn check_and_record(&self, record: SigningRecord) -> Result<(), SlashingProtectionError> {
+        let mut txn = WriteTransaction::new(&self.env);
+        if let Some(recorded) = txn.get::<str, SigningRecord>(&self.db, record.kind.db_key()) {
+            if !record.may_follow(&recorded) {
+                return Err(SlashingProtectionError::Conflict(record, recorded));
+            }
+        }
+        txn.put_reserve(&self.db, record.kind.db_key(), &record);
+        txn.commit();
+        Ok(())
+    }
+
+    /// Exports the store for the validator with the given key.
+    pub fn export(&self, network_id: NetworkId, public_key: CompressedPublicKey) -> SlashingProtectionInterchange {
+        let txn = ReadTransaction::new(&self.env);
+        let records = SignedMessageKind::ALL.iter()
+            .filter_map(|kind| txn.get(&self.db, kind.db_key()))
+            .collect();
+
+        SlashingProtectionInterchange {
+            version: SlashingProtectionInterchange::VERSION,
+            network_id,
+            public_key,
+            records,
+        }
+    }
+
+    /// Merges an interchange file into the store. For each kind, the later of the local and the
+    /// imported record is kept. If both are at the same block and view, the local one is kept,
+    /// which still refuses any other message at that position. Returns the number of records
+    /// that were updated.
+    pub fn import(&self, interchange: &SlashingProtectionInterchange, network_id: NetworkId, public_key: &CompressedPublicKey) -> Result<usize, SlashingProtectionError> {
+        if interchange.version != SlashingProtectionInterchange::VERSION {
+            return Err(SlashingProtectionError::UnsupportedVersion(interchange.version));
+        }
+        if interchange.network_id != network_id {
+            return Err(SlashingProtectionError::WrongNetwork(interchange.network_id, network_id));
+        }
+        if &interchange.public_key != public_key {
+            return Err(SlashingProtectionError::WrongPublicKey);
+        }
+
+        let mut txn = WriteTransaction::new(&self.env);
+        let mut updated = 0;
+        for record in interchange.records.iter() {
+            let recorded: Option<SigningRecord> = txn.get(&self.db, record.kind.db_key());
+            let is_later = recorded.map_or(true, |recorded| {
+                (record.block_number, record.view_number) > (recorded.block_number, recorded.view_number)
+            });
+            if is_later {
+                txn.put_reserve(&self.db, record.kind.db_key(), record);
+                updated += 1;
+            }
+        }
+        txn.commit();
+        Ok(updated)
+    }
+
+    /// Exports the store to an interchange file. Returns the number of exported records.
+    pub fn export_to_file<P: AsRef<Path>>(&self, path: P, network_id: NetworkId, public_key: CompressedPublicKey) -> Result<usize, SlashingProtectionError> {
+        let interchange = self.export(network_id, public_key);
+        let mut writer = BufWriter::new(File::create(path)?);
+        interchange.write(&mut writer)?;
+        writer.flush()?;
+        Ok(interchange.records.len())
+    }
+
+    /// Merges an interchange file into the store. Returns the number of updated records and the
+    /// number of records in the file.
+    pub fn import_from_file<P: AsRef<Path>>(&self, path: P, network_id: NetworkId, public_key: &CompressedPublicKey) -> Result<(usize, usize), SlashingProtectionError> {
+        let interchange = SlashingProtectionInterchange::read(&mut BufReader::new(File::open(path)?))?;
+        let updated = self.import(&interchange, network_id, public_key)?;
+        Ok((updated, interchange.records.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bls::bls12_381::KeyPair;
+    use database::volatile::VolatileEnvironment;
+    use hash::{Blake2bHasher, Hasher};
+
+    use super::*;
+
+    fn hash(data: &[u8]) -> Blake2bHash {
+        Blake2bHasher::default().digest(data)
+    }
+
+    #[test]
+    fn it_refuses_conflicting_messages() {
+        let env = VolatileEnvironment::new(1).unwrap();
+        let store = SlashingProtection::new(env);
+
+        let record = SigningRecord::new(SignedMessageKind::MicroBlock, 10, 1, hash(b"a"));
+        assert!(store.check_and_record(record.clone()).is_ok());
+
+        // Signing the same message again is fine.
+        assert!(store.check_and_record(record.clone()).is_ok());
+
+        // Another message at the same position or an earlier one is refused.
+        assert!(store.check_and_record(SigningRecord::new(SignedMessageKind::MicroBlock, 10, 1, hash(b"b"))).is_err());
+        assert!(store.check_and_record(SigningRecord::new(SignedMessageKind::MicroBlock, 10, 0, hash(b"b"))).is_err());
+        assert!(store.check_and_record(SigningRecord::new(SignedMessageKind::MicroBlock, 9, 5, hash(b"b"))).is_err());
+        assert_eq!(store.get(SignedMessageKind::MicroBlock), Some(record));
+
+        // Kinds are tracked separately.
+        assert!(store.check_and_record(SigningRecord::new(SignedMessageKind::ViewChange, 10, 1, hash(b"b"))).is_ok());
+
+        // Later positions are allowed.
+        assert!(store.check_and_record(SigningRecord::new(SignedMessageKind::MicroBlock, 10, 2, hash(b"b"))).is_ok());
+        assert!(store.check_and_record(SigningRecord::new(SignedMessageKind::MicroBlock, 11, 0, hash(b"c"))).is_ok());
+    }
+
+    #[test]
+    fn it_can_export_and_import_the_store() {
+        let public_key = KeyPair::generate_default_csprng().public.compress();
+        let network_id = NetworkId::DevAlbatross;
+
+        let store = SlashingProtection::new(VolatileEnvironment::new(1).unwrap());
+        store.check_and_record(SigningRecord::new(SignedMessageKind::MicroBlock, 10, 0, hash(b"a"))).unwrap();
+        store.check_and_record(SigningRecord::new(SignedMessageKind::PbftPrepare, 128, 0, hash(b"b"))).unwrap();
+
+        let interchange = store.export(network_id, public_key.clone());
+        let interchange = SlashingProtectionInterchange::deserialize_from_vec(&interchange.serialize_to_vec()).unwrap();
+        assert_eq!(interchange.records.len(), 2);
+
+        let other_store = SlashingProtection::new(VolatileEnvironment::new(1).unwrap());
+        other_store.check_and_record(SigningRecord::new(SignedMessageKind::MicroBlock, 12, 0, hash(b"c"))).unwrap();
+
+        let other_key = KeyPair::generate_default_csprng().public.compress();
+        assert!(other_store.import(&interchange, network_id, &other_key).is_err());
+        assert!(other_store.import(&interchange, NetworkId::TestAlbatross, &public_key).is_err());
+
+        // Only the pBFT prepare is newer than what the other store has.
+        assert_eq!(other_store.import(&interchange, network_id, &public_key).unwrap(), 1);
+        assert_eq!(other_store.get(SignedMessageKind::MicroBlock).unwrap().block_number, 12);
+        assert!(other_store.check_and_record(SigningRecord::new(SignedMessageKind::PbftPrepare, 128, 0, hash(b"d"))).is_err());
+    }
+}
@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use std::ops::Mul;
+use std::ops::{Mul, Range};
 use std::sync::{Arc, Weak};
 use std::time::{Duration, Instant};
 
@@ -15,37 +15,37 @@ use block_albatross::{
     MicroBlock,
     MicroExtrinsics,
     MicroHeader,
-    PbftCommitMessage,
-    PbftPrepareMessage,
+    MicroJustification,
     PbftProof,
     PbftProposal,
-    SignedPbftCommitMessage,
-    SignedPbftPrepareMessage,
-    SignedPbftProposal,
-    SignedViewChange,
     ViewChange,
     ViewChangeProof,
 };
 use block_production_albatross::BlockProducer;
 use blockchain_albatross::Blockchain;
 use blockchain_base::{AbstractBlockchain, BlockchainEvent};
-use bls::bls12_381::KeyPair;
+use bls::bls12_381::PublicKey;
 use consensus::{AlbatrossConsensusProtocol, Consensus, ConsensusEvent};
 use hash::{Blake2bHash, Hash};
 use keys::Address;
 use macros::upgrade_weak;
 use network_primitives::networks::NetworkInfo;
-use network_primitives::validator_info::{SignedValidatorInfo, ValidatorInfo};
+use network_primitives::validator_info::ValidatorInfo;
 use primitives::coin::Coin;
 use primitives::policy;
 use transaction_builder::{Recipient, TransactionBuilder};
 use utils::mutable_once::MutableOnce;
 use utils::observer::ListenerHandle;
 use utils::timers::Timers;
+use vrf::VrfSeed;
 
 use crate::error::Error;
+#[cfg(feature = "metrics")]
+use crate::metrics::ValidatorMetrics;
 use crate::slash::ForkProofPool;
-use crate::validator_network::{ValidatorNetwork, ValidatorNetworkEvent};
+use crate::signature_aggregation::AggregationConfig;
+use crate::signer::Signer;
+use crate::validator_network::{PbftTimeoutEvent, ValidatorNetwork, ValidatorNetworkEvent};
 
 #[derive(Clone, Debug)]
 pub enum SlotChange  {
@@ -72,7 +72,7 @@ pub struct Validator {
     block_producer: BlockProducer,
     consensus: Arc<Consensus<AlbatrossConsensusProtocol>>,
     pub validator_network: Arc<ValidatorNetwork>,
-    pub validator_key: KeyPair,
+    signer: Arc<dyn Signer>,
     pub validator_wallet_key: Option<keys::KeyPair>,
 
     timers: Timers<ValidatorTimer>,
@@ -81,6 +81,9 @@ pub struct Validator {
 
     self_weak: MutableOnce<Weak<Validator>>,
     listeners: MutableOnce<Option<ValidatorListeners>>,
+
+    #[cfg(feature = "metrics")]
+    metrics: ValidatorMetrics,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -95,23 +98,89 @@ pub struct ValidatorState {
     fork_proof_pool: ForkProofPool,
     view_number: u32,
     active_view_change: Option<ViewChange>,
+    /// Block and view number of the pBFT proposal we signed a commit for. We must not sign a view
+    /// change for this macro block anymore, since the proposal might already be committed by
+    /// others. A view change can thus only complete if fewer than a quorum of commits were signed,
+    /// in which case the proposal can't be committed anymore, and we may vote in the new view.
+    committed_pbft: Option<(u32, u32)>,
     proposed_extrinsics: HashMap<Blake2bHash, MacroExtrinsics>,
 }
 
+impl ValidatorState {
+    /// Whether we gave up on the given view, i.e. we changed or started to change it. We must not
+    /// vote for pBFT proposals from such views.
+    fn has_left_view(&self, block_number: u32, view_number: u32) -> bool {
+        let changing_view = self.active_view_change.as_ref().map_or(false, |view_change| {
+            view_change.block_number == block_number && view_number < view_change.new_view_number
+        });
+        changing_view || view_number < self.view_number
+    }
+
+    /// Marks the given pBFT proposal as committed before we sign a commit for it. Returns the
+    /// previously committed proposal to restore if signing fails, or `None` if we must not commit
+    /// to the proposal, since we left its view.
+    fn start_commit(&mut self, block_number: u32, view_number: u32) -> Option<Option<(u32, u32)>> {
+        if self.has_left_view(block_number, view_number) {
+            return None;
+        }
+        Some(self.committed_pbft.replace((block_number, view_number)))
+    }
+
+    /// Marks the view change of the given block to the next view as started. Returns the view
+    /// change and our validator index to sign it with, or `None` if we can't change the view.
+    fn start_view_change(&mut self, block_number: u32, prev_seed: VrfSeed) -> Option<(ViewChange, u16)> {
+        // View change messages should only be sent by active validators.
+        if self.status != ValidatorStatus::Active {
+            return None;
+        }
+
+        // If we already started a view change (i.e. added our contribution), we don't do anything
+        if self.active_view_change.is_some() {
+            debug!("View change already started");
+            return None;
+        }
+
+        if let Some((committed_block_number, committed_view_number)) = self.committed_pbft {
+            if committed_block_number == block_number {
+                debug!("Not changing view of macro block #{}, committed to pBFT proposal at view {}", block_number, committed_view_number);
+                return None;
+            }
+        }
+
+        let pk_idx = self.pk_idx.expect("Checked above that we are an active validator");
+        let view_change = ViewChange { block_number, new_view_number: self.view_number + 1, prev_seed };
+        self.active_view_change = Some(view_change.clone());
+        Some((view_change, pk_idx))
+    }
+
+    /// Moves to the view of the completed view change. Returns the views that were left, or `None`
+    /// if we're already at this view or a later one.
+    fn complete_view_change(&mut self, view_change: &ViewChange) -> Option<Range<u32>> {
+        if self.active_view_change.as_ref() == Some(view_change) {
+            self.active_view_change = None;
+        }
+        if self.view_number >= view_change.new_view_number {
+            return None;
+        }
+        let left_views = self.view_number..view_change.new_view_number;
+        self.view_number = view_change.new_view_number;
+        Some(left_views)
+    }
+}
+
 impl Validator {
     const BLOCK_TIMEOUT: Duration = Duration::from_secs(10);
-    //const PBFT_TIMEOUT: Duration = Duration::from_secs(60);
 
-    pub fn new(consensus: Arc<Consensus<AlbatrossConsensusProtocol>>, validator_key: KeyPair, validator_wallet_key: Option<keys::KeyPair>) -> Result<Arc<Self>, Error> {
-        let compressed_public_key = validator_key.public.compress();
+    pub fn new(consensus: Arc<Consensus<AlbatrossConsensusProtocol>>, signer: Arc<dyn Signer>, validator_wallet_key: Option<keys::KeyPair>, aggregation_config: AggregationConfig) -> Result<Arc<Self>, Error> {
+        let compressed_public_key = signer.public_key().compress();
         let info = ValidatorInfo {
             public_key: compressed_public_key,
             peer_address: consensus.network.network_config.peer_address().clone(),
             udp_address: None,
             valid_from: consensus.blockchain.block_number(),
         };
-        let validator_network = ValidatorNetwork::new(consensus.network.clone(), consensus.blockchain.clone(), SignedValidatorInfo::from_message(info, &validator_key.secret, 0));
-        let block_producer = BlockProducer::new(consensus.blockchain.clone(), consensus.mempool.clone(), validator_key.clone());
+        let validator_network = ValidatorNetwork::new(consensus.network.clone(), consensus.blockchain.clone(), signer.sign_validator_info(info)?, aggregation_config);
+        let block_producer = BlockProducer::new_without_key(consensus.blockchain.clone(), consensus.mempool.clone());
         let view_number = consensus.blockchain.next_view_number();
 
         debug!("Initializing validator");
@@ -122,7 +191,7 @@ impl Validator {
             consensus,
             validator_network,
 
-            validator_key,
+            signer,
             validator_wallet_key,
             timers: Timers::new(),
 
@@ -133,11 +202,15 @@ impl Validator {
                 fork_proof_pool: ForkProofPool::new(),
                 view_number,
                 active_view_change: None,
+                committed_pbft: None,
                 proposed_extrinsics: HashMap::new(),
             }),
 
             self_weak: MutableOnce::new(Weak::new()),
             listeners: MutableOnce::new(None),
+
+            #[cfg(feature = "metrics")]
+            metrics: ValidatorMetrics::default(),
         });
         Validator::init_listeners(&this);
 
@@ -249,6 +322,12 @@ impl Validator {
         // Clear out proposed extrinsics
         state.proposed_extrinsics.clear();
 
+        // A commit only concerns the view changes of its macro block.
+        let height = self.blockchain.height();
+        if state.committed_pbft.map_or(false, |(block_number, _)| block_number <= height) {
+            state.committed_pbft = None;
+        }
+
         if state.status == ValidatorStatus::Potential || state.status == ValidatorStatus::Active {
             // Reset the view change timeout because we received a valid block.
             // NOTE: This doesn't take the state lock, so we don't need to drop it
@@ -270,7 +349,7 @@ impl Validator {
 
             if self.is_parked(validator_registry) {
                 let mut recipient = Recipient::new_staking_builder(validator_registry.clone());
-                recipient.unpark_validator(&self.validator_key.public);
+                recipient.unpark_validator(self.signer.public_key());
 
                 // Send the unpark transaction with a fixed height each epoch in case there's already a pushed unpark transaction.
                 // Note: If the validity window is ever less than an epoch's length, using the last macro block's height would require
@@ -286,7 +365,13 @@ impl Validator {
                     .with_recipient(recipient.generate().unwrap());
 
                 let mut proof_builder = tx_builder.generate().unwrap().unwrap_signalling();
-                proof_builder.sign_with_validator_key_pair(&self.validator_key);
+                match self.signer.sign_staking_signal(&proof_builder.transaction) {
+                    Ok(signature) => { proof_builder.set_validator_signature(signature); },
+                    Err(e) => {
+                        warn!("Failed to sign unpark transaction: {}", e);
+                        return;
+                    },
+                }
                 let mut proof_builder = proof_builder.generate().unwrap().unwrap_basic();
                 proof_builder.sign_with_key_pair(validator_wallet_key);
                 let transaction = proof_builder.generate().unwrap();
@@ -344,6 +429,9 @@ impl Validator {
     }
 
     fn on_validator_network_event(&self, event: &ValidatorNetworkEvent) {
+        #[cfg(feature = "metrics")]
+        self.metrics.note_validator_network_event(event);
+
         {
             let state = self.state.write();
 
@@ -360,8 +448,7 @@ impl Validator {
                 self.on_slot_change(slot_change);
             },
             ValidatorNetworkEvent::PbftProposal(proposal) => {
-                let hash: Blake2bHash = proposal.header.hash();
-                self.on_pbft_proposal(&hash);
+                self.on_pbft_proposal(proposal);
             },
             ValidatorNetworkEvent::PbftPrepareComplete(hash) => {
                 self.on_pbft_prepare_complete(hash);
@@ -369,6 +456,9 @@ impl Validator {
             ValidatorNetworkEvent::PbftComplete(event) => {
                 self.on_pbft_commit_complete(&event.hash, &event.proposal, &event.proof);
             },
+            ValidatorNetworkEvent::PbftTimeout(event) => {
+                self.on_pbft_timeout(event);
+            },
             ValidatorNetworkEvent::ForkProof(fork_proof) => {
                 self.on_fork_proof(fork_proof);
             },
@@ -397,13 +487,6 @@ impl Validator {
                 let next_block_number = self.blockchain.height() + 1;
                 let current_seed = self.blockchain.head().seed().clone();
 
-                // If we have an active view change with this view number, clear it.
-                if let Some(vc) = &state.active_view_change {
-                    if vc == &view_change {
-                        state.active_view_change = None;
-                    }
-                }
-
                 // Check if this view change is still relevant.
                 // Check if view change concerns an old block.
                 if view_change.block_number < next_block_number {
@@ -414,13 +497,14 @@ impl Validator {
                     return;
                 } else if view_change.block_number == next_block_number
                     && view_change.prev_seed == current_seed { // Else, compare it with our current state.
-                    if state.view_number < view_change.new_view_number {
+                    // Update our view number and clear our view change, if it was this one.
+                    if let Some(left_views) = state.complete_view_change(&view_change) {
+                        #[cfg(feature = "metrics")]
+                        self.note_missed_micro_blocks(view_change.block_number, left_views);
+
                         // Reset view change interval again and increase the timeout linearly.
                         let num_view_changes = view_change.new_view_number - self.blockchain.next_view_number();
                         self.reset_view_change_interval(Self::BLOCK_TIMEOUT.mul(num_view_changes + 1));
-
-                        // Update our view number.
-                        state.view_number = view_change.new_view_number;
                     }
                     else {
                         // We're already at a better view number.
@@ -452,7 +536,7 @@ impl Validator {
         trace!("Next block producer: Slot #{}: {}", slot_number, slot.public_key());
 
         // Get our public key
-        let our_public_key = self.validator_key.public.compress();
+        let our_public_key = self.signer.public_key().compress();
 
         // Check if we're the slot owner
         if slot.public_key().compressed() == &our_public_key {
@@ -472,7 +556,8 @@ impl Validator {
         }
     }
 
-    pub fn on_pbft_proposal(&self, hash: &Blake2bHash) {
+    pub fn on_pbft_proposal(&self, proposal: &PbftProposal) {
+        let hash: Blake2bHash = proposal.header.hash();
         let state = self.state.write();
         trace!("Received proposal: {}", hash);
         // View change messages should only be sent by active validators.
@@ -480,17 +565,28 @@ impl Validator {
             return;
         }
 
+        if state.has_left_view(proposal.header.block_number, proposal.header.view_number) {
+            debug!("Ignoring pBFT proposal from old view: {}", hash);
+            return;
+        }
+
         // Note: we don't verify this hash as the network validator already did.
         let pk_idx = state.pk_idx.expect("Already checked that we are an active validator before calling this function");
 
         drop(state);
 
+        // The pBFT round has its own timeout in the validator network, so don't change the view
+        // while it's running.
+        self.timers.clear_interval(&ValidatorTimer::ViewChange);
+
         trace!("Signing prepare: pk_idx={}", pk_idx);
-        let prepare_message = SignedPbftPrepareMessage::from_message(
-            PbftPrepareMessage { block_hash: hash.clone() },
-            &self.validator_key.secret,
-            pk_idx
-        );
+        let prepare_message = match self.signer.sign_pbft_prepare(proposal, pk_idx) {
+            Ok(message) => message,
+            Err(e) => {
+                warn!("Failed to sign pBFT prepare: {}", e);
+                return;
+            },
+        };
 
         self.validator_network.push_prepare(prepare_message)
             .unwrap_or_else(|e| debug!("Failed to push pBFT prepare: {}", e));
@@ -509,12 +605,37 @@ impl Validator {
 
         drop(state);
 
+        let proposal = match self.validator_network.get_pbft_proposal(hash) {
+            Some(proposal) => proposal,
+            None => {
+                debug!("Prepare complete for unknown pBFT proposal: {}", hash);
+                return;
+            },
+        };
+
+        // Mark the proposal as committed before signing, so that we can't start a view change
+        // in between. The signer might be remote, so don't hold the state lock while signing.
+        let (block_number, view_number) = (proposal.header.block_number, proposal.header.view_number);
+        let prev_committed_pbft = match self.state.write().start_commit(block_number, view_number) {
+            Some(prev_committed_pbft) => prev_committed_pbft,
+            None => {
+                debug!("Not committing to pBFT proposal from old view: {}", hash);
+                return;
+            },
+        };
+
         trace!("Signing commit message: pk_idx={}", pk_idx);
-        let commit_message = SignedPbftCommitMessage::from_message(
-            PbftCommitMessage { block_hash: hash.clone() },
-            &self.validator_key.secret,
-            pk_idx
-        );
+        let commit_message = match self.signer.sign_pbft_commit(&proposal, pk_idx) {
+            Ok(message) => message,
+            Err(e) => {
+                warn!("Failed to sign pBFT commit: {}", e);
+                let mut state = self.state.write();
+                if state.committed_pbft == Some((block_number, view_number)) {
+                    state.committed_pbft = prev_committed_pbft;
+                }
+                return;
+            },
+        };
 
         self.validator_network.push_commit(commit_message)
             .unwrap_or_else(|e| debug!("Failed to push pBFT commit: {}", e));
@@ -542,34 +663,47 @@ impl Validator {
         }
     }
 
+    fn on_pbft_timeout(&self, event: &PbftTimeoutEvent) {
+        debug!("pBFT round for #{}.{} timed out, changing view", event.block_number, event.view_number);
+
+        // Resume the block timeout in case the next proposer doesn't propose either.
+        self.reset_view_change_interval(Self::BLOCK_TIMEOUT);
+        self.start_view_change();
+    }
+
     fn start_view_change(&self) {
         let _lock = self.blockchain.lock();
         let mut state = self.state.write();
 
-        // View change messages should only be sent by active validators.
-        if state.status != ValidatorStatus::Active {
-            return;
-        }
-
-        // If we already started a view change (i.e. added our contribution), we don't do anything
-        if state.active_view_change.is_some() {
-            debug!("View change already started");
-            return;
-        }
-
         // The number of the block that timed out.
         let prev_seed = self.blockchain.head().seed().clone();
         let block_number = self.blockchain.height() + 1;
-        let new_view_number = state.view_number + 1;
-        let message = ViewChange { block_number, new_view_number, prev_seed };
 
-        info!("Starting view change to {}", message);
+        // Mark the view change as started before signing, so that it isn't started twice. The
+        // signer might be remote, so don't hold the locks while signing.
+        let (message, pk_idx) = match state.start_view_change(block_number, prev_seed) {
+            Some(view_change) => view_change,
+            None => return,
+        };
+        drop(state);
+        drop(_lock);
 
-        let pk_idx = state.pk_idx.expect("Checked above that we are an active validator");
-        let view_change_message = SignedViewChange::from_message(message.clone(), &self.validator_key.secret, pk_idx);
-        state.active_view_change = Some(message);
+        let view_change_message = match self.signer.sign_view_change(message.clone(), pk_idx) {
+            Ok(view_change_message) => view_change_message,
+            Err(e) => {
+                warn!("Failed to sign view change to {}: {}", message, e);
+                let mut state = self.state.write();
+                if state.active_view_change.as_ref() == Some(&message) {
+                    state.active_view_change = None;
+                }
+                return;
+            },
+        };
 
-        drop(state);
+        info!("Starting view change to {}", message);
+
+        #[cfg(feature = "metrics")]
+        self.metrics.note_view_change_started();
 
         // Broadcast our view change number message to the other validators.
         self.validator_network.start_view_change(view_change_message);
@@ -577,12 +711,11 @@ impl Validator {
 
     fn get_pk_idx_and_slots(&self) -> Option<(u16, u16)> {
         self.blockchain.current_validators()
-            .find_idx_and_num_slots_by_public_key(&self.validator_key.public.compress())
+            .find_idx_and_num_slots_by_public_key(&self.signer.public_key().compress())
     }
 
     fn produce_macro_block(&self, block_number: u32, view_number: u32, view_change: Option<ViewChangeProof>) {
         let lock = self.blockchain.lock();
-        let mut state = self.state.write();
 
         // If we are not at the head of the chain, ignore this.
         // This may happen if a new block has been produced due to the async call.
@@ -591,16 +724,33 @@ impl Validator {
             return;
         }
 
+        // The signer might be remote, so don't hold the state lock while signing.
+        let seed = match self.signer.sign_seed(block_number, view_number, self.blockchain.head().seed()) {
+            Ok(seed) => seed,
+            Err(e) => {
+                warn!("Failed to sign seed of macro block #{}.{}: {}", block_number, view_number, e);
+                return;
+            },
+        };
+
+        let mut state = self.state.write();
+
         // FIXME: Don't use network time
         let timestamp = self.consensus.network.network_time.now();
-        let (pbft_proposal, proposed_extrinsics) = self.block_producer.next_macro_block_proposal(timestamp, view_number, view_change);
+        let (pbft_proposal, proposed_extrinsics) = self.block_producer.next_macro_block_proposal_with_seed(seed, timestamp, view_number, view_change);
         state.proposed_extrinsics.insert(pbft_proposal.header.hash(), proposed_extrinsics);
         let pk_idx = state.pk_idx.expect("Checked that we are an active validator before entering this function");
 
         drop(state);
         drop(lock);
 
-        let signed_proposal = SignedPbftProposal::from_message(pbft_proposal, &self.validator_key.secret, pk_idx);
+        let signed_proposal = match self.signer.sign_pbft_proposal(pbft_proposal, pk_idx) {
+            Ok(signed_proposal) => signed_proposal,
+            Err(e) => {
+                warn!("Failed to sign pBFT proposal: {}", e);
+                return;
+            },
+        };
         self.validator_network.start_pbft(signed_proposal)
             .unwrap_or_else(|e| error!("Failed to start pBFT proposal: {}", e));
 
@@ -620,33 +770,106 @@ impl Validator {
             - MicroHeader::SIZE
             - MicroExtrinsics::get_metadata_size(0, 0);
 
-        let state = self.state.read();
-        let fork_proofs = state.fork_proof_pool.get_fork_proofs_for_block(max_size);
+        // The signer might be remote, so don't hold the state lock while signing.
+        let fork_proofs = self.state.read().fork_proof_pool.get_fork_proofs_for_block(max_size);
         let timestamp = self.consensus.network.network_time.now();
 
-        let block = self.block_producer.next_micro_block(fork_proofs, timestamp, view_number, vec![], view_change_proof);
+        let seed = match self.signer.sign_seed(block_number, view_number, self.blockchain.head().seed()) {
+            Ok(seed) => seed,
+            Err(e) => {
+                warn!("Failed to sign seed of micro block #{}.{}: {}", block_number, view_number, e);
+                return;
+            },
+        };
+        let (header, extrinsics) = self.block_producer.next_unsigned_micro_block(seed, fork_proofs, timestamp, view_number, vec![]);
+        let signature = match self.signer.sign_micro_header(&header) {
+            Ok(signature) => signature,
+            Err(e) => {
+                warn!("Failed to sign micro block #{}.{}: {}", block_number, view_number, e);
+                return;
+            },
+        };
+        let block = MicroBlock {
+            header,
+            justification: MicroJustification {
+                signature,
+                view_change_proof,
+            },
+            extrinsics: Some(extrinsics),
+        };
+        let hash = block.header.hash::<Blake2bHash>();
+
         info!("Produced block #{}.{}: {}",
               block.header.block_number,
               block.header.view_number,
-              block.header.hash::<Blake2bHash>());
+              hash);
 
         // Drop lock before push, otherwise two concurrent threads can dead-lock because the
         // validator and blockchain lock are circular dependent.
-        drop(state);
         drop(lock);
 
         // Automatically relays block.
         match self.blockchain.push(Block::Micro(block)) {
-            Ok(r) => trace!("Push result: {:?}", r),
+            Ok(r) => {
+                trace!("Push result: {:?}", r);
+                #[cfg(feature = "metrics")]
+                self.metrics.note_micro_block_produced();
+            },
             Err(e) => error!("Failed to push produced micro block to blockchain: {:?}", e),
         }
     }
 
+    /// Counts a missed micro block for each of the given views of the block that we were the
+    /// producer of.
+    #[cfg(feature = "metrics")]
+    fn note_missed_micro_blocks(&self, block_number: u32, views: Range<u32>) {
+        if block_number == 0 || self.blockchain.get_next_block_type(Some(block_number - 1)) != BlockType::Micro {
+            return;
+        }
+
+        let our_public_key = self.signer.public_key().compress();
+        for view_number in views {
+            if let Some((slot, _)) = self.blockchain.get_slot_at(block_number, view_number, None) {
+                if slot.public_key().compressed() == &our_public_key {
+                    self.metrics.note_micro_block_missed();
+                }
+            }
+        }
+    }
+
+    /// The signer holding our validator key.
+    pub fn signer(&self) -> &Arc<dyn Signer> {
+        &self.signer
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        self.signer.public_key()
+    }
+
+    pub fn status(&self) -> ValidatorStatus {
+        self.state.read().status
+    }
+
+    /// Our validator index in the current epoch, if we're an active validator.
+    pub fn pk_idx(&self) -> Option<u16> {
+        self.state.read().pk_idx
+    }
+
+    /// Number of slots we have in the current epoch, if we're an active validator.
+    pub fn num_slots(&self) -> Option<u16> {
+        self.state.read().slots
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> &ValidatorMetrics {
+        &self.metrics
+    }
+
     fn is_potential_validator(&self) -> bool {
         let validator_registry = NetworkInfo::from_network_id(self.blockchain.network_id).validator_registry_address().expect("Albatross consensus always has the address set.");
         let contract = self.blockchain.state().accounts().get(validator_registry, None);
         if let Account::Staking(contract) = contract {
-            let public_key = self.validator_key.public.compress();
+            let public_key = self.signer.public_key().compress();
 
             // FIXME: Inefficient linear scan.
             contract.active_validators_by_key.contains_key(&public_key)
@@ -659,7 +882,7 @@ impl Validator {
         let contract = self.blockchain.state().accounts().get(validator_registry, None);
 
         if let Account::Staking(contract) = contract {
-            let public_key = self.validator_key.public.compress();
+            let public_key = self.signer.public_key().compress();
 
             contract.current_epoch_parking.contains(&public_key) || contract.previous_epoch_parking.contains(&public_key)
         } else {
@@ -677,3 +900,164 @@ impl Drop for Validator {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use block_albatross::MacroHeader;
+    use primitives::slot::ValidatorSlots;
+
+    use super::*;
+
+    fn state(view_number: u32, active_view_change: Option<ViewChange>) -> ValidatorState {
+        ValidatorState {
+            pk_idx: Some(0),
+            slots: Some(1),
+            status: ValidatorStatus::Active,
+            fork_proof_pool: ForkProofPool::new(),
+            view_number,
+            active_view_change,
+            committed_pbft: None,
+            proposed_extrinsics: HashMap::new(),
+        }
+    }
+
+    fn proposal(view_number: u32, timestamp: u64) -> PbftProposal {
+        let hash = Blake2bHash::from([0u8; 32]);
+        PbftProposal {
+            header: MacroHeader {
+                version: 1,
+                validators: ValidatorSlots::default(),
+                block_number: 128,
+                view_number,
+                parent_macro_hash: hash.clone(),
+                seed: Default::default(),
+                parent_hash: hash.clone(),
+                state_root: Blake2bHash::from([view_number as u8; 32]),
+                extrinsics_root: hash.clone(),
+                transactions_root: hash,
+                timestamp,
+            },
+            view_change: None,
+        }
+    }
+
+    #[test]
+    fn it_leaves_views_it_changes() {
+        let state_before = state(0, None);
+        assert!(!state_before.has_left_view(128, 0));
+
+        // Once we signed a view change, we must not vote for the old view anymore.
+        let view_change = ViewChange { block_number: 128, new_view_number: 1, prev_seed: Default::default() };
+        let changing = state(0, Some(view_change));
+        assert!(changing.has_left_view(128, 0));
+        assert!(!changing.has_left_view(128, 1));
+
+        // Neither after the view change completed.
+        let changed = state(1, None);
+        assert!(changed.has_left_view(128, 0));
+        assert!(!changed.has_left_view(128, 1));
+    }
+
+    #[test]
+    fn it_accounts_for_every_view_left_by_a_view_change() {
+        // The micro blocks of all views a view change skips are counted as missed, e.g. if we
+        // only received the proof of a later view change.
+        let mut validator = state(0, None);
+        let view_change = ViewChange { block_number: 128, new_view_number: 2, prev_seed: Default::default() };
+        assert_eq!(validator.complete_view_change(&view_change), Some(0..2));
+
+        // Each view is only accounted for once.
+        assert_eq!(validator.complete_view_change(&view_change), None);
+        let view_change = ViewChange { block_number: 128, new_view_number: 3, prev_seed: Default::default() };
+        assert_eq!(validator.complete_view_change(&view_change), Some(2..3));
+    }
+
+    const BLOCK_NUMBER: u32 = 128;
+
+    /// Number of validators that vote for everything
+    const BYZANTINE: usize = 1;
+
+    /// Two thirds of the 3 honest and the byzantine validator, plus one
+    const QUORUM: usize = 3;
+
+    fn validators() -> Vec<ValidatorState> {
+        (0..3).map(|pk_idx| ValidatorState { pk_idx: Some(pk_idx), ..state(0, None) }).collect()
+    }
+
+    /// Number of validators that vote for the proposal
+    fn prepare(validators: &[ValidatorState], proposal: &PbftProposal) -> usize {
+        let header = &proposal.header;
+        validators.iter()
+            .filter(|validator| !validator.has_left_view(header.block_number, header.view_number))
+            .count() + BYZANTINE
+    }
+
+    /// Number of validators among `signers` that sign a commit for the proposal
+    fn commit(validators: &mut [ValidatorState], signers: &[usize], proposal: &PbftProposal) -> usize {
+        let header = &proposal.header;
+        signers.iter()
+            .filter(|&&i| validators[i].start_commit(header.block_number, header.view_number).is_some())
+            .count()
+    }
+
+    /// Times out the current view at all validators. Returns the view change if enough validators
+    /// signed it.
+    fn change_view(validators: &mut [ValidatorState]) -> Option<ViewChange> {
+        let signed: Vec<ViewChange> = validators.iter_mut()
+            .filter_map(|validator| validator.start_view_change(BLOCK_NUMBER, Default::default()))
+            .map(|(view_change, _)| view_change)
+            .collect();
+        if signed.len() + BYZANTINE < QUORUM {
+            return None;
+        }
+        let view_change = signed[0].clone();
+        for validator in validators.iter_mut() {
+            validator.complete_view_change(&view_change);
+        }
+        Some(view_change)
+    }
+
+    #[test]
+    fn it_doesnt_change_the_view_of_a_committed_proposal() {
+        let mut validators = validators();
+        let proposal = proposal(0, 1000);
+        assert_eq!(prepare(&validators, &proposal), 4);
+
+        // Two honest validators commit, so the proposal might be finalized.
+        assert_eq!(commit(&mut validators, &[0, 1], &proposal) + BYZANTINE, QUORUM);
+
+        // Thus, the view must not be changed, otherwise a second proposal could be finalized.
+        assert!(change_view(&mut validators).is_none());
+        assert!(validators[0].start_view_change(BLOCK_NUMBER, Default::default()).is_none());
+        assert!(validators.iter().all(|validator| validator.view_number == 0));
+    }
+
+    #[test]
+    fn it_finalizes_a_single_proposal_after_a_view_change() {
+        let mut validators = validators();
+        let first = proposal(0, 1000);
+        assert_eq!(prepare(&validators, &first), 4);
+
+        // Only one honest validator got to commit before the pBFT round timed out.
+        let mut first_commits = commit(&mut validators, &[0], &first) + BYZANTINE;
+        assert!(first_commits < QUORUM);
+
+        // The others change the view, so the first proposal can't be finalized anymore.
+        let view_change = change_view(&mut validators).expect("Enough validators didn't commit");
+        assert_eq!(view_change.new_view_number, 1);
+        assert!(validators.iter().all(|validator| validator.view_number == 1));
+        first_commits += commit(&mut validators, &[1, 2], &first);
+        assert!(first_commits < QUORUM);
+
+        // A different proposal of the new view is finalized instead, with the committed validator
+        // voting too.
+        let second = proposal(1, 2000);
+        assert_ne!(first.header.hash::<Blake2bHash>(), second.header.hash::<Blake2bHash>());
+        assert_eq!(prepare(&validators, &second), 4);
+        assert_eq!(commit(&mut validators, &[0, 1, 2], &second) + BYZANTINE, 4);
+        assert_eq!(validators[0].committed_pbft, Some((BLOCK_NUMBER, 1)));
+
+        // Now, the view can't be changed anymore either.
+        assert!(change_view(&mut validators).is_none());
+    }
+}
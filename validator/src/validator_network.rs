@@ -1,6 +1,7 @@
 use std::collections::{HashMap, BTreeMap};
 use std::sync::{Arc, Weak};
 use std::fmt;
+use std::time::Duration;
 
 use failure::Fail;
 use parking_lot::{RwLock, RwLockUpgradableReadGuard};
@@ -24,13 +25,16 @@ use network_primitives::address::PeerId;
 use primitives::policy::{SLOTS, TWO_THIRD_SLOTS, is_macro_block_at};
 use utils::mutable_once::MutableOnce;
 use utils::observer::{Notifier, weak_listener, weak_passthru_listener};
+use utils::timers::Timers;
 use handel::aggregation::AggregationEvent;
+use handel::config::Config;
 use handel::update::LevelUpdateMessage;
 use bls::bls12_381::CompressedPublicKey;
 
 use crate::validator_agent::{ValidatorAgent, ValidatorAgentEvent};
 use crate::signature_aggregation::view_change::ViewChangeAggregation;
 use crate::signature_aggregation::pbft::PbftAggregation;
+use crate::signature_aggregation::AggregationConfig;
 use crate::pool::ValidatorPool;
 use primitives::slot::SlotCollection;
 
@@ -46,6 +50,8 @@ pub enum ValidatorNetworkError {
     UnknownProposal,
     #[fail(display = "Invalid pBFT proposal")]
     InvalidProposal,
+    #[fail(display = "pBFT proposal is from a view that was already changed")]
+    OutdatedProposal,
 }
 
 #[derive(Clone, Debug)]
@@ -74,6 +80,13 @@ pub struct PbftCompleteEvent {
     pub proof: PbftProof,
 }
 
+#[derive(Clone, Debug)]
+pub struct PbftTimeoutEvent {
+    pub hash: Blake2bHash,
+    pub block_number: u32,
+    pub view_number: u32,
+}
+
 #[derive(Clone, Debug)]
 pub enum ValidatorNetworkEvent {
     /// When a fork proof was given
@@ -95,6 +108,15 @@ pub enum ValidatorNetworkEvent {
 
     /// When the pBFT proof is complete
     PbftComplete(Box<PbftCompleteEvent>),
+
+    /// When the current pBFT round didn't complete in time. The validator should then start a
+    /// view change for the macro block.
+    PbftTimeout(Box<PbftTimeoutEvent>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum ValidatorNetworkTimer {
+    PbftTimeout,
 }
 
 
@@ -112,8 +134,8 @@ struct PbftState {
 }
 
 impl PbftState {
-    pub fn new(block_hash: Blake2bHash, proposal: SignedPbftProposal, node_id: usize, validators: Arc<RwLock<ValidatorPool>>) -> Self {
-        let aggregation = Arc::new(RwLock::new(PbftAggregation::new(block_hash.clone(), node_id, validators, None)));
+    pub fn new(block_hash: Blake2bHash, proposal: SignedPbftProposal, node_id: usize, validators: Arc<RwLock<ValidatorPool>>, prepare_config: Config, commit_config: Config) -> Self {
+        let aggregation = Arc::new(RwLock::new(PbftAggregation::new(block_hash.clone(), node_id, validators, Some(prepare_config), Some(commit_config))));
         Self {
             proposal,
             block_hash,
@@ -158,6 +180,17 @@ impl PbftState {
 
         false
     }
+
+    /// Whether the given view change replaces the view of this proposal.
+    fn is_outdated_by(&self, view_change: &ViewChange) -> bool {
+        pbft_is_outdated_by(&self.proposal.message, view_change)
+    }
+}
+
+/// Whether the given view change replaces the view of the proposal.
+fn pbft_is_outdated_by(proposal: &PbftProposal, view_change: &ViewChange) -> bool {
+    proposal.header.block_number == view_change.block_number
+        && proposal.header.view_number < view_change.new_view_number
 }
 
 impl fmt::Debug for PbftState {
@@ -214,6 +247,11 @@ pub struct ValidatorNetwork {
     /// NOTE: To avoid circular dead-locks, always acquire this after the validator pool lock.
     pub validators: Arc<RwLock<ValidatorPool>>,
 
+    /// Handel configurations for view change and pBFT aggregations
+    aggregation_config: AggregationConfig,
+
+    timers: Timers<ValidatorNetworkTimer>,
+
     self_weak: MutableOnce<Weak<ValidatorNetwork>>,
     pub notifier: RwLock<Notifier<'static, ValidatorNetworkEvent>>,
 }
@@ -221,7 +259,10 @@ pub struct ValidatorNetwork {
 impl ValidatorNetwork {
     const LIMIT_POTENTIAL_VALIDATOR_INFOS: usize = 64;
 
-    pub fn new(network: Arc<Network<Blockchain>>, blockchain: Arc<Blockchain>, info: SignedValidatorInfo) -> Arc<Self> {
+    /// Time after which a pBFT round that didn't complete is given up on.
+    pub const PBFT_TIMEOUT: Duration = Duration::from_secs(60);
+
+    pub fn new(network: Arc<Network<Blockchain>>, blockchain: Arc<Blockchain>, info: SignedValidatorInfo, aggregation_config: AggregationConfig) -> Arc<Self> {
         let pool = ValidatorPool::new(Arc::clone(&network));
 
         // blacklist ourself
@@ -232,6 +273,8 @@ impl ValidatorNetwork {
             info,
             state: RwLock::new(ValidatorNetworkState::default()),
             validators: Arc::new(RwLock::new(pool)),
+            aggregation_config,
+            timers: Timers::new(),
             self_weak: MutableOnce::new(Weak::new()),
             notifier: RwLock::new(Notifier::new()),
         });
@@ -345,6 +388,7 @@ impl ValidatorNetwork {
 
         // Clear pBFT states
         state.pbft_states.clear();
+        self.timers.clear_delay(&ValidatorNetworkTimer::PbftTimeout);
 
         // Set validator ID
         state.validator_id = validator_id;
@@ -411,6 +455,8 @@ impl ValidatorNetwork {
         // We need to drop the state before notifying and relaying
         drop(state);
 
+        self.start_pbft_timeout(&best_pbft);
+
         // Notify Validator (and send prepare message)
         self.notifier.read().notify(ValidatorNetworkEvent::PbftProposal(Box::new(best_pbft.proposal.message)));
     }
@@ -468,6 +514,7 @@ impl ValidatorNetwork {
         if let Err(e) = proof.verify(&view_change, &self.blockchain.current_validators(), TWO_THIRD_SLOTS) {
             // TODO: Make this use Display instead, once the implementation for it is merged.
             debug!("Invalid view change proof: {:?}", e);
+            return;
         }
 
         let state = self.state.upgradable_read();
@@ -484,6 +531,15 @@ impl ValidatorNetwork {
         // Remove active view change
         state.view_changes.remove(&view_change);
 
+        // Discard pBFT rounds of the views that were changed. Their aggregations can't complete
+        // anymore, since the validators that signed the view change won't vote in them.
+        let num_pbft_states = state.pbft_states.len();
+        state.pbft_states.retain(|pbft| !pbft.is_outdated_by(&view_change));
+        if state.pbft_states.len() < num_pbft_states {
+            debug!("Discarded pBFT proposals before view change to {}", view_change);
+            self.timers.clear_delay(&ValidatorNetworkTimer::PbftTimeout);
+        }
+
         // Remove all active view changes that are now obsolete
         // this was meant to also kill old view changes from older blocks even, do we need this?
         /*let cancel_view_changes = state.view_changes.keys()
@@ -524,6 +580,11 @@ impl ValidatorNetwork {
 
         debug!("pBFT proposal by validator {}: {}", signed_proposal.signer_idx, block_hash);
 
+        // Proposals from views that were already changed are ignored.
+        if state.complete_view_changes.keys().any(|view_change| pbft_is_outdated_by(&signed_proposal.message, view_change)) {
+            return Err(ValidatorNetworkError::OutdatedProposal);
+        }
+
         let validator_id = match state.validator_id {
             Some(validator_id) => validator_id,
             None => {
@@ -537,6 +598,8 @@ impl ValidatorNetwork {
             signed_proposal.clone(),
             validator_id,
             Arc::clone(&self.validators),
+            self.aggregation_config.pbft_prepare.clone(),
+            self.aggregation_config.pbft_commit.clone(),
         );
 
         let chain_height = self.blockchain.height();
@@ -632,11 +695,14 @@ impl ValidatorNetwork {
             state.pbft_states.push(pbft);
         }
 
+        let timeout_pbft = if !buffered { state.pbft_states.first().cloned() } else { None };
+
         // We need to drop the state before notifying and relaying
         drop(state);
 
         // Notify Validator (and send prepare message)
-        if !buffered {
+        if let Some(pbft) = timeout_pbft {
+            self.start_pbft_timeout(&pbft);
             self.notifier.read().notify(ValidatorNetworkEvent::PbftProposal(Box::new(signed_proposal.message.clone())));
         }
 
@@ -730,7 +796,7 @@ impl ValidatorNetwork {
             view_change.clone(),
             node_id,
             Arc::clone(&self.validators),
-            None
+            Some(self.aggregation_config.view_change.clone()),
         );
         debug!("New view change for: {}, node_id={}", view_change, node_id);
 
@@ -757,6 +823,41 @@ impl ValidatorNetwork {
         self.on_pbft_proposal(signed_proposal)
     }
 
+    /// Starts the timeout of the given pBFT round, replacing the timeout of the previous one.
+    fn start_pbft_timeout(&self, pbft: &PbftState) {
+        let header = &pbft.proposal.message.header;
+        let event = PbftTimeoutEvent {
+            hash: pbft.block_hash.clone(),
+            block_number: header.block_number,
+            view_number: header.view_number,
+        };
+
+        let weak = Weak::clone(&self.self_weak);
+        self.timers.reset_delay(ValidatorNetworkTimer::PbftTimeout, move || {
+            if let Some(this) = weak.upgrade() {
+                this.on_pbft_timeout(event);
+            }
+        }, Self::PBFT_TIMEOUT);
+    }
+
+    fn on_pbft_timeout(&self, event: PbftTimeoutEvent) {
+        self.timers.clear_delay(&ValidatorNetworkTimer::PbftTimeout);
+
+        // Ignore the timeout if the round was completed or replaced in the meantime.
+        if self.state.read().get_pbft_state(&event.hash).is_none() {
+            return;
+        }
+
+        info!("pBFT round for #{}.{} timed out: {}", event.block_number, event.view_number, event.hash);
+        self.notifier.read().notify(ValidatorNetworkEvent::PbftTimeout(Box::new(event)));
+    }
+
+    /// Returns the pBFT proposal for the block with the given hash, if we know it.
+    pub fn get_pbft_proposal(&self, hash: &Blake2bHash) -> Option<PbftProposal> {
+        self.state.read().get_pbft_state(hash)
+            .map(|pbft| pbft.proposal.message.clone())
+    }
+
     pub fn push_prepare(&self, signed_prepare: SignedPbftPrepareMessage) -> Result<(), ValidatorNetworkError> {
         trace!("Push prepare: {:#?}", signed_prepare);
         let state = self.state.read();
@@ -859,3 +960,49 @@ fn fmt_vote_progress(slots: usize) -> String {
     let done = slots >= (TWO_THIRD_SLOTS as usize);
     format!("votes={: >3} / {}, done={}", slots, SLOTS, done)
 }
+
+#[cfg(test)]
+mod tests {
+    use block_albatross::MacroHeader;
+    use primitives::slot::ValidatorSlots;
+
+    use super::*;
+
+    fn proposal(block_number: u32, view_number: u32) -> PbftProposal {
+        let hash = Blake2bHash::from([0u8; 32]);
+        PbftProposal {
+            header: MacroHeader {
+                version: 1,
+                validators: ValidatorSlots::default(),
+                block_number,
+                view_number,
+                parent_macro_hash: hash.clone(),
+                seed: Default::default(),
+                parent_hash: hash.clone(),
+                state_root: hash.clone(),
+                extrinsics_root: hash.clone(),
+                transactions_root: hash,
+                timestamp: 0,
+            },
+            view_change: None,
+        }
+    }
+
+    fn view_change(block_number: u32, new_view_number: u32) -> ViewChange {
+        ViewChange { block_number, new_view_number, prev_seed: Default::default() }
+    }
+
+    #[test]
+    fn view_changes_outdate_proposals_of_earlier_views() {
+        assert!(pbft_is_outdated_by(&proposal(128, 0), &view_change(128, 1)));
+        assert!(pbft_is_outdated_by(&proposal(128, 1), &view_change(128, 3)));
+
+        // A proposal of the new view or a later one is still valid.
+        assert!(!pbft_is_outdated_by(&proposal(128, 1), &view_change(128, 1)));
+        assert!(!pbft_is_outdated_by(&proposal(128, 2), &view_change(128, 1)));
+
+        // View changes of other blocks don't matter.
+        assert!(!pbft_is_outdated_by(&proposal(128, 0), &view_change(127, 1)));
+        assert!(!pbft_is_outdated_by(&proposal(256, 0), &view_change(128, 1)));
+    }
+}
@@ -36,32 +36,32 @@ impl VrfSeed {
         let signature = self.signature.uncompress()
             .map_err(|_| VrfError::InvalidSignature)?;
 
-        // Hash use-case prefix and signature
-        let mut hasher = Blake2bHasher::new();
-        hasher.write_u8(VrfUseCase::Seed as u8).unwrap();
-        hasher.write_all(prev_seed.signature.as_ref()).unwrap();
-
-        if !public_key.verify_hash(hasher.finish(), &signature) {
+        if !public_key.verify_hash(prev_seed.next_hash(), &signature) {
             return Err(VrfError::Forged);
         }
         Ok(())
     }
 
     pub fn sign_next(&self, secret_key: &SecretKey) -> Self {
-        // Hash use-case prefix and signature
-        let mut hasher = Blake2bHasher::new();
-        hasher.write_u8(VrfUseCase::Seed as u8).unwrap();
-        hasher.write_all(self.signature.as_ref()).unwrap();
-
-        // Sign that hash and contruct new VrfSeed from it
+        // Sign the hash and contruct new VrfSeed from it
         let signature = secret_key
-            .sign_hash(hasher.finish())
+            .sign_hash(self.next_hash())
             .compress();
         Self {
             signature
         }
     }
 
+    /// The hash that is signed to derive the next seed from this one. This allows the next seed
+    /// to be signed without access to the secret key, e.g. by a remote signer.
+    pub fn next_hash(&self) -> Blake2bHash {
+        // Hash use-case prefix and signature
+        let mut hasher = Blake2bHasher::new();
+        hasher.write_u8(VrfUseCase::Seed as u8).unwrap();
+        hasher.write_all(self.signature.as_ref()).unwrap();
+        hasher.finish()
+    }
+
     pub fn rng(&self, use_case: VrfUseCase, round: u32) -> VrfRng {
         VrfRng::new(&self.signature, use_case, round)
     }
@@ -3,9 +3,15 @@ extern crate log;
 
 extern crate nimiq_consensus as consensus;
 extern crate nimiq_utils as utils;
+extern crate nimiq_block_albatross as block_albatross;
 extern crate nimiq_blockchain_albatross as blockchain_albatross;
 extern crate nimiq_blockchain_base as blockchain_base;
 extern crate nimiq_hash as hash;
+extern crate nimiq_keys as keys;
+extern crate nimiq_mempool as mempool;
+extern crate nimiq_primitives as primitives;
+extern crate nimiq_rpc_server as rpc_server;
+extern crate nimiq_transaction as transaction;
 #[cfg(feature="validator")]
 extern crate nimiq_validator as validator;
 
@@ -21,18 +27,29 @@ use tokio::net::{TcpListener};
 use tokio_tungstenite::accept_async;
 use tokio_tungstenite::tungstenite::{Message, Error as WsError};
 use parking_lot::RwLock;
-use json::{JsonValue, object};
+use json::{JsonValue, Null, object};
 
+use utils::ip_allowlist::IpAllowlist;
 use utils::unique_id::UniqueId;
 use consensus::{Consensus, AlbatrossConsensusProtocol};
+use block_albatross::{Block, BlockType};
 use blockchain_base::AbstractBlockchain;
-use blockchain_albatross::blockchain::BlockchainEvent;
+use blockchain_albatross::blockchain::{Blockchain, BlockchainEvent};
 use hash::{Hash, Blake2bHash};
+use mempool::{Mempool, MempoolEvent};
+use primitives::policy;
+use rpc_server::Handler;
+use rpc_server::jsonrpc::{self, Handler as JsonRpcHandler};
+use transaction::Transaction;
 #[cfg(feature="validator")]
 use validator::validator_network::ValidatorNetworkEvent;
 #[cfg(feature="validator")]
 use validator::validator::Validator;
 
+use crate::subscription::{Subscription, SubscriptionId, Topic};
+
+pub mod subscription;
+
 pub type WsRpcServerFuture = Box<dyn Future<Item=(), Error=()> + Send + Sync + 'static>;
 
 type WsRpcConnections = Arc<RwLock<HashMap<UniqueId, WsRpcConnection>>>;
@@ -40,6 +57,9 @@ type WsRpcConnections = Arc<RwLock<HashMap<UniqueId, WsRpcConnection>>>;
 struct WsRpcConnection {
     address: SocketAddr,
     tx: Sender<Message>,
+    authenticated: bool,
+    next_subscription_id: SubscriptionId,
+    subscriptions: HashMap<SubscriptionId, Subscription>,
 }
 
 pub struct WsRpcServer {
@@ -49,8 +69,11 @@ pub struct WsRpcServer {
 
 impl WsRpcServer {
     const QUEUE_SIZE: usize = 64;
+    const SUBSCRIPTION_QUEUE_SIZE: usize = 16;
 
-    pub fn new(ip: IpAddr, port: u16) -> Result<Self, IoError>
+    /// Creates a websocket server that answers JSON-RPC requests using the methods of `handler`
+    /// and streams events to its clients' subscriptions.
+    pub fn new(ip: IpAddr, port: u16, allowip: IpAllowlist, handler: Arc<Handler>) -> Result<Self, IoError>
     {
         let socket = TcpListener::bind(&SocketAddr::new(ip, port))?;
 
@@ -59,14 +82,30 @@ impl WsRpcServer {
 
         // Listen for incoming connections, do websocket handshake and put them in connections.
         let future = socket.incoming()
+            .filter(move |stream| {
+                match stream.peer_addr() {
+                    Ok(address) if allowip.is_allowed(&address.ip()) => true,
+                    Ok(address) => {
+                        info!("Rejected connection from {}: IP not allowed", address);
+                        false
+                    },
+                    Err(e) => {
+                        warn!("Failed to get peer address: {}", e);
+                        false
+                    },
+                }
+            })
             .for_each(move |stream| {
                 let address = stream.peer_addr().unwrap();
                 let connection_id = UniqueId::new();
-                // TODO: IP filter here
                 info!("Client connected: {}, id={}", address, connection_id);
 
                 let connections_stream = Arc::clone(&connections_tcp);
                 let connections_err = Arc::clone(&connections_tcp);
+                let handler_recv = Arc::clone(&handler);
+
+                // Connections don't need to authenticate if there are no credentials configured.
+                let authenticated = handler.authorize("", "").is_ok();
 
                 accept_async(stream)
                     .and_then(move |ws_stream| {
@@ -79,18 +118,11 @@ impl WsRpcServer {
                         // Send everything from the MSPC channel
                         let send_future = sink.send_all(rx.map_err(|_| WsError::ConnectionClosed));
 
-                        // Receive messages (and ignore them)
+                        // Receive messages
                         let connection_id_recv = connection_id;
                         let connections_recv = Arc::clone(&connections_stream);
                         let recv_future = stream
                             .for_each(move |message: Message| {
-                                // Received message. We ignore those. But we could use this for
-                                // authentication and just set a flag in the connection to enable
-                                // streaming events.
-                                //
-                                // TODO: We'll also receive close frames, which let's us close the
-                                // connection gracefully.
-
                                 // Log message
                                 debug!("Received message from #{}: {}", connection_id_recv, message);
 
@@ -103,8 +135,8 @@ impl WsRpcServer {
 
                                         Ok(())
                                     },
-                                    Message::Text(_message) => {
-                                        // TODO: Handle authentication
+                                    Message::Text(message) => {
+                                        Self::handle_text_message(&connections_recv, &handler_recv, &connection_id_recv, &message);
                                         Ok(())
                                     },
                                     _ => {
@@ -119,6 +151,9 @@ impl WsRpcServer {
                             .insert(connection_id.clone(), WsRpcConnection {
                                 address,
                                 tx,
+                                authenticated,
+                                next_subscription_id: 1,
+                                subscriptions: HashMap::new(),
                             });
 
                         let connection_future = send_future
@@ -149,11 +184,38 @@ impl WsRpcServer {
 
     pub fn register_blockchain(&self, consensus: Arc<Consensus<AlbatrossConsensusProtocol>>) {
         let connections_listener = Arc::clone(&self.connections);
+        let weak_blockchain = Arc::downgrade(&consensus.blockchain);
 
         consensus.blockchain.register_listener(move |event: &BlockchainEvent| {
-            if !connections_listener.read().is_empty() {
-                if let Some(message) = Self::map_blockchain_event(event) {
-                    Self::broadcast_message(&connections_listener, message)
+            if connections_listener.read().is_empty() {
+                return;
+            }
+            if let Some(message) = Self::map_blockchain_event(event) {
+                Self::broadcast_message(&connections_listener, message);
+            }
+
+            if !Self::has_subscriptions(&connections_listener) {
+                return;
+            }
+            let blockchain = match weak_blockchain.upgrade() {
+                Some(blockchain) => blockchain,
+                None => return,
+            };
+            Self::on_blockchain_event(&connections_listener, &blockchain, event);
+        });
+    }
+
+    pub fn register_mempool(&self, mempool: Arc<Mempool<Blockchain>>) {
+        let connections_listener = Arc::clone(&self.connections);
+
+        mempool.notifier.write().register(move |event: &MempoolEvent| {
+            if let MempoolEvent::TransactionAdded(_, transaction) = event {
+                if Self::has_subscriptions(&connections_listener) {
+                    let result = Self::transaction_to_obj(transaction, None);
+                    Self::notify(&connections_listener, |topic| match topic {
+                        Topic::MempoolTransactions => vec![result.clone()],
+                        _ => vec![],
+                    });
                 }
             }
         });
@@ -164,14 +226,96 @@ impl WsRpcServer {
         let connections_listener = Arc::clone(&self.connections);
 
         validator.validator_network.notifier.write().register(move |event: &ValidatorNetworkEvent| {
-            if !connections_listener.read().is_empty() {
-                if let Some(message) = Self::map_validator_event(event) {
-                    Self::broadcast_message(&connections_listener, message)
+            if connections_listener.read().is_empty() {
+                return;
+            }
+            if let Some(result) = Self::map_validator_event(event) {
+                Self::broadcast_message(&connections_listener, result.clone());
+                if Self::has_subscriptions(&connections_listener) {
+                    Self::notify(&connections_listener, |topic| match topic {
+                        Topic::ValidatorUpdates => vec![result.clone()],
+                        _ => vec![],
+                    });
                 }
             }
         });
     }
 
+    fn on_blockchain_event(connections: &WsRpcConnections, blockchain: &Blockchain, event: &BlockchainEvent) {
+        match event {
+            BlockchainEvent::Extended(block_hash) => {
+                if let Some(block) = blockchain.get_block(block_hash, false, true) {
+                    Self::notify_block(connections, &block, false);
+                }
+            },
+            BlockchainEvent::Rebranched(reverted, rebranched) => {
+                // Revert the blocks starting from the old head, before announcing the new ones.
+                for (_, block) in reverted.iter().rev() {
+                    Self::notify_block(connections, block, true);
+                }
+                for (_, block) in rebranched {
+                    Self::notify_block(connections, block, false);
+                }
+            },
+            BlockchainEvent::Finalized(block_hash) => {
+                // Macro blocks are only announced as finalized, so they are new heads as well.
+                if let Some(block) = blockchain.get_block(block_hash, false, false) {
+                    Self::notify_block(connections, &block, false);
+
+                    let result = Self::block_to_obj(&block);
+                    Self::notify(connections, |topic| match topic {
+                        Topic::FinalizedMacroBlocks => vec![result.clone()],
+                        _ => vec![],
+                    });
+                }
+            },
+        }
+    }
+
+    /// Notifies the subscriptions about a new block, or a block that was `reverted` by a rebranch.
+    /// Notifications of reverted blocks and their transactions are marked with `reverted: true`.
+    fn notify_block(connections: &WsRpcConnections, block: &Block, reverted: bool) {
+        Self::notify(connections, |topic| Self::block_results(topic, block, reverted));
+    }
+
+    fn block_results(topic: &Topic, block: &Block, reverted: bool) -> Vec<JsonValue> {
+        let mut results = match topic {
+            Topic::NewHeads => vec![Self::block_to_obj(block)],
+            Topic::AddressTransactions(_) => block.transactions().iter()
+                .flat_map(|transactions| transactions.iter())
+                .filter(|tx| topic.matches_transaction(tx))
+                .map(|tx| Self::transaction_to_obj(tx, Some(block)))
+                .collect(),
+            _ => vec![],
+        };
+        if reverted {
+            for result in results.iter_mut() {
+                result["reverted"] = true.into();
+            }
+        }
+        results
+    }
+
+    fn has_subscriptions(connections: &WsRpcConnections) -> bool {
+        connections.read().values()
+            .any(|connection| !connection.subscriptions.is_empty())
+    }
+
+    /// Sends the results returned by `results` for a subscription's topic to all subscriptions.
+    fn notify<F>(connections: &WsRpcConnections, mut results: F)
+        where F: FnMut(&Topic) -> Vec<JsonValue>
+    {
+        for connection in connections.write().values_mut() {
+            for (subscription_id, subscription) in connection.subscriptions.iter_mut() {
+                for result in results(&subscription.topic) {
+                    subscription.notify(*subscription_id, result);
+                }
+            }
+        }
+    }
+
+    /// Maps blockchain events to the events that are sent to every connection, regardless of its
+    /// subscriptions. These events predate subscriptions and are kept for existing clients.
     fn map_blockchain_event(event: &BlockchainEvent) -> Option<JsonValue> {
         Some(match event {
             BlockchainEvent::Extended(block_hash) => object!{
@@ -182,11 +326,11 @@ impl WsRpcServer {
                 let (old_head, _) = reverted.last()?;
                 let (new_head, _) = rebranched.last()?;
 
-                let reverted = JsonValue::Array(reverted.into_iter()
+                let reverted = JsonValue::Array(reverted.iter()
                     .map(|(block_hash, _)|  JsonValue::String(block_hash.to_string()))
                     .collect());
 
-                let rebranched = JsonValue::Array(rebranched.into_iter()
+                let rebranched = JsonValue::Array(rebranched.iter()
                     .map(|(block_hash, _)|  JsonValue::String(block_hash.to_string()))
                     .collect());
 
@@ -205,6 +349,196 @@ impl WsRpcServer {
         })
     }
 
+    /// Sends `message` to all authenticated connections.
+    fn broadcast_message(connections: &WsRpcConnections, message: JsonValue) {
+        // Convert JSON message to Websocket TEXT frame
+        let message = Message::Text(message.dump());
+
+        for (_connection_id, connection) in connections.read().iter() {
+            if !connection.authenticated {
+                continue;
+            }
+
+            let mut tx = connection.tx.clone();
+
+            // If the buffer is full, we drop the event. It's not the end of the world.
+            if let Err(e) = tx.try_send(message.clone()) {
+                warn!("Unable to send event to {}: {}", connection.address, e);
+            }
+        }
+    }
+
+    fn handle_text_message(connections: &WsRpcConnections, handler: &Handler, connection_id: &UniqueId, message: &str) {
+        let response = match json::parse(message) {
+            Ok(JsonValue::Array(requests)) => Self::handle_batch(connections, handler, connection_id, &requests),
+            Ok(request) => Self::handle_request(connections, handler, connection_id, &request),
+            Err(_) => Some(jsonrpc::error_object(Null, -32700, "Invalid JSON")),
+        };
+
+        if let Some(response) = response {
+            if let Some(connection) = connections.read().get(connection_id) {
+                let mut tx = connection.tx.clone();
+                if let Err(e) = tx.try_send(Message::Text(response.dump())) {
+                    warn!("Unable to send response to {}: {}", connection.address, e);
+                }
+            }
+        }
+    }
+
+    fn handle_batch(connections: &WsRpcConnections, handler: &Handler, connection_id: &UniqueId, requests: &[JsonValue]) -> Option<JsonValue> {
+        if requests.is_empty() {
+            return Some(jsonrpc::error_object(Null, -32600, "Invalid request"));
+        }
+        if let Some(max_batch_size) = handler.max_batch_size() {
+            if requests.len() > max_batch_size {
+                return Some(jsonrpc::error_object(Null, -32600, "Batch size limit exceeded"));
+            }
+        }
+
+        let responses: Vec<JsonValue> = requests.iter()
+            .filter_map(|request| Self::handle_request(connections, handler, connection_id, request))
+            .collect();
+        if responses.is_empty() {
+            None
+        } else {
+            Some(JsonValue::Array(responses))
+        }
+    }
+
+    /// Handles a request and returns the response, or `None` if the request is a notification.
+    ///
+    /// Subscriptions and authentication are handled by the websocket server, all other methods
+    /// are dispatched to the RPC handler.
+    fn handle_request(connections: &WsRpcConnections, handler: &Handler, connection_id: &UniqueId, request: &JsonValue) -> Option<JsonValue> {
+        if !request.is_object() || request["jsonrpc"] != "2.0" || !request["method"].is_string() {
+            return Some(jsonrpc::error_object(request["id"].clone(), -32600, "Invalid request"));
+        }
+
+        let authenticated = connections.read().get(connection_id)
+            .map(|connection| connection.authenticated)
+            .unwrap_or(false);
+
+        let result = match request["method"].as_str().unwrap() {
+            "authenticate" => Self::authenticate(connections, handler, connection_id, &request["params"]),
+            _ if !authenticated => Err(object!{
+                "code" => -32001,
+                "message" => "Not authenticated",
+            }),
+            "subscribe" => Self::subscribe(connections, connection_id, &request["params"]),
+            "unsubscribe" => Self::unsubscribe(connections, connection_id, &request["params"]),
+            _ => return jsonrpc::handle_message(handler, request),
+        };
+
+        if !request.has_key("id") {
+            return None;
+        }
+
+        Some(match result {
+            Ok(result) => object!{
+                "jsonrpc" => "2.0",
+                "id" => request["id"].clone(),
+                "result" => result,
+            },
+            Err(error) => object!{
+                "jsonrpc" => "2.0",
+                "id" => request["id"].clone(),
+                "error" => error,
+            },
+        })
+    }
+
+    /// Authenticates the connection with the credentials of the RPC server, given as
+    /// `[username, password]`.
+    fn authenticate(connections: &WsRpcConnections, handler: &Handler, connection_id: &UniqueId, params: &JsonValue) -> Result<JsonValue, JsonValue> {
+        let username = params[0].as_str().unwrap_or_default();
+        let password = params[1].as_str().unwrap_or_default();
+
+        if let Err(e) = handler.authorize(username, password) {
+            info!("Authentication of #{} failed: {}", connection_id, e);
+            return Err(object!{
+                "code" => -32001,
+                "message" => "Authentication failed",
+            });
+        }
+
+        if let Some(connection) = connections.write().get_mut(connection_id) {
+            connection.authenticated = true;
+        }
+        Ok(true.into())
+    }
+
+    fn subscribe(connections: &WsRpcConnections, connection_id: &UniqueId, params: &JsonValue) -> Result<JsonValue, JsonValue> {
+        let topic = Topic::from_params(params)
+            .map_err(|e| object!{
+                "code" => -32602,
+                "message" => e,
+            })?;
+
+        let mut connections = connections.write();
+        let connection = connections.get_mut(connection_id)
+            .ok_or_else(|| object!{
+                "code" => -32603,
+                "message" => "Connection closed",
+            })?;
+
+        // Every subscription gets its own queue, which is forwarded to the connection.
+        let (tx, rx) = channel::<Message>(Self::SUBSCRIPTION_QUEUE_SIZE);
+        tokio::spawn(rx.forward(connection.tx.clone().sink_map_err(|_| ())).map(|_| ()));
+
+        let subscription_id = connection.next_subscription_id;
+        connection.next_subscription_id += 1;
+        debug!("Client #{} subscribed to {:?} with id {}", connection_id, topic, subscription_id);
+        connection.subscriptions.insert(subscription_id, Subscription::new(topic, tx));
+
+        Ok(subscription_id.into())
+    }
+
+    fn unsubscribe(connections: &WsRpcConnections, connection_id: &UniqueId, params: &JsonValue) -> Result<JsonValue, JsonValue> {
+        let subscription_id = params[0].as_u64()
+            .ok_or_else(|| object!{
+                "code" => -32602,
+                "message" => "Invalid subscription id",
+            })?;
+
+        // Dropping the subscription closes its queue and thereby stops the forwarding.
+        let removed = connections.write().get_mut(connection_id)
+            .and_then(|connection| connection.subscriptions.remove(&subscription_id))
+            .is_some();
+
+        Ok(removed.into())
+    }
+
+    fn block_to_obj(block: &Block) -> JsonValue {
+        object!{
+            "hash" => block.hash().to_string(),
+            "blockNumber" => block.block_number(),
+            "viewNumber" => block.view_number(),
+            "epoch" => policy::epoch_at(block.block_number()),
+            "parentHash" => block.parent_hash().to_string(),
+            "timestamp" => block.timestamp(),
+            "type" => match block.ty() {
+                BlockType::Macro => "macro",
+                BlockType::Micro => "micro",
+            },
+        }
+    }
+
+    fn transaction_to_obj(transaction: &Transaction, block: Option<&Block>) -> JsonValue {
+        let mut obj = object!{
+            "hash" => transaction.hash::<Blake2bHash>().to_string(),
+            "from" => transaction.sender.to_user_friendly_address(),
+            "to" => transaction.recipient.to_user_friendly_address(),
+            "value" => u64::from(transaction.value),
+            "fee" => u64::from(transaction.fee),
+            "validityStartHeight" => transaction.validity_start_height,
+        };
+        if let Some(block) = block {
+            obj["blockHash"] = block.hash().to_string().into();
+            obj["blockNumber"] = block.block_number().into();
+        }
+        obj
+    }
+
     #[cfg(feature="validator")]
     fn map_validator_event(event: &ValidatorNetworkEvent) -> Option<JsonValue> {
         Some(match event {
@@ -225,23 +559,15 @@ impl WsRpcServer {
                 "newViewNumber" => event.view_change.new_view_number,
                 "votes" => event.votes,
             },
+            ValidatorNetworkEvent::PbftTimeout(event) => object!{
+                "eventType" => "pbftTimeout",
+                "hash" => event.hash.to_string(),
+                "blockNumber" => event.block_number,
+                "viewNumber" => event.view_number,
+            },
             _ => return None,
         })
     }
-
-    fn broadcast_message(connections: &WsRpcConnections, message: JsonValue) {
-        // Convert JSON message to Websocket TEXT frame
-        let message = Message::Text(message.dump());
-
-        for (_connection_id, connection) in connections.read().iter() {
-            let mut tx = connection.tx.clone();
-
-            // If the buffer is full, we drop the event. It's not the end of the world.
-            if let Err(e) = tx.try_send(message.clone()) {
-                warn!("Unable to send event to {}: {}", connection.address, e);
-            }
-        }
-    }
 }
 
 impl IntoFuture for WsRpcServer {
@@ -253,3 +579,120 @@ impl IntoFuture for WsRpcServer {
         self.future
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use block_albatross::{MicroBlock, MicroExtrinsics, MicroHeader, MicroJustification};
+    use keys::Address;
+    use primitives::coin::Coin;
+    use primitives::networks::NetworkId;
+
+    use super::*;
+
+    fn address(byte: u8) -> Address {
+        Address::from([byte; Address::SIZE])
+    }
+
+    fn transaction(sender: u8, recipient: u8) -> Transaction {
+        Transaction::new_basic(address(sender), address(recipient), Coin::from_u64_unchecked(10), Coin::ZERO, 1, NetworkId::UnitAlbatross)
+    }
+
+    fn micro_block(transactions: Vec<Transaction>) -> Block {
+        Block::Micro(MicroBlock {
+            header: MicroHeader {
+                version: 1,
+                block_number: 2,
+                view_number: 0,
+                parent_hash: Default::default(),
+                extrinsics_root: Default::default(),
+                state_root: Default::default(),
+                seed: Default::default(),
+                timestamp: 0,
+            },
+            justification: MicroJustification {
+                signature: Default::default(),
+                view_change_proof: None,
+            },
+            extrinsics: Some(MicroExtrinsics {
+                extra_data: vec![],
+                fork_proofs: vec![],
+                transactions,
+            }),
+        })
+    }
+
+    fn address_topic(bytes: &[u8]) -> Topic {
+        Topic::AddressTransactions(bytes.iter().map(|&byte| address(byte)).collect::<HashSet<Address>>())
+    }
+
+    #[test]
+    fn it_filters_transactions_by_address() {
+        let block = micro_block(vec![transaction(1, 2), transaction(3, 4), transaction(5, 1)]);
+
+        let results = WsRpcServer::block_results(&address_topic(&[1]), &block, false);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["from"], address(1).to_user_friendly_address());
+        assert_eq!(results[1]["to"], address(1).to_user_friendly_address());
+        assert_eq!(results[0]["blockNumber"], 2);
+        assert!(!results[0].has_key("reverted"));
+
+        let results = WsRpcServer::block_results(&address_topic(&[4, 6]), &block, false);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["from"], address(3).to_user_friendly_address());
+
+        assert!(WsRpcServer::block_results(&address_topic(&[6]), &block, false).is_empty());
+        assert!(WsRpcServer::block_results(&Topic::MempoolTransactions, &block, false).is_empty());
+        assert!(WsRpcServer::block_results(&Topic::FinalizedMacroBlocks, &block, false).is_empty());
+    }
+
+    #[test]
+    fn it_marks_reverted_blocks() {
+        let block = micro_block(vec![transaction(1, 2)]);
+
+        let results = WsRpcServer::block_results(&Topic::NewHeads, &block, true);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["hash"], block.hash().to_string());
+        assert_eq!(results[0]["reverted"], true);
+
+        let results = WsRpcServer::block_results(&address_topic(&[2]), &block, true);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["reverted"], true);
+    }
+
+    #[test]
+    fn it_notifies_matching_subscriptions() {
+        let (tx, _rx) = channel::<Message>(4);
+        let (address_tx, address_rx) = channel::<Message>(4);
+        let (mempool_tx, mempool_rx) = channel::<Message>(4);
+
+        let mut subscriptions = HashMap::new();
+        subscriptions.insert(1, Subscription::new(address_topic(&[2]), address_tx));
+        subscriptions.insert(2, Subscription::new(Topic::MempoolTransactions, mempool_tx));
+
+        let connections: WsRpcConnections = Arc::new(RwLock::new(HashMap::new()));
+        connections.write().insert(UniqueId::new(), WsRpcConnection {
+            address: "127.0.0.1:8650".parse().unwrap(),
+            tx,
+            authenticated: true,
+            next_subscription_id: 3,
+            subscriptions,
+        });
+
+        let block = micro_block(vec![transaction(1, 2), transaction(3, 4)]);
+        WsRpcServer::notify_block(&connections, &block, true);
+        // Closes the subscription queues
+        connections.write().clear();
+
+        let messages: Vec<Message> = address_rx.wait().map(Result::unwrap).collect();
+        assert_eq!(messages.len(), 1);
+        let message = json::parse(messages[0].to_text().unwrap()).unwrap();
+        assert_eq!(message["method"], "subscription");
+        assert_eq!(message["params"]["subscription"], 1);
+        assert_eq!(message["params"]["result"]["to"], address(2).to_user_friendly_address());
+        assert_eq!(message["params"]["result"]["reverted"], true);
+
+        assert_eq!(mempool_rx.wait().count(), 0);
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::HashSet;
+
+use futures::sync::mpsc::Sender;
+use json::{JsonValue, object};
+use tokio_tungstenite::tungstenite::Message;
+
+use keys::Address;
+use transaction::Transaction;
+
+pub type SubscriptionId = u64;
+
+/// The topics a client can subscribe to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Topic {
+    /// New blocks on the main chain, including blocks of a rebranch, and reverted blocks.
+    NewHeads,
+    /// Finalized macro blocks.
+    FinalizedMacroBlocks,
+    /// Mined and reverted transactions with one of the given addresses as sender or recipient.
+    AddressTransactions(HashSet<Address>),
+    /// Transactions added to the mempool.
+    MempoolTransactions,
+    /// pBFT and view change updates of the validator network.
+    ValidatorUpdates,
+}
+
+impl Topic {
+    /// Parses the params of a `subscribe` call: the topic name, followed by a list of addresses
+    /// for `addressTransactions`.
+    pub fn from_params(params: &JsonValue) -> Result<Self, String> {
+        let name = params[0].as_str()
+            .ok_or_else(|| "Missing topic".to_string())?;
+
+        Ok(match name {
+            "newHeads" => Topic::NewHeads,
+            "finalizedMacroBlocks" => Topic::FinalizedMacroBlocks,
+            "addressTransactions" => {
+                if !params[1].is_array() || params[1].is_empty() {
+                    return Err("Expected a list of addresses".to_string());
+                }
+                let addresses = params[1].members()
+                    .map(|address| address.as_str()
+                        .and_then(|s| Address::from_any_str(s).ok())
+                        .ok_or_else(|| format!("Invalid address: {}", address)))
+                    .collect::<Result<HashSet<Address>, String>>()?;
+                Topic::AddressTransactions(addresses)
+            },
+            "mempoolTransactions" => Topic::MempoolTransactions,
+            "validatorUpdates" => Topic::ValidatorUpdates,
+            _ => return Err(format!("Unknown topic: {}", name)),
+        })
+    }
+
+    /// Whether `transaction` is sent to subscriptions of this topic when it is mined or reverted.
+    pub fn matches_transaction(&self, transaction: &Transaction) -> bool {
+        match self {
+            Topic::AddressTransactions(addresses) => addresses.contains(&transaction.sender)
+                || addresses.contains(&transaction.recipient),
+            _ => false,
+        }
+    }
+}
+
+/// A subscription of a connection to a topic.
+///
+/// Every subscription has its own bounded queue that is forwarded to the connection. If the
+/// queue is full, notifications are dropped and the number of dropped notifications is reported
+/// as `missed` with the next notification that could be queued.
+pub struct Subscription {
+    pub topic: Topic,
+    tx: Sender<Message>,
+    missed: u64,
+}
+
+impl Subscription {
+    pub fn new(topic: Topic, tx: Sender<Message>) -> Self {
+        Subscription {
+            topic,
+            tx,
+            missed: 0,
+        }
+    }
+
+    pub fn notify(&mut self, id: SubscriptionId, result: JsonValue) {
+        let mut params = object!{
+            "subscription" => id,
+            "result" => result,
+        };
+        if self.missed > 0 {
+            params["missed"] = self.missed.into();
+        }
+
+        let message = object!{
+            "jsonrpc" => "2.0",
+            "method" => "subscription",
+            "params" => params,
+        };
+
+        match self.tx.try_send(Message::Text(message.dump())) {
+            Ok(()) => self.missed = 0,
+            Err(e) => {
+                if self.missed == 0 {
+                    debug!("Subscription #{} is lagging behind: {}", id, e);
+                }
+                self.missed += 1;
+            },
+        }
+    }
+}